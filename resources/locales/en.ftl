@@ -0,0 +1,491 @@
+# Main menu
+menu-file = File
+menu-new = 🖹 New
+menu-open-project = 📂 Open project
+menu-load-chain = ⛓ Load chain preset
+menu-import-chain = ⛓ Import chain from project
+menu-import-chain-append = Append to current chain
+menu-import-chain-replace = Replace current chain
+menu-save-chain = ⛓ Save chain preset
+menu-save = 💾 Save
+menu-save-as = 💾 Save as
+menu-export-image = 🖼 Export image
+menu-export-profile = 🖼 Export with profile
+menu-export-difference = 🖼 Export difference
+menu-export-report = 📄 Export processing report
+menu-export-options = ⚙ Export options
+menu-edit-externally = ✏ Edit in external editor
+menu-copy-image = 📋 Copy image
+menu-exit = ❎ Exit
+menu-tools = Tools
+menu-noise = ⧯ Generate noise image
+menu-open-sequence = 🗐 Open image sequence
+menu-animation-from-image = 🗐 Make animation from image
+menu-frames = frames
+menu-export-sequence = 💾 Export image sequence
+menu-surprise = 🎲 Surprise me
+menu-random-effects = Random effects
+menu-plugin-library = 📁 Plugin library ({ $count } plugins)
+menu-log-console = 🗒 Log console
+menu-preferences = ⚙ Preferences
+menu-about = About
+menu-about-window = ℹ About
+menu-github = ⬌ GitHub repository page
+watch-image = Reload image when it changes
+watch-image-hint = Re-import the image when it is saved by another editor, chain is applied again
+dock-editors = Dock plugin editors
+
+# Preferences
+prefs-title = Preferences
+prefs-plugin-dirs = Plugin directories
+prefs-add-folder = ➕ Add folder
+prefs-blacklist = Plugin blacklist
+prefs-blacklist-add = ➕ Add plugin
+prefs-processing = Processing
+prefs-tile-size = Default tile size
+prefs-cli-threads = CLI threads
+prefs-cli-threads-hint = 0 uses every CPU
+prefs-autosave = Partial save interval
+prefs-minutes = min
+prefs-project = Project files
+prefs-project-original = Store original image
+prefs-project-original-hint = Processing can be undone after the project is reopened
+prefs-project-masks = Store plugin masks
+prefs-project-history = Undo steps stored
+prefs-export = Export
+prefs-default-format = Default format
+prefs-external-editor = External editor
+prefs-not-set = not set
+prefs-interface = Interface
+prefs-language = Language
+prefs-watch-plugins = Offer to reload changed plugins
+prefs-watch-plugins-hint = Useful when testing your own plugin builds
+prefs-pixel-grid = Pixel grid
+prefs-rulers = Rulers
+prefs-theme = Theme
+prefs-shortcuts = Keyboard shortcuts
+prefs-press-key = Press a key...
+prefs-set = Set
+prefs-reset-shortcuts = Reset shortcuts
+prefs-ok = Ok
+theme-dark = Dark
+theme-light = Light
+theme-custom = Custom
+theme-accent = Accent
+theme-canvas = Canvas background
+theme-grid = Pixel grid
+theme-panels = Panels
+theme-text = Text
+theme-reset = Reset theme
+
+# Commands
+command-undo = Undo
+command-redo = Redo
+command-process = Apply FX
+command-stop = Cancel processing
+command-eyedropper = Eyedropper
+command-next-compare-mode = Next compare mode
+command-blink = Blink original
+command-bypass-selected = Bypass selected plugin
+command-zoom-fit = Zoom to fit
+command-actual-pixels = Actual pixels
+command-zoom-selection = Zoom to selection
+command-save-project = Save project
+command-export-image = Export image
+command-preferences = Preferences
+command-command-palette = Command palette
+
+# Log console
+console-title = Log console
+console-level = Level
+console-copy = 📋 Copy
+console-clear = 🗑 Clear
+
+# Render queue
+menu-render-queue = Render queue
+jobs-title = Render queue
+jobs-add = ➕ Add images with current chain...
+jobs-workers = Workers
+jobs-workers-hint = Jobs rendered at the same time, 1 renders them one by one
+jobs-start = ▶ Start
+jobs-clear = Clear finished
+jobs-empty = Queue is empty
+jobs-cancel = Cancel job
+jobs-queued = Queued
+jobs-done = Done
+jobs-failed = Failed
+jobs-cancelled = Cancelled
+
+# Checkpoints
+checkpoints = Checkpoints
+checkpoints-hint = Periodically saves processing state into the project, so interrupted render can be resumed
+resume-title = Resume render
+resume-message = Project contains interrupted render ({ $percentage }% of pass { $pass }). Continue it?
+resume-continue = ▶ Continue
+resume-discard = Start over
+
+# Seeds
+menu-seed = Seed
+menu-replay-seed = ⟲ Replay
+menu-replay-seed-hint = Generates the chain with this seed again, plugin library must be the same
+
+# Macros
+menu-record-macro = ⏺ Record macro
+menu-stop-recording = ⏹ Stop recording and save...
+menu-replay-macro = ▶ Replay macro...
+
+# Plugin panel
+plugin-bypass-group = Bypass group
+plugin-group-wet = Group wet
+plugin-not-initialized = This plugin is not initialized
+plugin-configuration-preserved = Effect configuration is preserved
+plugin-remove = Remove
+plugin-hint = Click to inspect, right-click for more options
+plugin-category = Category
+plugin-initial-delay = Initial delay
+plugin-f64-mixing = 64 bit mixing support
+plugin-built-in = Built-in effect
+plugin-duplicate = ⧉ Duplicate
+plugin-group-with-next = 📁 Group with next
+plugin-ungroup = Ungroup
+plugin-copy-settings = 📋 Copy settings
+plugin-paste-settings = 📋 Paste settings
+plugin-input-channel = Image input channel processing:
+plugin-output-channel = Output audio channel:
+plugin-left-mono = Left/Mono
+plugin-right = Right
+plugin-mix-outputs = Mix all outputs
+plugin-mix-outputs-hint = Average of all plugin outputs is written instead of the selected channel
+plugin-output-to = Output { $output } to:
+plugin-output-to-hint = Writes the output into another channel after the main output
+plugin-off = Off
+plugin-program = Program: { $name }
+plugin-parameters = Parameters:
+plugin-search-parameters = 🔍 Search parameters
+plugin-midi-learn-hint = MIDI learn: click and move a controller, right-click to remove mapping
+plugin-automation = Automation: { $count } points
+plugin-automation-hint = Parameter changes made in the plugin GUI, changes made while processing are replayed at the same position
+plugin-clear-automation = 🗑 Clear
+plugin-wet = Wet:
+plugin-sample-rate = Sample rate:
+plugin-override-rate = Override project rate
+plugin-iterations = Iterations:
+plugin-sample-offset = Sample offset:
+plugin-sample-offset-hint = Shifts output written back into the image, wraps around block edges
+plugin-field = Field:
+plugin-field-both = Both
+plugin-field-even = Even
+plugin-field-odd = Odd
+plugin-tile-pattern = Tile pattern:
+plugin-tiles-all = All
+plugin-tiles-every-nth = Every Nth
+plugin-tiles-checkerboard = Checkerboard
+plugin-tiles-even-rows = Even rows
+plugin-jitter = Jitter:
+plugin-jitter-hint = Random parameter offset picked for every tile
+plugin-all-parameters = All parameters
+plugin-parameter-count = { $count } parameters
+plugin-reseed = 🎲 Reseed
+plugin-region = Region:
+plugin-limit-region = Limit to region
+region-top = Top
+region-bottom = Bottom
+region-left = Left
+region-right = Right
+plugin-mask = Mask:
+plugin-bypass = Bypass/Mute processing
+plugin-solo = Solo, bypasses all other plugins
+plugin-open-editor = Open GUI Editor
+
+# Windows
+export-title = Export options
+export-quality = Quality
+export-subsampling = Chroma subsampling
+export-compression = Compression
+export-bit-depth = Bit depth:
+export-source-depth = Source
+export-indexed = Indexed (PNG-8)
+export-palette-heading = Palette (GIF, indexed PNG)
+export-palette = Palette
+export-colors = Colors
+export-palette-dither = Palette dithering
+export-lossless = Lossless
+export-speed = Speed
+export-tone-mapping = Tone mapping
+export-exposure = Exposure
+export-sidecar = Write chain next to the image
+export-sidecar-hint = Saves [name].{ $extension } with the image
+export-profile = Export profile
+export-tokens = Tokens
+export-max-size = Max size
+export-add-output = ➕ Add output
+export-load-profile = 📂 Load profile
+export-save-profile = 💾 Save profile
+navigator-title = Navigator
+histogram-title = Histogram
+histogram-clipped-shadows = Clipped shadows: { $percentage }%
+histogram-clipped-highlights = Clipped highlights: { $percentage }%
+metrics-title = Quality metrics
+metrics-identical = PSNR: ∞ (images are identical)
+metrics-processing = Metrics are measured when processing is finished
+metrics-unavailable = Original and processed images can't be compared
+plugins-changed-title = Plugins changed
+plugins-changed-reload = ⟲ Reload
+plugins-changed-reload-hint = Plugin can be reloaded after processing
+plugins-changed-ignore = Ignore
+
+# Errors
+error-open-editor = Unable to open editor
+error-open-dropped-file = Unable to open dropped file
+error-change-plugin = Unable to change plugin
+error-undo = Unable to undo
+error-redo = Unable to redo
+error-save-macro = Unable to save macro
+error-load-macro = Unable to load macro
+error-replay-macro = Unable to replay macro
+error-randomize-chain = Unable to randomize chain
+error-save-chain-preset = Unable to save chain preset
+error-load-chain-preset = Unable to load chain preset
+error-import-chain = Unable to import chain
+error-save-image = Image saving error
+error-cannot-save-image = Cannot save image:
+error-profile-no-outputs = Export profile has no outputs
+error-export-report = Unable to export report
+error-save-export-profile = Unable to save export profile
+error-load-export-profile = Unable to load export profile
+error-copy-chain = Unable to copy chain
+error-plugin-loading = Plugin loading failed!
+error-effect-loading = Effect loading failed!
+error-open-url = Unable to open image from URL
+error-capture-screen = Unable to capture screen
+error-open-video = Unable to open video
+error-open-svg = Unable to open SVG
+error-copy-log = Unable to copy log
+error-save-chain = Unable to save chain
+error-load-project = Unable to load project
+error-process-image = Unable to process image
+error-cancel-processing = Unable to cancel processing
+error-reload-plugin = Unable to reload plugin
+error-resume-processing = Unable to resume processing
+error-open-external-editor = Unable to open external editor
+error-copy-image = Unable to copy image
+error-load-sequence = Unable to load sequence
+error-save-sequence = Unable to save sequence
+error-open-midi = Unable to open MIDI input
+error-copy-settings = Unable to copy settings
+error-paste-settings = Unable to paste settings
+error-open-image = Unable to open image
+error-capture-webcam = Unable to capture webcam
+error-switch-snapshot = Unable to switch snapshot
+
+# Masks
+mask-new = New
+mask-source = Source: { $name }
+mask-add-layer = ➕ Layer
+mask-remove-layer = Remove layer
+mask-add-point = ➕ Point
+mask-remove-point = Remove point
+mask-low = Low
+mask-high = High
+mask-threshold = Threshold
+mask-hue = Hue
+mask-tolerance = Tolerance
+mask-font-family = Font family
+mask-baseline = Baseline
+mask-size = Size
+mask-softness = Softness
+mask-grow = Grow
+mask-grow-hint = Expands or contracts the selection
+mask-border = Border
+mask-border-hint = Keeps only a band around the selection edge
+mask-invert = Invert
+mask-brightness = Brightness
+mask-shadows = Shadows
+mask-highlights = Highlights
+mask-edges = Edges
+mask-color = Color
+mask-text = Text
+mask-ellipse = Ellipse
+mask-polygon = Polygon
+mask-add = Add
+mask-subtract = Subtract
+mask-intersect = Intersect
+
+# Processing graph
+graph-title = Routing
+graph-use = Use processing graph
+graph-use-hint = When disabled plugins are processed as a simple chain
+graph-parallel-chain = ➕ Parallel chain
+graph-parallel-chain-hint = Process another chain from the same source and mix it with the others
+graph-channel-chains = ➕ Channel chains
+graph-channel-chains-hint = Process red, green and blue with separate chains in one run
+graph-split = Split
+graph-split-hint = Split into parallel branches
+graph-unroute = Unroute
+graph-add-branch = Add branch
+graph-remove-branch = Remove branch
+graph-flatten = Flatten into a chain
+graph-branch = Branch { $number }
+graph-gain = Gain
+graph-channel-hint = Channel processed by the branch
+graph-route-plugin = ➕ Route plugin
+blend-normal = Normal
+blend-add = Add
+blend-multiply = Multiply
+blend-screen = Screen
+blend-difference = Difference
+blend-lighten = Lighten
+blend-darken = Darken
+channel-red = Red
+channel-green = Green
+channel-blue = Blue
+
+# Inspector
+inspector-close = Close inspector
+inspector-vendor = Vendor
+inspector-product = Product
+inspector-version = Version
+inspector-unique-id = Unique ID
+inspector-audio-io = Audio I/O
+inspector-midi-io = MIDI I/O
+inspector-parameters = Parameters
+inspector-presets = Presets
+inspector-latency = Latency
+inspector-samples = { $count } samples
+inspector-chunks = State chunks
+inspector-f64 = 64 bit processing
+inspector-can-do = Can do:
+inspector-yes = Yes
+inspector-maybe = Maybe
+inspector-no = No
+inspector-custom = Custom
+keyframes-title = Keyframes:
+keyframes-wet = Wet
+keyframes-parameter = Parameter { $index }
+keyframes-add = ➕ Add at frame { $frame }
+keyframes-add-hint = Stores current value of the target
+keyframes-frame = Frame
+
+# Main window
+tab-untitled = Untitled
+tabs-new = New tab
+tabs-copy-chain-here = ⛓ Copy current chain here
+tabs-use-chain = ⛓ Use chain of this tab
+palette-hint = Type a command
+palette-toggle-bypass = Toggle bypass: { $name }
+palette-add-plugin = Add plugin: { $name }
+rack-add-vst = ➕ Add VST Effect
+rack-add-built-in = ➕ Add built-in effect
+open-image = 📂 Open image
+open-video = 🎞 Open video
+open-url = 🌐 Open URL
+open-capture-screen = 📷 Capture screen
+open-webcam = 📸 Webcam
+process-apply = ✅ Apply FX on image
+process-preview = 👁 Preview
+process-preview-hint = Processes downscaled copy of the image
+process-live = Live
+process-live-hint = Updates preview or tile preview when plugin parameters are changed
+process-full-resolution = ⛶ Full resolution
+process-cancel = ☠ Cancel
+process-undo = ↻ Undo
+process-redo = ↺ Redo
+process-snapshot-hint = Chain snapshot, switching re-renders the image
+process-preview-size = Preview size
+process-passes = Passes
+process-wet-decay = Wet decay
+process-auto-sample-rate = Auto sample rate
+process-auto-sample-rate-hint = Every image row lasts the same time
+process-row-length = Row length
+process-interlaced = Interlaced
+process-interlaced-hint = Processes even and odd scanlines as two separate streams
+process-dither = Dither
+process-dither-hint = Rounding of processed values of 8 and 16 bit images
+process-grayscale = Grayscale
+process-grayscale-hint = Processes image luminance as a single stream
+process-partial-save = Partial save
+process-partial-save-hint = Periodically saves partially processed image during long runs
+process-every = Every
+animation-reset-plugins = Reset plugins every frame
+animation-reset-plugins-hint = Otherwise plugin state (delays, reverb tails, etc.) carries over to the next frame
+animation-processed = Processed frames: { $processed }/{ $count }
+view-fit = ⛶ Fit
+view-selection = 🔍 Selection
+view-selection-hint = Zoom to region of the selected plugin
+view-metrics = Metrics
+view-metrics-hint = PSNR and SSIM of the result against the original image
+view-clipping = Clipping
+view-clipping-hint = Highlight clipped pixels: red for 255, blue for 0
+view-eyedropper-hint = Inspect pixel values under the cursor
+compare-title = Compare:
+compare-split = Split
+compare-split-hint = Original image on the left of the divider
+compare-blink = Blink
+compare-blink-hint = Blink shortcut flips between original and result
+compare-checkerboard = Checkerboard
+compare-difference = Difference
+compare-original = Original
+guides-vertical = ➕ Vertical guide
+guides-horizontal = ➕ Horizontal guide
+guides-clear = Clear guides
+canvas-tile-size = Tile size
+canvas-preview-tile = 🔍 Preview this tile
+canvas-hide-tile-preview = ✖ Hide tile preview
+canvas-processing = Processing image, please wait...
+canvas-welcome = Welcome to PhotoConsequences!
+status-progress = Memory used: { $memory } MiB Processed: { $percentage }% Pass: { $pass }/{ $passes } Time: { $time } s
+status-slowest = Slowest: { $name }
+status-frame = Frame: { $frame }/{ $count }
+
+# Dialogs
+dialog-ok = Ok
+dialog-open = Open
+dialog-cancel = Cancel
+exit-title = Project management
+exit-save-file = Save { $path } before exiting?
+exit-save-project = Save project before exiting?
+exit-yes = ✅ Yes
+exit-no = ❎ No
+exit-cancel = 🚫 Cancel
+about-version = Version: { $version }
+about-description = Tool to apply VST™ effects on the images
+about-trademark = VST™ is a trademark of Steinberg Media Technologies GmbH.
+url-title = Open image from URL
+url-downloading = Downloading...
+capture-title = Capture screen
+capture-whole-screen = Whole screen
+capture-position = Position
+capture-size = Size
+capture-capture = Capture
+video-title = Open video
+video-first-frame = First frame
+video-frame-count = Frame count
+svg-title = Open SVG image
+svg-width = Width
+export-8-bit = 8 bit
+export-16-bit = 16 bit
+pixel-title = Pixel inspector
+pixel-hover = Hover the image to inspect pixels
+pixel-position = Pixel: { $x }, { $y }
+pixel-sample = { $channel } sample: { $value }
+pixel-tile = Tile: { $column }, { $row }
+pixel-not-affected = Pixel is not affected by this plugin
+dialog-save-macro = Save macro
+dialog-replay-macro = Replay macro
+dialog-plugin-folder = Select plugin library folder
+dialog-save-chain-preset = Save chain preset
+dialog-load-chain-preset = Load chain preset
+dialog-import-chain = Import chain from project
+dialog-export-image = Export image
+dialog-export-profile = Export with profile
+dialog-export-difference = Export difference
+dialog-export-report = Export processing report
+dialog-save-export-profile = Save export profile
+dialog-load-export-profile = Load export profile
+dialog-render-images = Select images to render
+dialog-output-folder = Select output folder
+dialog-save-project = Save project
+dialog-open-project = Open project
+dialog-sequence-folder = Select image sequence folder
+dialog-partial-image = Partial image file
+dialog-checkpoint-file = Checkpoint project file
@@ -0,0 +1,491 @@
+# メインメニュー
+menu-file = ファイル
+menu-new = 🖹 新規
+menu-open-project = 📂 プロジェクトを開く
+menu-load-chain = ⛓ チェーンプリセットを読み込む
+menu-import-chain = ⛓ プロジェクトからチェーンをインポート
+menu-import-chain-append = 現在のチェーンに追加
+menu-import-chain-replace = 現在のチェーンを置き換える
+menu-save-chain = ⛓ チェーンプリセットを保存
+menu-save = 💾 保存
+menu-save-as = 💾 名前を付けて保存
+menu-export-image = 🖼 画像を書き出す
+menu-export-profile = 🖼 プロファイルで書き出す
+menu-export-difference = 🖼 差分を書き出す
+menu-export-report = 📄 処理レポートを書き出す
+menu-export-options = ⚙ 書き出しオプション
+menu-edit-externally = ✏ 外部エディタで編集
+menu-copy-image = 📋 画像をコピー
+menu-exit = ❎ 終了
+menu-tools = ツール
+menu-noise = ⧯ ノイズ画像を生成
+menu-open-sequence = 🗐 連番画像を開く
+menu-animation-from-image = 🗐 画像からアニメーションを作成
+menu-frames = フレーム
+menu-export-sequence = 💾 連番画像を書き出す
+menu-surprise = 🎲 おまかせ
+menu-random-effects = ランダムエフェクト数
+menu-plugin-library = 📁 プラグインライブラリ ({ $count } 個)
+menu-log-console = 🗒 ログコンソール
+menu-preferences = ⚙ 環境設定
+menu-about = ヘルプ
+menu-about-window = ℹ このソフトについて
+menu-github = ⬌ GitHub リポジトリ
+watch-image = 変更時に画像を再読み込み
+watch-image-hint = 他のエディタで保存されたときに画像を再読み込みし、チェーンを再適用します
+dock-editors = プラグインエディタをドッキング
+
+# 環境設定
+prefs-title = 環境設定
+prefs-plugin-dirs = プラグインフォルダ
+prefs-add-folder = ➕ フォルダを追加
+prefs-blacklist = プラグインのブラックリスト
+prefs-blacklist-add = ➕ プラグインを追加
+prefs-processing = 処理
+prefs-tile-size = 既定のタイルサイズ
+prefs-cli-threads = CLI スレッド数
+prefs-cli-threads-hint = 0 はすべての CPU を使用
+prefs-autosave = 部分保存の間隔
+prefs-minutes = 分
+prefs-project = プロジェクトファイル
+prefs-project-original = 元画像を保存する
+prefs-project-original-hint = プロジェクトを開き直した後も処理を元に戻せます
+prefs-project-masks = プラグインのマスクを保存する
+prefs-project-history = 保存する元に戻す履歴の数
+prefs-export = 書き出し
+prefs-default-format = 既定の形式
+prefs-external-editor = 外部エディタ
+prefs-not-set = 未設定
+prefs-interface = インターフェース
+prefs-language = 言語
+prefs-watch-plugins = 変更されたプラグインの再読み込みを提案
+prefs-watch-plugins-hint = 自作プラグインのビルドをテストする際に便利です
+prefs-pixel-grid = ピクセルグリッド
+prefs-rulers = ルーラー
+prefs-theme = テーマ
+prefs-shortcuts = キーボードショートカット
+prefs-press-key = キーを押してください...
+prefs-set = 設定
+prefs-reset-shortcuts = ショートカットをリセット
+prefs-ok = OK
+theme-dark = ダーク
+theme-light = ライト
+theme-custom = カスタム
+theme-accent = アクセント
+theme-canvas = キャンバス背景
+theme-grid = ピクセルグリッド
+theme-panels = パネル
+theme-text = テキスト
+theme-reset = テーマをリセット
+
+# コマンド
+command-undo = 元に戻す
+command-redo = やり直し
+command-process = エフェクトを適用
+command-stop = 処理を中止
+command-eyedropper = スポイト
+command-next-compare-mode = 次の比較モード
+command-blink = オリジナルを点滅
+command-bypass-selected = 選択したプラグインをバイパス
+command-zoom-fit = 全体表示
+command-actual-pixels = 実寸表示
+command-zoom-selection = 選択範囲にズーム
+command-save-project = プロジェクトを保存
+command-export-image = 画像を書き出す
+command-preferences = 環境設定
+command-command-palette = コマンドパレット
+
+# ログコンソール
+console-title = ログコンソール
+console-level = レベル
+console-copy = 📋 コピー
+console-clear = 🗑 クリア
+
+# レンダーキュー
+menu-render-queue = レンダーキュー
+jobs-title = レンダーキュー
+jobs-add = ➕ 現在のチェーンで画像を追加...
+jobs-workers = ワーカー
+jobs-workers-hint = 同時にレンダリングするジョブ数、1 の場合は順番に処理します
+jobs-start = ▶ 開始
+jobs-clear = 完了したジョブを削除
+jobs-empty = キューは空です
+jobs-cancel = ジョブをキャンセル
+jobs-queued = 待機中
+jobs-done = 完了
+jobs-failed = 失敗
+jobs-cancelled = キャンセル済み
+
+# チェックポイント
+checkpoints = チェックポイント
+checkpoints-hint = 処理状態を定期的にプロジェクトに保存し、中断したレンダリングを再開できるようにします
+resume-title = レンダリングの再開
+resume-message = プロジェクトに中断されたレンダリングがあります（パス { $pass } の { $percentage }%）。続行しますか？
+resume-continue = ▶ 続行
+resume-discard = 最初からやり直す
+
+# シード
+menu-seed = シード
+menu-replay-seed = ⟲ 再現
+menu-replay-seed-hint = このシードでチェーンを再生成します。プラグインライブラリが同じである必要があります
+
+# マクロ
+menu-record-macro = ⏺ マクロを記録
+menu-stop-recording = ⏹ 記録を停止して保存...
+menu-replay-macro = ▶ マクロを再生...
+
+# プラグインパネル
+plugin-bypass-group = グループをバイパス
+plugin-group-wet = グループのウェット
+plugin-not-initialized = このプラグインは初期化されていません
+plugin-configuration-preserved = エフェクトの設定は保持されます
+plugin-remove = 削除
+plugin-hint = クリックで詳細、右クリックでその他のオプション
+plugin-category = カテゴリ
+plugin-initial-delay = 初期遅延
+plugin-f64-mixing = 64ビットミキシング対応
+plugin-built-in = 内蔵エフェクト
+plugin-duplicate = ⧉ 複製
+plugin-group-with-next = 📁 次とグループ化
+plugin-ungroup = グループ解除
+plugin-copy-settings = 📋 設定をコピー
+plugin-paste-settings = 📋 設定を貼り付け
+plugin-input-channel = 処理する画像チャンネル:
+plugin-output-channel = 出力オーディオチャンネル:
+plugin-left-mono = 左/モノ
+plugin-right = 右
+plugin-mix-outputs = すべての出力をミックス
+plugin-mix-outputs-hint = 選択したチャンネルの代わりにすべての出力の平均を書き込みます
+plugin-output-to = 出力 { $output } の書き込み先:
+plugin-output-to-hint = メイン出力の後に別のチャンネルへ書き込みます
+plugin-off = オフ
+plugin-program = プログラム: { $name }
+plugin-parameters = パラメーター:
+plugin-search-parameters = 🔍 パラメーターを検索
+plugin-midi-learn-hint = MIDIラーン: クリックしてコントローラーを動かす、右クリックで割り当てを解除
+plugin-automation = オートメーション: { $count } ポイント
+plugin-automation-hint = プラグインGUIで行ったパラメーター変更、処理中の変更は同じ位置で再生されます
+plugin-clear-automation = 🗑 クリア
+plugin-wet = ウェット:
+plugin-sample-rate = サンプルレート:
+plugin-override-rate = プロジェクトのレートを上書き
+plugin-iterations = 反復回数:
+plugin-sample-offset = サンプルオフセット:
+plugin-sample-offset-hint = 画像に書き戻す出力をずらします。ブロックの端で折り返します
+plugin-field = フィールド:
+plugin-field-both = 両方
+plugin-field-even = 偶数
+plugin-field-odd = 奇数
+plugin-tile-pattern = タイルパターン:
+plugin-tiles-all = すべて
+plugin-tiles-every-nth = N個ごと
+plugin-tiles-checkerboard = 市松模様
+plugin-tiles-even-rows = 偶数行
+plugin-jitter = ジッター:
+plugin-jitter-hint = タイルごとに選ばれるランダムなパラメーターのずれ
+plugin-all-parameters = すべてのパラメーター
+plugin-parameter-count = { $count } パラメーター
+plugin-reseed = 🎲 シードを変更
+plugin-region = 領域:
+plugin-limit-region = 領域に制限
+region-top = 上
+region-bottom = 下
+region-left = 左
+region-right = 右
+plugin-mask = マスク:
+plugin-bypass = 処理をバイパス/ミュート
+plugin-solo = ソロ、他のすべてのプラグインをバイパス
+plugin-open-editor = GUIエディターを開く
+
+# ウィンドウ
+export-title = エクスポートオプション
+export-quality = 品質
+export-subsampling = クロマサブサンプリング
+export-compression = 圧縮
+export-bit-depth = ビット深度:
+export-source-depth = 元画像と同じ
+export-indexed = インデックス (PNG-8)
+export-palette-heading = パレット (GIF、インデックスPNG)
+export-palette = パレット
+export-colors = 色数
+export-palette-dither = パレットのディザリング
+export-lossless = ロスレス
+export-speed = 速度
+export-tone-mapping = トーンマッピング
+export-exposure = 露出
+export-sidecar = 画像の横にチェーンを書き出す
+export-sidecar-hint = 画像と一緒に [name].{ $extension } を保存します
+export-profile = エクスポートプロファイル
+export-tokens = トークン
+export-max-size = 最大サイズ
+export-add-output = ➕ 出力を追加
+export-load-profile = 📂 プロファイルを読み込む
+export-save-profile = 💾 プロファイルを保存
+navigator-title = ナビゲーター
+histogram-title = ヒストグラム
+histogram-clipped-shadows = 黒つぶれ: { $percentage }%
+histogram-clipped-highlights = 白飛び: { $percentage }%
+metrics-title = 品質指標
+metrics-identical = PSNR: ∞ (画像は同一です)
+metrics-processing = 指標は処理の完了後に測定されます
+metrics-unavailable = 元画像と処理後の画像は比較できません
+plugins-changed-title = プラグインが変更されました
+plugins-changed-reload = ⟲ 再読み込み
+plugins-changed-reload-hint = プラグインは処理の後に再読み込みできます
+plugins-changed-ignore = 無視
+
+# エラー
+error-open-editor = エディターを開けません
+error-open-dropped-file = ドロップしたファイルを開けません
+error-change-plugin = プラグインを変更できません
+error-undo = 元に戻せません
+error-redo = やり直せません
+error-save-macro = マクロを保存できません
+error-load-macro = マクロを読み込めません
+error-replay-macro = マクロを再生できません
+error-randomize-chain = ランダムなチェーンを作成できません
+error-save-chain-preset = チェーンプリセットを保存できません
+error-load-chain-preset = チェーンプリセットを読み込めません
+error-import-chain = チェーンをインポートできません
+error-save-image = 画像の保存エラー
+error-cannot-save-image = 画像を保存できません:
+error-profile-no-outputs = エクスポートプロファイルに出力がありません
+error-export-report = レポートをエクスポートできません
+error-save-export-profile = エクスポートプロファイルを保存できません
+error-load-export-profile = エクスポートプロファイルを読み込めません
+error-copy-chain = チェーンをコピーできません
+error-plugin-loading = プラグインの読み込みに失敗しました!
+error-effect-loading = エフェクトの読み込みに失敗しました!
+error-open-url = URLから画像を開けません
+error-capture-screen = 画面をキャプチャできません
+error-open-video = 動画を開けません
+error-open-svg = SVGを開けません
+error-copy-log = ログをコピーできません
+error-save-chain = チェーンを保存できません
+error-load-project = プロジェクトを読み込めません
+error-process-image = 画像を処理できません
+error-cancel-processing = 処理をキャンセルできません
+error-reload-plugin = プラグインを再読み込みできません
+error-resume-processing = 処理を再開できません
+error-open-external-editor = 外部エディターを開けません
+error-copy-image = 画像をコピーできません
+error-load-sequence = 連番画像を読み込めません
+error-save-sequence = 連番画像を保存できません
+error-open-midi = MIDI入力を開けません
+error-copy-settings = 設定をコピーできません
+error-paste-settings = 設定を貼り付けられません
+error-open-image = 画像を開けません
+error-capture-webcam = Webカメラをキャプチャできません
+error-switch-snapshot = スナップショットを切り替えられません
+
+# Masks
+mask-new = 新規
+mask-source = ソース: { $name }
+mask-add-layer = ➕ レイヤー
+mask-remove-layer = レイヤーを削除
+mask-add-point = ➕ 点
+mask-remove-point = 点を削除
+mask-low = 下限
+mask-high = 上限
+mask-threshold = しきい値
+mask-hue = 色相
+mask-tolerance = 許容範囲
+mask-font-family = フォント
+mask-baseline = ベースライン
+mask-size = サイズ
+mask-softness = ぼかし
+mask-grow = 拡張
+mask-grow-hint = 選択範囲を拡張または縮小します
+mask-border = 境界
+mask-border-hint = 選択範囲の縁に沿った帯だけを残します
+mask-invert = 反転
+mask-brightness = 明るさ
+mask-shadows = シャドウ
+mask-highlights = ハイライト
+mask-edges = エッジ
+mask-color = 色
+mask-text = テキスト
+mask-ellipse = 楕円
+mask-polygon = 多角形
+mask-add = 追加
+mask-subtract = 減算
+mask-intersect = 交差
+
+# Processing graph
+graph-title = ルーティング
+graph-use = 処理グラフを使用
+graph-use-hint = 無効の場合、プラグインは単純なチェーンとして処理されます
+graph-parallel-chain = ➕ 並列チェーン
+graph-parallel-chain-hint = 同じソースから別のチェーンを処理し、他のチェーンとミックスします
+graph-channel-chains = ➕ チャンネルチェーン
+graph-channel-chains-hint = 赤・緑・青を別々のチェーンで一度に処理します
+graph-split = 分岐
+graph-split-hint = 並列ブランチに分割
+graph-unroute = ルートから外す
+graph-add-branch = ブランチを追加
+graph-remove-branch = ブランチを削除
+graph-flatten = チェーンに平坦化
+graph-branch = ブランチ { $number }
+graph-gain = ゲイン
+graph-channel-hint = ブランチが処理するチャンネル
+graph-route-plugin = ➕ プラグインをルーティング
+blend-normal = 通常
+blend-add = 加算
+blend-multiply = 乗算
+blend-screen = スクリーン
+blend-difference = 差の絶対値
+blend-lighten = 比較(明)
+blend-darken = 比較(暗)
+channel-red = 赤
+channel-green = 緑
+channel-blue = 青
+
+# Inspector
+inspector-close = インスペクターを閉じる
+inspector-vendor = ベンダー
+inspector-product = 製品
+inspector-version = バージョン
+inspector-unique-id = 固有ID
+inspector-audio-io = オーディオ入出力
+inspector-midi-io = MIDI入出力
+inspector-parameters = パラメーター
+inspector-presets = プリセット
+inspector-latency = レイテンシー
+inspector-samples = { $count } サンプル
+inspector-chunks = ステートチャンク
+inspector-f64 = 64ビット処理
+inspector-can-do = 対応機能:
+inspector-yes = はい
+inspector-maybe = 不明
+inspector-no = いいえ
+inspector-custom = カスタム
+keyframes-title = キーフレーム:
+keyframes-wet = ウェット
+keyframes-parameter = パラメーター { $index }
+keyframes-add = ➕ フレーム { $frame } に追加
+keyframes-add-hint = 対象の現在の値を保存します
+keyframes-frame = フレーム
+
+# Main window
+tab-untitled = 無題
+tabs-new = 新しいタブ
+tabs-copy-chain-here = ⛓ 現在のチェーンをここにコピー
+tabs-use-chain = ⛓ このタブのチェーンを使用
+palette-hint = コマンドを入力
+palette-toggle-bypass = バイパス切り替え: { $name }
+palette-add-plugin = プラグインを追加: { $name }
+rack-add-vst = ➕ VSTエフェクトを追加
+rack-add-built-in = ➕ 内蔵エフェクトを追加
+open-image = 📂 画像を開く
+open-video = 🎞 動画を開く
+open-url = 🌐 URLを開く
+open-capture-screen = 📷 画面キャプチャ
+open-webcam = 📸 Webカメラ
+process-apply = ✅ 画像にエフェクトを適用
+process-preview = 👁 プレビュー
+process-preview-hint = 縮小した画像のコピーを処理します
+process-live = ライブ
+process-live-hint = プラグインのパラメーターが変更されたときにプレビューまたはタイルプレビューを更新します
+process-full-resolution = ⛶ フル解像度
+process-cancel = ☠ キャンセル
+process-undo = ↻ 元に戻す
+process-redo = ↺ やり直す
+process-snapshot-hint = チェーンのスナップショット、切り替えると画像が再処理されます
+process-preview-size = プレビューサイズ
+process-passes = パス数
+process-wet-decay = ウェット減衰
+process-auto-sample-rate = 自動サンプルレート
+process-auto-sample-rate-hint = 画像の各行が同じ長さになります
+process-row-length = 行の長さ
+process-interlaced = インターレース
+process-interlaced-hint = 偶数行と奇数行を別々のストリームとして処理します
+process-dither = ディザ
+process-dither-hint = 8ビットと16ビット画像の処理値の丸め方
+process-grayscale = グレースケール
+process-grayscale-hint = 画像の輝度を単一のストリームとして処理します
+process-partial-save = 部分保存
+process-partial-save-hint = 長時間の処理中に処理途中の画像を定期的に保存します
+process-every = 間隔
+animation-reset-plugins = フレームごとにプラグインをリセット
+animation-reset-plugins-hint = リセットしない場合、プラグインの状態(ディレイ、リバーブの余韻など)は次のフレームに引き継がれます
+animation-processed = 処理済みフレーム: { $processed }/{ $count }
+view-fit = ⛶ 全体表示
+view-selection = 🔍 選択範囲
+view-selection-hint = 選択したプラグインの領域にズーム
+view-metrics = 指標
+view-metrics-hint = 元画像に対する結果のPSNRとSSIM
+view-clipping = クリッピング
+view-clipping-hint = クリップされたピクセルを強調表示: 255は赤、0は青
+view-eyedropper-hint = カーソル下のピクセル値を調べます
+compare-title = 比較:
+compare-split = 分割
+compare-split-hint = 区切り線の左側に元画像を表示
+compare-blink = 点滅
+compare-blink-hint = 点滅ショートカットで元画像と結果を切り替えます
+compare-checkerboard = チェッカーボード
+compare-difference = 差分
+compare-original = 元画像
+guides-vertical = ➕ 垂直ガイド
+guides-horizontal = ➕ 水平ガイド
+guides-clear = ガイドを消去
+canvas-tile-size = タイルサイズ
+canvas-preview-tile = 🔍 このタイルをプレビュー
+canvas-hide-tile-preview = ✖ タイルプレビューを隠す
+canvas-processing = 画像を処理中です、お待ちください...
+canvas-welcome = PhotoConsequencesへようこそ!
+status-progress = メモリ: { $memory } MiB 処理済み: { $percentage }% パス: { $pass }/{ $passes } 時間: { $time } 秒
+status-slowest = 最も遅い: { $name }
+status-frame = フレーム: { $frame }/{ $count }
+
+# Dialogs
+dialog-ok = OK
+dialog-open = 開く
+dialog-cancel = キャンセル
+exit-title = プロジェクト管理
+exit-save-file = 終了する前に { $path } を保存しますか?
+exit-save-project = 終了する前にプロジェクトを保存しますか?
+exit-yes = ✅ はい
+exit-no = ❎ いいえ
+exit-cancel = 🚫 キャンセル
+about-version = バージョン: { $version }
+about-description = 画像にVST™エフェクトを適用するツール
+about-trademark = VST™はSteinberg Media Technologies GmbHの商標です。
+url-title = URLから画像を開く
+url-downloading = ダウンロード中...
+capture-title = 画面キャプチャ
+capture-whole-screen = 画面全体
+capture-position = 位置
+capture-size = サイズ
+capture-capture = キャプチャ
+video-title = 動画を開く
+video-first-frame = 最初のフレーム
+video-frame-count = フレーム数
+svg-title = SVG画像を開く
+svg-width = 幅
+export-8-bit = 8ビット
+export-16-bit = 16ビット
+pixel-title = ピクセルインスペクター
+pixel-hover = 画像にカーソルを合わせるとピクセルを調べられます
+pixel-position = ピクセル: { $x }, { $y }
+pixel-sample = { $channel } サンプル: { $value }
+pixel-tile = タイル: { $column }, { $row }
+pixel-not-affected = このピクセルはこのプラグインの影響を受けません
+dialog-save-macro = マクロを保存
+dialog-replay-macro = マクロを再生
+dialog-plugin-folder = プラグインライブラリのフォルダーを選択
+dialog-save-chain-preset = チェーンプリセットを保存
+dialog-load-chain-preset = チェーンプリセットを読み込む
+dialog-import-chain = プロジェクトからチェーンをインポート
+dialog-export-image = 画像をエクスポート
+dialog-export-profile = プロファイルでエクスポート
+dialog-export-difference = 差分をエクスポート
+dialog-export-report = 処理レポートをエクスポート
+dialog-save-export-profile = エクスポートプロファイルを保存
+dialog-load-export-profile = エクスポートプロファイルを読み込む
+dialog-render-images = 処理する画像を選択
+dialog-output-folder = 出力フォルダーを選択
+dialog-save-project = プロジェクトを保存
+dialog-open-project = プロジェクトを開く
+dialog-sequence-folder = 画像シーケンスのフォルダーを選択
+dialog-partial-image = 部分保存の画像ファイル
+dialog-checkpoint-file = チェックポイントのプロジェクトファイル
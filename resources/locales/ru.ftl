@@ -0,0 +1,491 @@
+# Главное меню
+menu-file = Файл
+menu-new = 🖹 Новый
+menu-open-project = 📂 Открыть проект
+menu-load-chain = ⛓ Загрузить цепочку
+menu-import-chain = ⛓ Импортировать цепочку из проекта
+menu-import-chain-append = Добавить к текущей цепочке
+menu-import-chain-replace = Заменить текущую цепочку
+menu-save-chain = ⛓ Сохранить цепочку
+menu-save = 💾 Сохранить
+menu-save-as = 💾 Сохранить как
+menu-export-image = 🖼 Экспорт изображения
+menu-export-profile = 🖼 Экспорт с профилем
+menu-export-difference = 🖼 Экспорт разницы
+menu-export-report = 📄 Экспорт отчёта об обработке
+menu-export-options = ⚙ Параметры экспорта
+menu-edit-externally = ✏ Редактировать во внешнем редакторе
+menu-copy-image = 📋 Копировать изображение
+menu-exit = ❎ Выход
+menu-tools = Инструменты
+menu-noise = ⧯ Сгенерировать шум
+menu-open-sequence = 🗐 Открыть последовательность
+menu-animation-from-image = 🗐 Анимация из изображения
+menu-frames = кадров
+menu-export-sequence = 💾 Экспорт последовательности
+menu-surprise = 🎲 Удиви меня
+menu-random-effects = Случайных эффектов
+menu-plugin-library = 📁 Библиотека плагинов ({ $count } шт.)
+menu-log-console = 🗒 Журнал
+menu-preferences = ⚙ Настройки
+menu-about = Справка
+menu-about-window = ℹ О программе
+menu-github = ⬌ Страница на GitHub
+watch-image = Перезагружать изображение при изменении
+watch-image-hint = Загружать изображение заново после сохранения в другом редакторе, цепочка применяется снова
+dock-editors = Встроить редакторы плагинов
+
+# Настройки
+prefs-title = Настройки
+prefs-plugin-dirs = Папки плагинов
+prefs-add-folder = ➕ Добавить папку
+prefs-blacklist = Чёрный список плагинов
+prefs-blacklist-add = ➕ Добавить плагин
+prefs-processing = Обработка
+prefs-tile-size = Размер тайла по умолчанию
+prefs-cli-threads = Потоков в CLI
+prefs-cli-threads-hint = 0 — использовать все процессоры
+prefs-autosave = Интервал частичного сохранения
+prefs-minutes = мин
+prefs-project = Файлы проекта
+prefs-project-original = Сохранять исходное изображение
+prefs-project-original-hint = Обработку можно отменить после повторного открытия проекта
+prefs-project-masks = Сохранять маски плагинов
+prefs-project-history = Сохраняемые шаги отмены
+prefs-export = Экспорт
+prefs-default-format = Формат по умолчанию
+prefs-external-editor = Внешний редактор
+prefs-not-set = не задан
+prefs-interface = Интерфейс
+prefs-language = Язык
+prefs-watch-plugins = Предлагать перезагрузку изменённых плагинов
+prefs-watch-plugins-hint = Полезно при тестировании собственных сборок плагинов
+prefs-pixel-grid = Пиксельная сетка
+prefs-rulers = Линейки
+prefs-theme = Тема
+prefs-shortcuts = Горячие клавиши
+prefs-press-key = Нажмите клавишу...
+prefs-set = Задать
+prefs-reset-shortcuts = Сбросить клавиши
+prefs-ok = ОК
+theme-dark = Тёмная
+theme-light = Светлая
+theme-custom = Своя
+theme-accent = Акцент
+theme-canvas = Фон холста
+theme-grid = Пиксельная сетка
+theme-panels = Панели
+theme-text = Текст
+theme-reset = Сбросить тему
+
+# Команды
+command-undo = Отменить
+command-redo = Повторить
+command-process = Применить эффекты
+command-stop = Прервать обработку
+command-eyedropper = Пипетка
+command-next-compare-mode = Следующий режим сравнения
+command-blink = Мигание оригинала
+command-bypass-selected = Обход выбранного плагина
+command-zoom-fit = Вписать в окно
+command-actual-pixels = Реальный размер
+command-zoom-selection = Масштаб по выделению
+command-save-project = Сохранить проект
+command-export-image = Экспорт изображения
+command-preferences = Настройки
+command-command-palette = Палитра команд
+
+# Журнал
+console-title = Журнал
+console-level = Уровень
+console-copy = 📋 Копировать
+console-clear = 🗑 Очистить
+
+# Очередь рендеринга
+menu-render-queue = Очередь рендеринга
+jobs-title = Очередь рендеринга
+jobs-add = ➕ Добавить изображения с текущей цепочкой...
+jobs-workers = Потоки
+jobs-workers-hint = Сколько заданий обрабатывается одновременно, 1 - по очереди
+jobs-start = ▶ Запустить
+jobs-clear = Убрать завершённые
+jobs-empty = Очередь пуста
+jobs-cancel = Отменить задание
+jobs-queued = В очереди
+jobs-done = Готово
+jobs-failed = Ошибка
+jobs-cancelled = Отменено
+
+# Контрольные точки
+checkpoints = Контрольные точки
+checkpoints-hint = Периодически сохраняет состояние обработки в проект, чтобы прерванную обработку можно было продолжить
+resume-title = Продолжить обработку
+resume-message = Проект содержит прерванную обработку ({ $percentage }% прохода { $pass }). Продолжить её?
+resume-continue = ▶ Продолжить
+resume-discard = Начать заново
+
+# Зерно
+menu-seed = Зерно
+menu-replay-seed = ⟲ Повторить
+menu-replay-seed-hint = Заново создаёт цепочку с этим зерном, библиотека плагинов должна быть той же
+
+# Макросы
+menu-record-macro = ⏺ Записать макрос
+menu-stop-recording = ⏹ Остановить запись и сохранить...
+menu-replay-macro = ▶ Воспроизвести макрос...
+
+# Панель плагинов
+plugin-bypass-group = Обход группы
+plugin-group-wet = Уровень эффекта группы
+plugin-not-initialized = Этот плагин не инициализирован
+plugin-configuration-preserved = Настройки эффекта сохранены
+plugin-remove = Удалить
+plugin-hint = Нажмите для просмотра, правый клик - другие действия
+plugin-category = Категория
+plugin-initial-delay = Начальная задержка
+plugin-f64-mixing = Поддержка 64-битного микширования
+plugin-built-in = Встроенный эффект
+plugin-duplicate = ⧉ Дублировать
+plugin-group-with-next = 📁 Сгруппировать со следующим
+plugin-ungroup = Разгруппировать
+plugin-copy-settings = 📋 Копировать настройки
+plugin-paste-settings = 📋 Вставить настройки
+plugin-input-channel = Обрабатываемый канал изображения:
+plugin-output-channel = Выходной аудиоканал:
+plugin-left-mono = Левый/Моно
+plugin-right = Правый
+plugin-mix-outputs = Смешать все выходы
+plugin-mix-outputs-hint = Вместо выбранного канала записывается среднее всех выходов плагина
+plugin-output-to = Выход { $output } в:
+plugin-output-to-hint = Записывает выход в другой канал после основного выхода
+plugin-off = Выкл
+plugin-program = Программа: { $name }
+plugin-parameters = Параметры:
+plugin-search-parameters = 🔍 Поиск параметров
+plugin-midi-learn-hint = Обучение MIDI: нажмите и подвигайте контроллер, правый клик удаляет привязку
+plugin-automation = Автоматизация: { $count } точек
+plugin-automation-hint = Изменения параметров в интерфейсе плагина, изменения во время обработки воспроизводятся в той же позиции
+plugin-clear-automation = 🗑 Очистить
+plugin-wet = Уровень эффекта:
+plugin-sample-rate = Частота дискретизации:
+plugin-override-rate = Заменить частоту проекта
+plugin-iterations = Итерации:
+plugin-sample-offset = Смещение сэмплов:
+plugin-sample-offset-hint = Сдвигает выход, записываемый в изображение, с переносом через края блока
+plugin-field = Поле:
+plugin-field-both = Оба
+plugin-field-even = Чётное
+plugin-field-odd = Нечётное
+plugin-tile-pattern = Шаблон тайлов:
+plugin-tiles-all = Все
+plugin-tiles-every-nth = Каждый N-й
+plugin-tiles-checkerboard = Шахматный
+plugin-tiles-even-rows = Чётные строки
+plugin-jitter = Разброс:
+plugin-jitter-hint = Случайное смещение параметров для каждого тайла
+plugin-all-parameters = Все параметры
+plugin-parameter-count = Параметров: { $count }
+plugin-reseed = 🎲 Новое зерно
+plugin-region = Область:
+plugin-limit-region = Ограничить областью
+region-top = Верх
+region-bottom = Низ
+region-left = Лево
+region-right = Право
+plugin-mask = Маска:
+plugin-bypass = Обход/отключение обработки
+plugin-solo = Соло, все остальные плагины в обходе
+plugin-open-editor = Открыть редактор плагина
+
+# Окна
+export-title = Параметры экспорта
+export-quality = Качество
+export-subsampling = Субдискретизация цвета
+export-compression = Сжатие
+export-bit-depth = Глубина цвета:
+export-source-depth = Как в исходнике
+export-indexed = Индексированный (PNG-8)
+export-palette-heading = Палитра (GIF, индексированный PNG)
+export-palette = Палитра
+export-colors = Цвета
+export-palette-dither = Дизеринг палитры
+export-lossless = Без потерь
+export-speed = Скорость
+export-tone-mapping = Тональная компрессия
+export-exposure = Экспозиция
+export-sidecar = Сохранять цепочку рядом с изображением
+export-sidecar-hint = Сохраняет [name].{ $extension } вместе с изображением
+export-profile = Профиль экспорта
+export-tokens = Подстановки
+export-max-size = Макс. размер
+export-add-output = ➕ Добавить выход
+export-load-profile = 📂 Загрузить профиль
+export-save-profile = 💾 Сохранить профиль
+navigator-title = Навигатор
+histogram-title = Гистограмма
+histogram-clipped-shadows = Обрезанные тени: { $percentage }%
+histogram-clipped-highlights = Обрезанные света: { $percentage }%
+metrics-title = Метрики качества
+metrics-identical = PSNR: ∞ (изображения совпадают)
+metrics-processing = Метрики измеряются после завершения обработки
+metrics-unavailable = Исходное и обработанное изображения нельзя сравнить
+plugins-changed-title = Плагины изменились
+plugins-changed-reload = ⟲ Перезагрузить
+plugins-changed-reload-hint = Плагин можно перезагрузить после обработки
+plugins-changed-ignore = Игнорировать
+
+# Ошибки
+error-open-editor = Не удалось открыть редактор
+error-open-dropped-file = Не удалось открыть перетащенный файл
+error-change-plugin = Не удалось изменить плагин
+error-undo = Не удалось отменить
+error-redo = Не удалось повторить
+error-save-macro = Не удалось сохранить макрос
+error-load-macro = Не удалось загрузить макрос
+error-replay-macro = Не удалось воспроизвести макрос
+error-randomize-chain = Не удалось создать случайную цепочку
+error-save-chain-preset = Не удалось сохранить пресет цепочки
+error-load-chain-preset = Не удалось загрузить пресет цепочки
+error-import-chain = Не удалось импортировать цепочку
+error-save-image = Ошибка сохранения изображения
+error-cannot-save-image = Не удалось сохранить изображение:
+error-profile-no-outputs = В профиле экспорта нет выходов
+error-export-report = Не удалось экспортировать отчёт
+error-save-export-profile = Не удалось сохранить профиль экспорта
+error-load-export-profile = Не удалось загрузить профиль экспорта
+error-copy-chain = Не удалось скопировать цепочку
+error-plugin-loading = Не удалось загрузить плагин!
+error-effect-loading = Не удалось загрузить эффект!
+error-open-url = Не удалось открыть изображение по URL
+error-capture-screen = Не удалось сделать снимок экрана
+error-open-video = Не удалось открыть видео
+error-open-svg = Не удалось открыть SVG
+error-copy-log = Не удалось скопировать журнал
+error-save-chain = Не удалось сохранить цепочку
+error-load-project = Не удалось загрузить проект
+error-process-image = Не удалось обработать изображение
+error-cancel-processing = Не удалось отменить обработку
+error-reload-plugin = Не удалось перезагрузить плагин
+error-resume-processing = Не удалось продолжить обработку
+error-open-external-editor = Не удалось открыть внешний редактор
+error-copy-image = Не удалось скопировать изображение
+error-load-sequence = Не удалось загрузить последовательность
+error-save-sequence = Не удалось сохранить последовательность
+error-open-midi = Не удалось открыть MIDI-вход
+error-copy-settings = Не удалось скопировать настройки
+error-paste-settings = Не удалось вставить настройки
+error-open-image = Не удалось открыть изображение
+error-capture-webcam = Не удалось получить снимок с веб-камеры
+error-switch-snapshot = Не удалось переключить снимок
+
+# Masks
+mask-new = Новая
+mask-source = Источник: { $name }
+mask-add-layer = ➕ Слой
+mask-remove-layer = Удалить слой
+mask-add-point = ➕ Точка
+mask-remove-point = Удалить точку
+mask-low = Нижний
+mask-high = Верхний
+mask-threshold = Порог
+mask-hue = Оттенок
+mask-tolerance = Допуск
+mask-font-family = Шрифт
+mask-baseline = Базовая линия
+mask-size = Размер
+mask-softness = Мягкость
+mask-grow = Расширение
+mask-grow-hint = Расширяет или сужает выделение
+mask-border = Граница
+mask-border-hint = Оставляет только полосу вдоль края выделения
+mask-invert = Инвертировать
+mask-brightness = Яркость
+mask-shadows = Тени
+mask-highlights = Света
+mask-edges = Края
+mask-color = Цвет
+mask-text = Текст
+mask-ellipse = Эллипс
+mask-polygon = Многоугольник
+mask-add = Добавить
+mask-subtract = Вычесть
+mask-intersect = Пересечь
+
+# Processing graph
+graph-title = Маршрутизация
+graph-use = Использовать граф обработки
+graph-use-hint = Если выключено, плагины обрабатываются простой цепочкой
+graph-parallel-chain = ➕ Параллельная цепочка
+graph-parallel-chain-hint = Обрабатывает ещё одну цепочку из того же источника и смешивает её с остальными
+graph-channel-chains = ➕ Цепочки каналов
+graph-channel-chains-hint = Обрабатывает красный, зелёный и синий отдельными цепочками за один проход
+graph-split = Разветвление
+graph-split-hint = Разделить на параллельные ветви
+graph-unroute = Убрать из графа
+graph-add-branch = Добавить ветвь
+graph-remove-branch = Удалить ветвь
+graph-flatten = Свернуть в цепочку
+graph-branch = Ветвь { $number }
+graph-gain = Усиление
+graph-channel-hint = Канал, обрабатываемый ветвью
+graph-route-plugin = ➕ Добавить плагин в граф
+blend-normal = Обычный
+blend-add = Сложение
+blend-multiply = Умножение
+blend-screen = Экран
+blend-difference = Разница
+blend-lighten = Замена светлым
+blend-darken = Замена тёмным
+channel-red = Красный
+channel-green = Зелёный
+channel-blue = Синий
+
+# Inspector
+inspector-close = Закрыть инспектор
+inspector-vendor = Производитель
+inspector-product = Продукт
+inspector-version = Версия
+inspector-unique-id = Уникальный ID
+inspector-audio-io = Аудио входы/выходы
+inspector-midi-io = MIDI входы/выходы
+inspector-parameters = Параметры
+inspector-presets = Пресеты
+inspector-latency = Задержка
+inspector-samples = { $count } сэмплов
+inspector-chunks = Блоки состояния
+inspector-f64 = 64-битная обработка
+inspector-can-do = Возможности:
+inspector-yes = Да
+inspector-maybe = Возможно
+inspector-no = Нет
+inspector-custom = Особое
+keyframes-title = Ключевые кадры:
+keyframes-wet = Wet
+keyframes-parameter = Параметр { $index }
+keyframes-add = ➕ Добавить на кадре { $frame }
+keyframes-add-hint = Сохраняет текущее значение цели
+keyframes-frame = Кадр
+
+# Main window
+tab-untitled = Без названия
+tabs-new = Новая вкладка
+tabs-copy-chain-here = ⛓ Скопировать текущую цепочку сюда
+tabs-use-chain = ⛓ Использовать цепочку этой вкладки
+palette-hint = Введите команду
+palette-toggle-bypass = Переключить обход: { $name }
+palette-add-plugin = Добавить плагин: { $name }
+rack-add-vst = ➕ Добавить VST эффект
+rack-add-built-in = ➕ Добавить встроенный эффект
+open-image = 📂 Открыть изображение
+open-video = 🎞 Открыть видео
+open-url = 🌐 Открыть URL
+open-capture-screen = 📷 Снимок экрана
+open-webcam = 📸 Веб-камера
+process-apply = ✅ Применить эффекты
+process-preview = 👁 Предпросмотр
+process-preview-hint = Обрабатывает уменьшенную копию изображения
+process-live = Сразу
+process-live-hint = Обновляет предпросмотр или предпросмотр плитки при изменении параметров плагина
+process-full-resolution = ⛶ Полное разрешение
+process-cancel = ☠ Отмена
+process-undo = ↻ Отменить
+process-redo = ↺ Повторить
+process-snapshot-hint = Снимок цепочки, при переключении изображение обрабатывается заново
+process-preview-size = Размер предпросмотра
+process-passes = Проходы
+process-wet-decay = Затухание wet
+process-auto-sample-rate = Автоматическая частота
+process-auto-sample-rate-hint = Каждая строка изображения длится одинаковое время
+process-row-length = Длина строки
+process-interlaced = Чересстрочно
+process-interlaced-hint = Обрабатывает чётные и нечётные строки как два отдельных потока
+process-dither = Дизеринг
+process-dither-hint = Округление обработанных значений 8- и 16-битных изображений
+process-grayscale = Оттенки серого
+process-grayscale-hint = Обрабатывает яркость изображения одним потоком
+process-partial-save = Частичное сохранение
+process-partial-save-hint = Периодически сохраняет частично обработанное изображение при долгой обработке
+process-every = Каждые
+animation-reset-plugins = Сбрасывать плагины каждый кадр
+animation-reset-plugins-hint = Иначе состояние плагина (задержки, хвосты реверберации и т.д.) переходит на следующий кадр
+animation-processed = Обработано кадров: { $processed }/{ $count }
+view-fit = ⛶ Вписать
+view-selection = 🔍 Выделение
+view-selection-hint = Приблизить к области выбранного плагина
+view-metrics = Метрики
+view-metrics-hint = PSNR и SSIM результата относительно оригинала
+view-clipping = Клиппинг
+view-clipping-hint = Подсвечивать обрезанные пиксели: красным для 255, синим для 0
+view-eyedropper-hint = Показывать значения пикселя под курсором
+compare-title = Сравнение:
+compare-split = Разделение
+compare-split-hint = Оригинал слева от разделителя
+compare-blink = Мигание
+compare-blink-hint = Сочетание клавиш мигания переключает оригинал и результат
+compare-checkerboard = Шахматка
+compare-difference = Разница
+compare-original = Оригинал
+guides-vertical = ➕ Вертикальная направляющая
+guides-horizontal = ➕ Горизонтальная направляющая
+guides-clear = Убрать направляющие
+canvas-tile-size = Размер плитки
+canvas-preview-tile = 🔍 Предпросмотр этой плитки
+canvas-hide-tile-preview = ✖ Скрыть предпросмотр плитки
+canvas-processing = Обработка изображения, подождите...
+canvas-welcome = Добро пожаловать в PhotoConsequences!
+status-progress = Память: { $memory } МиБ Обработано: { $percentage }% Проход: { $pass }/{ $passes } Время: { $time } с
+status-slowest = Самый медленный: { $name }
+status-frame = Кадр: { $frame }/{ $count }
+
+# Dialogs
+dialog-ok = Ок
+dialog-open = Открыть
+dialog-cancel = Отмена
+exit-title = Управление проектом
+exit-save-file = Сохранить { $path } перед выходом?
+exit-save-project = Сохранить проект перед выходом?
+exit-yes = ✅ Да
+exit-no = ❎ Нет
+exit-cancel = 🚫 Отмена
+about-version = Версия: { $version }
+about-description = Инструмент для применения VST™ эффектов к изображениям
+about-trademark = VST™ является товарным знаком Steinberg Media Technologies GmbH.
+url-title = Открыть изображение по URL
+url-downloading = Загрузка...
+capture-title = Снимок экрана
+capture-whole-screen = Весь экран
+capture-position = Положение
+capture-size = Размер
+capture-capture = Снять
+video-title = Открыть видео
+video-first-frame = Первый кадр
+video-frame-count = Количество кадров
+svg-title = Открыть SVG изображение
+svg-width = Ширина
+export-8-bit = 8 бит
+export-16-bit = 16 бит
+pixel-title = Инспектор пикселей
+pixel-hover = Наведите курсор на изображение, чтобы изучить пиксели
+pixel-position = Пиксель: { $x }, { $y }
+pixel-sample = Сэмпл ({ $channel }): { $value }
+pixel-tile = Плитка: { $column }, { $row }
+pixel-not-affected = Пиксель не затрагивается этим плагином
+dialog-save-macro = Сохранить макрос
+dialog-replay-macro = Воспроизвести макрос
+dialog-plugin-folder = Выберите папку библиотеки плагинов
+dialog-save-chain-preset = Сохранить пресет цепочки
+dialog-load-chain-preset = Загрузить пресет цепочки
+dialog-import-chain = Импорт цепочки из проекта
+dialog-export-image = Экспорт изображения
+dialog-export-profile = Экспорт по профилю
+dialog-export-difference = Экспорт разницы
+dialog-export-report = Экспорт отчёта об обработке
+dialog-save-export-profile = Сохранить профиль экспорта
+dialog-load-export-profile = Загрузить профиль экспорта
+dialog-render-images = Выберите изображения для обработки
+dialog-output-folder = Выберите папку для сохранения
+dialog-save-project = Сохранить проект
+dialog-open-project = Открыть проект
+dialog-sequence-folder = Выберите папку с последовательностью изображений
+dialog-partial-image = Файл частичного сохранения
+dialog-checkpoint-file = Файл проекта для контрольных точек
@@ -0,0 +1,327 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    AnimationDecoder, Delay, Frames, RgbaImage,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::ExportOptions;
+use crate::video;
+pub use crate::video::VideoSource;
+
+/// Frame delay of image sequences and stills (25 fps)
+const DEFAULT_FRAME_DELAY_MS: u32 = 40;
+
+fn extension<P: AsRef<Path>>(file: P) -> String {
+    file.as_ref()
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Whether file format can hold an animation
+pub fn is_animated_format<P: AsRef<Path>>(file: P) -> bool {
+    matches!(extension(&file).as_str(), "gif" | "png" | "apng" | "webp") || video::is_video(file)
+}
+
+/// Plugin property animated by keyframes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyframeTarget {
+    Wet,
+    Parameter(i32),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    #[serde(rename = "Frame")]
+    pub frame: usize,
+    #[serde(rename = "Target")]
+    pub target: KeyframeTarget,
+    #[serde(rename = "Value")]
+    pub value: f32,
+}
+
+/// Linearly interpolated value of the target at the frame, `None` if target has no keyframes
+pub fn keyframe_value(keyframes: &[Keyframe], target: KeyframeTarget, frame: usize) -> Option<f32> {
+    let mut before: Option<&Keyframe> = None;
+    let mut after: Option<&Keyframe> = None;
+
+    for keyframe in keyframes
+        .iter()
+        .filter(|keyframe| keyframe.target == target)
+    {
+        if keyframe.frame <= frame {
+            if before.map_or(true, |before| keyframe.frame >= before.frame) {
+                before = Some(keyframe);
+            }
+        } else if after.map_or(true, |after| keyframe.frame < after.frame) {
+            after = Some(keyframe);
+        }
+    }
+
+    match (before, after) {
+        (Some(before), Some(after)) => {
+            let t = (frame - before.frame) as f32 / (after.frame - before.frame) as f32;
+            Some(before.value + (after.value - before.value) * t)
+        }
+        (Some(keyframe), None) | (None, Some(keyframe)) => Some(keyframe.value),
+        (None, None) => None,
+    }
+}
+
+pub struct AnimationFrame {
+    pub image: RgbaImage,
+    pub delay: Delay,
+}
+
+/// Sequence of frames which are processed through the rack one by one
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    /// Processed frames, `None` if frame is not processed yet
+    pub processed: Vec<Option<RgbaImage>>,
+    /// Frame which is currently shown or processed
+    pub current: usize,
+    /// Set when frames are decoded from a video
+    pub video: Option<VideoSource>,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<AnimationFrame>) -> Self {
+        Self {
+            processed: vec![None; frames.len()],
+            frames,
+            current: 0,
+            video: None,
+        }
+    }
+
+    /// Loads GIF or APNG file, returns `None` if file is not animated
+    pub fn load<P: AsRef<Path>>(file: P) -> anyhow::Result<Option<Self>> {
+        match extension(&file).as_str() {
+            "gif" => Self::load_gif(file),
+            "png" | "apng" => Self::load_apng(file),
+            "webp" => Self::load_webp(file),
+            _ if video::is_video(&file) => Ok(Some(video::load(file, 0, None)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Loads all images of the directory ordered by file name
+    pub fn load_sequence<P: AsRef<Path>>(dir: P) -> anyhow::Result<Self> {
+        let mut files: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && image::ImageFormat::from_path(path).is_ok())
+            .collect();
+        files.sort();
+
+        if files.is_empty() {
+            anyhow::bail!("Directory does not contain images");
+        }
+
+        let mut frames = Vec::new();
+        for file in files {
+            let image = image::open(&file)?.to_rgba8();
+            if let Some(first) = frames.first() {
+                let first: &AnimationFrame = first;
+                if first.image.dimensions() != image.dimensions() {
+                    anyhow::bail!("{} has different size than other frames", file.display());
+                }
+            }
+
+            frames.push(AnimationFrame {
+                image,
+                delay: Delay::from_numer_denom_ms(DEFAULT_FRAME_DELAY_MS, 1),
+            });
+        }
+
+        Ok(Self::new(frames))
+    }
+
+    /// Repeats still image `count` times, so it can be animated with keyframes
+    pub fn from_still(image: &RgbaImage, count: usize) -> Self {
+        Self::new(
+            (0..count.max(1))
+                .map(|_| AnimationFrame {
+                    image: image.clone(),
+                    delay: Delay::from_numer_denom_ms(DEFAULT_FRAME_DELAY_MS, 1),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn load_gif<P: AsRef<Path>>(file: P) -> anyhow::Result<Option<Self>> {
+        let decoder = GifDecoder::new(BufReader::new(File::open(file)?))?;
+        Self::from_frames(decoder.into_frames())
+    }
+
+    pub fn load_apng<P: AsRef<Path>>(file: P) -> anyhow::Result<Option<Self>> {
+        let decoder = PngDecoder::new(BufReader::new(File::open(file)?))?;
+        if !decoder.is_apng() {
+            return Ok(None);
+        }
+        Self::from_frames(decoder.apng().into_frames())
+    }
+
+    pub fn load_webp<P: AsRef<Path>>(file: P) -> anyhow::Result<Option<Self>> {
+        let decoder = WebPDecoder::new(BufReader::new(File::open(file)?))?;
+        if !decoder.has_animation() {
+            return Ok(None);
+        }
+        Self::from_frames(decoder.into_frames())
+    }
+
+    fn from_frames(frames: Frames) -> anyhow::Result<Option<Self>> {
+        let frames = frames.collect_frames()?;
+
+        if frames.len() < 2 {
+            return Ok(None);
+        }
+
+        Ok(Some(Self::new(
+            frames
+                .into_iter()
+                .map(|frame| AnimationFrame {
+                    delay: frame.delay(),
+                    image: frame.into_buffer(),
+                })
+                .collect(),
+        )))
+    }
+
+    /// Processed frame or original one if it is not processed yet
+    pub fn frame(&self, id: usize) -> &RgbaImage {
+        self.processed[id]
+            .as_ref()
+            .unwrap_or(&self.frames[id].image)
+    }
+
+    pub fn clear_processed(&mut self) {
+        self.processed.iter_mut().for_each(|frame| *frame = None);
+    }
+
+    pub fn processed_count(&self) -> usize {
+        self.processed
+            .iter()
+            .filter(|frame| frame.is_some())
+            .count()
+    }
+
+    pub fn memory_size(&self) -> usize {
+        let original: usize = self.frames.iter().map(|frame| frame.image.len()).sum();
+        let processed: usize = self
+            .processed
+            .iter()
+            .flatten()
+            .map(|image| image.len())
+            .sum();
+        original + processed
+    }
+
+    /// Saves animation with original timings into GIF, APNG or video depending on extension,
+    /// unprocessed frames are saved as is
+    pub fn save<P: AsRef<Path>>(&self, file: P, options: &ExportOptions) -> anyhow::Result<()> {
+        match extension(&file).as_str() {
+            "gif" => self.save_gif(file, options),
+            "png" | "apng" => self.save_apng(file),
+            "webp" => self.save_webp(file, options),
+            _ if video::is_video(&file) => video::save(self, file),
+            ext => anyhow::bail!("Animation can not be saved as {}", ext),
+        }
+    }
+
+    /// Saves frames as numbered PNG files into directory
+    pub fn save_sequence<P: AsRef<Path>>(&self, dir: P) -> anyhow::Result<()> {
+        for id in 0..self.frames.len() {
+            let file = dir.as_ref().join(format!("frame_{:05}.png", id));
+            self.frame(id).save(file)?;
+        }
+        Ok(())
+    }
+
+    /// Every frame is reduced to the export palette, adaptive palettes are made per frame
+    pub fn save_gif<P: AsRef<Path>>(&self, file: P, options: &ExportOptions) -> anyhow::Result<()> {
+        let first = &self.frames[0].image;
+        let mut encoder = gif::Encoder::new(
+            BufWriter::new(File::create(file)?),
+            first.width() as u16,
+            first.height() as u16,
+            &[],
+        )?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        for (id, frame) in self.frames.iter().enumerate() {
+            let mut gif_frame = options.quantize(self.frame(id)).gif_frame();
+            // GIF delays are in 1/100 s
+            let (numer, denom) = frame.delay.numer_denom_ms();
+            gif_frame.delay = (numer as f32 / denom as f32 / 10.0).round() as u16;
+            encoder.write_frame(&gif_frame)?;
+        }
+        Ok(())
+    }
+
+    pub fn save_webp<P: AsRef<Path>>(
+        &self,
+        file: P,
+        options: &ExportOptions,
+    ) -> anyhow::Result<()> {
+        let encoding_type = if options.webp_lossless {
+            webp_animation::EncodingType::Lossless
+        } else {
+            webp_animation::EncodingType::Lossy(Default::default())
+        };
+        let encoder_options = webp_animation::EncoderOptions {
+            encoding_config: Some(webp_animation::EncodingConfig {
+                encoding_type,
+                quality: options.webp_quality as f32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let first = &self.frames[0].image;
+        let mut encoder = webp_animation::Encoder::new_with_options(
+            (first.width(), first.height()),
+            encoder_options,
+        )?;
+
+        let mut timestamp = 0;
+        for (id, frame) in self.frames.iter().enumerate() {
+            encoder.add_frame(self.frame(id), timestamp)?;
+            let (numer, denom) = frame.delay.numer_denom_ms();
+            timestamp += (numer as f32 / denom as f32).round() as i32;
+        }
+
+        let data = encoder.finalize(timestamp)?;
+        std::fs::write(file, &*data)?;
+        Ok(())
+    }
+
+    /// APNG keeps full RGBA colors unlike GIF
+    pub fn save_apng<P: AsRef<Path>>(&self, file: P) -> anyhow::Result<()> {
+        let first = &self.frames[0].image;
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(file)?),
+            first.width(),
+            first.height(),
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+
+        let mut writer = encoder.write_header()?;
+        for (id, frame) in self.frames.iter().enumerate() {
+            let (numer, denom) = frame.delay.numer_denom_ms();
+            let delay_ms = (numer as f32 / denom as f32).round() as u16;
+            writer.set_frame_delay(delay_ms, 1000)?;
+            writer.write_image_data(self.frame(id))?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
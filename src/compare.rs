@@ -0,0 +1,20 @@
+use image::RgbaImage;
+
+/// Absolute per channel difference between the images multiplied by `gain`,
+/// returns `None` if image sizes don't match
+pub fn difference(original: &RgbaImage, processed: &RgbaImage, gain: f32) -> Option<RgbaImage> {
+    if original.dimensions() != processed.dimensions() {
+        return None;
+    }
+
+    let mut result = RgbaImage::new(original.width(), original.height());
+    let pixels = original.pixels().zip(processed.pixels());
+    for (pixel, (original, processed)) in result.pixels_mut().zip(pixels) {
+        for channel in 0..3 {
+            let delta = (original[channel] as f32 - processed[channel] as f32).abs();
+            pixel[channel] = (delta * gain).min(255.0) as u8;
+        }
+        pixel[3] = 255;
+    }
+    Some(result)
+}
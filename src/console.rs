@@ -0,0 +1,97 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Messages kept for the log console, older ones are dropped
+const MAX_ENTRIES: usize = 2000;
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+static ENTRIES: Mutex<VecDeque<Entry>> = Mutex::new(VecDeque::new());
+static START: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub level: Level,
+    /// Time since logger initialization
+    pub time: Duration,
+    pub target: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{:>9.3}] {:<5} {}: {}",
+            self.time.as_secs_f64(),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// Prints log messages to stdout and keeps them for the in-app console
+struct ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // dependencies are too noisy at debug level
+        metadata.level() <= Level::Info || metadata.target().starts_with("photoconsequences")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let time = START
+            .lock()
+            .unwrap()
+            .map(|start| start.elapsed())
+            .unwrap_or_default();
+        let entry = Entry {
+            level: record.level(),
+            time,
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        println!("{}", entry);
+
+        let mut entries = ENTRIES.lock().unwrap();
+        if entries.len() == MAX_ENTRIES {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn init() {
+    *START.lock().unwrap() = Some(Instant::now());
+    if let Err(error) = log::set_logger(&LOGGER) {
+        println!("Unable to set logger: {}", error);
+        return;
+    }
+    log::set_max_level(LevelFilter::Debug);
+}
+
+/// Logged messages at `filter` level or more severe
+pub fn entries(filter: LevelFilter) -> Vec<Entry> {
+    ENTRIES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|entry| entry.level <= filter)
+        .cloned()
+        .collect()
+}
+
+pub fn clear() {
+    ENTRIES.lock().unwrap().clear();
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::plugin_rack::Sample;
+
+/// 4x4 Bayer threshold matrix
+pub(crate) const BAYER: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+/// How processed values are rounded to integer channels
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Debug, Default)]
+pub enum Dither {
+    #[default]
+    None,
+    Ordered,
+    /// Rounding error is spread to the neighbour pixels
+    FloydSteinberg,
+}
+
+impl Dither {
+    pub const ALL: [Dither; 3] = [Dither::None, Dither::Ordered, Dither::FloydSteinberg];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dither::None => "None",
+            Dither::Ordered => "Ordered",
+            Dither::FloydSteinberg => "Floyd-Steinberg",
+        }
+    }
+}
+
+/// Rounds RGBA pixels written in image order. Diffused error is kept by image row, so the
+/// quantizer is kept between blocks to continue dithering across block boundaries
+pub struct Quantizer {
+    dither: Dither,
+    width: usize,
+    /// Image row being quantized
+    row: Option<usize>,
+    current: Vec<[f32; 3]>,
+    /// Error diffused into the row below, `None` if that row is already quantized
+    next: Option<Vec<[f32; 3]>>,
+    /// Errors of rows which are not quantized yet
+    pending: HashMap<usize, Vec<[f32; 3]>>,
+    diffuse_down: bool,
+}
+
+impl Quantizer {
+    pub fn new(dither: Dither, width: usize) -> Self {
+        Self {
+            dither,
+            width,
+            row: None,
+            current: Vec::new(),
+            next: None,
+            pending: HashMap::new(),
+            diffuse_down: true,
+        }
+    }
+
+    /// Whether the quantizer continues dithering of image with the same settings
+    pub fn matches(&self, dither: Dither, width: usize) -> bool {
+        self.dither == dither && self.width == width
+    }
+
+    /// Sets if error is diffused into the row below the quantized one, which is disabled when
+    /// that row is quantized earlier (odd field of interlaced image)
+    pub fn diffuse_down(&mut self, enabled: bool) {
+        self.diffuse_down = enabled;
+    }
+
+    /// Moves to image row `y`, error diffused into the previous row below is kept for later
+    fn start_row(&mut self, y: usize) {
+        if let (Some(row), Some(next)) = (self.row, self.next.take()) {
+            self.pending.insert(row + 1, next);
+        }
+
+        self.current = self
+            .pending
+            .remove(&y)
+            .unwrap_or_else(|| vec![[0.0; 3]; self.width]);
+        self.next = self.diffuse_down.then(|| vec![[0.0; 3]; self.width]);
+        self.row = Some(y);
+    }
+
+    /// Converts unnormalized pixel at (`x`, `y`), alpha is not dithered
+    pub fn quantize<S: Sample>(&mut self, mut pixel: [f32; 4], x: usize, y: usize) -> [S; 4] {
+        if !S::INTEGER {
+            return pixel.map(S::from_f32);
+        }
+
+        match self.dither {
+            Dither::None => {}
+            Dither::Ordered => {
+                let threshold = (BAYER[y % 4][x % 4] + 0.5) / 16.0;
+                for value in &mut pixel[..3] {
+                    *value = (*value + threshold).floor();
+                }
+            }
+            Dither::FloydSteinberg => {
+                if self.row != Some(y) {
+                    self.start_row(y);
+                }
+
+                for (channel, value) in pixel[..3].iter_mut().enumerate() {
+                    let wanted = *value + self.current[x][channel];
+                    *value = wanted.round().clamp(0.0, S::MAX);
+                    let error = wanted - *value;
+
+                    if x + 1 < self.width {
+                        self.current[x + 1][channel] += error * 7.0 / 16.0;
+                    }
+                    if let Some(next) = &mut self.next {
+                        if x + 1 < self.width {
+                            next[x + 1][channel] += error / 16.0;
+                        }
+                        if x > 0 {
+                            next[x - 1][channel] += error * 3.0 / 16.0;
+                        }
+                        next[x][channel] += error * 5.0 / 16.0;
+                    }
+                }
+            }
+        }
+        pixel.map(S::from_f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = 16;
+    /// Mid grey which has no exact 8 bit value
+    const GREY: f32 = 127.5;
+
+    /// Mean of flat grey image quantized in blocks of `block` pixels by one quantizer,
+    /// rows are written in the `rows` order
+    fn dithered_mean(block: usize, rows: &[(usize, bool)]) -> f32 {
+        let mut quantizer = Quantizer::new(Dither::FloydSteinberg, SIZE);
+        let pixels: Vec<(usize, usize, bool)> = rows
+            .iter()
+            .flat_map(|&(y, down)| (0..SIZE).map(move |x| (x, y, down)))
+            .collect();
+
+        let mut sum = 0.0;
+        for chunk in pixels.chunks(block) {
+            for &(x, y, down) in chunk {
+                quantizer.diffuse_down(down);
+                let pixel: [u8; 4] = quantizer.quantize([GREY, GREY, GREY, 255.0], x, y);
+                sum += pixel[0] as f32;
+            }
+        }
+        sum / pixels.len() as f32
+    }
+
+    #[test]
+    fn grey_keeps_mean_across_blocks() {
+        let rows: Vec<(usize, bool)> = (0..SIZE).map(|y| (y, true)).collect();
+        for block in [1, 5, 7, SIZE, SIZE * SIZE] {
+            let mean = dithered_mean(block, &rows);
+            assert!((mean - GREY).abs() < 0.1, "block {}: mean {}", block, mean);
+        }
+    }
+
+    #[test]
+    fn grey_keeps_mean_in_interlaced_fields() {
+        let even = (0..SIZE).step_by(2).map(|y| (y, true));
+        let odd = (1..SIZE).step_by(2).map(|y| (y, false));
+        let rows: Vec<(usize, bool)> = even.chain(odd).collect();
+        for block in [3, SIZE] {
+            let mean = dithered_mean(block, &rows);
+            assert!((mean - GREY).abs() < 0.1, "block {}: mean {}", block, mean);
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::renderer;
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
 use vst::editor::Editor;
 use winit::{
-    dpi::LogicalSize,
+    dpi::{LogicalSize, PhysicalPosition},
     event_loop::EventLoopWindowTarget,
     window::{Window, WindowId},
 };
@@ -11,6 +11,8 @@ use winit::{
 pub struct EditorWrapper {
     pub editor: Option<Box<dyn Editor>>,
     pub window_id: Option<WindowId>,
+    /// Window position when editor was closed last time
+    pub position: Option<PhysicalPosition<i32>>,
 }
 
 impl EditorWrapper {
@@ -18,6 +20,7 @@ impl EditorWrapper {
         Self {
             editor,
             window_id: None,
+            position: None,
         }
     }
 
@@ -25,20 +28,33 @@ impl EditorWrapper {
         Self {
             editor: None,
             window_id: None,
+            position: None,
         }
     }
 
-    pub fn show(
+    pub fn is_open(&self) -> bool {
+        self.window_id.is_some()
+    }
+
+    /// Opens editor in a new window, docked windows are created without decorations
+    pub(crate) fn show(
         &mut self,
         event_loop: &EventLoopWindowTarget<renderer::Event>,
+        docked: bool,
     ) -> anyhow::Result<Window> {
-        println!("opening editor");
+        log::debug!("opening editor");
 
         if let Some(editor) = &mut self.editor {
-            let window = winit::window::WindowBuilder::new()
+            let mut builder = winit::window::WindowBuilder::new()
                 .with_resizable(false)
-                .with_inner_size(LogicalSize::new(editor.size().0, editor.size().1))
-                .build(event_loop)?;
+                .with_decorations(!docked)
+                .with_inner_size(LogicalSize::new(editor.size().0, editor.size().1));
+
+            if let Some(position) = self.position {
+                builder = builder.with_position(position);
+            }
+
+            let window = builder.build(event_loop)?;
             self.window_id = Some(window.id());
             let whandle = window.raw_window_handle();
             match whandle {
@@ -54,11 +70,21 @@ impl EditorWrapper {
         }
     }
 
-    pub fn close(&mut self, window_id: WindowId) {
-        if let Some(window) = self.window_id {
-            if window == window_id {
-                self.editor.as_mut().unwrap().close()
+    /// Lets the editor process its GUI events
+    pub fn idle(&mut self) {
+        if let Some(editor) = &mut self.editor {
+            if editor.is_open() {
+                editor.idle();
             }
         }
     }
+
+    /// Closes editor if it is opened in the window, window position is remembered for reopening
+    pub fn close(&mut self, window: &Window) {
+        if self.window_id == Some(window.id()) {
+            self.position = window.outer_position().ok();
+            self.window_id = None;
+            self.editor.as_mut().unwrap().close()
+        }
+    }
 }
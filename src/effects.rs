@@ -0,0 +1,384 @@
+use std::{ops::Range, path::Path, path::PathBuf, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::plugin_rack::BlockLocation;
+
+/// Plugin path prefix of built-in effects in chains and projects
+pub const EFFECT_PATH_PREFIX: &str = "builtin:";
+
+/// Largest channel and scanline shift in pixels (at parameter value 1.0)
+const MAX_SHIFT: f32 = 64.0;
+
+/// Effects registered by `register_effect` by their IDs
+static EFFECTS: RwLock<Vec<(String, fn() -> Box<dyn RackEffect>)>> = RwLock::new(Vec::new());
+
+/// Data passed to `RackEffect::process`
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum EffectInput {
+    /// RGBA values (0.0 - 1.0), 4 values per pixel, result is mixed with the input by wet
+    Pixels,
+    /// Input channel value of every pixel, written back like VST plugin output
+    Samples,
+}
+
+/// Effect which is placed in the rack like a VST plugin, so it is saved, bypassed
+/// and mixed the same way. Parameter values are in 0.0 - 1.0 range
+pub trait RackEffect: Send {
+    fn name(&self) -> String;
+
+    /// Identifies effect settings the same way as VST plugin unique ID
+    fn unique_id(&self) -> i32;
+
+    fn input(&self) -> EffectInput {
+        EffectInput::Pixels
+    }
+
+    fn parameter_count(&self) -> i32;
+
+    fn parameter_name(&self, index: i32) -> String;
+
+    fn get_parameter(&self, index: i32) -> f32;
+
+    fn set_parameter(&mut self, index: i32, value: f32);
+
+    /// Processes the block in place, `data` layout depends on `input`
+    fn process(&mut self, data: &mut [f32], location: &BlockLocation);
+
+    /// Clears internal state between images or animation frames
+    fn reset(&mut self) {}
+
+    /// Effect state stored in chains and projects, parameter values by default
+    fn save_state(&self) -> String {
+        let parameters: Vec<f32> = (0..self.parameter_count())
+            .map(|index| self.get_parameter(index))
+            .collect();
+        serde_json::to_string(&parameters).unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: &str) -> anyhow::Result<()> {
+        let parameters: Vec<f32> = serde_json::from_str(state)?;
+        for (index, value) in parameters.into_iter().enumerate() {
+            self.set_parameter(index as i32, value);
+        }
+        Ok(())
+    }
+}
+
+/// Makes effect available in the rack as `builtin:<id>` path, so chains with it can be loaded.
+/// Registering the same ID again replaces the constructor
+pub fn register_effect(id: &str, constructor: fn() -> Box<dyn RackEffect>) {
+    let mut effects = EFFECTS.write().unwrap();
+    effects.retain(|(effect_id, _)| effect_id != id);
+    effects.push((id.to_string(), constructor));
+}
+
+/// Rack path of the effect with specified ID
+pub fn effect_path(id: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", EFFECT_PATH_PREFIX, id))
+}
+
+/// Creates built-in or registered effect from its rack path
+pub fn create_effect(path: &Path) -> Option<Box<dyn RackEffect>> {
+    if let Some(kind) = EffectKind::from_path(path) {
+        return Some(Box::new(NativeEffect::new(kind)));
+    }
+
+    let id = path.to_str()?.strip_prefix(EFFECT_PATH_PREFIX)?;
+    EFFECTS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|(effect_id, _)| effect_id == id)
+        .map(|(_, constructor)| constructor())
+}
+
+/// Paths and names of built-in and registered effects
+pub fn available_effects() -> Vec<(PathBuf, String)> {
+    let mut effects: Vec<(PathBuf, String)> = EffectKind::ALL
+        .into_iter()
+        .map(|kind| (kind.path(), kind.name().to_string()))
+        .collect();
+    effects.extend(
+        EFFECTS
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(id, constructor)| (effect_path(id), constructor().name())),
+    );
+    effects
+}
+
+/// Image effect implemented in the application, placed in the rack next to VST plugins
+#[derive(Serialize, Deserialize, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum EffectKind {
+    PixelSort,
+    ChannelShift,
+    ScanlineOffset,
+    Bitcrush,
+    /// Swaps and inverts RGB channels
+    ChannelRemap,
+}
+
+impl EffectKind {
+    pub const ALL: [EffectKind; 5] = [
+        EffectKind::PixelSort,
+        EffectKind::ChannelShift,
+        EffectKind::ScanlineOffset,
+        EffectKind::Bitcrush,
+        EffectKind::ChannelRemap,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EffectKind::PixelSort => "Pixel sort",
+            EffectKind::ChannelShift => "Channel shift",
+            EffectKind::ScanlineOffset => "Scanline offset",
+            EffectKind::Bitcrush => "Bitcrush",
+            EffectKind::ChannelRemap => "Channel remap",
+        }
+    }
+
+    fn id(&self) -> &'static str {
+        match self {
+            EffectKind::PixelSort => "pixel-sort",
+            EffectKind::ChannelShift => "channel-shift",
+            EffectKind::ScanlineOffset => "scanline-offset",
+            EffectKind::Bitcrush => "bitcrush",
+            EffectKind::ChannelRemap => "channel-remap",
+        }
+    }
+
+    fn unique_id(&self) -> i32 {
+        i32::from_be_bytes(match self {
+            EffectKind::PixelSort => *b"VIps",
+            EffectKind::ChannelShift => *b"VIcs",
+            EffectKind::ScanlineOffset => *b"VIso",
+            EffectKind::Bitcrush => *b"VIbc",
+            EffectKind::ChannelRemap => *b"VIcr",
+        })
+    }
+
+    fn parameter_names(&self) -> &'static [&'static str] {
+        match self {
+            EffectKind::PixelSort => &["Threshold low", "Threshold high", "Reverse"],
+            EffectKind::ChannelShift => &["Red offset", "Blue offset"],
+            EffectKind::ScanlineOffset => &["Amount", "Frequency", "Randomness"],
+            EffectKind::Bitcrush => &["Bits", "Downsample"],
+            EffectKind::ChannelRemap => &[
+                "Red source",
+                "Green source",
+                "Blue source",
+                "Invert red",
+                "Invert green",
+                "Invert blue",
+            ],
+        }
+    }
+
+    fn default_parameters(&self) -> Vec<f32> {
+        match self {
+            EffectKind::PixelSort => vec![0.25, 0.8, 0.0],
+            EffectKind::ChannelShift => vec![0.6, 0.4],
+            EffectKind::ScanlineOffset => vec![0.2, 0.1, 0.5],
+            EffectKind::Bitcrush => vec![0.3, 0.1],
+            EffectKind::ChannelRemap => vec![0.0, 0.5, 1.0, 0.0, 0.0, 0.0],
+        }
+    }
+
+    /// Path stored instead of plugin file, e.g. `builtin:pixel-sort`
+    pub fn path(&self) -> PathBuf {
+        effect_path(self.id())
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let id = path.to_str()?.strip_prefix(EFFECT_PATH_PREFIX)?;
+        Self::ALL.into_iter().find(|kind| kind.id() == id)
+    }
+}
+
+/// Built-in effect with its parameters (0.0 - 1.0 like VST parameters)
+#[derive(Clone, Debug)]
+pub struct NativeEffect {
+    pub kind: EffectKind,
+    parameters: Vec<f32>,
+}
+
+impl NativeEffect {
+    pub fn new(kind: EffectKind) -> Self {
+        Self {
+            kind,
+            parameters: kind.default_parameters(),
+        }
+    }
+}
+
+impl RackEffect for NativeEffect {
+    fn name(&self) -> String {
+        self.kind.name().to_string()
+    }
+
+    fn unique_id(&self) -> i32 {
+        self.kind.unique_id()
+    }
+
+    fn parameter_count(&self) -> i32 {
+        self.parameters.len() as i32
+    }
+
+    fn parameter_name(&self, index: i32) -> String {
+        self.kind
+            .parameter_names()
+            .get(index as usize)
+            .map(|name| name.to_string())
+            .unwrap_or_default()
+    }
+
+    fn get_parameter(&self, index: i32) -> f32 {
+        self.parameters.get(index as usize).copied().unwrap_or(0.0)
+    }
+
+    fn set_parameter(&mut self, index: i32, value: f32) {
+        if let Some(parameter) = self.parameters.get_mut(index as usize) {
+            *parameter = value.clamp(0.0, 1.0);
+        }
+    }
+
+    fn process(&mut self, pixels: &mut [f32], location: &BlockLocation) {
+        let p = &self.parameters;
+        for row in rows(location, pixels.len() / 4) {
+            let y = location.pixel(row.start).1;
+            let row = &mut pixels[row.start * 4..row.end * 4];
+            match self.kind {
+                EffectKind::PixelSort => pixel_sort(row, p[0], p[1], p[2] > 0.5),
+                EffectKind::ChannelShift => {
+                    let red = ((p[0] - 0.5) * 2.0 * MAX_SHIFT * location.scale).round();
+                    let blue = ((p[1] - 0.5) * 2.0 * MAX_SHIFT * location.scale).round();
+                    shift_channel(row, 0, red as isize);
+                    shift_channel(row, 2, blue as isize);
+                }
+                EffectKind::ScanlineOffset => {
+                    // rows keep their displacement on scaled proxy images
+                    let y = y as f32 / location.scale;
+                    let wave = (y * p[1] * 0.5).sin();
+                    let noise = row_noise(y as u64) * 2.0 - 1.0;
+                    let offset = (wave + (noise - wave) * p[2]) * p[0] * MAX_SHIFT * location.scale;
+                    let pixel_count = row.len() / 4;
+                    if pixel_count > 0 {
+                        let offset = (offset.round() as isize).rem_euclid(pixel_count as isize);
+                        row.rotate_right(offset as usize * 4);
+                    }
+                }
+                EffectKind::Bitcrush => {
+                    let levels = (2u32.pow(1 + (p[0] * 7.0).round() as u32) - 1) as f32;
+                    let hold = 1 + (p[1] * 31.0 * location.scale).round() as usize;
+                    bitcrush(row, levels, hold);
+                }
+                EffectKind::ChannelRemap => {
+                    // source parameters select red, green or blue by thirds of the range
+                    let sources = [p[0], p[1], p[2]].map(|value| (value * 3.0).min(2.0) as usize);
+                    let inverts = [p[3], p[4], p[5]].map(|value| value > 0.5);
+                    remap_channels(row, sources, inverts);
+                }
+            }
+        }
+    }
+}
+
+/// Pixel ranges of the block which belong to the same image row
+fn rows(location: &BlockLocation, length: usize) -> Vec<Range<usize>> {
+    let width = location.width.max(1);
+    let mut rows = Vec::new();
+    let mut start = 0;
+    while start < length {
+        let x = (location.offset + start) % width;
+        let end = (start + width - x).min(length);
+        rows.push(start..end);
+        start = end;
+    }
+    rows
+}
+
+fn brightness(pixel: &[f32]) -> f32 {
+    0.2126 * pixel[0] + 0.7152 * pixel[1] + 0.0722 * pixel[2]
+}
+
+/// Sorts runs of pixels with brightness between thresholds
+fn pixel_sort(row: &mut [f32], low: f32, high: f32, reverse: bool) {
+    let pixel_count = row.len() / 4;
+    let mut start = 0;
+    while start < pixel_count {
+        let in_range = |index: usize| {
+            let value = brightness(&row[index * 4..index * 4 + 4]);
+            value >= low && value <= high
+        };
+        if !in_range(start) {
+            start += 1;
+            continue;
+        }
+
+        let mut end = start + 1;
+        while end < pixel_count && in_range(end) {
+            end += 1;
+        }
+
+        let mut run: Vec<[f32; 4]> = row[start * 4..end * 4]
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect();
+        run.sort_by(|a, b| brightness(a).total_cmp(&brightness(b)));
+        if reverse {
+            run.reverse();
+        }
+        row[start * 4..end * 4].copy_from_slice(&run.concat());
+        start = end;
+    }
+}
+
+/// Moves one channel by `offset` pixels, edge pixels are repeated
+fn shift_channel(row: &mut [f32], channel: usize, offset: isize) {
+    let pixel_count = row.len() / 4;
+    if offset == 0 || pixel_count == 0 {
+        return;
+    }
+
+    let source: Vec<f32> = row.iter().skip(channel).step_by(4).copied().collect();
+    for index in 0..pixel_count {
+        let from = (index as isize - offset).clamp(0, pixel_count as isize - 1);
+        row[index * 4 + channel] = source[from as usize];
+    }
+}
+
+/// Reduces color levels and repeats every `hold`-th pixel
+fn bitcrush(row: &mut [f32], levels: f32, hold: usize) {
+    let mut held = [0.0; 4];
+    for (index, pixel) in row.chunks_exact_mut(4).enumerate() {
+        if index % hold == 0 {
+            held.copy_from_slice(pixel);
+            for value in &mut held[..3] {
+                *value = (*value * levels).round() / levels;
+            }
+        }
+        pixel.copy_from_slice(&held);
+    }
+}
+
+/// Takes every RGB channel from its source channel, optionally inverted
+fn remap_channels(row: &mut [f32], sources: [usize; 3], inverts: [bool; 3]) {
+    for pixel in row.chunks_exact_mut(4) {
+        let source = [pixel[0], pixel[1], pixel[2]];
+        for (channel, (from, invert)) in sources.into_iter().zip(inverts).enumerate() {
+            let value = source[from];
+            pixel[channel] = if invert { 1.0 - value } else { value };
+        }
+    }
+}
+
+/// Stable pseudo-random value (0.0 - 1.0) of the row
+fn row_noise(y: u64) -> f32 {
+    let mut hash = y.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash ^= hash >> 32;
+    (hash % 10_000) as f32 / 10_000.0
+}
@@ -6,7 +6,7 @@
 
 use std::collections::HashMap;
 
-use copypasta::{ClipboardContext, ClipboardProvider};
+use arboard::Clipboard;
 use egui::{
     emath::{pos2, vec2},
     Context, Key, Pos2,
@@ -37,16 +37,16 @@ fn handle_links(output: &egui::PlatformOutput) {
         // This does not handle open_url.new_tab
         // webbrowser does not support web anyway
         if let Err(err) = webbrowser::open(&open_url.url) {
-            eprintln!("Failed to open url: {}", err);
+            log::error!("Failed to open url: {}", err);
         }
     }
 }
 
-fn handle_clipboard(output: &egui::PlatformOutput, clipboard: Option<&mut ClipboardContext>) {
+fn handle_clipboard(output: &egui::PlatformOutput, clipboard: Option<&mut Clipboard>) {
     if !output.copied_text.is_empty() {
         if let Some(clipboard) = clipboard {
-            if let Err(err) = clipboard.set_contents(output.copied_text.clone()) {
-                eprintln!("Copy/Cut error: {}", err);
+            if let Err(err) = clipboard.set_text(output.copied_text.clone()) {
+                log::error!("Copy/Cut error: {}", err);
             }
         }
     }
@@ -59,7 +59,7 @@ pub struct Platform {
     raw_input: egui::RawInput,
     modifier_state: ModifiersState,
     pointer_pos: Option<egui::Pos2>,
-    clipboard: Option<ClipboardContext>,
+    clipboard: Option<Clipboard>,
 
     // For emulating pointer events from touch events we merge multi-touch
     // pointers, and ref-count the press state.
@@ -96,7 +96,7 @@ impl Platform {
             raw_input,
             modifier_state: winit::event::ModifiersState::empty(),
             pointer_pos: Some(Pos2::default()),
-            clipboard: ClipboardContext::new().ok(),
+            clipboard: Clipboard::new().ok(),
             touch_pointer_pressed: 0,
             device_indices: HashMap::new(),
             next_device_index: 1,
@@ -220,7 +220,7 @@ impl Platform {
                             {
                                 Some(count) => count,
                                 None => {
-                                    eprintln!("Pointer emulation error: Unbalanced touch start/stop events from Winit");
+                                    log::error!("Pointer emulation error: Unbalanced touch start/stop events from Winit");
                                     0
                                 }
                             };
@@ -307,7 +307,7 @@ impl Platform {
                             }
                             (true, true, VirtualKeyCode::V) => {
                                 if let Some(ref mut clipboard) = self.clipboard {
-                                    if let Ok(contents) = clipboard.get_contents() {
+                                    if let Ok(contents) = clipboard.get_text() {
                                         self.raw_input.events.push(egui::Event::Text(contents))
                                     }
                                 }
@@ -341,31 +341,6 @@ impl Platform {
         }
     }
 
-    /// Returns `true` if egui should handle the event exclusively. Check this to
-    /// avoid unexpected interactions, e.g. a mouse click registering "behind" the UI.
-    pub fn captures_event<T>(&self, winit_event: &Event<T>) -> bool {
-        match winit_event {
-            Event::WindowEvent {
-                window_id: _window_id,
-                event,
-            } => match event {
-                ReceivedCharacter(_) | KeyboardInput { .. } | ModifiersChanged(_) => {
-                    self.context().wants_keyboard_input()
-                }
-
-                MouseWheel { .. } | MouseInput { .. } => self.context().wants_pointer_input(),
-
-                CursorMoved { .. } => self.context().is_using_pointer(),
-
-                Touch { .. } => self.context().is_using_pointer(),
-
-                _ => false,
-            },
-
-            _ => false,
-        }
-    }
-
     /// Updates the internal time for egui used for animations. `elapsed_seconds` should be the seconds since some point in time (for example application start).
     pub fn update_time(&mut self, elapsed_seconds: f64) {
         self.raw_input.time = Some(elapsed_seconds);
@@ -406,12 +381,6 @@ impl Platform {
     pub fn context(&self) -> Context {
         self.context.clone()
     }
-
-    /// Returns a mutable reference to the raw input that will be passed to egui
-    /// the next time [`Self::begin_frame`] is called
-    pub fn raw_input_mut(&mut self) -> &mut egui::RawInput {
-        &mut self.raw_input
-    }
 }
 
 /// Translates winit to egui keycodes.
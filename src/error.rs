@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::plugin_library::LoadFailure;
+
+/// Errors returned by the plugin rack
+#[derive(Error, Debug)]
+pub enum VstImageError {
+    #[error("Unable to load plugin {}: {failure}\n{}", .path.display(), .failure.remedy())]
+    PluginLoad { path: PathBuf, failure: LoadFailure },
+    #[error("Unable to decode or encode image: {0}")]
+    ImageDecode(#[from] image::ImageError),
+    #[error("Plugin rack is empty")]
+    EmptyRack,
+    #[error("No image is loaded")]
+    NoImage,
+    #[error("Image is not animated")]
+    NotAnimated,
+    #[error("Unable to read or write file: {0}")]
+    ProjectIo(#[from] std::io::Error),
+    #[error("Invalid project data: {0}")]
+    ProjectFormat(#[from] serde_json::Error),
+    #[error("Invalid project archive: {0}")]
+    ProjectArchive(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub type Result<T> = std::result::Result<T, VstImageError>;
@@ -0,0 +1,472 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use image::{
+    codecs::{
+        avif::AvifEncoder,
+        png::{CompressionType, FilterType, PngEncoder},
+        webp::{WebPEncoder, WebPQuality},
+    },
+    imageops, ColorType, DynamicImage, ImageEncoder, ImageFormat, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::dither::Dither;
+use crate::indexed::{IndexedImage, IndexedPalette};
+use crate::plugin_rack::DeepImage;
+
+/// File extension of export profiles
+pub const PROFILE_EXTENSION: &str = "vstimage-export";
+
+/// Conversion of HDR values into displayable 0.0 - 1.0 range
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ToneMapping {
+    Clamp,
+    Reinhard,
+    /// Filmic curve approximation by Krzysztof Narkowicz
+    Aces,
+    /// Display gamma (2.2) curve, brightens shadows and midtones, highlights are clipped
+    Gamma,
+}
+
+impl ToneMapping {
+    pub const ALL: [ToneMapping; 4] = [
+        ToneMapping::Clamp,
+        ToneMapping::Reinhard,
+        ToneMapping::Aces,
+        ToneMapping::Gamma,
+    ];
+
+    pub fn map(&self, value: f32, exposure: f32) -> f32 {
+        let value = value.max(0.0) * exposure;
+        let mapped = match self {
+            ToneMapping::Clamp => value,
+            ToneMapping::Reinhard => value / (1.0 + value),
+            ToneMapping::Aces => {
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+            ToneMapping::Gamma => value.powf(1.0 / 2.2),
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ChromaSubsampling {
+    Yuv444,
+    Yuv422,
+    Yuv420,
+}
+
+impl ChromaSubsampling {
+    pub const ALL: [ChromaSubsampling; 3] = [
+        ChromaSubsampling::Yuv444,
+        ChromaSubsampling::Yuv422,
+        ChromaSubsampling::Yuv420,
+    ];
+
+    fn sampling_factor(&self) -> jpeg_encoder::SamplingFactor {
+        match self {
+            ChromaSubsampling::Yuv444 => jpeg_encoder::SamplingFactor::R_4_4_4,
+            ChromaSubsampling::Yuv422 => jpeg_encoder::SamplingFactor::R_4_2_2,
+            ChromaSubsampling::Yuv420 => jpeg_encoder::SamplingFactor::R_4_2_0,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum PngBitDepth {
+    /// 16 bit for high precision images, 8 bit otherwise
+    Source,
+    Eight,
+    Sixteen,
+}
+
+/// Format specific settings used on image export
+#[derive(Clone, Copy, Debug)]
+pub struct ExportOptions {
+    /// JPEG quality (1 - 100)
+    pub jpeg_quality: u8,
+    pub jpeg_subsampling: ChromaSubsampling,
+    pub png_compression: CompressionType,
+    pub png_bit_depth: PngBitDepth,
+    /// Saves PNG with up to 256 palette colors
+    pub png_indexed: bool,
+    /// Colors of GIF and indexed PNG
+    pub palette: IndexedPalette,
+    /// Color count of adaptive and grayscale palettes (2 - 256)
+    pub palette_colors: u16,
+    pub palette_dither: Dither,
+    pub webp_lossless: bool,
+    /// WebP quality (0 - 100)
+    pub webp_quality: u8,
+    /// AVIF quality (0 - 100)
+    pub avif_quality: u8,
+    /// AVIF encoder speed (1 - slowest, 10 - fastest)
+    pub avif_speed: u8,
+    /// Used for HDR images when saved into low dynamic range formats
+    pub tone_mapping: ToneMapping,
+    pub exposure: f32,
+    /// Writes the chain next to exported images, so the recipe travels with the image
+    pub sidecar_chain: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            jpeg_quality: 90,
+            jpeg_subsampling: ChromaSubsampling::Yuv420,
+            png_compression: CompressionType::Default,
+            png_bit_depth: PngBitDepth::Source,
+            png_indexed: false,
+            palette: IndexedPalette::Adaptive,
+            palette_colors: 256,
+            palette_dither: Dither::None,
+            webp_lossless: false,
+            webp_quality: 90,
+            avif_quality: 80,
+            avif_speed: 6,
+            tone_mapping: ToneMapping::Reinhard,
+            exposure: 1.0,
+            sidecar_chain: false,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Maps HDR pixel to 8 bit, alpha is clamped
+    pub fn tone_map(&self, pixel: &[f32]) -> [u8; 4] {
+        let map = |value| (self.tone_mapping.map(value, self.exposure) * 255.0) as u8;
+        [
+            map(pixel[0]),
+            map(pixel[1]),
+            map(pixel[2]),
+            (pixel[3].clamp(0.0, 1.0) * 255.0) as u8,
+        ]
+    }
+
+    pub fn tone_map_image(&self, image: &image::Rgba32FImage) -> RgbaImage {
+        let mut result = RgbaImage::new(image.width(), image.height());
+        for (pixel, hdr) in result.pixels_mut().zip(image.pixels()) {
+            pixel.0 = self.tone_map(&hdr.0);
+        }
+        result
+    }
+
+    /// Converts image to the palette colors for GIF and indexed PNG
+    pub fn quantize(&self, image: &RgbaImage) -> IndexedImage {
+        IndexedImage::quantize(
+            image,
+            self.palette,
+            self.palette_colors as usize,
+            self.palette_dither,
+        )
+    }
+
+    pub fn webp_quality(&self) -> WebPQuality {
+        if self.webp_lossless {
+            WebPQuality::lossless()
+        } else {
+            WebPQuality::lossy(self.webp_quality)
+        }
+    }
+
+    /// Applies `--option value` command line arguments
+    pub fn parse_args(&mut self, args: &[String]) -> anyhow::Result<()> {
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--webp-lossless" {
+                self.webp_lossless = true;
+                continue;
+            }
+            if arg == "--png-indexed" {
+                self.png_indexed = true;
+                continue;
+            }
+            if arg == "--sidecar-chain" {
+                self.sidecar_chain = true;
+                continue;
+            }
+
+            let value = match args.next() {
+                Some(value) => value.as_str(),
+                None => bail!("Missing value for {}", arg),
+            };
+
+            match arg.as_str() {
+                "--jpeg-quality" => self.jpeg_quality = value.parse::<u8>()?.clamp(1, 100),
+                "--jpeg-subsampling" => {
+                    self.jpeg_subsampling = match value {
+                        "444" => ChromaSubsampling::Yuv444,
+                        "422" => ChromaSubsampling::Yuv422,
+                        "420" => ChromaSubsampling::Yuv420,
+                        _ => bail!("Unknown chroma subsampling {}, use 444, 422 or 420", value),
+                    }
+                }
+                "--png-compression" => {
+                    self.png_compression = match value {
+                        "fast" => CompressionType::Fast,
+                        "default" => CompressionType::Default,
+                        "best" => CompressionType::Best,
+                        _ => bail!(
+                            "Unknown PNG compression {}, use fast, default or best",
+                            value
+                        ),
+                    }
+                }
+                "--png-bit-depth" => {
+                    self.png_bit_depth = match value {
+                        "8" => PngBitDepth::Eight,
+                        "16" => PngBitDepth::Sixteen,
+                        _ => bail!("Unsupported PNG bit depth {}, use 8 or 16", value),
+                    }
+                }
+                "--palette" => {
+                    self.palette = match IndexedPalette::ALL
+                        .into_iter()
+                        .find(|palette| palette.id() == value)
+                    {
+                        Some(palette) => palette,
+                        None => bail!(
+                            "Unknown palette {}, use {}",
+                            value,
+                            IndexedPalette::ALL.map(|palette| palette.id()).join(", ")
+                        ),
+                    }
+                }
+                "--palette-colors" => self.palette_colors = value.parse::<u16>()?.clamp(2, 256),
+                "--palette-dither" => {
+                    self.palette_dither = match value {
+                        "none" => Dither::None,
+                        "ordered" => Dither::Ordered,
+                        "floyd-steinberg" => Dither::FloydSteinberg,
+                        _ => bail!(
+                            "Unknown dithering {}, use none, ordered or floyd-steinberg",
+                            value
+                        ),
+                    }
+                }
+                "--tone-mapping" => {
+                    self.tone_mapping = match value {
+                        "clamp" => ToneMapping::Clamp,
+                        "reinhard" => ToneMapping::Reinhard,
+                        "aces" => ToneMapping::Aces,
+                        "gamma" => ToneMapping::Gamma,
+                        _ => bail!(
+                            "Unknown tone mapping {}, use clamp, reinhard, aces or gamma",
+                            value
+                        ),
+                    }
+                }
+                "--exposure" => self.exposure = value.parse::<f32>()?.clamp(0.01, 16.0),
+                "--webp-quality" => self.webp_quality = value.parse::<u8>()?.min(100),
+                "--avif-quality" => self.avif_quality = value.parse::<u8>()?.min(100),
+                _ => bail!("Unknown option {}", arg),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 16 bit images are saved without precision loss into PNG and TIFF,
+/// HDR images into OpenEXR and Radiance HDR, otherwise they are tone mapped.
+/// GIF and indexed PNG are reduced to the palette colors
+pub fn encode_image<P: AsRef<Path>>(
+    file: P,
+    image: &RgbaImage,
+    deep: Option<&DeepImage>,
+    options: &ExportOptions,
+) -> anyhow::Result<()> {
+    let format = ImageFormat::from_path(&file)?;
+
+    match (format, deep) {
+        // Radiance HDR has no alpha channel
+        (ImageFormat::Hdr, Some(DeepImage::Rgba32F(hdr))) => {
+            DynamicImage::ImageRgba32F(hdr.clone())
+                .to_rgb32f()
+                .save(file)?;
+        }
+        (ImageFormat::OpenExr, Some(DeepImage::Rgba32F(hdr))) => hdr.save(file)?,
+        (ImageFormat::Tiff, Some(DeepImage::Rgba16(deep))) => deep.save(file)?,
+        (ImageFormat::Png, _) if options.png_indexed => {
+            options
+                .quantize(image)
+                .save_png(file, options.png_compression)?;
+        }
+        (ImageFormat::Gif, _) => options.quantize(image).save_gif(file)?,
+        (ImageFormat::Png, deep) => {
+            let sixteen = match options.png_bit_depth {
+                PngBitDepth::Source => matches!(deep, Some(DeepImage::Rgba16(_))),
+                PngBitDepth::Eight => false,
+                PngBitDepth::Sixteen => true,
+            };
+            let encoder = PngEncoder::new_with_quality(
+                BufWriter::new(File::create(file)?),
+                options.png_compression,
+                FilterType::Adaptive,
+            );
+
+            if sixteen {
+                let deep = match deep {
+                    Some(DeepImage::Rgba16(deep)) => deep.clone(),
+                    _ => DynamicImage::ImageRgba8(image.clone()).to_rgba16(),
+                };
+                let bytes: Vec<u8> = deep.iter().flat_map(|value| value.to_ne_bytes()).collect();
+                encoder.write_image(&bytes, deep.width(), deep.height(), ColorType::Rgba16)?;
+            } else {
+                encoder.write_image(image, image.width(), image.height(), ColorType::Rgba8)?;
+            }
+        }
+        (ImageFormat::Jpeg, _) => {
+            let mut encoder = jpeg_encoder::Encoder::new_file(file, options.jpeg_quality)?;
+            encoder.set_sampling_factor(options.jpeg_subsampling.sampling_factor());
+            encoder.encode(
+                image,
+                image.width() as u16,
+                image.height() as u16,
+                jpeg_encoder::ColorType::Rgba,
+            )?;
+        }
+        (ImageFormat::WebP, _) => {
+            WebPEncoder::new_with_quality(File::create(file)?, options.webp_quality()).encode(
+                image,
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+            )?;
+        }
+        (ImageFormat::Avif, _) => {
+            AvifEncoder::new_with_speed_quality(
+                File::create(file)?,
+                options.avif_speed,
+                options.avif_quality,
+            )
+            .write_image(image, image.width(), image.height(), ColorType::Rgba8)?;
+        }
+        _ => image.save(file)?,
+    }
+
+    Ok(())
+}
+
+/// Single output file of export profile
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExportTarget {
+    /// File name, supports `{name}`, `{date}`, `{chain}`, `{width}` and `{height}` tokens
+    pub template: String,
+    /// Longest side of the saved image, smaller images are not upscaled
+    pub max_size: Option<u32>,
+}
+
+/// Set of files written in one export action
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExportProfile {
+    pub targets: Vec<ExportTarget>,
+}
+
+impl Default for ExportProfile {
+    fn default() -> Self {
+        Self {
+            targets: vec![
+                ExportTarget {
+                    template: String::from("{name}.png"),
+                    max_size: None,
+                },
+                ExportTarget {
+                    template: String::from("{name}_2048.jpg"),
+                    max_size: Some(2048),
+                },
+                ExportTarget {
+                    template: String::from("{name}_thumb.jpg"),
+                    max_size: Some(256),
+                },
+            ],
+        }
+    }
+}
+
+impl ExportProfile {
+    pub fn load<P: AsRef<Path>>(file: P) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(file)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, file: P) -> anyhow::Result<()> {
+        std::fs::write(file, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Replaces template tokens, characters which are not allowed in file names are removed from values
+pub fn expand_template(template: &str, name: &str, chain: &str, width: u32, height: u32) -> String {
+    template
+        .replace("{name}", &sanitize_file_name(name))
+        .replace("{date}", &current_date())
+        .replace("{chain}", &sanitize_file_name(chain))
+        .replace("{width}", &width.to_string())
+        .replace("{height}", &height.to_string())
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_+. ".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Current UTC date in YYYY-MM-DD format
+fn current_date() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs() / 86400)
+        .unwrap_or_default() as i64;
+
+    // days to civil date conversion by Howard Hinnant
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Downscales image (and its high precision copy) so the longest side fits into `max_size`
+pub fn fit_image(
+    image: &RgbaImage,
+    deep: Option<&DeepImage>,
+    max_size: u32,
+) -> Option<(RgbaImage, Option<DeepImage>)> {
+    let longest = image.width().max(image.height());
+    if longest <= max_size || max_size == 0 {
+        return None;
+    }
+
+    let scale = max_size as f64 / longest as f64;
+    let width = ((image.width() as f64 * scale).round() as u32).max(1);
+    let height = ((image.height() as f64 * scale).round() as u32).max(1);
+    let filter = imageops::FilterType::Lanczos3;
+
+    let deep = deep.map(|deep| match deep {
+        DeepImage::Rgba16(deep) => DeepImage::Rgba16(imageops::resize(deep, width, height, filter)),
+        DeepImage::Rgba32F(deep) => {
+            DeepImage::Rgba32F(imageops::resize(deep, width, height, filter))
+        }
+    });
+    Some((imageops::resize(image, width, height, filter), deep))
+}
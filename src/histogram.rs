@@ -0,0 +1,70 @@
+use image::RgbaImage;
+
+/// Largest number of pixels used for histogram, bigger images are sampled with a step
+const MAX_SAMPLES: usize = 1024 * 1024;
+
+pub const CHANNEL_NAMES: [&str; 4] = ["Red", "Green", "Blue", "Luma"];
+
+pub struct Histogram {
+    /// Value counts of red, green, blue and luma
+    pub channels: [[u32; 256]; 4],
+    /// Share of pixels with any channel at 0
+    pub clipped_shadows: f32,
+    /// Share of pixels with any channel at 255
+    pub clipped_highlights: f32,
+}
+
+impl Histogram {
+    pub fn new(image: &RgbaImage) -> Self {
+        let mut channels = [[0; 256]; 4];
+        let (mut shadows, mut highlights, mut count) = (0, 0, 0);
+        let step = (image.pixels().len() / MAX_SAMPLES).max(1);
+
+        for pixel in image.pixels().step_by(step) {
+            let [r, g, b, _] = pixel.0;
+            let luma = 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32;
+            channels[0][r as usize] += 1;
+            channels[1][g as usize] += 1;
+            channels[2][b as usize] += 1;
+            channels[3][luma.round() as usize] += 1;
+
+            if r == 0 || g == 0 || b == 0 {
+                shadows += 1;
+            }
+            if r == 255 || g == 255 || b == 255 {
+                highlights += 1;
+            }
+            count += 1;
+        }
+
+        let count = count.max(1) as f32;
+        Self {
+            channels,
+            clipped_shadows: shadows as f32 / count,
+            clipped_highlights: highlights as f32 / count,
+        }
+    }
+
+    /// Largest value count, clipped values are skipped so they don't flatten the rest
+    pub fn peak(&self) -> u32 {
+        self.channels
+            .iter()
+            .flat_map(|channel| channel[1..255].iter())
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+}
+
+/// Paints pixels with clipped highlights red and clipped shadows blue
+pub fn highlight_clipping(image: &mut RgbaImage) {
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        if r == 255 || g == 255 || b == 255 {
+            pixel.0 = [255, 0, 0, a];
+        } else if r == 0 || g == 0 || b == 0 {
+            pixel.0 = [0, 0, 255, a];
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::{collections::HashMap, path::Path, sync::RwLock};
+
+use egui::{FontData, FontDefinitions, FontFamily};
+use serde::{Deserialize, Serialize};
+
+const ENGLISH: &str = include_str!("../resources/locales/en.ftl");
+const RUSSIAN: &str = include_str!("../resources/locales/ru.ftl");
+const JAPANESE: &str = include_str!("../resources/locales/ja.ftl");
+
+/// System fonts with japanese glyphs, default egui fonts don't have them
+const CJK_FONTS: [&str; 8] = [
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/takao-gothic/TakaoPGothic.ttf",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\meiryo.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+];
+
+/// Messages of the current language with english fallback
+static CATALOG: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Russian,
+    Japanese,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::Russian, Language::Japanese];
+
+    /// Name of the language in itself
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Russian => "Русский",
+            Language::Japanese => "日本語",
+        }
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Language::English => ENGLISH,
+            Language::Russian => RUSSIAN,
+            Language::Japanese => JAPANESE,
+        }
+    }
+}
+
+/// Parses messages of fluent file. Only a subset of the format is supported:
+/// `key = value` messages, indented lines continue the value of the message above them
+/// (joined by line breaks, so the value may also start on the next line), `#` comments and
+/// blank lines. Placeables other than `{ $name }` variables, attributes, terms and selectors
+/// are not supported, messages without value are skipped
+fn parse(source: &str) -> Vec<(String, String)> {
+    let mut messages: Vec<(String, String)> = Vec::new();
+    let mut continued = false;
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continued = false;
+            continue;
+        }
+
+        if line.starts_with(char::is_whitespace) {
+            if let Some((_, value)) = messages.last_mut().filter(|_| continued) {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(trimmed);
+            }
+            continue;
+        }
+
+        continued = match line.split_once('=') {
+            Some((key, value)) => {
+                messages.push((key.trim().to_string(), value.trim().to_string()));
+                true
+            }
+            None => false,
+        };
+    }
+    messages.retain(|(key, value)| !key.is_empty() && !value.is_empty());
+    messages
+}
+
+pub fn set_language(language: Language) {
+    let mut messages: HashMap<String, String> = parse(ENGLISH).into_iter().collect();
+    messages.extend(parse(language.source()));
+    *CATALOG.write().unwrap() = Some(messages);
+}
+
+/// Translated message, message id is returned if it is missing in every catalog
+pub fn tr(id: &str) -> String {
+    if CATALOG.read().unwrap().is_none() {
+        set_language(Language::English);
+    }
+
+    CATALOG
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|messages| messages.get(id).cloned())
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Translated message with `{ $name }` placeables replaced by arguments
+pub fn tr_args(id: &str, args: &[(&str, &str)]) -> String {
+    let mut message = tr(id);
+    for (name, value) in args {
+        message = message.replace(&format!("{{ ${} }}", name), value);
+    }
+    message
+}
+
+/// Default fonts with system CJK font added as fallback for japanese
+pub fn font_definitions(language: Language) -> FontDefinitions {
+    let mut fonts = FontDefinitions::default();
+    if language != Language::Japanese {
+        return fonts;
+    }
+
+    let data = CJK_FONTS
+        .iter()
+        .map(Path::new)
+        .find_map(|path| std::fs::read(path).ok());
+    let data = match data {
+        Some(data) => data,
+        None => {
+            log::warn!("No japanese font found, text may be displayed incorrectly");
+            return fonts;
+        }
+    };
+
+    fonts
+        .font_data
+        .insert("cjk".to_string(), FontData::from_owned(data));
+    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+        if let Some(names) = fonts.families.get_mut(&family) {
+            names.push("cjk".to_string());
+        }
+    }
+    fonts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_line_messages() {
+        let messages = parse("# comment\nfirst = First\nsecond=Second = 2\n\nempty =\n");
+        assert_eq!(
+            messages,
+            vec![
+                ("first".to_string(), "First".to_string()),
+                ("second".to_string(), "Second = 2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_multiline_messages() {
+        let source = "multi = First line\n    second line\nblock =\n    Only line\nnext = Next\n";
+        let messages = parse(source);
+        assert_eq!(
+            messages,
+            vec![
+                ("multi".to_string(), "First line\nsecond line".to_string()),
+                ("block".to_string(), "Only line".to_string()),
+                ("next".to_string(), "Next".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_end_multiline_messages() {
+        let messages = parse("message = Text\n# comment\n    indented\n");
+        assert_eq!(messages, vec![("message".to_string(), "Text".to_string())]);
+    }
+
+    #[test]
+    fn catalogs_have_every_english_message() {
+        let english: Vec<String> = parse(ENGLISH).into_iter().map(|(key, _)| key).collect();
+        for language in Language::ALL {
+            let keys: Vec<String> = parse(language.source())
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect();
+            for key in &english {
+                assert!(keys.contains(key), "{:?} has no {}", language, key);
+            }
+        }
+    }
+}
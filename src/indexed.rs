@@ -0,0 +1,325 @@
+use std::{collections::HashMap, fs::File, io::BufWriter, path::Path};
+
+use image::{codecs::png::CompressionType, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::dither::{Dither, BAYER};
+
+/// Pixels with lower alpha are saved as transparent
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// Adaptive palette is built from at most this many pixels of large images
+const MAX_PALETTE_SAMPLES: usize = 1 << 20;
+
+const GAMEBOY: [u32; 4] = [0x0f380f, 0x306230, 0x8bac0f, 0x9bbc0f];
+
+const CGA: [u32; 16] = [
+    0x000000, 0x0000aa, 0x00aa00, 0x00aaaa, 0xaa0000, 0xaa00aa, 0xaa5500, 0xaaaaaa, 0x555555,
+    0x5555ff, 0x55ff55, 0x55ffff, 0xff5555, 0xff55ff, 0xffff55, 0xffffff,
+];
+
+const PICO8: [u32; 16] = [
+    0x000000, 0x1d2b53, 0x7e2553, 0x008751, 0xab5236, 0x5f574f, 0xc2c3c7, 0xfff1e8, 0xff004d,
+    0xffa300, 0xffec27, 0x00e436, 0x29adff, 0x83769c, 0xff77a8, 0xffccaa,
+];
+
+/// Colors of GIF and 8 bit PNG images
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum IndexedPalette {
+    /// Most representative colors of the image (median cut)
+    Adaptive,
+    Grayscale,
+    GameBoy,
+    Cga,
+    Pico8,
+    /// 216 colors of 6x6x6 RGB cube
+    WebSafe,
+}
+
+impl IndexedPalette {
+    pub const ALL: [IndexedPalette; 6] = [
+        IndexedPalette::Adaptive,
+        IndexedPalette::Grayscale,
+        IndexedPalette::GameBoy,
+        IndexedPalette::Cga,
+        IndexedPalette::Pico8,
+        IndexedPalette::WebSafe,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IndexedPalette::Adaptive => "Adaptive",
+            IndexedPalette::Grayscale => "Grayscale",
+            IndexedPalette::GameBoy => "Game Boy",
+            IndexedPalette::Cga => "CGA",
+            IndexedPalette::Pico8 => "PICO-8",
+            IndexedPalette::WebSafe => "Web safe",
+        }
+    }
+
+    /// Palette name used in command line options
+    pub fn id(&self) -> &'static str {
+        match self {
+            IndexedPalette::Adaptive => "adaptive",
+            IndexedPalette::Grayscale => "grayscale",
+            IndexedPalette::GameBoy => "gameboy",
+            IndexedPalette::Cga => "cga",
+            IndexedPalette::Pico8 => "pico8",
+            IndexedPalette::WebSafe => "websafe",
+        }
+    }
+
+    /// Whether the color count setting is used
+    pub fn has_color_count(&self) -> bool {
+        matches!(self, IndexedPalette::Adaptive | IndexedPalette::Grayscale)
+    }
+
+    /// Palette colors, `count` is used by adaptive and grayscale palettes
+    pub fn colors(&self, image: &RgbaImage, count: usize) -> Vec<[u8; 3]> {
+        let count = count.clamp(2, 256);
+        let hex = |colors: &[u32]| -> Vec<[u8; 3]> {
+            colors
+                .iter()
+                .map(|color| {
+                    let [_, r, g, b] = color.to_be_bytes();
+                    [r, g, b]
+                })
+                .collect()
+        };
+
+        match self {
+            IndexedPalette::Adaptive => median_cut(image, count),
+            IndexedPalette::Grayscale => (0..count)
+                .map(|level| {
+                    let value = (level * 255 / (count - 1)) as u8;
+                    [value; 3]
+                })
+                .collect(),
+            IndexedPalette::GameBoy => hex(&GAMEBOY),
+            IndexedPalette::Cga => hex(&CGA),
+            IndexedPalette::Pico8 => hex(&PICO8),
+            IndexedPalette::WebSafe => (0..216)
+                .map(|index| [index / 36, index / 6 % 6, index % 6].map(|value| value as u8 * 51))
+                .collect(),
+        }
+    }
+}
+
+/// Image with up to 256 colors
+pub struct IndexedImage {
+    pub width: u32,
+    pub height: u32,
+    pub palette: Vec<[u8; 3]>,
+    /// Index of transparent color, placed after palette colors
+    pub transparent: Option<u8>,
+    pub indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// Maps image colors to the palette, one palette entry is reserved if image has transparency
+    pub fn quantize(
+        image: &RgbaImage,
+        palette: IndexedPalette,
+        count: usize,
+        dither: Dither,
+    ) -> Self {
+        let has_alpha = image.pixels().any(|pixel| pixel[3] < ALPHA_THRESHOLD);
+        let mut colors = palette.colors(image, if has_alpha { count.min(255) } else { count });
+        colors.truncate(if has_alpha { 255 } else { 256 });
+        let transparent = has_alpha.then_some(colors.len() as u8);
+
+        let width = image.width() as usize;
+        // threshold amplitude of ordered dithering, about a distance between palette colors
+        let spread = 255.0 / (colors.len() as f32).cbrt();
+        let mut cache = HashMap::new();
+        let mut current = vec![[0.0; 3]; width];
+        let mut next = vec![[0.0; 3]; width];
+        let mut indices = Vec::with_capacity(image.len() / 4);
+
+        for (index, pixel) in image.pixels().enumerate() {
+            let (x, y) = (index % width, index / width);
+            if x == 0 && y > 0 {
+                std::mem::swap(&mut current, &mut next);
+                next.iter_mut().for_each(|error| *error = [0.0; 3]);
+            }
+
+            if pixel[3] < ALPHA_THRESHOLD {
+                indices.push(transparent.unwrap_or(0));
+                continue;
+            }
+
+            let mut wanted = [pixel[0], pixel[1], pixel[2]].map(f32::from);
+            let nearest = match dither {
+                Dither::None => *cache
+                    .entry([pixel[0], pixel[1], pixel[2]])
+                    .or_insert_with(|| nearest_color(&colors, wanted)),
+                Dither::Ordered => {
+                    let threshold = (BAYER[y % 4][x % 4] + 0.5) / 16.0 - 0.5;
+                    wanted
+                        .iter_mut()
+                        .for_each(|value| *value += threshold * spread);
+                    nearest_color(&colors, wanted)
+                }
+                Dither::FloydSteinberg => {
+                    for (channel, value) in wanted.iter_mut().enumerate() {
+                        *value += current[x][channel];
+                    }
+                    let nearest = nearest_color(&colors, wanted);
+
+                    for (channel, value) in wanted.iter().enumerate() {
+                        let error = value - f32::from(colors[nearest as usize][channel]);
+                        if x + 1 < width {
+                            current[x + 1][channel] += error * 7.0 / 16.0;
+                            next[x + 1][channel] += error / 16.0;
+                        }
+                        if x > 0 {
+                            next[x - 1][channel] += error * 3.0 / 16.0;
+                        }
+                        next[x][channel] += error * 5.0 / 16.0;
+                    }
+                    nearest
+                }
+            };
+            indices.push(nearest);
+        }
+
+        Self {
+            width: image.width(),
+            height: image.height(),
+            palette: colors,
+            transparent,
+            indices,
+        }
+    }
+
+    pub fn save_png<P: AsRef<Path>>(
+        &self,
+        file: P,
+        compression: CompressionType,
+    ) -> anyhow::Result<()> {
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(File::create(file)?), self.width, self.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(match compression {
+            CompressionType::Fast => png::Compression::Fast,
+            CompressionType::Best => png::Compression::Best,
+            _ => png::Compression::Default,
+        });
+
+        let mut palette = self.palette.concat();
+        if let Some(transparent) = self.transparent {
+            palette.extend([0; 3]);
+            let mut alpha = vec![255; transparent as usize];
+            alpha.push(0);
+            encoder.set_trns(alpha);
+        }
+        encoder.set_palette(palette);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.indices)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// GIF frame with its own (local) palette
+    pub fn gif_frame(&self) -> gif::Frame<'static> {
+        let mut palette = self.palette.concat();
+        if self.transparent.is_some() {
+            palette.extend([0; 3]);
+        }
+        gif::Frame::from_palette_pixels(
+            self.width as u16,
+            self.height as u16,
+            &self.indices,
+            &palette,
+            self.transparent,
+        )
+    }
+
+    pub fn save_gif<P: AsRef<Path>>(&self, file: P) -> anyhow::Result<()> {
+        let mut encoder = gif::Encoder::new(
+            BufWriter::new(File::create(file)?),
+            self.width as u16,
+            self.height as u16,
+            &[],
+        )?;
+        encoder.write_frame(&self.gif_frame())?;
+        Ok(())
+    }
+}
+
+/// Index of the palette color closest to RGB value
+fn nearest_color(palette: &[[u8; 3]], color: [f32; 3]) -> u8 {
+    let distance = |entry: &[u8; 3]| -> f32 {
+        entry
+            .iter()
+            .zip(color)
+            .map(|(entry, value)| (f32::from(*entry) - value).powi(2))
+            .sum()
+    };
+
+    palette
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        .map_or(0, |(index, _)| index as u8)
+}
+
+/// Splits color space into `count` boxes of equal pixel count along their widest channel,
+/// box average colors make the palette
+fn median_cut(image: &RgbaImage, count: usize) -> Vec<[u8; 3]> {
+    let step = image.len() / 4 / MAX_PALETTE_SAMPLES + 1;
+    let colors: Vec<[u8; 3]> = image
+        .pixels()
+        .step_by(step)
+        .filter(|pixel| pixel[3] >= ALPHA_THRESHOLD)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+
+    let widest_channel = |colors: &[[u8; 3]]| {
+        (0..3)
+            .map(|channel| {
+                let values = colors.iter().map(|color| color[channel]);
+                let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+                (channel, range)
+            })
+            .max_by_key(|(_, range)| *range)
+            .unwrap_or((0, 0))
+    };
+
+    let mut boxes = vec![colors];
+    while boxes.len() < count {
+        let split = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, colors)| (index, widest_channel(colors)))
+            .max_by_key(|(_, (_, range))| *range);
+
+        let (index, channel) = match split {
+            Some((index, (channel, range))) if range > 0 => (index, channel),
+            // every box has a single color
+            _ => break,
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|color| color[channel]);
+        let upper = colors.split_off(colors.len() / 2);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes
+        .iter()
+        .filter(|colors| !colors.is_empty())
+        .map(|colors| {
+            let mut sum = [0u64; 3];
+            for color in colors {
+                for (sum, value) in sum.iter_mut().zip(color) {
+                    *sum += u64::from(*value);
+                }
+            }
+            sum.map(|value| (value / colors.len() as u64) as u8)
+        })
+        .collect()
+}
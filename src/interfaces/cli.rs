@@ -2,20 +2,335 @@ use std::{
     fs::{self, DirEntry},
     path::PathBuf,
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
 
-use crate::state_headless::StateHeadless;
+use crate::export::{ExportOptions, ExportProfile};
+use crate::macros::Macro;
+use crate::metrics::Metrics;
+use crate::plugin_library;
+use crate::plugin_rack::Autosave;
+use crate::recipe::Recipe;
+use crate::remote::{self, JobError, RemoteWorker};
+use crate::report::ProcessingReport;
+use crate::settings::Settings;
+use crate::state_headless::{self, StateHeadless};
+use crate::stream;
+
+/// Resolution of the processing progress bar
+const PROGRESS_STEPS: u64 = 1000;
+
+/// Entry of the `--metrics` report
+#[derive(Serialize)]
+struct ImageReport {
+    file: PathBuf,
+    #[serde(flatten)]
+    metrics: Metrics,
+}
+
+/// `apply [recipe] [input image] -o [output image] [--report report path] [export options]`
+fn apply_recipe(args: &[String]) -> anyhow::Result<()> {
+    let mut options = args.to_vec();
+    let output = match options.iter().position(|arg| arg == "-o") {
+        Some(id) if id + 1 < options.len() => {
+            let file = options.remove(id + 1);
+            options.remove(id);
+            PathBuf::from(file)
+        }
+        _ => anyhow::bail!("Missing output path, use -o [output image]"),
+    };
+    let report = match options.iter().position(|arg| arg == "--report") {
+        Some(id) if id + 1 < options.len() => {
+            let file = options.remove(id + 1);
+            options.remove(id);
+            Some(PathBuf::from(file))
+        }
+        Some(_) => anyhow::bail!("Missing value for --report"),
+        None => None,
+    };
+    if options.len() < 2 {
+        anyhow::bail!(
+            "Usage: photoconsequences apply [recipe path] [input image] -o [output image] \
+             [--report report path]"
+        );
+    }
+    let recipe = Recipe::load(&options[0])?;
+    let input = PathBuf::from(&options[1]);
+
+    let settings = Settings::load();
+    plugin_library::set_blacklist(settings.plugin_blacklist);
+
+    let mut state = StateHeadless::new();
+    state.rack.block_size = 16384;
+    state.rack.export.parse_args(&options[2..])?;
+    recipe.apply(&mut state.rack)?;
+    state.load_image(&input)?;
+    state.rack.start_process()?;
+
+    let pb = ProgressBar::new(PROGRESS_STEPS);
+    pb.set_style(ProgressStyle::with_template(
+        "[{elapsed_precise}] {bar:40.cyan/blue} {msg}",
+    )?);
+    while !state.rack.is_finished() {
+        state.rack.process_next();
+        let progress = state.rack.progress();
+        pb.set_position((progress.fraction * PROGRESS_STEPS as f32) as u64);
+        if let Some(plugin) = progress.plugin {
+            pb.set_message(plugin);
+        }
+    }
+    pb.finish_and_clear();
+
+    state.rack.export_image(&output)?;
+    println!("Saved: {}", output.display());
+    if let Some(report) = report {
+        ProcessingReport::new(&state.rack)?.save(&report)?;
+        println!("Report: {}", report.display());
+    }
+    Ok(())
+}
+
+/// `macro [macro path] [input image] -o [output image] [export options]`
+fn replay_macro(args: &[String]) -> anyhow::Result<()> {
+    let mut options = args.to_vec();
+    let output = match options.iter().position(|arg| arg == "-o") {
+        Some(id) if id + 1 < options.len() => {
+            let file = options.remove(id + 1);
+            options.remove(id);
+            PathBuf::from(file)
+        }
+        _ => anyhow::bail!("Missing output path, use -o [output image]"),
+    };
+    if options.len() < 2 {
+        anyhow::bail!(
+            "Usage: photoconsequences macro [macro path] [input image] -o [output image]"
+        );
+    }
+    let recorded = Macro::load(&options[0])?;
+
+    let settings = Settings::load();
+    plugin_library::set_blacklist(settings.plugin_blacklist);
+
+    let mut state = StateHeadless::new();
+    state.rack.block_size = 16384;
+    state.rack.export.parse_args(&options[2..])?;
+    state.load_image(&options[1])?;
+    recorded.run(&mut state.rack)?;
+
+    state.rack.export_image(&output)?;
+    println!("Saved: {}", output.display());
+    Ok(())
+}
+
+/// Removes `name value` pair from the arguments
+fn take_option(options: &mut Vec<String>, name: &str) -> anyhow::Result<Option<String>> {
+    match options.iter().position(|arg| arg == name) {
+        Some(id) if id + 1 < options.len() => {
+            let value = options.remove(id + 1);
+            options.remove(id);
+            Ok(Some(value))
+        }
+        Some(_) => anyhow::bail!("Missing value for {}", name),
+        None => Ok(None),
+    }
+}
+
+/// `worker [port] --token [token] [--bind address]`
+fn worker(args: &[String]) -> anyhow::Result<()> {
+    let mut options = args.to_vec();
+    let token = take_option(&mut options, "--token")?
+        .ok_or_else(|| anyhow::anyhow!("Missing worker token, use --token [token]"))?;
+    let address = take_option(&mut options, "--bind")?
+        .unwrap_or_else(|| String::from(remote::DEFAULT_BIND_ADDRESS));
+    let port = match options.first() {
+        Some(port) => port.parse()?,
+        None => remote::DEFAULT_PORT,
+    };
+    remote::serve(&address, port, &token)
+}
+
+/// `farm [project] [input folder] [output folder] --workers [host:port,...] --token [token]
+/// [export options]`
+fn farm(args: &[String]) -> anyhow::Result<()> {
+    let mut options = args.to_vec();
+    let token = Arc::new(
+        take_option(&mut options, "--token")?
+            .ok_or_else(|| anyhow::anyhow!("Missing worker token, use --token [token]"))?,
+    );
+    let addresses: Vec<String> = match options.iter().position(|arg| arg == "--workers") {
+        Some(id) if id + 1 < options.len() => {
+            let list = options.remove(id + 1);
+            options.remove(id);
+            list.split(',')
+                .map(|address| address.trim().to_string())
+                .collect()
+        }
+        _ => anyhow::bail!("Missing worker list, use --workers [host:port,...]"),
+    };
+    if options.len() < 3 {
+        anyhow::bail!("Usage: photoconsequences farm [project path] [input image folder] [output path] --workers [host:port,...] --token [token]");
+    }
+    let chain = Arc::new(state_headless::read_chain(&options[0])?);
+    let output_dir = PathBuf::from(&options[2]);
+    let export = Arc::new(options[3..].to_vec());
+    // validated before connecting to workers
+    ExportOptions::default().parse_args(&export)?;
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&options[1])?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    // workers take images from the end
+    paths.reverse();
+    let queue = Arc::new(Mutex::new(paths));
+
+    let m = MultiProgress::new();
+    let sty = ProgressStyle::with_template("[{elapsed_precise}] {bar:40.cyan/blue} {msg:>50}")
+        .unwrap()
+        .progress_chars("##-");
+
+    let mut threads = Vec::new();
+    for address in addresses {
+        let chain = Arc::clone(&chain);
+        let export = Arc::clone(&export);
+        let queue = Arc::clone(&queue);
+        let token = Arc::clone(&token);
+        let output_dir = output_dir.clone();
+        let pb = m.add(ProgressBar::new(100));
+        pb.set_style(sty.clone());
+        let m_clone = m.clone();
+
+        threads.push(std::thread::spawn(move || {
+            let mut worker = match RemoteWorker::connect(&address, &token) {
+                Ok(worker) => worker,
+                Err(op) => {
+                    println!("Unable to connect to worker {}: {}", address, op);
+                    return;
+                }
+            };
+
+            loop {
+                let image = match queue.lock().unwrap().pop() {
+                    Some(image) => image,
+                    None => break,
+                };
+                let output = output_dir.join(image.file_name().unwrap_or_default());
+                pb.set_message(format!("{}: {}", worker.address, image.display()));
+                pb.set_position(0);
+
+                let result = worker.render(&chain, &image, &output, &export, |position, total| {
+                    pb.set_length(total.max(1) as u64);
+                    pb.set_position(position as u64);
+                });
+                match result {
+                    Ok(()) => m_clone
+                        .println(format!("Saved: {}", output.display()))
+                        .unwrap(),
+                    // lost connection, image is left for other workers
+                    Err(JobError::Connection(op)) => {
+                        println!(
+                            "Worker {} failed on {}: {}",
+                            worker.address,
+                            image.display(),
+                            op
+                        );
+                        queue.lock().unwrap().push(image);
+                        break;
+                    }
+                    Err(op) => {
+                        println!("Unable to process {}: {}", image.display(), op);
+                    }
+                }
+            }
+            pb.finish_and_clear();
+        }));
+    }
+
+    for thread in threads {
+        thread.join().ok();
+    }
+
+    let left = queue.lock().unwrap().len();
+    if left > 0 {
+        anyhow::bail!("{} images were not processed, no workers left", left);
+    }
+    println!("Processing is done!");
+    Ok(())
+}
 
 pub fn cli(args: Vec<String>) -> anyhow::Result<()> {
+    if args[1] == "apply" {
+        return apply_recipe(&args[2..]);
+    }
+    if args[1] == "worker" {
+        return worker(&args[2..]);
+    }
+    if args[1] == "macro" {
+        return replay_macro(&args[2..]);
+    }
+    if args[1] == "farm" {
+        return farm(&args[2..]);
+    }
+
     if args.len() > 3 {
         let project_path = Arc::new(PathBuf::from_str(&args[1])?);
         let image_path = PathBuf::from_str(&args[2])?;
         let image_export_path = Arc::new(PathBuf::from_str(&args[3])?);
+        let mut options = args[4..].to_vec();
+        let profile = match options.iter().position(|arg| arg == "--profile") {
+            Some(id) if id + 1 < options.len() => {
+                let file = options.remove(id + 1);
+                options.remove(id);
+                Some(ExportProfile::load(file)?)
+            }
+            Some(_) => anyhow::bail!("Missing value for --profile"),
+            None => None,
+        };
+        let profile = Arc::new(profile);
+        let partial_save = match options.iter().position(|arg| arg == "--partial-save") {
+            Some(id) if id + 1 < options.len() => {
+                let minutes: u64 = options.remove(id + 1).parse()?;
+                options.remove(id);
+                Some(Duration::from_secs(minutes.max(1) * 60))
+            }
+            Some(_) => anyhow::bail!("Missing value for --partial-save"),
+            None => None,
+        };
+        let streaming = match options.iter().position(|arg| arg == "--stream") {
+            Some(id) => {
+                options.remove(id);
+                true
+            }
+            None => false,
+        };
+        let metrics_path = match options.iter().position(|arg| arg == "--metrics") {
+            Some(id) if id + 1 < options.len() => {
+                let file = options.remove(id + 1);
+                options.remove(id);
+                Some(PathBuf::from(file))
+            }
+            Some(_) => anyhow::bail!("Missing value for --metrics"),
+            None => None,
+        };
+        if streaming && metrics_path.is_some() {
+            anyhow::bail!("--metrics can't be used with --stream");
+        }
+        let measure = metrics_path.is_some();
+        let mut export_options = ExportOptions::default();
+        export_options.parse_args(&options)?;
 
-        let cpus = num_cpus::get();
+        let settings = Settings::load();
+        plugin_library::set_blacklist(settings.plugin_blacklist);
+        let cpus = match settings.threads {
+            0 => num_cpus::get(),
+            threads => threads,
+        };
         let mut paths =
             fs::read_dir(image_path)?.collect::<Vec<Result<DirEntry, std::io::Error>>>();
 
@@ -39,6 +354,7 @@ pub fn cli(args: Vec<String>) -> anyhow::Result<()> {
             let paths_ar = Arc::clone(&paths_arc);
             let project_path = Arc::clone(&project_path);
             let image_export_path = Arc::clone(&image_export_path);
+            let profile = Arc::clone(&profile);
             let pb = m.add(ProgressBar::new(100));
             pb.set_style(sty.clone());
 
@@ -50,18 +366,41 @@ pub fn cli(args: Vec<String>) -> anyhow::Result<()> {
                 let my_chunk = chunks[i];
 
                 let mut state = StateHeadless::new();
+                let mut reports = Vec::new();
 
                 state.load_project(project_path.as_path()).unwrap();
                 state.rack.block_size = 16384;
+                state.rack.export = export_options;
 
                 for (idx, image_path) in my_chunk.iter().enumerate() {
                     let img_path = image_path.as_ref().unwrap();
                     let export_path = image_export_path.join(img_path.file_name());
                     //println!("Processing: {}", img_path.path().display());
+                    if streaming {
+                        pb.set_message(format!("{}", img_path.path().display()));
+                        pb.set_length(my_chunk.len() as u64);
+                        pb.set_position(idx as u64);
+
+                        match stream::process_png(&mut state.rack, img_path.path(), &export_path) {
+                            Ok(()) => m_clone
+                                .println(format!("Saved: {}", export_path.display()))
+                                .unwrap(),
+                            Err(op) => println!("Unable to process image: {}", op),
+                        }
+                        continue;
+                    }
+
                     state
                         .load_image(img_path.path())
                         .unwrap_or_else(|op| println!("Unable to load image: {}", op));
-                    state.rack.start_process();
+                    // partially processed image is written into the output file
+                    if let Some(interval) = partial_save {
+                        state.rack.autosave = Some(Autosave::new(export_path.clone(), interval));
+                    }
+                    if let Err(op) = state.rack.start_process() {
+                        println!("Unable to process image: {}", op);
+                        continue;
+                    }
 
                     pb.set_message(format!("{}", img_path.path().display()));
                     pb.set_length(my_chunk.len() as u64);
@@ -71,25 +410,67 @@ pub fn cli(args: Vec<String>) -> anyhow::Result<()> {
                         state.rack.process_next();
                     }
 
+                    if measure {
+                        match state.rack.measure_quality() {
+                            Ok(Some(metrics)) => reports.push(ImageReport {
+                                file: img_path.path(),
+                                metrics,
+                            }),
+                            Ok(None) => println!("Unable to measure image: sizes differ"),
+                            Err(op) => println!("Unable to measure image: {}", op),
+                        }
+                    }
+
+                    if let Some(profile) = profile.as_ref() {
+                        match state
+                            .rack
+                            .save_profile(image_export_path.as_path(), profile)
+                        {
+                            Ok(files) => {
+                                for file in files {
+                                    m_clone
+                                        .println(format!("Saved: {}", file.display()))
+                                        .unwrap();
+                                }
+                            }
+                            Err(op) => println!("Unable to save image: {}", op),
+                        }
+                        continue;
+                    }
+
                     state
                         .rack
-                        .save_image(export_path.as_path())
+                        .export_image(export_path.as_path())
                         .unwrap_or_else(|op| println!("Unable to save image: {}", op));
                     m_clone
                         .println(format!("Saved: {}", export_path.display()))
                         .unwrap();
                 }
+                reports
             }))
         }
 
+        let mut reports = Vec::new();
         for thread in threads {
-            let _ = thread.join();
+            if let Ok(thread_reports) = thread.join() {
+                reports.extend(thread_reports);
+            }
+        }
+
+        if let Some(path) = metrics_path {
+            fs::write(&path, serde_json::to_string_pretty(&reports)?)?;
+            println!("Metrics saved: {}", path.display());
         }
 
         println!("Processing is done!");
     } else {
         eprintln!("Not enough arguments. Exiting");
-        println!("Usage: photoconsequences [project .viproj path] [input image folder pathj] [output path]");
+        println!("Usage: photoconsequences [project .viproj path] [input image folder pathj] [output path] [export options]");
+        println!("       photoconsequences apply [recipe .yml/.json path] [input image] -o [output image] [--report report .json/.csv path] [export options]");
+        println!("       photoconsequences macro [macro path] [input image] -o [output image] [export options]");
+        println!("       photoconsequences worker [port] --token [token] [--bind address]");
+        println!("       photoconsequences farm [project .viproj path] [input image folder path] [output path] --workers [host:port,...] --token [token] [export options]");
+        println!("Export options: --jpeg-quality 1-100, --jpeg-subsampling 444|422|420, --png-compression fast|default|best, --png-bit-depth 8|16, --png-indexed, --sidecar-chain, --palette adaptive|grayscale|gameboy|cga|pico8|websafe, --palette-colors 2-256, --palette-dither none|ordered|floyd-steinberg, --webp-quality 0-100, --webp-lossless, --avif-quality 0-100, --tone-mapping clamp|reinhard|aces|gamma, --exposure 0.01-16, --profile [export profile path], --partial-save [minutes], --stream, --metrics [report .json path]");
     }
     Ok(())
 }
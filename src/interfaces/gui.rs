@@ -11,6 +11,7 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Instant;
 use winit::event::Event::*;
+use winit::event::{ElementState, ModifiersState, VirtualKeyCode};
 use winit::window::Icon;
 
 use image::io::Reader as ImageReader;
@@ -30,7 +31,7 @@ pub fn gui(args: Vec<String>) {
     if let Ok(icn) = Icon::from_rgba(img.to_rgba8().to_vec(), img.width(), img.height()) {
         icon = Some(icn);
     } else {
-        println!("Unable set icon!");
+        log::warn!("Unable set icon!");
     }
 
     let window = winit::window::WindowBuilder::new()
@@ -80,6 +81,7 @@ pub fn gui(args: Vec<String>) {
     // We use the egui_wgpu_backend crate as the render backend
 
     let start_time = Instant::now();
+    let mut modifiers = ModifiersState::empty();
     event_loop.run(move |event, event_loop, _control_flow| {
         // Pass the winit events to the platform integration.
         platform.handle_event(&event, window.id());
@@ -97,7 +99,7 @@ pub fn gui(args: Vec<String>) {
                         return;
                     }
                     Err(e) => {
-                        eprintln!("Dropped frame with error: {}", e);
+                        log::error!("Dropped frame with error: {}", e);
                         return;
                     }
                 };
@@ -108,7 +110,7 @@ pub fn gui(args: Vec<String>) {
                 // Begin to draw the UI frame.
                 platform.begin_frame();
                 state.draw_ui(&platform.context(), &mut renderer, event_loop);
-                state.update(&mut renderer);
+                state.update(&mut renderer, &window);
 
                 // End the UI frame. We could now handle the output and draw the UI with the backend.
                 let full_output = platform.end_frame(Some(&window));
@@ -178,12 +180,31 @@ pub fn gui(args: Vec<String>) {
                             .configure(&renderer.device, &renderer.surface_config);
                     }
                 }
-                winit::event::WindowEvent::CloseRequested => {
-                    println!("id: {:?}", window_id);
-                    for _ in &renderer.windows {
-                        state.close_editor(window_id);
+                winit::event::WindowEvent::ModifiersChanged(state) => {
+                    modifiers = state;
+                }
+                winit::event::WindowEvent::KeyboardInput { input, .. } => {
+                    let paste = input.state == ElementState::Pressed
+                        && input.virtual_keycode == Some(VirtualKeyCode::V)
+                        && modifiers.ctrl();
+
+                    if paste
+                        && window_id == window.id()
+                        && !platform.context().wants_keyboard_input()
+                    {
+                        state.paste_image(&mut renderer).unwrap_or_else(|error| {
+                            messagebox("Unable to paste image", &error.to_string());
+                        });
+                    }
+                }
+                winit::event::WindowEvent::DroppedFile(path) => {
+                    if window_id == window.id() {
+                        state.open_dropped_file(&mut renderer, path);
                     }
-                    renderer.windows.retain(|w| window_id != w.id());
+                }
+                winit::event::WindowEvent::CloseRequested => {
+                    log::debug!("id: {:?}", window_id);
+                    state.close_editor(&mut renderer, window_id);
                     if window_id == window.id() {
                         state.exit(&mut renderer);
                     }
@@ -0,0 +1,182 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
+
+use crate::export::ExportOptions;
+use crate::state_headless::StateHeadless;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+/// Render of one image with one chain
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub id: usize,
+    pub image: PathBuf,
+    pub output: PathBuf,
+    /// Chain name shown in the queue
+    pub chain_name: String,
+    /// Chain serialized by `PluginRack::save_chain`
+    chain: String,
+    export: ExportOptions,
+    pub status: JobStatus,
+    /// Processing progress (0.0 - 1.0)
+    pub progress: f32,
+}
+
+impl Job {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self.status, JobStatus::Queued | JobStatus::Running)
+    }
+}
+
+/// Renders queued jobs in background workers, every worker has its own plugin instances
+#[derive(Default)]
+pub struct JobQueue {
+    jobs: Arc<Mutex<Vec<Job>>>,
+    workers: Vec<JoinHandle<()>>,
+    next_id: usize,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enqueue(
+        &mut self,
+        image: PathBuf,
+        output: PathBuf,
+        chain_name: String,
+        chain: String,
+        export: ExportOptions,
+    ) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.lock().unwrap().push(Job {
+            id,
+            image,
+            output,
+            chain_name,
+            chain,
+            export,
+            status: JobStatus::Queued,
+            progress: 0.0,
+        });
+        id
+    }
+
+    /// Snapshot of the queue in processing order
+    pub fn jobs(&self) -> Vec<Job> {
+        self.jobs.lock().unwrap().clone()
+    }
+
+    /// Moves job by `offset` positions, negative values move it closer to the queue start
+    pub fn move_job(&mut self, id: usize, offset: isize) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(position) = jobs.iter().position(|job| job.id == id) {
+            let target = (position as isize + offset).clamp(0, jobs.len() as isize - 1);
+            let job = jobs.remove(position);
+            jobs.insert(target as usize, job);
+        }
+    }
+
+    /// Running job is stopped after the current block, its output is not saved
+    pub fn cancel(&mut self, id: usize) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.iter_mut().find(|job| job.id == id) {
+            if !job.is_finished() {
+                job.status = JobStatus::Cancelled;
+            }
+        }
+    }
+
+    /// Removes finished, failed and cancelled jobs
+    pub fn clear_finished(&mut self) {
+        self.jobs.lock().unwrap().retain(|job| !job.is_finished());
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.workers.iter().any(|worker| !worker.is_finished())
+    }
+
+    /// Starts `workers` threads which take queued jobs until the queue is empty
+    pub fn start(&mut self, workers: usize) {
+        self.workers.retain(|worker| !worker.is_finished());
+        for _ in self.workers.len()..workers.max(1) {
+            let jobs = Arc::clone(&self.jobs);
+            self.workers.push(std::thread::spawn(move || worker(jobs)));
+        }
+    }
+}
+
+fn worker(jobs: Arc<Mutex<Vec<Job>>>) {
+    loop {
+        let job = {
+            let mut jobs = jobs.lock().unwrap();
+            match jobs.iter_mut().find(|job| job.status == JobStatus::Queued) {
+                Some(job) => {
+                    job.status = JobStatus::Running;
+                    job.clone()
+                }
+                None => return,
+            }
+        };
+
+        log::info!("Rendering job {}: {}", job.id, job.image.display());
+        let status = match render(&job, &jobs) {
+            Ok(true) => {
+                log::info!("Saved: {}", job.output.display());
+                JobStatus::Done
+            }
+            Ok(false) => JobStatus::Cancelled,
+            Err(error) => {
+                log::error!("Job {} failed: {}", job.id, error);
+                JobStatus::Failed(error.to_string())
+            }
+        };
+
+        if let Some(entry) = jobs
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|entry| entry.id == job.id)
+        {
+            if entry.status == JobStatus::Running {
+                entry.progress = 1.0;
+                entry.status = status;
+            }
+        }
+    }
+}
+
+/// Processes a job, returns `false` if it was cancelled
+fn render(job: &Job, jobs: &Mutex<Vec<Job>>) -> anyhow::Result<bool> {
+    let mut state = StateHeadless::new();
+    state.rack.load_chain(&job.chain)?;
+    state.rack.export = job.export;
+    state.load_image(&job.image)?;
+    state.rack.start_process()?;
+
+    while !state.rack.is_finished() {
+        state.rack.process_next();
+
+        let progress = state.rack.progress().fraction;
+        let mut jobs = jobs.lock().unwrap();
+        match jobs.iter_mut().find(|entry| entry.id == job.id) {
+            Some(entry) if entry.status == JobStatus::Running => entry.progress = progress,
+            _ => return Ok(false),
+        }
+    }
+
+    state.rack.export_image(&job.output)?;
+    Ok(true)
+}
@@ -0,0 +1,216 @@
+use egui::{InputState, Key, Modifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n;
+
+/// Keys which can be bound to commands
+const KEYS: [Key; 51] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::ArrowDown,
+    Key::ArrowLeft,
+    Key::ArrowRight,
+    Key::ArrowUp,
+    Key::Escape,
+    Key::Tab,
+    Key::Backspace,
+    Key::Enter,
+    Key::Space,
+    Key::Insert,
+    Key::Delete,
+    Key::Home,
+    Key::End,
+    Key::PageUp,
+    Key::PageDown,
+];
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Command {
+    Undo,
+    Redo,
+    Process,
+    Stop,
+    ToggleEyedropper,
+    NextCompareMode,
+    /// Flips between original and result in blink compare mode
+    Blink,
+    BypassSelected,
+    ZoomFit,
+    ZoomActualPixels,
+    ZoomSelection,
+    SaveProject,
+    ExportImage,
+    Preferences,
+    CommandPalette,
+}
+
+impl Command {
+    pub const ALL: [Command; 15] = [
+        Command::Undo,
+        Command::Redo,
+        Command::Process,
+        Command::Stop,
+        Command::ToggleEyedropper,
+        Command::NextCompareMode,
+        Command::Blink,
+        Command::BypassSelected,
+        Command::ZoomFit,
+        Command::ZoomActualPixels,
+        Command::ZoomSelection,
+        Command::SaveProject,
+        Command::ExportImage,
+        Command::Preferences,
+        Command::CommandPalette,
+    ];
+
+    pub fn name(&self) -> String {
+        let id = match self {
+            Command::Undo => "command-undo",
+            Command::Redo => "command-redo",
+            Command::Process => "command-process",
+            Command::Stop => "command-stop",
+            Command::ToggleEyedropper => "command-eyedropper",
+            Command::NextCompareMode => "command-next-compare-mode",
+            Command::Blink => "command-blink",
+            Command::BypassSelected => "command-bypass-selected",
+            Command::ZoomFit => "command-zoom-fit",
+            Command::ZoomActualPixels => "command-actual-pixels",
+            Command::ZoomSelection => "command-zoom-selection",
+            Command::SaveProject => "command-save-project",
+            Command::ExportImage => "command-export-image",
+            Command::Preferences => "command-preferences",
+            Command::CommandPalette => "command-command-palette",
+        };
+        i18n::tr(id)
+    }
+}
+
+/// Key combination bound to command, key is stored by its name
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Binding {
+    pub command: Command,
+    pub key: String,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+impl Binding {
+    fn new(command: Command, key: Key, ctrl: bool, shift: bool) -> Self {
+        Self {
+            command,
+            key: format!("{:?}", key),
+            ctrl,
+            shift,
+            alt: false,
+        }
+    }
+
+    fn matches(&self, key: Key, modifiers: Modifiers) -> bool {
+        self.key == format!("{:?}", key)
+            && self.ctrl == modifiers.command
+            && self.shift == modifiers.shift
+            && self.alt == modifiers.alt
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{}", self.key.trim_start_matches("Num"))
+    }
+}
+
+pub fn default_bindings() -> Vec<Binding> {
+    vec![
+        Binding::new(Command::Undo, Key::Z, true, false),
+        Binding::new(Command::Redo, Key::Y, true, false),
+        Binding::new(Command::Redo, Key::Z, true, true),
+        Binding::new(Command::Process, Key::Enter, true, false),
+        Binding::new(Command::Stop, Key::Escape, false, false),
+        Binding::new(Command::ToggleEyedropper, Key::I, false, false),
+        Binding::new(Command::NextCompareMode, Key::C, false, false),
+        Binding::new(Command::Blink, Key::B, false, false),
+        Binding::new(Command::BypassSelected, Key::B, true, false),
+        Binding::new(Command::ZoomFit, Key::F, false, false),
+        Binding::new(Command::ZoomActualPixels, Key::Num1, false, false),
+        Binding::new(Command::ZoomSelection, Key::S, false, false),
+        Binding::new(Command::SaveProject, Key::S, true, false),
+        Binding::new(Command::ExportImage, Key::E, true, false),
+        Binding::new(Command::CommandPalette, Key::P, true, false),
+    ]
+}
+
+/// Commands bound to keys pressed this frame
+pub fn pressed_commands(input: &InputState, bindings: &[Binding]) -> Vec<Command> {
+    let mut commands = Vec::new();
+    for key in KEYS {
+        if !input.key_pressed(key) {
+            continue;
+        }
+        commands.extend(
+            bindings
+                .iter()
+                .filter(|binding| binding.matches(key, input.modifiers))
+                .map(|binding| binding.command),
+        );
+    }
+    commands
+}
+
+/// Makes binding from the first bindable key pressed this frame
+pub fn capture_binding(input: &InputState, command: Command) -> Option<Binding> {
+    let key = KEYS.into_iter().find(|key| input.key_pressed(*key))?;
+    Some(Binding {
+        command,
+        key: format!("{:?}", key),
+        ctrl: input.modifiers.command,
+        shift: input.modifiers.shift,
+        alt: input.modifiers.alt,
+    })
+}
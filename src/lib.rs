@@ -0,0 +1,61 @@
+//! PhotoConsequences image processing with VST plugins, the rack can be used from other crates
+//! (GUI, CLI and render worker modules are private to the application)
+
+pub mod animation;
+pub mod compare;
+mod console;
+pub mod dither;
+pub mod editor_wrapper;
+pub mod effects;
+mod egui_platform_winit;
+pub mod error;
+pub mod export;
+mod histogram;
+mod i18n;
+mod image_generators;
+pub mod indexed;
+mod interfaces;
+mod jobs;
+mod keymap;
+mod macros;
+pub mod mask;
+pub mod metadata;
+pub mod metrics;
+pub mod midi;
+mod msgboxwrapper;
+mod palette;
+pub mod plugin_library;
+pub mod plugin_rack;
+pub mod raw;
+mod recipe;
+mod remote;
+mod renderer;
+mod report;
+pub mod routing;
+mod settings;
+pub mod simd;
+mod state_headless;
+mod stream;
+pub mod svg;
+pub mod swap;
+mod theme;
+mod ui;
+mod ui_enums;
+mod video;
+
+pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+/// Runs the application, CLI mode is used when files are given or the worker is started
+pub fn run(args: Vec<String>) {
+    console::init();
+    log::info!("PhotoConsequences by @140bpmdubstep");
+    log::info!("Version {}", VERSION);
+
+    if args.len() > 2 || args.get(1).map(String::as_str) == Some("worker") {
+        log::info!("Running in cli mode");
+        interfaces::cli::cli(args).expect("Error while running cli mode");
+    } else {
+        log::info!("Running in gui mode");
+        interfaces::gui::gui(args);
+    }
+}
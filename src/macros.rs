@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::plugin_rack::PluginRack;
+use crate::ui_enums::Action;
+
+/// File extension of recorded macros
+pub const MACRO_EXTENSION: &str = "vstimage-macro";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum MacroStep {
+    AddPlugin(PathBuf),
+    Action(Action),
+    /// Plugin parameters made by `PluginRackInstance::export_settings`
+    PluginSettings(usize, String),
+    /// Processes the image with the current chain
+    Process,
+}
+
+/// Recorded sequence of rack edits and processing runs which can be replayed on other images
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Macro {
+    #[serde(rename = "Steps")]
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    pub fn load<P: AsRef<Path>>(file: P) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(file)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, file: P) -> anyhow::Result<()> {
+        std::fs::write(file, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Records actions which change the rack, view and clipboard actions are skipped
+    /// (pasted settings are recorded with the next processing run)
+    pub fn record_action(&mut self, action: &Action) {
+        if !matches!(
+            action,
+            Action::OpenEditor(_)
+                | Action::Select(_)
+                | Action::LearnMidi(..)
+                | Action::CopySettings(_)
+                | Action::PasteSettings(_)
+        ) {
+            self.steps.push(MacroStep::Action(action.clone()));
+        }
+    }
+
+    /// Records processing run with current parameters of every plugin,
+    /// since parameters are changed in plugin editors which are not recorded
+    pub fn record_process(&mut self, rack: &mut PluginRack) {
+        for (id, plugin) in rack.plugins.iter_mut().enumerate() {
+            if let Some(settings) = plugin.export_settings() {
+                self.steps.push(MacroStep::PluginSettings(id, settings));
+            }
+        }
+        self.steps.push(MacroStep::Process);
+    }
+
+    /// Replays every step on the loaded image, processing runs are finished before the next step
+    pub fn run(&self, rack: &mut PluginRack) -> Result<()> {
+        for step in &self.steps {
+            if apply_step(rack, step)? {
+                while !rack.is_finished() {
+                    rack.process_next();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies a single step, returns `true` if processing was started
+pub fn apply_step(rack: &mut PluginRack, step: &MacroStep) -> Result<bool> {
+    match step {
+        MacroStep::AddPlugin(file) => rack.load_plugin(file.clone())?,
+        MacroStep::Action(action) => {
+            rack.apply_action(action)?;
+        }
+        MacroStep::PluginSettings(id, settings) => match rack.plugins.get_mut(*id) {
+            Some(plugin) => plugin.import_settings(settings)?,
+            None => return Err(anyhow::anyhow!("Rack has no slot {}", id).into()),
+        },
+        MacroStep::Process => {
+            rack.start_process()?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
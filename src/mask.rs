@@ -0,0 +1,400 @@
+use image::RgbaImage;
+use palette::{FromColor, Hsv, Srgb};
+use serde::{Deserialize, Serialize};
+
+use crate::{i18n, svg};
+
+/// Pixels with lower saturation have no meaningful hue and are not selected by color
+const MIN_SATURATION: f32 = 0.1;
+
+/// Image property or drawn shape which selects pixels of the mask,
+/// shape coordinates are relative (0.0 - 1.0) to the image size
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub enum MaskSource {
+    /// Luminance between `low` and `high` (0.0 - 1.0)
+    Brightness {
+        low: f32,
+        high: f32,
+    },
+    /// Luminance gradient (Sobel) stronger than `threshold` (0.0 - 1.0)
+    Edges {
+        threshold: f32,
+    },
+    /// Hue within `tolerance` degrees of `hue`
+    Color {
+        hue: f32,
+        tolerance: f32,
+    },
+    /// Text line starting at (`x`, `y`) baseline, `size` is relative to the image height
+    Text {
+        text: String,
+        font: String,
+        x: f32,
+        y: f32,
+        size: f32,
+    },
+    Ellipse {
+        left: f32,
+        top: f32,
+        right: f32,
+        bottom: f32,
+    },
+    Polygon {
+        points: Vec<(f32, f32)>,
+    },
+}
+
+impl MaskSource {
+    pub fn name(&self) -> String {
+        let id = match self {
+            MaskSource::Brightness { .. } => "mask-brightness",
+            MaskSource::Edges { .. } => "mask-edges",
+            MaskSource::Color { .. } => "mask-color",
+            MaskSource::Text { .. } => "mask-text",
+            MaskSource::Ellipse { .. } => "mask-ellipse",
+            MaskSource::Polygon { .. } => "mask-polygon",
+        };
+        i18n::tr(id)
+    }
+
+    pub fn shadows() -> Self {
+        MaskSource::Brightness {
+            low: 0.0,
+            high: 0.3,
+        }
+    }
+
+    pub fn highlights() -> Self {
+        MaskSource::Brightness {
+            low: 0.7,
+            high: 1.0,
+        }
+    }
+
+    pub fn edges() -> Self {
+        MaskSource::Edges { threshold: 0.2 }
+    }
+
+    pub fn color() -> Self {
+        MaskSource::Color {
+            hue: 0.0,
+            tolerance: 30.0,
+        }
+    }
+
+    pub fn text() -> Self {
+        MaskSource::Text {
+            text: String::from("TEXT"),
+            font: String::from("sans-serif"),
+            x: 0.1,
+            y: 0.6,
+            size: 0.3,
+        }
+    }
+
+    pub fn ellipse() -> Self {
+        MaskSource::Ellipse {
+            left: 0.25,
+            top: 0.25,
+            right: 0.75,
+            bottom: 0.75,
+        }
+    }
+
+    pub fn polygon() -> Self {
+        MaskSource::Polygon {
+            points: vec![(0.5, 0.2), (0.8, 0.8), (0.2, 0.8)],
+        }
+    }
+
+    /// Named starting points of mask sources
+    pub fn presets() -> Vec<(String, MaskSource)> {
+        [
+            ("mask-shadows", MaskSource::shadows()),
+            ("mask-highlights", MaskSource::highlights()),
+            ("mask-edges", MaskSource::edges()),
+            ("mask-color", MaskSource::color()),
+            ("mask-text", MaskSource::text()),
+            ("mask-ellipse", MaskSource::ellipse()),
+            ("mask-polygon", MaskSource::polygon()),
+        ]
+        .into_iter()
+        .map(|(id, source)| (i18n::tr(id), source))
+        .collect()
+    }
+
+    /// SVG document of the image size with the shape, empty for sources made from the image
+    fn shape_svg(&self, width: u32, height: u32) -> String {
+        let (w, h) = (width as f32, height as f32);
+        let shape = match self {
+            MaskSource::Text {
+                text,
+                font,
+                x,
+                y,
+                size,
+            } => format!(
+                r#"<text x="{}" y="{}" font-family="{}" font-size="{}">{}</text>"#,
+                x * w,
+                y * h,
+                escape_xml(font),
+                size * h,
+                escape_xml(text)
+            ),
+            MaskSource::Ellipse {
+                left,
+                top,
+                right,
+                bottom,
+            } => format!(
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"/>"#,
+                (left + right) / 2.0 * w,
+                (top + bottom) / 2.0 * h,
+                (right - left).abs() / 2.0 * w,
+                (bottom - top).abs() / 2.0 * h
+            ),
+            MaskSource::Polygon { points } => {
+                let points: Vec<String> = points
+                    .iter()
+                    .map(|(x, y)| format!("{},{}", x * w, y * h))
+                    .collect();
+                format!(r#"<polygon points="{}"/>"#, points.join(" "))
+            }
+            MaskSource::Brightness { .. } | MaskSource::Edges { .. } | MaskSource::Color { .. } => {
+                String::new()
+            }
+        };
+
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">{}</svg>"#,
+            width, height, shape
+        )
+    }
+}
+
+/// How a mask layer is combined with the layers above it
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum MaskOperation {
+    Add,
+    Subtract,
+    Intersect,
+}
+
+impl MaskOperation {
+    pub const ALL: [MaskOperation; 3] = [
+        MaskOperation::Add,
+        MaskOperation::Subtract,
+        MaskOperation::Intersect,
+    ];
+
+    pub fn name(&self) -> String {
+        let id = match self {
+            MaskOperation::Add => "mask-add",
+            MaskOperation::Subtract => "mask-subtract",
+            MaskOperation::Intersect => "mask-intersect",
+        };
+        i18n::tr(id)
+    }
+
+    fn combine(&self, base: f32, layer: f32) -> f32 {
+        match self {
+            MaskOperation::Add => base.max(layer),
+            MaskOperation::Subtract => base.min(1.0 - layer),
+            MaskOperation::Intersect => base.min(layer),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct MaskLayer {
+    pub operation: MaskOperation,
+    pub source: MaskSource,
+}
+
+/// Wet mask made from the image at the start of processing (so effects follow image content)
+/// or drawn from text and shapes
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct ImageMask {
+    pub source: MaskSource,
+    /// Width (0.0 - 1.0) of the gradual transition at thresholds
+    #[serde(default)]
+    pub softness: f32,
+    #[serde(default)]
+    pub invert: bool,
+    /// Sources added to, subtracted from or intersected with `source` in order
+    #[serde(default)]
+    pub layers: Vec<MaskLayer>,
+    /// Expands (positive) or contracts (negative) the selection by pixels
+    #[serde(default)]
+    pub grow: i32,
+    /// Keeps only a band of this width (in pixels) around the selection edge if not 0
+    #[serde(default)]
+    pub border: u32,
+}
+
+impl ImageMask {
+    pub fn new(source: MaskSource) -> Self {
+        Self {
+            source,
+            softness: 0.1,
+            invert: false,
+            layers: Vec::new(),
+            grow: 0,
+            border: 0,
+        }
+    }
+
+    /// Mask weight (0.0 - 1.0) of every image pixel in row order,
+    /// pixel distances are multiplied by `scale` for proxy images
+    pub fn build(&self, image: &RgbaImage, scale: f32) -> anyhow::Result<Vec<f32>> {
+        let mut weights = self.source_weights(&self.source, image)?;
+        for layer in &self.layers {
+            let layer_weights = self.source_weights(&layer.source, image)?;
+            for (weight, value) in weights.iter_mut().zip(layer_weights) {
+                *weight = layer.operation.combine(*weight, value);
+            }
+        }
+
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let pixels = |distance: f32| (distance * scale).round() as usize;
+        if self.grow > 0 {
+            weights = morph(&weights, width, height, pixels(self.grow as f32), f32::max);
+        } else if self.grow < 0 {
+            weights = morph(&weights, width, height, pixels(-self.grow as f32), f32::min);
+        }
+
+        if self.border > 0 {
+            let radius = pixels(self.border as f32 / 2.0).max(1);
+            let outer = morph(&weights, width, height, radius, f32::max);
+            let inner = morph(&weights, width, height, radius, f32::min);
+            weights = outer
+                .into_iter()
+                .zip(inner)
+                .map(|(outer, inner)| outer - inner)
+                .collect();
+        }
+
+        if self.invert {
+            weights
+                .iter_mut()
+                .for_each(|weight| *weight = 1.0 - *weight);
+        }
+        Ok(weights)
+    }
+
+    fn source_weights(&self, source: &MaskSource, image: &RgbaImage) -> anyhow::Result<Vec<f32>> {
+        let softness = self.softness;
+        let weights: Vec<f32> = match *source {
+            MaskSource::Brightness { low, high } => image
+                .pixels()
+                .map(|pixel| {
+                    let value = luminance(&pixel.0);
+                    ramp(value, low, softness) * (1.0 - ramp(value, high, softness))
+                })
+                .collect(),
+            MaskSource::Edges { threshold } => edges(image)
+                .into_iter()
+                .map(|value| ramp(value, threshold, softness))
+                .collect(),
+            MaskSource::Color { hue, tolerance } => image
+                .pixels()
+                .map(|pixel| {
+                    let [r, g, b, _] = pixel.0.map(|value| value as f32 / 255.0);
+                    let hsv = Hsv::from_color(Srgb::new(r, g, b));
+                    let distance = (hsv.hue.to_positive_degrees() - hue).rem_euclid(360.0);
+                    let distance = distance.min(360.0 - distance) / 180.0;
+                    (1.0 - ramp(distance, tolerance / 180.0, softness))
+                        * ramp(hsv.saturation, MIN_SATURATION, softness)
+                })
+                .collect(),
+            ref shape => {
+                // shapes are filled with opaque black on transparent background
+                let svg = shape.shape_svg(image.width(), image.height());
+                svg::rasterize_str(&svg)?
+                    .pixels()
+                    .map(|pixel| pixel[3] as f32 / 255.0)
+                    .collect()
+            }
+        };
+        Ok(weights)
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn luminance(pixel: &[u8]) -> f32 {
+    (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32) / 255.0
+}
+
+/// 0.0 below `edge` and 1.0 above it, transition of `softness` width is centered on the edge
+fn ramp(value: f32, edge: f32, softness: f32) -> f32 {
+    if softness <= 0.0 {
+        return if value >= edge { 1.0 } else { 0.0 };
+    }
+    ((value - edge) / softness + 0.5).clamp(0.0, 1.0)
+}
+
+/// Replaces every weight with the largest (`f32::max`) or smallest (`f32::min`) weight
+/// of the square around it, separately by rows and columns
+fn morph(
+    weights: &[f32],
+    width: usize,
+    height: usize,
+    radius: usize,
+    pick: fn(f32, f32) -> f32,
+) -> Vec<f32> {
+    let pass =
+        |weights: &[f32], length: usize, count: usize, index: &dyn Fn(usize, usize) -> usize| {
+            let mut result = weights.to_vec();
+            for line in 0..count {
+                for position in 0..length {
+                    let start = position.saturating_sub(radius);
+                    let end = (position + radius).min(length - 1);
+                    result[index(line, position)] = (start..=end)
+                        .map(|other| weights[index(line, other)])
+                        .reduce(pick)
+                        .unwrap_or(0.0);
+                }
+            }
+            result
+        };
+
+    if weights.is_empty() || radius == 0 {
+        return weights.to_vec();
+    }
+    let rows = pass(weights, width, height, &|y, x| y * width + x);
+    pass(&rows, height, width, &|x, y| y * width + x)
+}
+
+/// Sobel gradient magnitude of luminance, edge pixels are repeated outside the image
+fn edges(image: &RgbaImage) -> Vec<f32> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let values: Vec<f32> = image.pixels().map(|pixel| luminance(&pixel.0)).collect();
+    let at = |x: isize, y: isize| {
+        let x = x.clamp(0, width as isize - 1) as usize;
+        let y = y.clamp(0, height as isize - 1) as usize;
+        values[y * width + x]
+    };
+
+    let mut result = Vec::with_capacity(values.len());
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let gx = at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x - 1, y)
+                - at(x - 1, y + 1);
+            let gy = at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x, y - 1)
+                - at(x + 1, y - 1);
+            // largest magnitude of a hard edge is 4
+            result.push(((gx * gx + gy * gy).sqrt() / 4.0).min(1.0));
+        }
+    }
+    result
+}
@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use image::DynamicImage;
+use img_parts::{Bytes, DynImage, ImageEXIF, ImageICC};
+
+/// EXIF tags which point into the original file and become invalid after rewriting
+const OFFSET_TAGS: [exif::Tag; 4] = [
+    exif::Tag::JPEGInterchangeFormat,
+    exif::Tag::JPEGInterchangeFormatLength,
+    exif::Tag::StripOffsets,
+    exif::Tag::StripByteCounts,
+];
+
+/// Metadata of the source image which is written back into the exported file
+#[derive(Default, Clone)]
+pub struct Metadata {
+    pub icc_profile: Option<Bytes>,
+    /// EXIF with orientation reset, since it is applied to pixels on load
+    pub exif: Option<Bytes>,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        self.icc_profile.is_none() && self.exif.is_none()
+    }
+
+    /// Reads metadata from JPEG, PNG or WebP file data, returns it with EXIF orientation
+    pub fn read(data: &[u8]) -> (Self, u32) {
+        let image = match DynImage::from_bytes(Bytes::copy_from_slice(data)) {
+            Ok(Some(image)) => image,
+            _ => return (Self::default(), 1),
+        };
+
+        let mut orientation = 1;
+        let exif = image.exif().and_then(|raw| {
+            let exif = exif::Reader::new().read_raw(raw.to_vec()).ok()?;
+            if let Some(field) = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY) {
+                orientation = field.value.get_uint(0).unwrap_or(1);
+            }
+            if orientation == 1 {
+                return Some(raw);
+            }
+            reset_orientation(&exif)
+        });
+
+        let metadata = Self {
+            icc_profile: image.icc_profile(),
+            exif,
+        };
+        (metadata, orientation)
+    }
+
+    /// Writes metadata into already saved JPEG, PNG or WebP file, other formats are left as is
+    pub fn write<P: AsRef<Path>>(&self, file: P) -> anyhow::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let data = Bytes::from(std::fs::read(&file)?);
+        let mut image = match DynImage::from_bytes(data)? {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+
+        image.set_icc_profile(self.icc_profile.clone());
+        image.set_exif(self.exif.clone());
+
+        let output = std::fs::File::create(file)?;
+        image.encoder().write_to(output)?;
+        Ok(())
+    }
+}
+
+fn reset_orientation(exif: &exif::Exif) -> Option<Bytes> {
+    let orientation = exif::Field {
+        tag: exif::Tag::Orientation,
+        ifd_num: exif::In::PRIMARY,
+        value: exif::Value::Short(vec![1]),
+    };
+
+    let fields: Vec<exif::Field> = exif
+        .fields()
+        .filter(|field| field.ifd_num == exif::In::PRIMARY && !OFFSET_TAGS.contains(&field.tag))
+        .map(|field| {
+            if field.tag == exif::Tag::Orientation {
+                orientation.clone()
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+
+    let mut writer = exif::experimental::Writer::new();
+    for field in &fields {
+        writer.push_field(field);
+    }
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    writer.write(&mut buf, exif.little_endian()).ok()?;
+    Some(Bytes::from(buf.into_inner()))
+}
+
+/// Rotates and flips image according to EXIF orientation
+pub fn apply_orientation(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
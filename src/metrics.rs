@@ -0,0 +1,91 @@
+use image::RgbaImage;
+use serde::Serialize;
+
+/// Side of the square window used for SSIM
+const SSIM_WINDOW: u32 = 8;
+
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Similarity of the processed image to the original
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Metrics {
+    /// Peak signal-to-noise ratio of RGB channels in dB, infinite for identical images
+    pub psnr: f64,
+    /// Mean structural similarity of luma, 1.0 for identical images
+    pub ssim: f64,
+}
+
+/// Computes metrics of the processed image, returns `None` if image sizes don't match
+pub fn measure(original: &RgbaImage, processed: &RgbaImage) -> Option<Metrics> {
+    if original.dimensions() != processed.dimensions() || original.is_empty() {
+        return None;
+    }
+
+    Some(Metrics {
+        psnr: psnr(original, processed),
+        ssim: ssim(original, processed),
+    })
+}
+
+pub fn psnr(original: &RgbaImage, processed: &RgbaImage) -> f64 {
+    let mut error = 0.0;
+    for (a, b) in original.pixels().zip(processed.pixels()) {
+        for channel in 0..3 {
+            let delta = a[channel] as f64 - b[channel] as f64;
+            error += delta * delta;
+        }
+    }
+
+    let mse = error / (original.pixels().len() * 3) as f64;
+    if mse == 0.0 {
+        return f64::INFINITY;
+    }
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+/// SSIM averaged over non-overlapping windows, edge windows are cropped
+pub fn ssim(original: &RgbaImage, processed: &RgbaImage) -> f64 {
+    let (width, height) = original.dimensions();
+    let mut total = 0.0;
+    let mut windows = 0;
+
+    for top in (0..height).step_by(SSIM_WINDOW as usize) {
+        for left in (0..width).step_by(SSIM_WINDOW as usize) {
+            let right = (left + SSIM_WINDOW).min(width);
+            let bottom = (top + SSIM_WINDOW).min(height);
+
+            let (mut sum_a, mut sum_b) = (0.0, 0.0);
+            let (mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0);
+            for y in top..bottom {
+                for x in left..right {
+                    let a = luma(original, x, y);
+                    let b = luma(processed, x, y);
+                    sum_a += a;
+                    sum_b += b;
+                    sum_aa += a * a;
+                    sum_bb += b * b;
+                    sum_ab += a * b;
+                }
+            }
+
+            let count = ((right - left) * (bottom - top)) as f64;
+            let (mean_a, mean_b) = (sum_a / count, sum_b / count);
+            let variance_a = sum_aa / count - mean_a * mean_a;
+            let variance_b = sum_bb / count - mean_b * mean_b;
+            let covariance = sum_ab / count - mean_a * mean_b;
+
+            total += ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1)
+                    * (variance_a + variance_b + SSIM_C2));
+            windows += 1;
+        }
+    }
+
+    total / windows as f64
+}
+
+fn luma(image: &RgbaImage, x: u32, y: u32) -> f64 {
+    let [r, g, b, _] = image.get_pixel(x, y).0;
+    0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64
+}
@@ -0,0 +1,121 @@
+//! MIDI control change input used to drive plugin parameters from hardware controllers
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use midir::MidiInputConnection;
+use serde::{Deserialize, Serialize};
+
+const CLIENT_NAME: &str = "PhotoConsequences";
+
+/// Control change controller on a MIDI channel
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MidiControl {
+    #[serde(rename = "Channel")]
+    pub channel: u8,
+    #[serde(rename = "Controller")]
+    pub controller: u8,
+}
+
+impl std::fmt::Display for MidiControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CC{} ch{}", self.controller, self.channel + 1)
+    }
+}
+
+/// Plugin parameter driven by the MIDI controller
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct MidiMapping {
+    #[serde(rename = "Control")]
+    pub control: MidiControl,
+    #[serde(rename = "Parameter")]
+    pub parameter: i32,
+}
+
+/// Control changes from all MIDI input ports
+pub struct MidiInput {
+    sender: Sender<(MidiControl, u8)>,
+    receiver: Receiver<(MidiControl, u8)>,
+    connections: Vec<(String, MidiInputConnection<()>)>,
+}
+
+impl MidiInput {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            connections: Vec::new(),
+        }
+    }
+
+    /// Connects ports which were plugged in since the last call and drops unplugged ones
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        let input = midir::MidiInput::new(CLIENT_NAME)?;
+        let ports: Vec<(String, midir::MidiInputPort)> = input
+            .ports()
+            .into_iter()
+            .filter_map(|port| Some((input.port_name(&port).ok()?, port)))
+            .collect();
+        self.connections
+            .retain(|(connected, _)| ports.iter().any(|(name, _)| name == connected));
+
+        for (name, port) in ports {
+            if self
+                .connections
+                .iter()
+                .any(|(connected, _)| *connected == name)
+            {
+                continue;
+            }
+
+            // every connection consumes its own client
+            let input = midir::MidiInput::new(CLIENT_NAME)?;
+            let sender = self.sender.clone();
+            let connection = input.connect(
+                &port,
+                "parameter-control",
+                move |_, message, _| {
+                    if let Some(change) = parse_control_change(message) {
+                        let _ = sender.send(change);
+                    }
+                },
+                (),
+            );
+            match connection {
+                Ok(connection) => {
+                    log::info!("MIDI input connected: {}", name);
+                    self.connections.push((name, connection));
+                }
+                Err(error) => log::warn!("Unable to connect MIDI input {}: {}", name, error),
+            }
+        }
+        Ok(())
+    }
+
+    /// Control changes received since the last call, values are normalized to 0.0 - 1.0
+    pub fn poll(&self) -> Vec<(MidiControl, f32)> {
+        self.receiver
+            .try_iter()
+            .map(|(control, value)| (control, value as f32 / 127.0))
+            .collect()
+    }
+}
+
+impl Default for MidiInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_control_change(message: &[u8]) -> Option<(MidiControl, u8)> {
+    match *message {
+        [status, controller, value] if status & 0xF0 == 0xB0 => Some((
+            MidiControl {
+                channel: status & 0x0F,
+                controller,
+            },
+            value,
+        )),
+        _ => None,
+    }
+}
@@ -1,10 +1,12 @@
 #[cfg(target_os = "linux")]
 pub fn messagebox(title: &str, message: &str) {
+    log::error!("{}: {}", title, message);
     messagebox_x11::msgbox(title, message);
 }
 
 #[cfg(target_os = "windows")]
 pub fn messagebox(title: &str, message: &str) {
+    log::error!("{}: {}", title, message);
     extern crate msgbox;
 
     use msgbox::IconType;
@@ -13,6 +15,7 @@ pub fn messagebox(title: &str, message: &str) {
 
 #[cfg(target_os = "macos")]
 pub fn messagebox(title: &str, message: &str) {
+    log::error!("{}: {}", title, message);
     extern crate msgbox;
 
     use msgbox::IconType;
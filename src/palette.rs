@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use crate::keymap::Command;
+
+pub enum PaletteEntry {
+    Command(Command),
+    AddPlugin(PathBuf),
+    /// Toggles bypass of the rack plugin
+    Bypass(usize),
+}
+
+pub struct PaletteItem {
+    pub label: String,
+    pub entry: PaletteEntry,
+}
+
+/// Scores how well `query` matches `text` as case insensitive subsequence,
+/// consecutive characters and word starts score higher. `None` if it doesn't match
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut last = None;
+
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|c| *c == query_char)?;
+        score += 1;
+        if found > 0 && last == Some(found - 1) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(found);
+        position = found + 1;
+    }
+
+    // shorter labels win among equal matches
+    Some(score * 100 - text.len() as i32)
+}
+
+/// Items matching the query, best matches first
+pub fn search(items: Vec<PaletteItem>, query: &str) -> Vec<PaletteItem> {
+    let mut matches: Vec<(i32, PaletteItem)> = items
+        .into_iter()
+        .filter_map(|item| fuzzy_score(query, &item.label).map(|score| (score, item)))
+        .collect();
+    if !query.trim().is_empty() {
+        matches.sort_by_key(|(score, _)| -score);
+    }
+    matches.into_iter().map(|(_, item)| item).collect()
+}
@@ -0,0 +1,209 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::SystemTime,
+};
+
+use vst::host::PluginLoadError;
+
+#[cfg(target_os = "windows")]
+pub const PLUGIN_EXTENSION: &str = "dll";
+#[cfg(target_os = "macos")]
+pub const PLUGIN_EXTENSION: &str = "vst";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub const PLUGIN_EXTENSION: &str = "so";
+
+/// Plugins which are refused to load, e.g. ones known to crash
+static BLACKLIST: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+
+/// Reason why plugin could not be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadFailure {
+    Blacklisted,
+    /// Plugin is built for different CPU architecture than the host
+    WrongArchitecture(&'static str),
+    /// Library can't be loaded, message of the system loader
+    MissingDependency(String),
+    NotAPlugin,
+    Other(String),
+}
+
+impl LoadFailure {
+    pub fn remedy(&self) -> String {
+        match self {
+            LoadFailure::Blacklisted => {
+                String::from("Remove the plugin from the blacklist in Preferences")
+            }
+            LoadFailure::WrongArchitecture(_) => format!(
+                "Install {} version of the plugin or load it through a bridge \
+                (e.g. yabridge, jBridge)",
+                std::env::consts::ARCH
+            ),
+            LoadFailure::MissingDependency(_) => String::from(
+                "Install libraries required by the plugin (e.g. Visual C++ Redistributable) \
+                or put them next to the plugin file",
+            ),
+            LoadFailure::NotAPlugin => String::from(
+                "Make sure the file is a VST 2.4 effect, VST3 and other formats are not supported",
+            ),
+            LoadFailure::Other(_) => String::from("Try to reinstall the plugin"),
+        }
+    }
+}
+
+impl std::fmt::Display for LoadFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadFailure::Blacklisted => write!(f, "plugin is blacklisted"),
+            LoadFailure::WrongArchitecture(arch) => write!(
+                f,
+                "plugin is built for {}, but PhotoConsequences runs on {}",
+                arch,
+                std::env::consts::ARCH
+            ),
+            LoadFailure::MissingDependency(message) => {
+                write!(f, "library or its dependency can't be loaded: {}", message)
+            }
+            LoadFailure::NotAPlugin => write!(f, "file is not a VST 2.4 plugin"),
+            LoadFailure::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+pub fn is_plugin<P: AsRef<Path>>(file: P) -> bool {
+    file.as_ref()
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case(PLUGIN_EXTENSION))
+        .unwrap_or(false)
+}
+
+/// Recursively looks for VST plugins in directory, result is sorted so it can be used with seeded randomization
+pub fn scan<P: AsRef<Path>>(dir: P) -> Vec<PathBuf> {
+    let mut plugins = Vec::new();
+    scan_dir(dir.as_ref(), &mut plugins);
+    plugins.sort();
+    plugins
+}
+
+fn scan_dir(dir: &Path, plugins: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("Unable to scan {}: {}", dir.display(), error);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if is_plugin(&path) {
+            plugins.push(path);
+        } else if path.is_dir() {
+            scan_dir(&path, plugins);
+        }
+    }
+}
+
+pub fn set_blacklist(plugins: Vec<PathBuf>) {
+    *BLACKLIST.write().unwrap() = plugins;
+}
+
+pub fn is_blacklisted<P: AsRef<Path>>(file: P) -> bool {
+    BLACKLIST
+        .read()
+        .unwrap()
+        .iter()
+        .any(|plugin| plugin == file.as_ref())
+}
+
+/// Finds out why `PluginLoader` failed to load the plugin
+pub fn diagnose(file: &Path, error: &PluginLoadError) -> LoadFailure {
+    if !is_plugin(file) {
+        return LoadFailure::NotAPlugin;
+    }
+
+    if let Some(arch) = binary_architecture(file) {
+        if arch != std::env::consts::ARCH {
+            return LoadFailure::WrongArchitecture(arch);
+        }
+    }
+
+    match error {
+        // loader error is discarded by vst crate, library is opened again to get it
+        PluginLoadError::InvalidPath => match unsafe { libloading::Library::new(file) } {
+            Err(error) => LoadFailure::MissingDependency(error.to_string()),
+            Ok(_) => LoadFailure::Other(error.to_string()),
+        },
+        PluginLoadError::NotAPlugin => LoadFailure::NotAPlugin,
+        error => LoadFailure::Other(error.to_string()),
+    }
+}
+
+/// CPU architecture of the plugin binary (as in `std::env::consts::ARCH`),
+/// `None` if it is unknown or binary is universal
+pub fn binary_architecture(file: &Path) -> Option<&'static str> {
+    let mut header = Vec::new();
+    std::fs::File::open(binary_path(file)?)
+        .ok()?
+        .take(4096)
+        .read_to_end(&mut header)
+        .ok()?;
+
+    let u16_le = |offset: usize| -> Option<u16> {
+        Some(u16::from_le_bytes(
+            header.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_le = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            header.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    match header.get(0..4)? {
+        // PE, machine follows the "PE\0\0" signature
+        [b'M', b'Z', ..] => match u16_le(u32_le(0x3c)? as usize + 4)? {
+            0x14c => Some("x86"),
+            0x8664 => Some("x86_64"),
+            0x1c4 => Some("arm"),
+            0xaa64 => Some("aarch64"),
+            _ => None,
+        },
+        // little endian ELF
+        [0x7f, b'E', b'L', b'F'] if header.get(5) == Some(&1) => match u16_le(18)? {
+            3 => Some("x86"),
+            62 => Some("x86_64"),
+            40 => Some("arm"),
+            183 => Some("aarch64"),
+            _ => None,
+        },
+        // thin Mach-O, 32 and 64 bit
+        [0xce, 0xfa, 0xed, 0xfe] | [0xcf, 0xfa, 0xed, 0xfe] => match u32_le(4)? {
+            7 => Some("x86"),
+            0x0100_0007 => Some("x86_64"),
+            12 => Some("arm"),
+            0x0100_000c => Some("aarch64"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Executable of the plugin, macOS plugins are bundles with executable inside
+fn binary_path(file: &Path) -> Option<PathBuf> {
+    if !file.is_dir() {
+        return Some(file.to_path_buf());
+    }
+
+    std::fs::read_dir(file.join("Contents").join("MacOS"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.is_file())
+}
+
+/// Modification time of the plugin executable
+pub fn modified(file: &Path) -> Option<SystemTime> {
+    std::fs::metadata(binary_path(file)?).ok()?.modified().ok()
+}
@@ -1,32 +1,218 @@
 use std::{
-    io::{Cursor, Write},
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    io::{Cursor, Read, Seek, Write},
+    ops::Range,
     path::PathBuf,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use image::io::Reader as ImageReader;
-use palette::{FromColor, Hsva, RgbHue, Srgba};
+use palette::{FromColor, Hsva, Srgba};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use crate::animation::{self, Animation, Keyframe, KeyframeTarget};
+use crate::compare;
+use crate::dither::{Dither, Quantizer};
 use crate::editor_wrapper::EditorWrapper;
-use anyhow::Result;
+use crate::effects::{self, EffectInput, RackEffect};
+use crate::error::{Result, VstImageError};
+use crate::export::{self, ExportOptions, ExportProfile};
+use crate::mask::ImageMask;
+use crate::metadata::{self, Metadata};
+use crate::metrics::{self, Metrics};
+use crate::midi::{MidiControl, MidiMapping};
+use crate::plugin_library::{self, LoadFailure};
+use crate::raw;
+use crate::routing::{ColorChannel, ProcessingGraph};
+use crate::simd::{self, Planar};
+use crate::svg;
+use crate::swap::{self, Area, TileHistory, TileSnapshot};
+pub use crate::ui_enums::{Action, UndoStep};
+use crate::video;
 
 use vst::{
     host::{Host, HostBuffer, PluginInstance, PluginLoader},
+    plugin::{CanDo, Category, Supported},
     prelude::Plugin,
 };
 
-pub struct PluginHost;
+/// File extension of standalone chain presets
+pub const CHAIN_EXTENSION: &str = "vstimage-chain";
+
+/// Extension of the chain written next to exported images (`image.chain.json`)
+pub const SIDECAR_EXTENSION: &str = "chain.json";
+
+/// Project sample rate of new racks, also the rate every plugin used before it could be changed
+const DEFAULT_SAMPLE_RATE: f32 = 44100.0;
+
+/// Chain preset or chain sidecar of an exported image
+pub fn is_chain_file<P: AsRef<std::path::Path>>(file: P) -> bool {
+    let name = file
+        .as_ref()
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    name.ends_with(&format!(".{}", CHAIN_EXTENSION))
+        || name.ends_with(&format!(".{}", SIDECAR_EXTENSION))
+}
+
+/// Parameter change made in the plugin GUI, replayed when processing reaches its position
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AutomationPoint {
+    #[serde(rename = "Parameter")]
+    pub parameter: i32,
+    #[serde(rename = "Value")]
+    pub value: f32,
+    /// Processing position (pixel offset in the pass) when the change was made,
+    /// `None` for the latest value set while the rack was idle
+    #[serde(rename = "Position", default)]
+    pub position: Option<usize>,
+}
+
+/// Parameter changes and display updates reported by a plugin,
+/// shared between its host and its rack slot
+#[derive(Default)]
+pub struct PluginEvents {
+    changes: Mutex<Vec<(i32, f32)>>,
+    /// Set while the rack changes parameters itself, so only plugin changes are recorded
+    paused: AtomicBool,
+    /// Set when plugin asks to update its display, e.g. after switching program in its GUI
+    display_changed: AtomicBool,
+}
+
+/// Host of a single plugin
+#[derive(Default)]
+pub struct PluginHost {
+    /// Time of the last parameter change made in plugin editor, shared by all plugins
+    parameter_changed: Arc<Mutex<Option<Instant>>>,
+    events: Arc<PluginEvents>,
+}
+
+/// Full resolution images which are put aside while downscaled proxy is processed
+struct ProxySource {
+    images: Vec<image::RgbaImage>,
+    deep_images: Vec<DeepImage>,
+    block_size: i64,
+    scale: f32,
+}
+
+/// Periodic saving of partially processed image, so long runs are not lost on crash
+pub struct Autosave {
+    pub path: PathBuf,
+    pub interval: Duration,
+    last: Instant,
+}
+
+impl Autosave {
+    pub fn new(path: PathBuf, interval: Duration) -> Self {
+        Self {
+            path,
+            interval,
+            last: Instant::now(),
+        }
+    }
+}
+
+/// Rack change which reverts (or repeats) a plugin table action
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RackEdit {
+    Action(Action),
+    /// Chain made by `save_chain` before a change which can't be reverted by an action
+    Chain(String),
+    /// Groups and group of every plugin before plugins were regrouped
+    Groups {
+        groups: Vec<PluginGroup>,
+        members: Vec<Option<usize>>,
+    },
+}
+
+/// Processing progress stored in the project, so interrupted render can be resumed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Checkpoint {
+    #[serde(rename = "Position")]
+    pub position: usize,
+    #[serde(rename = "Pass")]
+    pub pass: usize,
+    #[serde(rename = "Total")]
+    pub total: usize,
+}
+
+/// Optional parts of the project archive besides the chain and the latest image
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(default)]
+pub struct ProjectContents {
+    /// Unprocessed image, so the processing can be undone after the project is reopened
+    pub original: bool,
+    /// Wet masks of plugins built in the last run, as grayscale images
+    pub masks: bool,
+    /// Number of the latest undo steps stored
+    pub history: usize,
+}
 
 pub struct PluginRack {
-    pub host: Arc<Mutex<PluginHost>>,
+    /// Time of the last parameter change made in plugin editor, shared with plugin hosts
+    parameter_changed: Arc<Mutex<Option<Instant>>>,
     pub plugins: Vec<PluginRackInstance>,
+    pub groups: Vec<PluginGroup>,
     pub block_size: i64,
     pub images: Vec<image::RgbaImage>,
+    /// High precision copies of `images` which are processed instead of them,
+    /// empty for 8 bit images
+    pub deep_images: Vec<DeepImage>,
+    /// Processing graph, plugins are processed as a simple chain if not set
+    pub graph: Option<ProcessingGraph>,
+    /// How many times the whole chain is applied in a single processing run
+    pub iterations: usize,
+    /// Wet reduction applied on every next pass (0.0 - no decay)
+    pub wet_decay: f32,
+    /// Processes even and odd scanlines as two separate streams
+    pub interlaced: bool,
+    pub sample_rate: SampleRate,
+    /// Rounding of processed values of 8 and 16 bit images
+    pub dither: Dither,
+    /// Processes image luminance as a single stream
+    pub grayscale: Option<Grayscale>,
+    /// Seed of the randomized chain, stored in the project so the chain can be generated again
+    pub seed: Option<u64>,
+    /// Frames of animated image, every frame is processed separately
+    pub animation: Option<Animation>,
+    /// Resets plugins before processing every animation frame
+    pub reset_state_per_frame: bool,
+    pub export: ExportOptions,
+    pub export_profile: ExportProfile,
+    /// File name of the loaded image without extension, used in export file name templates
+    pub source_name: String,
+    /// File of the loaded image, `None` if image came from elsewhere
+    pub source_path: Option<PathBuf>,
+    /// ICC profile and EXIF of the loaded image
+    pub metadata: Metadata,
+    pub autosave: Option<Autosave>,
+    /// Project file where processing state is periodically saved during long runs
+    pub checkpoint: Option<Autosave>,
+    /// Tiles of the still image saved right before processing runs changed them,
+    /// `images[0]` is left empty while there are any
+    history: TileHistory,
+    /// Result removed by undo
+    redo: Option<(image::RgbaImage, Option<DeepImage>)>,
+    /// Size of proxy preview relative to the image
+    pub proxy_scale: f32,
+    proxy: Option<ProxySource>,
     position: usize,
     total: usize,
+    pass: usize,
     finished: bool,
+    /// Tiles (in row-major order) changed since the preview was updated
+    dirty: BTreeSet<usize>,
+    started: Instant,
+    /// Duration of the current or last processing run
+    run_time: Duration,
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Serialize_repr, Deserialize_repr, Debug)]
@@ -37,247 +223,3232 @@ pub enum InputChannelType {
     Value = 2,
 }
 
+/// Additional plugin output written into another HSV channel of the pixels
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputRoute {
+    #[serde(rename = "Output")]
+    pub output: usize,
+    #[serde(rename = "Channel")]
+    pub channel: InputChannelType,
+}
+
+/// Which tiles of the image grid are affected by a plugin
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Debug, Default)]
+pub enum TilePattern {
+    #[default]
+    All,
+    EveryNth(usize),
+    Checkerboard,
+    EvenRows,
+}
+
+impl TilePattern {
+    pub fn contains(&self, tile_x: usize, tile_y: usize, tiles_per_row: usize) -> bool {
+        match *self {
+            TilePattern::All => true,
+            TilePattern::EveryNth(n) => (tile_y * tiles_per_row + tile_x) % n.max(1) == 0,
+            TilePattern::Checkerboard => (tile_x + tile_y) % 2 == 0,
+            TilePattern::EvenRows => tile_y % 2 == 0,
+        }
+    }
+}
+
+/// Sample rate of plugins which don't override it
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum SampleRate {
+    Fixed(f32),
+    /// Rate at which one image row lasts specified number of seconds
+    PerRow(f32),
+}
+
+impl Default for SampleRate {
+    fn default() -> Self {
+        SampleRate::Fixed(DEFAULT_SAMPLE_RATE)
+    }
+}
+
+impl SampleRate {
+    /// Rate for image of specified width
+    pub fn rate(&self, width: usize) -> f32 {
+        match *self {
+            SampleRate::Fixed(rate) => rate,
+            SampleRate::PerRow(seconds) => width as f32 / seconds.max(f32::EPSILON),
+        }
+    }
+}
+
+/// How the processed luminance is written back in grayscale mode
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum Grayscale {
+    /// Image is converted to gray
+    Gray,
+    /// Original colors are shifted to the processed luminance
+    Retint,
+}
+
+impl Grayscale {
+    pub const ALL: [Grayscale; 2] = [Grayscale::Gray, Grayscale::Retint];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Grayscale::Gray => "Gray",
+            Grayscale::Retint => "Retint",
+        }
+    }
+}
+
+/// Scanlines of interlaced image, processed as separate streams
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum Field {
+    Even,
+    Odd,
+}
+
+/// Location of the processed block inside the image
+#[derive(Clone, Copy, Debug)]
+pub struct BlockLocation {
+    /// Index of the first pixel of the block, counted inside the field for interlaced blocks
+    pub offset: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Size of the processed image relative to the source, below 1.0 for proxy preview
+    pub scale: f32,
+    /// Field of the block when the image is processed interlaced
+    pub field: Option<Field>,
+    /// Project sample rate for the full resolution image
+    pub sample_rate: f32,
+    pub dither: Dither,
+    /// Pass of the chain over the image, dithering continues only between blocks of one pass
+    pub pass: usize,
+    pub grayscale: Option<Grayscale>,
+    /// Color channel processed instead of the input channel inside per-channel branches
+    pub channel: Option<ColorChannel>,
+}
+
+impl BlockLocation {
+    /// Image column and row of the block pixel with specified index
+    pub fn pixel(&self, index: usize) -> (usize, usize) {
+        let position = self.offset + index;
+        let (x, row) = (position % self.width, position / self.width);
+        match self.field {
+            Some(Field::Even) => (x, row * 2),
+            Some(Field::Odd) => (x, row * 2 + 1),
+            None => (x, row),
+        }
+    }
+}
+
+/// Image channel value which can be processed by plugins
+pub trait Sample: Copy {
+    /// Largest channel value (full intensity)
+    const MAX: f32;
+    /// Values are rounded to whole numbers
+    const INTEGER: bool;
+
+    fn to_f32(self) -> f32;
+    /// Saturating conversion from the unnormalized value
+    fn from_f32(value: f32) -> Self;
+}
+
+impl Sample for u8 {
+    const MAX: f32 = u8::MAX as f32;
+    const INTEGER: bool = true;
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value as u8
+    }
+}
+
+impl Sample for u16 {
+    const MAX: f32 = u16::MAX as f32;
+    const INTEGER: bool = true;
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value as u16
+    }
+}
+
+impl Sample for f32 {
+    const MAX: f32 = 1.0;
+    const INTEGER: bool = false;
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    /// Float samples are not clamped, so values can go far above 1.0
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+pub type Rgba16Image = image::ImageBuffer<image::Rgba<u16>, Vec<u16>>;
+
+/// High precision copy of the working image
+#[derive(Clone)]
+pub enum DeepImage {
+    Rgba16(Rgba16Image),
+    /// HDR image, channel values are not limited to 0.0 - 1.0
+    Rgba32F(image::Rgba32FImage),
+}
+
+impl DeepImage {
+    pub fn memory_size(&self) -> usize {
+        match self {
+            DeepImage::Rgba16(image) => std::mem::size_of::<u16>() * image.len(),
+            DeepImage::Rgba32F(image) => std::mem::size_of::<f32>() * image.len(),
+        }
+    }
+
+    /// Empty image of the same precision
+    fn empty(&self) -> Self {
+        match self {
+            DeepImage::Rgba16(_) => DeepImage::Rgba16(Rgba16Image::new(0, 0)),
+            DeepImage::Rgba32F(_) => DeepImage::Rgba32F(image::Rgba32FImage::new(0, 0)),
+        }
+    }
+}
+
+/// State of the processing run
+#[derive(Clone, Debug)]
+pub struct Progress {
+    /// Processed pixels of the current pass
+    pub done: usize,
+    pub total: usize,
+    /// Processed part (0.0 - 1.0) of the whole run including passes and animation frames
+    pub fraction: f32,
+    /// Plugin which took the most time so far, every block goes through the whole chain
+    pub plugin: Option<String>,
+    /// Column and row of the first pixel of the block processed next
+    pub tile: (usize, usize),
+    pub elapsed: Duration,
+}
+
+/// Rectangular part of the image in relative (0.0 - 1.0) coordinates
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub struct Region {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Default for Region {
+    fn default() -> Self {
+        Self {
+            left: 0.0,
+            top: 0.0,
+            right: 1.0,
+            bottom: 1.0,
+        }
+    }
+}
+
+impl Region {
+    pub fn top_half() -> Self {
+        Self {
+            bottom: 0.5,
+            ..Default::default()
+        }
+    }
+
+    pub fn bottom_half() -> Self {
+        Self {
+            top: 0.5,
+            ..Default::default()
+        }
+    }
+
+    pub fn left_half() -> Self {
+        Self {
+            right: 0.5,
+            ..Default::default()
+        }
+    }
+
+    pub fn right_half() -> Self {
+        Self {
+            left: 0.5,
+            ..Default::default()
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        let x = x as f32 / width.max(1) as f32;
+        let y = y as f32 / height.max(1) as f32;
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PluginRackInstance {
     #[serde(skip)]
     pub instance: Option<PluginInstance>,
+    /// Built-in or registered effect used instead of VST plugin, its path is made by
+    /// `effects::effect_path`
+    #[serde(skip)]
+    pub effect: Option<Box<dyn RackEffect>>,
     #[serde(skip)]
     pub editor: EditorWrapper,
     #[serde(rename = "ImageProcessingInput")]
     pub input_channel: InputChannelType,
     #[serde(rename = "AudioProcessingOuput")]
     pub output_channel: usize,
+    /// Averages all plugin outputs instead of using `output_channel`
+    #[serde(rename = "MixOutputs", default)]
+    pub mix_outputs: bool,
+    /// Outputs written after `output_channel`, e.g. the right channel of stereo effects
+    #[serde(rename = "OutputRoutes", default)]
+    pub output_routes: Vec<OutputRoute>,
     #[serde(rename = "PluginPath")]
     path: PathBuf,
     #[serde(rename = "PluginData")]
     plugin_data: String,
     #[serde(rename = "Wet")]
     pub wet: f32,
-    #[serde(rename = "SampleRate")]
-    pub sample_rate: f32,
+    /// Overrides project sample rate if set
+    #[serde(rename = "SampleRateOverride", default)]
+    pub sample_rate: Option<f32>,
+    /// Plugin sample rate of projects saved before the project sample rate was added,
+    /// moved into `sample_rate` when the chain is parsed
+    #[serde(rename = "SampleRate", default, skip_serializing)]
+    legacy_sample_rate: Option<f32>,
+    #[serde(rename = "Bypass", default)]
+    pub bypass: bool,
+    #[serde(rename = "Iterations", default = "default_iterations")]
+    pub iterations: usize,
+    #[serde(rename = "TilePattern", default)]
+    pub tile_pattern: TilePattern,
+    #[serde(rename = "TileSize", default = "default_tile_size")]
+    pub tile_size: usize,
+    #[serde(rename = "Region", default)]
+    pub region: Option<Region>,
+    #[serde(rename = "Mask", default)]
+    pub mask: Option<ImageMask>,
+    /// Weights of `mask` for the image being processed, empty if not built
+    #[serde(skip)]
+    mask_weights: Vec<f32>,
+    #[serde(skip)]
+    buffers: ProcessBuffers,
+    /// Quantizers of written outputs by pass, plugin iteration and output (0 is the main output,
+    /// routes follow), kept so dithering continues in the next block
+    #[serde(skip)]
+    quantizers: HashMap<(usize, usize, usize), Quantizer>,
+    /// Processing time of the current or last run
+    #[serde(skip)]
+    pub elapsed: Duration,
+    /// Largest random offset (0.0 - 1.0) of parameters, picked separately for every tile
+    #[serde(rename = "Jitter", default)]
+    pub jitter: f32,
+    /// Parameters changed by jitter, every parameter if empty
+    #[serde(rename = "JitterParameters", default)]
+    pub jitter_parameters: Vec<i32>,
+    #[serde(rename = "JitterSeed", default)]
+    pub jitter_seed: u64,
+    /// Shift in pixels of the output written back into the block, wraps around block edges
+    #[serde(rename = "SampleOffset", default)]
+    pub sample_offset: isize,
+    /// Field processed by the plugin in interlaced mode, both fields if not set
+    #[serde(rename = "Field", default)]
+    pub field: Option<Field>,
+    #[serde(rename = "Group", default)]
+    pub group: Option<usize>,
+    #[serde(rename = "Keyframes", default)]
+    pub keyframes: Vec<Keyframe>,
+    #[serde(rename = "MidiMappings", default)]
+    pub midi_mappings: Vec<MidiMapping>,
+    #[serde(rename = "Automation", default)]
+    pub automation: Vec<AutomationPoint>,
+    /// Parameters recorded in the current run, their previous recording is replaced
+    #[serde(skip)]
+    recorded: Vec<i32>,
+    /// Parameter values at the start of the current run
+    #[serde(skip)]
+    initial: Vec<f32>,
+    #[serde(skip)]
+    events: Arc<PluginEvents>,
+    /// Name of the current plugin program
+    #[serde(skip)]
+    pub program_name: String,
+    /// Parameter names and values shown in the parameter list
+    #[serde(skip)]
+    parameters: Vec<(String, f32)>,
+    #[serde(skip)]
+    pub solo: bool,
+    /// Set when another plugin is soloed or plugin group is bypassed
+    #[serde(skip)]
+    muted: bool,
+    /// Wet multiplier of the plugin group
+    #[serde(skip, default = "default_wet_scale")]
+    wet_scale: f32,
+    /// Reason why plugin can't process images, such plugins are skipped
+    #[serde(skip)]
+    pub inactive: Option<String>,
+    /// Possible problem which doesn't prevent processing
+    #[serde(skip)]
+    pub warning: Option<String>,
+}
+
+/// Named folder of adjacent rack slots with collective bypass and wet
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PluginGroup {
+    #[serde(rename = "Name")]
+    pub name: String,
     #[serde(rename = "Bypass", default)]
     pub bypass: bool,
+    #[serde(rename = "Wet")]
+    pub wet: f32,
+    #[serde(rename = "Collapsed", default)]
+    pub collapsed: bool,
+}
+
+/// Serializable rack configuration (without image data)
+#[derive(Serialize, Deserialize)]
+pub struct RackState {
+    #[serde(rename = "Plugins")]
+    pub plugins: Vec<PluginRackInstance>,
+    #[serde(rename = "Graph", default)]
+    pub graph: Option<ProcessingGraph>,
+    #[serde(rename = "Groups", default)]
+    pub groups: Vec<PluginGroup>,
+    #[serde(rename = "Iterations", default = "default_iterations")]
+    pub iterations: usize,
+    #[serde(rename = "WetDecay", default)]
+    pub wet_decay: f32,
+    #[serde(rename = "Interlaced", default)]
+    pub interlaced: bool,
+    #[serde(rename = "SampleRate", default)]
+    pub sample_rate: SampleRate,
+    #[serde(rename = "Dither", default)]
+    pub dither: Dither,
+    #[serde(rename = "Grayscale", default)]
+    pub grayscale: Option<Grayscale>,
+    #[serde(rename = "Seed", default)]
+    pub seed: Option<u64>,
+}
+
+impl Default for RackState {
+    fn default() -> Self {
+        Self {
+            plugins: Vec::new(),
+            graph: None,
+            groups: Vec::new(),
+            iterations: 1,
+            wet_decay: 0.0,
+            interlaced: false,
+            sample_rate: SampleRate::default(),
+            dither: Dither::None,
+            grayscale: None,
+            seed: None,
+        }
+    }
+}
+
+fn parse_chain(json: &str) -> Result<RackState> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let mut state = if value.is_array() {
+        // older projects store only the plugin list
+        RackState {
+            plugins: serde_json::from_value(value)?,
+            ..Default::default()
+        }
+    } else {
+        serde_json::from_value::<RackState>(value)?
+    };
+
+    // plugins with the old default rate follow the project rate
+    for plugin in &mut state.plugins {
+        if let Some(rate) = plugin.legacy_sample_rate.take() {
+            if plugin.sample_rate.is_none() && rate != DEFAULT_SAMPLE_RATE {
+                plugin.sample_rate = Some(rate);
+            }
+        }
+    }
+    Ok(state)
+}
+
+fn default_iterations() -> usize {
+    1
+}
+
+fn default_tile_size() -> usize {
+    64
+}
+
+fn default_wet_scale() -> f32 {
+    1.0
+}
+
+/// Plugin input and output channels kept between blocks, so they are allocated only
+/// when a block is larger than the previous ones
+#[derive(Default)]
+struct ProcessBuffers {
+    inputs: Vec<Vec<f32>>,
+    outputs: Vec<Vec<f32>>,
+}
+
+impl ProcessBuffers {
+    /// Fills every input with a zero sample followed by `values` and zeroes the outputs
+    fn prepare(&mut self, input_count: usize, output_count: usize, values: &[f32]) {
+        self.inputs.resize_with(input_count, Vec::new);
+        for input in &mut self.inputs {
+            input.clear();
+            input.push(0.0);
+            input.extend_from_slice(values);
+        }
+
+        self.outputs.resize_with(output_count, Vec::new);
+        for output in &mut self.outputs {
+            output.clear();
+            output.resize(values.len() + 1, 0.0);
+        }
+    }
+
+    /// Averages all outputs into the first one
+    fn mix_outputs(&mut self) {
+        let count = self.outputs.len();
+        if let Some((first, rest)) = self.outputs.split_first_mut() {
+            for (index, sample) in first.iter_mut().enumerate() {
+                let sum: f32 = rest.iter().map(|output| output[index]).sum();
+                *sample = (*sample + sum) / count as f32;
+            }
+        }
+    }
 }
 
 impl PluginRackInstance {
-    fn new(path: PathBuf, instance: PluginInstance) -> Self {
+    fn new(path: PathBuf) -> Self {
         Self {
-            instance: Some(instance),
+            instance: None,
+            effect: None,
             editor: EditorWrapper::default(),
             input_channel: InputChannelType::Hue,
             output_channel: 0,
+            mix_outputs: false,
+            output_routes: Vec::new(),
             path,
             plugin_data: String::new(),
             wet: 1.0,
-            sample_rate: 44100.0,
+            sample_rate: None,
+            legacy_sample_rate: None,
             bypass: false,
+            iterations: 1,
+            tile_pattern: TilePattern::All,
+            tile_size: default_tile_size(),
+            region: None,
+            mask: None,
+            mask_weights: Vec::new(),
+            buffers: ProcessBuffers::default(),
+            quantizers: HashMap::new(),
+            elapsed: Duration::ZERO,
+            jitter: 0.0,
+            jitter_parameters: Vec::new(),
+            jitter_seed: 0,
+            sample_offset: 0,
+            field: None,
+            group: None,
+            keyframes: Vec::new(),
+            midi_mappings: Vec::new(),
+            automation: Vec::new(),
+            recorded: Vec::new(),
+            initial: Vec::new(),
+            events: Arc::default(),
+            program_name: String::new(),
+            parameters: Vec::new(),
+            solo: false,
+            muted: false,
+            wet_scale: 1.0,
+            inactive: None,
+            warning: None,
+        }
+    }
+
+    pub fn get_path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    pub fn get_name(&self) -> String {
+        match (&self.instance, &self.effect) {
+            (Some(instance), _) => instance.get_info().name,
+            (None, Some(effect)) => effect.name(),
+            (None, None) => self
+                .path
+                .file_stem()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Checks if VST plugin or built-in effect is loaded
+    pub fn is_loaded(&self) -> bool {
+        self.instance.is_some() || self.effect.is_some()
+    }
+
+    pub fn unique_id(&self) -> Option<i32> {
+        match (&self.instance, &self.effect) {
+            (Some(instance), _) => Some(instance.get_info().unique_id),
+            (None, Some(effect)) => Some(effect.unique_id()),
+            (None, None) => None,
+        }
+    }
+
+    pub fn parameter_count(&self) -> i32 {
+        match (&self.instance, &self.effect) {
+            (Some(instance), _) => instance.get_info().parameters,
+            (None, Some(effect)) => effect.parameter_count(),
+            (None, None) => 0,
+        }
+    }
+
+    pub fn parameter_name(&self, index: i32) -> String {
+        match (&self.instance, &self.effect) {
+            (Some(instance), _) => instance.get_parameter_object().get_parameter_name(index),
+            (None, Some(effect)) => effect.parameter_name(index),
+            (None, None) => String::new(),
+        }
+    }
+
+    pub fn get_parameter(&self, index: i32) -> f32 {
+        match (&self.instance, &self.effect) {
+            (Some(instance), _) => instance.get_parameter_object().get_parameter(index),
+            (None, Some(effect)) => effect.get_parameter(index),
+            (None, None) => 0.0,
+        }
+    }
+
+    pub fn set_parameter(&mut self, index: i32, value: f32) {
+        match (&self.instance, &mut self.effect) {
+            (Some(instance), _) => {
+                // changes made by the host are not recorded as automation
+                self.events.paused.store(true, Ordering::Relaxed);
+                instance.get_parameter_object().set_parameter(index, value);
+                self.events.paused.store(false, Ordering::Relaxed);
+            }
+            (None, Some(effect)) => effect.set_parameter(index, value),
+            (None, None) => {}
+        }
+        if let Some(parameter) = self.parameters.get_mut(index as usize) {
+            parameter.1 = value;
+        }
+    }
+
+    /// Parameter names and values as of the last refresh, used by the parameter list
+    pub fn cached_parameters(&self) -> &[(String, f32)] {
+        &self.parameters
+    }
+
+    /// Reads program name and parameters from the plugin again
+    pub fn refresh_display(&mut self) {
+        self.program_name = match &self.instance {
+            Some(instance) => {
+                let parameters = instance.get_parameter_object();
+                parameters.get_preset_name(parameters.get_preset_num())
+            }
+            None => String::new(),
+        };
+        self.parameters = (0..self.parameter_count())
+            .map(|index| (self.parameter_name(index), self.get_parameter(index)))
+            .collect();
+    }
+
+    /// Starts a new automation recording, recorded parameters return to these values
+    fn start_automation(&mut self) {
+        self.recorded.clear();
+        self.initial = (0..self.parameter_count())
+            .map(|index| self.get_parameter(index))
+            .collect();
+    }
+
+    /// Stores parameter changes made in the plugin GUI since the last call,
+    /// `position` is the processing position or `None` if the rack is idle
+    fn collect_automation(&mut self, position: Option<usize>) {
+        let changes = std::mem::take(&mut *self.events.changes.lock().unwrap());
+        for (parameter, value) in changes {
+            if let Some(cached) = self.parameters.get_mut(parameter as usize) {
+                cached.1 = value;
+            }
+
+            let first = match position {
+                Some(_) => !self.recorded.contains(&parameter),
+                None => true,
+            };
+            if first {
+                // idle change replaces the latest value, first change in a run
+                // replaces the previous recording of the parameter
+                self.automation.retain(|point| {
+                    point.parameter != parameter || point.position.is_some() != position.is_some()
+                });
+            }
+
+            if first && position.is_some() {
+                self.recorded.push(parameter);
+                let idle = self
+                    .automation
+                    .iter()
+                    .any(|point| point.parameter == parameter && point.position.is_none());
+                // parameter returns to its value from the start of the run
+                if let (false, Some(initial)) = (idle, self.initial.get(parameter as usize)) {
+                    self.automation.push(AutomationPoint {
+                        parameter,
+                        value: *initial,
+                        position: None,
+                    });
+                }
+            }
+
+            self.automation.push(AutomationPoint {
+                parameter,
+                value,
+                position,
+            });
+        }
+    }
+
+    /// Sets parameters with recorded automation to the last values recorded before `position`,
+    /// parameters recorded in this run are left to the plugin GUI
+    fn apply_automation(&mut self, position: usize) {
+        let automated: Vec<i32> = self
+            .automation
+            .iter()
+            .filter(|point| point.position.is_some() && !self.recorded.contains(&point.parameter))
+            .map(|point| point.parameter)
+            .collect();
+        if automated.is_empty() {
+            return;
+        }
+
+        let mut values: Vec<(i32, Option<usize>, f32)> = Vec::new();
+        for point in &self.automation {
+            if !automated.contains(&point.parameter)
+                || point.position.map_or(false, |at| at > position)
+            {
+                continue;
+            }
+
+            // recorded points win over the idle value, later points win over earlier ones
+            let value = values
+                .iter_mut()
+                .find(|(parameter, ..)| *parameter == point.parameter);
+            match value {
+                Some(value) if point.position >= value.1 => {
+                    *value = (point.parameter, point.position, point.value)
+                }
+                Some(_) => {}
+                None => values.push((point.parameter, point.position, point.value)),
+            }
+        }
+
+        for (parameter, _, value) in values {
+            if self.get_parameter(parameter) != value {
+                self.set_parameter(parameter, value);
+            }
+        }
+    }
+
+    /// Keeps the idle value of an automated parameter in sync with changes made by the host
+    fn update_automation(&mut self, parameter: i32, value: f32) {
+        for point in &mut self.automation {
+            if point.parameter == parameter && point.position.is_none() {
+                point.value = value;
+            }
+        }
+    }
+
+    pub fn midi_control(&self, parameter: i32) -> Option<MidiControl> {
+        self.midi_mappings
+            .iter()
+            .find(|mapping| mapping.parameter == parameter)
+            .map(|mapping| mapping.control)
+    }
+
+    /// Maps the parameter to the MIDI controller, mapping is removed if `control` is `None`
+    pub fn set_midi_control(&mut self, parameter: i32, control: Option<MidiControl>) {
+        self.midi_mappings
+            .retain(|mapping| mapping.parameter != parameter);
+        if let Some(control) = control {
+            self.midi_mappings.push(MidiMapping { control, parameter });
+        }
+    }
+
+    /// Number of audio outputs, built-in effects have one
+    pub fn output_count(&self) -> usize {
+        match (&self.instance, &self.effect) {
+            (Some(instance), _) => instance.get_info().outputs.max(0) as usize,
+            (None, Some(_)) => 1,
+            (None, None) => 0,
+        }
+    }
+
+    /// Host related capabilities reported by the plugin
+    pub fn capabilities(&self) -> Vec<(&'static str, Supported)> {
+        let instance = match &self.instance {
+            Some(instance) => instance,
+            None => return Vec::new(),
+        };
+
+        [
+            ("Receive events", CanDo::ReceiveEvents),
+            ("Receive MIDI", CanDo::ReceiveMidiEvent),
+            ("Send events", CanDo::SendEvents),
+            ("Send MIDI", CanDo::SendMidiEvent),
+            ("Receive time info", CanDo::ReceiveTimeInfo),
+            ("Offline processing", CanDo::Offline),
+            ("Bypass", CanDo::Bypass),
+            ("MIDI program names", CanDo::MidiProgramNames),
+        ]
+        .into_iter()
+        .map(|(name, can_do)| (name, instance.can_do(can_do)))
+        .collect()
+    }
+
+    /// Current value of the keyframe target
+    pub fn target_value(&self, target: KeyframeTarget) -> f32 {
+        match target {
+            KeyframeTarget::Wet => self.wet,
+            KeyframeTarget::Parameter(index) => self.get_parameter(index),
+        }
+    }
+
+    /// Sets wet and parameters to values interpolated from keyframes
+    pub fn apply_keyframes(&mut self, frame: usize) {
+        let targets: Vec<KeyframeTarget> = self.keyframes.iter().map(|key| key.target).collect();
+
+        for target in targets {
+            let value = match animation::keyframe_value(&self.keyframes, target, frame) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            match target {
+                KeyframeTarget::Wet => self.wet = value,
+                KeyframeTarget::Parameter(index) => self.set_parameter(index, value),
+            }
+        }
+    }
+
+    fn initialize(&mut self) -> Result<()> {
+        if let Some(inst) = self.instance.as_mut() {
+            inst.init();
+            self.editor = EditorWrapper::new(inst.get_editor());
+            self.validate();
+            if !self.plugin_data.is_empty() {
+                log::debug!("found a plugin data LOADING NOW!");
+                self.load_block()?;
+            }
+        } else if self.effect.is_some() && !self.plugin_data.is_empty() {
+            self.load_block()?;
+        }
+        self.refresh_display();
+        Ok(())
+    }
+
+    /// Checks if plugin can process images, incompatible plugins are marked inactive
+    fn validate(&mut self) {
+        let instance = match &self.instance {
+            Some(instance) => instance,
+            None => return,
+        };
+        let info = instance.get_info();
+
+        self.inactive = if info.inputs == 0 {
+            Some(String::from("Plugin has no audio inputs"))
+        } else if info.outputs == 0 {
+            Some(String::from("Plugin has no audio outputs"))
+        } else if matches!(info.category, Category::OfflineProcess) {
+            Some(String::from("Offline processing plugins are not supported"))
+        } else if matches!(info.category, Category::Shell) {
+            Some(String::from("Shell plugins are not supported"))
+        } else {
+            None
+        };
+
+        let midi = matches!(instance.can_do(CanDo::ReceiveMidiEvent), Supported::Yes);
+        self.warning = if matches!(info.category, Category::Synth) && !midi {
+            Some(String::from(
+                "Instrument without MIDI input, output may not depend on image",
+            ))
+        } else if !self.mix_outputs && self.output_channel >= info.outputs as usize {
+            Some(format!(
+                "Output channel {} is not available, plugin has {} outputs and is skipped",
+                self.output_channel, info.outputs
+            ))
+        } else {
+            None
+        };
+
+        if let Some(reason) = self.inactive.as_ref().or(self.warning.as_ref()) {
+            log::warn!("{}: {}", info.name, reason);
+        }
+    }
+
+    fn save_block(&mut self) {
+        if let Some(inst) = self.instance.as_mut() {
+            let bank_data = inst.get_parameter_object().get_bank_data();
+            self.plugin_data = base64::encode(bank_data);
+        } else if let Some(effect) = &self.effect {
+            self.plugin_data = effect.save_state();
+        }
+    }
+
+    fn load_block(&mut self) -> Result<()> {
+        if let Some(inst) = self.instance.as_mut() {
+            inst.get_parameter_object()
+                .load_bank_data(&base64::decode(&self.plugin_data).map_err(anyhow::Error::from)?);
+        } else if let Some(effect) = self.effect.as_mut() {
+            effect.load_state(&self.plugin_data)?;
+        }
+        self.refresh_display();
+        Ok(())
+    }
+
+    /// Checks if pixel at specified coordinates is written back by this plugin
+    pub fn affects(&self, x: usize, y: usize, width: usize, height: usize, scale: f32) -> bool {
+        if let Some(region) = &self.region {
+            if !region.contains(x, y, width, height) {
+                return false;
+            }
+        }
+
+        let tile_size = self.scaled_tile_size(scale);
+        let tiles_per_row = (width + tile_size - 1) / tile_size;
+        let (tile_x, tile_y) = self.tile_at(x, y, scale);
+        self.tile_pattern.contains(tile_x, tile_y, tiles_per_row)
+    }
+
+    /// Mask weight (0.0 - 1.0) of the pixel, 1.0 without mask
+    fn mask_weight(&self, x: usize, y: usize, width: usize) -> f32 {
+        match self.mask {
+            Some(_) => self.mask_weights.get(y * width + x).copied().unwrap_or(1.0),
+            None => 1.0,
+        }
+    }
+
+    /// Column and row of the tile grid containing pixel at specified coordinates
+    pub fn tile_at(&self, x: usize, y: usize, scale: f32) -> (usize, usize) {
+        let tile_size = self.scaled_tile_size(scale);
+        (x / tile_size, y / tile_size)
+    }
+
+    fn scaled_tile_size(&self, scale: f32) -> usize {
+        ((self.tile_size as f32 * scale).round() as usize).max(1)
+    }
+
+    /// Exports plugin parameters as text tagged with the plugin unique ID
+    pub fn export_settings(&mut self) -> Option<String> {
+        self.save_block();
+        self.unique_id()
+            .map(|unique_id| format!("vstimage:{}:{}", unique_id, self.plugin_data))
+    }
+
+    /// Loads parameters exported by `export_settings`, only settings of the same plugin are accepted
+    pub fn import_settings(&mut self, settings: &str) -> Result<()> {
+        let mut parts = settings.trim().splitn(3, ':');
+
+        let (unique_id, data) = match (parts.next(), parts.next(), parts.next()) {
+            (Some("vstimage"), Some(unique_id), Some(data)) => {
+                (unique_id.parse::<i32>().map_err(anyhow::Error::from)?, data)
+            }
+            _ => return Err(anyhow::anyhow!("Clipboard does not contain plugin settings").into()),
+        };
+
+        if self.unique_id().map_or(false, |id| id != unique_id) {
+            return Err(anyhow::anyhow!("Settings belong to a different plugin").into());
+        }
+
+        self.plugin_data = data.to_string();
+        self.load_block()
+    }
+
+    /// Sets random parameters, channels and wet
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        for index in 0..self.parameter_count() {
+            self.set_parameter(index, rng.gen());
+        }
+
+        if let Some(instance) = self.instance.as_mut() {
+            let info = instance.get_info();
+            self.input_channel = *[
+                InputChannelType::Hue,
+                InputChannelType::Saturation,
+                InputChannelType::Value,
+            ]
+            .choose(rng)
+            .unwrap();
+            self.output_channel = rng.gen_range(0..info.outputs.max(1) as usize);
+        }
+        if self.is_loaded() {
+            self.wet = rng.gen_range(0.5..=1.0);
+        }
+    }
+
+    /// Processes pixels of a block with all plugin iterations
+    pub fn process<S: Sample>(&mut self, pixels: &mut [S], location: &BlockLocation, decay: f32) {
+        if self.field.is_some() && location.field.is_some() && self.field != location.field {
+            return;
+        }
+
+        let started = Instant::now();
+        self.process_tiles(pixels, location, decay);
+        self.elapsed += started.elapsed();
+    }
+
+    /// Processes the block, split by tiles if parameters are jittered
+    fn process_tiles<S: Sample>(&mut self, pixels: &mut [S], location: &BlockLocation, decay: f32) {
+        let wet = self.wet * self.wet_scale * decay;
+        let base = self.jitter_base();
+        if base.is_empty() {
+            for iteration in 0..self.iterations {
+                self.process_block(pixels, location, wet, iteration);
+            }
+            return;
+        }
+
+        // block is split into runs of pixels from the same tile
+        let tile_size = self.scaled_tile_size(location.scale);
+        let mut start = 0;
+        while start < pixels.len() / 4 {
+            let position = location.offset + start;
+            let (x, y) = location.pixel(start);
+            let length = (tile_size - x % tile_size)
+                .min(location.width - x)
+                .min(pixels.len() / 4 - start);
+            let run_location = BlockLocation {
+                offset: position,
+                ..*location
+            };
+
+            self.apply_jitter(&base, self.tile_at(x, y, location.scale));
+            let run = &mut pixels[start * 4..(start + length) * 4];
+            for iteration in 0..self.iterations {
+                self.process_block(run, &run_location, wet, iteration);
+            }
+            start += length;
+        }
+
+        for (index, value) in base {
+            self.set_parameter(index, value);
+        }
+    }
+
+    /// Current values of parameters changed by jitter, empty if jitter is disabled
+    fn jitter_base(&self) -> Vec<(i32, f32)> {
+        if self.jitter <= 0.0 {
+            return Vec::new();
+        }
+
+        let count = self.parameter_count();
+        let indices: Vec<i32> = if self.jitter_parameters.is_empty() {
+            (0..count).collect()
+        } else {
+            self.jitter_parameters
+                .iter()
+                .copied()
+                .filter(|index| (0..count).contains(index))
+                .collect()
+        };
+        indices
+            .into_iter()
+            .map(|index| (index, self.get_parameter(index)))
+            .collect()
+    }
+
+    /// Sets parameters to base values with offsets of the tile, same seed gives same offsets
+    fn apply_jitter(&mut self, base: &[(i32, f32)], (tile_x, tile_y): (usize, usize)) {
+        let tile = ((tile_y as u64) << 32) | tile_x as u64;
+        let mut rng =
+            StdRng::seed_from_u64(self.jitter_seed ^ tile.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        for (index, value) in base {
+            let offset = rng.gen_range(-1.0..=1.0) * self.jitter;
+            self.set_parameter(*index, (value + offset).clamp(0.0, 1.0));
+        }
+    }
+
+    /// Value of the input channel which is sent to the plugin for RGBA pixel
+    pub fn map_sample<S: Sample>(&self, pixel: &[S]) -> f32 {
+        let srgb = Srgba::new(
+            pixel[0].to_f32() / S::MAX,
+            pixel[1].to_f32() / S::MAX,
+            pixel[2].to_f32() / S::MAX,
+            pixel[3].to_f32() / S::MAX,
+        );
+        let hsv = Hsva::from_color(srgb);
+        match self.input_channel {
+            InputChannelType::Hue => hsv.hue.to_positive_degrees() / 360.0,
+            InputChannelType::Saturation => hsv.saturation,
+            InputChannelType::Value => hsv.value,
+        }
+    }
+
+    /// Values sent to the plugin for RGBA pixels, luminance in grayscale mode
+    fn input_samples<S: Sample>(&self, pixels: &[S], location: &BlockLocation) -> Vec<f32> {
+        match (location.channel, location.grayscale) {
+            (Some(channel), _) => pixels
+                .chunks_exact(4)
+                .map(|pixel| pixel[channel.index()].to_f32() / S::MAX)
+                .collect(),
+            (None, Some(_)) => pixels.chunks_exact(4).map(luminance).collect(),
+            (None, None) => simd::rgb_to_channel(&planar(pixels), self.input_channel),
+        }
+    }
+
+    /// Maps RGBA pixels to the input channel, runs them through the plugin and writes the result back
+    fn process_block<S: Sample>(
+        &mut self,
+        pixels: &mut [S],
+        location: &BlockLocation,
+        wet: f32,
+        iteration: usize,
+    ) {
+        if self.effect.is_some() {
+            self.process_effect(pixels, location, wet, iteration);
+            return;
+        }
+        let (input_count, output_count) = match self.instance.as_ref() {
+            Some(instance) => {
+                let info = instance.get_info();
+                (info.inputs as usize, info.outputs as usize)
+            }
+            None => return,
+        };
+
+        // channel is validated when it is changed, but plugin may have fewer outputs after update
+        if self.bypass
+            || self.muted
+            || self.inactive.is_some()
+            || (!self.mix_outputs && self.output_channel >= output_count)
+        {
+            return;
+        }
+        //println!("i: {} o: {}", input_count, output_count);
+        //let start = std::time::Instant::now();
+        let values = self.input_samples(pixels, location);
+        let instance = match self.instance.as_mut() {
+            Some(instance) => instance,
+            None => return,
+        };
+        let mut buffers = std::mem::take(&mut self.buffers);
+        buffers.prepare(input_count, output_count, &values);
+        let block_size = values.len() + 1;
+
+        let mut buf: HostBuffer<f32> = HostBuffer::new(input_count, output_count);
+        let mut audio_buffer = buf.bind(&buffers.inputs, &mut buffers.outputs);
+
+        //println!("Mapping took: {} ms", start.elapsed().as_millis());
+
+        //let start = std::time::Instant::now();
+        //println!("processing");
+        instance.suspend();
+        // keeps delays and filters covering the same image area on scaled images
+        let sample_rate = self.sample_rate.unwrap_or(location.sample_rate);
+        instance.set_sample_rate(sample_rate * location.scale);
+        instance.set_block_size(block_size as i64);
+        instance.resume();
+        instance.start_process();
+        instance.process(&mut audio_buffer);
+        instance.stop_process();
+        instance.suspend();
+
+        //println!("VST Processing took: {} ms", start.elapsed().as_millis());
+        //let start = std::time::Instant::now();
+        let channel = if self.mix_outputs {
+            buffers.mix_outputs();
+            0
+        } else {
+            self.output_channel
+        };
+        let output = &mut buffers.outputs[channel];
+        self.apply_sample_offset(output, 1, location);
+        let key = (location.pass, iteration, 0);
+        let mut quantizer = self.take_quantizer(key, location);
+        self.write_samples(
+            pixels,
+            output,
+            self.input_channel,
+            location,
+            wet,
+            &mut quantizer,
+        );
+        self.quantizers.insert(key, quantizer);
+
+        // routes replace HSV channels, so they are not used with RGB and grayscale processing
+        let hsv = location.channel.is_none() && location.grayscale.is_none();
+        if hsv && !self.mix_outputs {
+            for (index, route) in self.output_routes.clone().into_iter().enumerate() {
+                if route.output == channel {
+                    continue;
+                }
+                if let Some(output) = buffers.outputs.get_mut(route.output) {
+                    self.apply_sample_offset(output, 1, location);
+                    let key = (location.pass, iteration, index + 1);
+                    let mut quantizer = self.take_quantizer(key, location);
+                    self.write_samples(
+                        pixels,
+                        output,
+                        route.channel,
+                        location,
+                        wet,
+                        &mut quantizer,
+                    );
+                    self.quantizers.insert(key, quantizer);
+                }
+            }
+        }
+        //println!("Image return took: {} ms", start.elapsed().as_millis());
+        self.buffers = buffers;
+    }
+
+    /// Takes quantizer which continues dithering of the previous block written by the same pass,
+    /// plugin iteration and output
+    fn take_quantizer(
+        &mut self,
+        key: (usize, usize, usize),
+        location: &BlockLocation,
+    ) -> Quantizer {
+        let mut quantizer = self
+            .quantizers
+            .remove(&key)
+            .filter(|quantizer| quantizer.matches(location.dither, location.width))
+            .unwrap_or_else(|| Quantizer::new(location.dither, location.width));
+        // rows below the odd field are quantized with the even field
+        quantizer.diffuse_down(location.field != Some(Field::Odd));
+        quantizer
+    }
+
+    /// Rotates processed values (`channels` per pixel) by the sample offset
+    fn apply_sample_offset(&self, values: &mut [f32], channels: usize, location: &BlockLocation) {
+        let count = values.len() / channels;
+        if self.sample_offset == 0 || count == 0 {
+            return;
+        }
+
+        // offset covers the same image area on scaled images
+        let offset = (self.sample_offset as f32 * location.scale).round() as isize;
+        values.rotate_right(offset.rem_euclid(count as isize) as usize * channels);
+    }
+
+    /// Replaces HSV `channel` of pixels (RGB channel or luminance if the block is processed
+    /// that way) with processed samples
+    fn write_samples<S: Sample>(
+        &self,
+        pixels: &mut [S],
+        samples: &[f32],
+        channel: InputChannelType,
+        location: &BlockLocation,
+        wet: f32,
+        quantizer: &mut Quantizer,
+    ) {
+        // HSV channel of the whole block is replaced at once
+        let replaced = (location.channel.is_none() && location.grayscale.is_none()).then(|| {
+            let mut rgb = planar(pixels);
+            let values: Vec<f32> = samples.iter().map(|sample| sample * wet).collect();
+            simd::replace_channel(&mut rgb, &values, channel);
+            rgb
+        });
+
+        for (idx, (pixel, sample)) in pixels.chunks_exact_mut(4).zip(samples).enumerate() {
+            let (x, y) = location.pixel(idx);
+            if !self.affects(x, y, location.width, location.height, location.scale) {
+                continue;
+            }
+
+            let alpha = pixel[3].to_f32() / S::MAX;
+            let values = match (location.channel, location.grayscale) {
+                (Some(channel), _) => {
+                    let mut values = [0, 1, 2, 3].map(|index| pixel[index].to_f32() / S::MAX);
+                    values[channel.index()] = *sample * wet;
+                    values
+                }
+                (None, Some(Grayscale::Gray)) => {
+                    [*sample * wet, *sample * wet, *sample * wet, alpha]
+                }
+                (None, Some(Grayscale::Retint)) => {
+                    // chroma is kept as difference of channels from luminance
+                    let offset = *sample * wet - luminance(pixel);
+                    let channel = |index: usize| pixel[index].to_f32() / S::MAX + offset;
+                    [channel(0), channel(1), channel(2), alpha]
+                }
+                (None, None) => {
+                    let [red, green, blue] = replaced.as_ref().unwrap().get(idx);
+                    [red, green, blue, alpha]
+                }
+            };
+
+            let weight = self.mask_weight(x, y, location.width);
+            let values = [0, 1, 2, 3].map(|index| {
+                let dry = pixel[index].to_f32();
+                dry + (values[index] * S::MAX - dry) * weight
+            });
+            pixel.copy_from_slice(&quantizer.quantize(values, x, y));
+        }
+    }
+
+    /// Runs pixels or input channel samples through the effect and writes the result back
+    fn process_effect<S: Sample>(
+        &mut self,
+        pixels: &mut [S],
+        location: &BlockLocation,
+        wet: f32,
+        iteration: usize,
+    ) {
+        let input = match &self.effect {
+            Some(effect) if !self.bypass && !self.muted => effect.input(),
+            _ => return,
+        };
+
+        let mut processed: Vec<f32> = match input {
+            EffectInput::Pixels => pixels.iter().map(|value| value.to_f32() / S::MAX).collect(),
+            EffectInput::Samples => self.input_samples(pixels, location),
+        };
+        if let Some(effect) = self.effect.as_mut() {
+            effect.process(&mut processed, location);
+        }
+
+        let key = (location.pass, iteration, 0);
+        let mut quantizer = self.take_quantizer(key, location);
+        if input == EffectInput::Samples {
+            self.apply_sample_offset(&mut processed, 1, location);
+            self.write_samples(
+                pixels,
+                &processed,
+                self.input_channel,
+                location,
+                wet,
+                &mut quantizer,
+            );
+            self.quantizers.insert(key, quantizer);
+            return;
+        }
+        self.apply_sample_offset(&mut processed, 4, location);
+
+        // pixel effects are mixed with the input instead of scaling the channel
+        for (idx, (pixel, processed)) in pixels
+            .chunks_exact_mut(4)
+            .zip(processed.chunks_exact(4))
+            .enumerate()
+        {
+            let (x, y) = location.pixel(idx);
+            if !self.affects(x, y, location.width, location.height, location.scale) {
+                continue;
+            }
+
+            let wet = wet * self.mask_weight(x, y, location.width);
+            let mut values = [0.0; 4];
+            for ((value, dry), processed) in values.iter_mut().zip(pixel.iter()).zip(processed) {
+                let dry = dry.to_f32() / S::MAX;
+                *value = (dry + (processed - dry) * wet) * S::MAX;
+            }
+            pixel.copy_from_slice(&quantizer.quantize(values, x, y));
+        }
+        self.quantizers.insert(key, quantizer);
+    }
+}
+
+/// Relative luminance (0.0 - 1.0) of RGBA pixel
+fn luminance<S: Sample>(pixel: &[S]) -> f32 {
+    (0.2126 * pixel[0].to_f32() + 0.7152 * pixel[1].to_f32() + 0.0722 * pixel[2].to_f32()) / S::MAX
+}
+
+/// Normalized RGB channels of RGBA pixels
+fn planar<S: Sample>(pixels: &[S]) -> Planar {
+    let mut rgb = Planar::with_capacity(pixels.len() / 4);
+    for pixel in pixels.chunks_exact(4) {
+        rgb.push([0, 1, 2].map(|index| pixel[index].to_f32() / S::MAX));
+    }
+    rgb
+}
+
+/// Replaces colors of RGBA pixels with their luminance
+fn desaturate<S: Sample>(data: &mut [S]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let gray = S::from_f32(luminance(pixel) * S::MAX);
+        pixel[..3].fill(gray);
+    }
+}
+
+/// Copies rectangle (left, top, width, height) of RGBA pixels
+fn copy_area<S: Copy>(
+    data: &[S],
+    width: u32,
+    (left, top, tile_width, tile_height): (u32, u32, u32, u32),
+) -> Vec<S> {
+    let (width, left, tile_width) = (width as usize, left as usize, tile_width as usize);
+    let mut area = Vec::with_capacity(tile_width * tile_height as usize * 4);
+    for row in top as usize..(top + tile_height) as usize {
+        let start = (row * width + left) * 4;
+        area.extend_from_slice(&data[start..start + tile_width * 4]);
+    }
+    area
+}
+
+impl Host for PluginHost {
+    fn automate(&self, index: i32, value: f32) {
+        log::debug!("Parameter {} had its value changed to {}", index, value);
+        if self.events.paused.load(Ordering::Relaxed) {
+            return;
+        }
+        *self.parameter_changed.lock().unwrap() = Some(Instant::now());
+        self.events.changes.lock().unwrap().push((index, value));
+    }
+
+    fn process_events(&self, events: &vst::api::Events) {
+        log::debug!("Plugin called the {:?} event", events.events);
+    }
+
+    fn update_display(&self) {
+        log::debug!("Plugin requested display update");
+        self.events.display_changed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl PluginRack {
+    pub fn new() -> Self {
+        Self {
+            parameter_changed: Arc::default(),
+            plugins: Vec::new(),
+            groups: Vec::new(),
+            images: Vec::new(),
+            deep_images: Vec::new(),
+            graph: None,
+            block_size: 8192,
+            iterations: 1,
+            wet_decay: 0.0,
+            interlaced: false,
+            sample_rate: SampleRate::default(),
+            dither: Dither::None,
+            grayscale: None,
+            seed: None,
+            animation: None,
+            reset_state_per_frame: false,
+            export: ExportOptions::default(),
+            export_profile: ExportProfile::default(),
+            source_name: String::from("image"),
+            source_path: None,
+            metadata: Metadata::default(),
+            autosave: None,
+            checkpoint: None,
+            history: TileHistory::default(),
+            redo: None,
+            proxy_scale: 0.25,
+            proxy: None,
+            position: 0,
+            total: 0,
+            pass: 0,
+            dirty: BTreeSet::new(),
+            finished: true,
+            started: Instant::now(),
+            run_time: Duration::ZERO,
+        }
+    }
+
+    /// Returns `true` once after plugin parameters were changed and left untouched for `debounce`
+    pub fn take_parameter_change(&self, debounce: Duration) -> bool {
+        let mut changed = self.parameter_changed.lock().unwrap();
+        match *changed {
+            Some(time) if time.elapsed() >= debounce => {
+                *changed = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn has_midi_mappings(&self) -> bool {
+        self.plugins
+            .iter()
+            .any(|plugin| !plugin.midi_mappings.is_empty())
+    }
+
+    /// Sets parameters mapped to the MIDI controller, changes are reported like
+    /// the ones made in plugin editors
+    pub fn apply_midi(&mut self, control: MidiControl, value: f32) {
+        let mut changed = false;
+        for plugin in &mut self.plugins {
+            let parameters: Vec<i32> = plugin
+                .midi_mappings
+                .iter()
+                .filter(|mapping| mapping.control == control)
+                .map(|mapping| mapping.parameter)
+                .collect();
+            for parameter in parameters {
+                plugin.set_parameter(parameter, value);
+                plugin.update_automation(parameter, value);
+                changed = true;
+            }
+        }
+
+        if changed {
+            *self.parameter_changed.lock().unwrap() = Some(Instant::now());
+        }
+    }
+
+    /// Toggles solo of the plugin, while any plugin is soloed all other plugins are bypassed
+    pub fn toggle_solo(&mut self, id: usize) {
+        self.plugins[id].solo = !self.plugins[id].solo;
+        self.refresh_plugin_states();
+    }
+
+    pub fn is_muted(&self, id: usize) -> bool {
+        self.plugins[id].muted
+    }
+
+    /// Adds next plugin into the group of the plugin, new group is created if plugin is not grouped
+    pub fn group_with_next(&mut self, id: usize) {
+        if id + 1 >= self.plugins.len() {
+            return;
+        }
+
+        let group = match self.plugins[id].group {
+            Some(group) => group,
+            None => {
+                self.groups.push(PluginGroup {
+                    name: format!("Group {}", self.groups.len() + 1),
+                    bypass: false,
+                    wet: 1.0,
+                    collapsed: false,
+                });
+                self.groups.len() - 1
+            }
+        };
+
+        self.plugins[id].group = Some(group);
+        self.plugins[id + 1].group = Some(group);
+        self.refresh_plugin_states();
+    }
+
+    pub fn ungroup(&mut self, id: usize) {
+        self.plugins[id].group = None;
+        self.refresh_plugin_states();
+    }
+
+    pub fn set_group_bypass(&mut self, group: usize, bypass: bool) {
+        self.groups[group].bypass = bypass;
+        self.refresh_plugin_states();
+    }
+
+    pub fn set_group_wet(&mut self, group: usize, wet: f32) {
+        self.groups[group].wet = wet;
+        self.refresh_plugin_states();
+    }
+
+    /// Removes empty groups and updates solo and group state of the plugins
+    fn refresh_plugin_states(&mut self) {
+        let mut remap = Vec::with_capacity(self.groups.len());
+        let mut groups = Vec::new();
+        for (id, group) in std::mem::take(&mut self.groups).into_iter().enumerate() {
+            if self.plugins.iter().any(|plugin| plugin.group == Some(id)) {
+                remap.push(Some(groups.len()));
+                groups.push(group);
+            } else {
+                remap.push(None);
+            }
+        }
+        self.groups = groups;
+
+        let soloing = self.plugins.iter().any(|plugin| plugin.solo);
+        for plugin in &mut self.plugins {
+            plugin.group = plugin.group.and_then(|id| remap.get(id).copied().flatten());
+            let group = plugin.group.and_then(|id| self.groups.get(id));
+            plugin.muted =
+                (soloing && !plugin.solo) || group.map(|group| group.bypass).unwrap_or(false);
+            plugin.wet_scale = group.map(|group| group.wet).unwrap_or(1.0);
+        }
+    }
+
+    /// Prepares still image for processing in place, tiles are saved only when processing is
+    /// about to change them
+    fn snapshot_original(&mut self) {
+        if self.images.len() == 1 {
+            let image = std::mem::replace(&mut self.images[0], image::RgbaImage::new(0, 0));
+            self.images.push(image);
+            if let Some(deep) = self.deep_images.first_mut() {
+                let empty = deep.empty();
+                let deep = std::mem::replace(deep, empty);
+                self.deep_images.push(deep);
+            }
+        } else if self.history.is_empty() {
+            // result loaded with the project is processed again,
+            // its original is kept as tiles which differ from it
+            let mut snapshot = TileSnapshot::new(&self.images[1], self.deep_images.get(1));
+            snapshot.save_changed(
+                (&self.images[0], self.deep_images.first()),
+                (&self.images[1], self.deep_images.get(1)),
+            );
+            self.images[0] = image::RgbaImage::new(0, 0);
+            if let Some(deep) = self.deep_images.first_mut() {
+                *deep = deep.empty();
+            }
+            self.history.push(snapshot);
+        }
+
+        let snapshot = TileSnapshot::new(self.images.last().unwrap(), self.deep_images.last());
+        self.history.push(snapshot);
+    }
+
+    /// Saves tiles covering pixel `range` (or the whole image) before the current run changes
+    /// them
+    fn save_snapshot_tiles(&mut self, range: Option<Range<usize>>) {
+        let snapshot = match self.history.current() {
+            Some(snapshot) if self.proxy.is_none() => snapshot,
+            _ => return,
+        };
+        let image = self.images.last().unwrap();
+        match range {
+            Some(range) => snapshot.save_range(image, self.deep_images.last(), range),
+            None => snapshot.save_all(image, self.deep_images.last()),
+        }
+    }
+
+    /// Original made of the result with saved tiles of all runs pasted back
+    fn snapshot_original_images(&self) -> anyhow::Result<(image::RgbaImage, Option<DeepImage>)> {
+        let mut image = self.images.last().unwrap().clone();
+        let mut deep = self.deep_images.last().cloned();
+        self.history.restore(&mut image, deep.as_mut())?;
+        Ok((image, deep))
+    }
+
+    /// Removes empty `images[0]` when there are no tiles of the original left
+    fn remove_original_placeholder(&mut self) {
+        self.images.remove(0);
+        if self.deep_images.len() > 1 {
+            self.deep_images.remove(0);
+        }
+    }
+
+    /// Pastes tiles of all runs into the result, so it becomes the only (unprocessed) image
+    fn discard_result(&mut self) {
+        if self.history.is_empty() || self.proxy.is_some() {
+            return;
+        }
+
+        let result = self
+            .history
+            .restore(self.images.last_mut().unwrap(), self.deep_images.last_mut());
+        if let Err(error) = result {
+            log::error!("Unable to restore original image: {}", error);
+        }
+        self.history.clear();
+        self.remove_original_placeholder();
+    }
+
+    /// Undoes the last processing run, still images bring back only the tiles it changed
+    pub fn undo(&mut self) {
+        if !self.history.is_empty() {
+            let result = self
+                .history
+                .undo(self.images.last_mut().unwrap(), self.deep_images.last_mut());
+            if let Err(error) = result {
+                log::error!("Unable to undo processing: {}", error);
+            }
+            if self.history.is_empty() {
+                self.remove_original_placeholder();
+            }
+            return;
+        }
+
+        if self.images.len() > 1 {
+            let image = self.images.pop().unwrap();
+            let deep = if self.deep_images.len() > self.images.len() {
+                self.deep_images.pop()
+            } else {
+                None
+            };
+            self.redo = Some((image, deep));
+        }
+    }
+
+    /// Restores result removed by undo
+    pub fn redo(&mut self) {
+        if self.history.can_redo() {
+            if self.history.is_empty() {
+                self.images.insert(0, image::RgbaImage::new(0, 0));
+                if let Some(deep) = self.deep_images.first() {
+                    let empty = deep.empty();
+                    self.deep_images.insert(0, empty);
+                }
+            }
+            let result = self
+                .history
+                .redo(self.images.last_mut().unwrap(), self.deep_images.last_mut());
+            if let Err(error) = result {
+                log::error!("Unable to redo processing: {}", error);
+            }
+            return;
+        }
+
+        if let Some((image, deep)) = self.redo.take() {
+            self.images.push(image);
+            self.deep_images.extend(deep);
+        }
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.redo.is_some() || self.history.can_redo()
+    }
+
+    pub fn calculate_memory_size(&self) -> usize {
+        let mut size = 0;
+        for image in &self.images {
+            size += std::mem::size_of_val(image) * image.len();
+        }
+        for image in &self.deep_images {
+            size += image.memory_size();
+        }
+        if let Some(animation) = &self.animation {
+            size += animation.memory_size();
+        }
+        size + self.history.memory_size()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn revert(&mut self) {
+        self.discard_result();
+        self.redo = None;
+        if self.images.len() > 1 {
+            self.images.drain(1..);
+            self.deep_images.truncate(1);
+        }
+    }
+
+    /// Loads VST plugin or effect if `file` is made by `effects::effect_path`
+    pub fn load_plugin(&mut self, file: PathBuf) -> Result<()> {
+        let mut plugin = PluginRackInstance::new(file);
+        self.load_slot(&mut plugin)?;
+        self.insert_plugin(plugin)
+    }
+
+    /// Loads plugin instance or creates effect for the slot path
+    fn load_slot(&self, plugin: &mut PluginRackInstance) -> Result<()> {
+        match effects::create_effect(&plugin.path) {
+            Some(effect) => plugin.effect = Some(effect),
+            None => plugin.instance = Some(self.load_instance(&plugin.path, &plugin.events)?),
+        }
+        Ok(())
+    }
+
+    /// Loads plugin with its own host, parameter changes made by the plugin go to `events`
+    fn load_instance(
+        &self,
+        file: &std::path::Path,
+        events: &Arc<PluginEvents>,
+    ) -> Result<PluginInstance> {
+        let host = Arc::new(Mutex::new(PluginHost {
+            parameter_changed: Arc::clone(&self.parameter_changed),
+            events: Arc::clone(events),
+        }));
+        let failure = if plugin_library::is_blacklisted(file) {
+            LoadFailure::Blacklisted
+        } else {
+            match PluginLoader::load(file, host).and_then(|mut loader| loader.instance()) {
+                Ok(instance) => return Ok(instance),
+                Err(error) => plugin_library::diagnose(file, &error),
+            }
+        };
+
+        Err(VstImageError::PluginLoad {
+            path: file.to_path_buf(),
+            failure,
+        })
+    }
+
+    /// Replaces the chain with `count` random effects from `library` with random settings
+    pub fn randomize_chain(&mut self, library: &[PathBuf], count: usize, seed: u64) -> Result<()> {
+        if library.is_empty() {
+            return Err(anyhow::anyhow!("Plugin library is empty").into());
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        while !self.plugins.is_empty() {
+            self.remove_plugin(0);
+        }
+        self.graph = None;
+
+        for _ in 0..count {
+            let path = library.choose(&mut rng).unwrap().clone();
+
+            if let Err(error) = self.load_plugin(path.clone()) {
+                log::warn!("Skipping {}: {}", path.display(), error);
+                continue;
+            }
+
+            let plugin = self.plugins.last_mut().unwrap();
+            plugin.randomize(&mut rng);
+        }
+
+        self.seed = Some(seed);
+        Ok(())
+    }
+
+    pub fn progress(&self) -> Progress {
+        let width = self.images.last().map_or(0, |image| image.width() as usize);
+        let total = match self.total {
+            0 => self.images.last().map_or(0, |image| image.pixels().len()),
+            total => total,
+        };
+        let done = self.position.min(total);
+        let part = if total == 0 {
+            0.0
+        } else {
+            done as f32 / total as f32
+        };
+
+        let mut fraction = (self.pass as f32 + part) / self.iterations.max(1) as f32;
+        if let Some(animation) = &self.animation {
+            fraction = (animation.current as f32 + fraction) / animation.frames.len().max(1) as f32;
+        }
+
+        let plugin = self
+            .plugins
+            .iter()
+            .filter(|plugin| !plugin.elapsed.is_zero())
+            .max_by_key(|plugin| plugin.elapsed)
+            .map(PluginRackInstance::get_name);
+
+        let next = done.min(total.saturating_sub(1));
+        Progress {
+            done,
+            total,
+            fraction: fraction.clamp(0.0, 1.0),
+            plugin,
+            tile: (next % width.max(1), next / width.max(1)),
+            elapsed: self.run_time,
+        }
+    }
+
+    pub fn get_current_pass(&self) -> usize {
+        self.pass
+    }
+
+    pub fn load_uninitialzed_plugins(&mut self) -> Result<()> {
+        for id in 0..self.plugins.len() {
+            if self.plugins[id].instance.is_some() || self.plugins[id].effect.is_some() {
+                continue;
+            }
+
+            if let Some(effect) = effects::create_effect(&self.plugins[id].path) {
+                self.plugins[id].effect = Some(effect);
+                self.plugins[id].initialize()?;
+                continue;
+            }
+
+            match self.load_instance(&self.plugins[id].path, &self.plugins[id].events) {
+                Ok(instance) => {
+                    self.plugins[id].instance = Some(instance);
+                    self.plugins[id].initialize()?;
+                }
+                Err(error) => log::error!("{}", error),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn load_image<P: AsRef<std::path::Path>>(&mut self, file: P) -> Result<()> {
+        self.decode_image(&file)?;
+        if let Some(name) = file.as_ref().file_stem() {
+            self.source_name = name.to_string_lossy().to_string();
+        }
+        self.source_path = Some(file.as_ref().to_path_buf());
+        Ok(())
+    }
+
+    fn decode_image<P: AsRef<std::path::Path>>(&mut self, file: P) -> Result<()> {
+        if animation::is_animated_format(&file) {
+            if let Some(animation) = Animation::load(&file)? {
+                self.deep_images.clear();
+                self.history.clear();
+                self.proxy = None;
+                self.redo = None;
+                self.images = vec![animation.frames[0].image.clone()];
+                self.animation = Some(animation);
+                return Ok(());
+            }
+        }
+
+        if raw::is_raw(&file) {
+            self.set_image(raw::load(file)?);
+            return Ok(());
+        }
+
+        let data = std::fs::read(&file)?;
+        let mut reader = ImageReader::new(Cursor::new(data.as_slice()));
+        if let Ok(format) = image::ImageFormat::from_path(&file) {
+            reader.set_format(format);
+        }
+
+        let img = reader.with_guessed_format()?.decode()?;
+        self.set_decoded_image(img, &data);
+        Ok(())
+    }
+
+    pub fn load_image_data(&mut self, file: &[u8]) -> Result<()> {
+        let img = ImageReader::new(Cursor::new(file))
+            .with_guessed_format()?
+            .decode()?;
+        self.set_decoded_image(img, file);
+        Ok(())
+    }
+
+    /// Keeps metadata of the encoded image, EXIF orientation is applied to pixels
+    fn set_decoded_image(&mut self, img: image::DynamicImage, data: &[u8]) {
+        let (metadata, orientation) = Metadata::read(data);
+        self.set_image(metadata::apply_orientation(img, orientation));
+        self.metadata = metadata;
+    }
+
+    /// Rasterizes SVG file to the given width
+    pub fn load_svg<P: AsRef<std::path::Path>>(&mut self, file: P, width: u32) -> Result<()> {
+        let img = svg::rasterize(file, width)?;
+        self.set_image(image::DynamicImage::ImageRgba8(img));
+        Ok(())
+    }
+
+    /// Replaces working image, images with more than 8 bits per channel are processed in 16 bit,
+    /// float images are processed without clamping
+    pub fn set_image(&mut self, img: image::DynamicImage) {
+        self.images.clear();
+        self.deep_images.clear();
+        self.history.clear();
+        self.proxy = None;
+        self.redo = None;
+        self.animation = None;
+        self.metadata = Metadata::default();
+        self.source_name = String::from("image");
+        self.source_path = None;
+
+        let color = img.color();
+        if matches!(color, image::ColorType::Rgb32F | image::ColorType::Rgba32F) {
+            let hdr = img.to_rgba32f();
+            self.images.push(self.export.tone_map_image(&hdr));
+            self.deep_images.push(DeepImage::Rgba32F(hdr));
+            return;
+        }
+
+        if color.bytes_per_pixel() > color.channel_count() {
+            self.deep_images.push(DeepImage::Rgba16(img.to_rgba16()));
+        }
+        self.images.push(img.to_rgba8());
+    }
+
+    /// Updates preview of HDR image after tone mapping settings change
+    pub fn refresh_tone_mapping(&mut self) {
+        let preview = match self.deep_images.last() {
+            Some(DeepImage::Rgba32F(image)) => self.export.tone_map_image(image),
+            _ => return,
+        };
+        *self.images.last_mut().unwrap() = preview;
+    }
+
+    /// Loads image from raw RGBA8 pixels
+    pub fn load_image_rgba(&mut self, width: u32, height: u32, pixels: Vec<u8>) -> Result<()> {
+        let img = image::RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("Image data does not match its size"))?;
+        self.set_image(image::DynamicImage::ImageRgba8(img));
+        Ok(())
+    }
+
+    /// Saves processed image with metadata of the source image
+    pub fn save_image<P: AsRef<std::path::Path>>(&self, file: P) -> Result<()> {
+        let image = self.images.last().ok_or(VstImageError::NoImage)?;
+        export::encode_image(&file, image, self.deep_images.last(), &self.export)?;
+        Ok(self.metadata.write(file)?)
+    }
+
+    /// Saves processed image, the chain is written next to it if `export.sidecar_chain` is set
+    pub fn export_image<P: AsRef<std::path::Path>>(&mut self, file: P) -> Result<()> {
+        self.save_image(&file)?;
+        if self.export.sidecar_chain {
+            std::fs::write(
+                file.as_ref().with_extension(SIDECAR_EXTENSION),
+                self.save_chain()?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Unprocessed image, made of the result and saved tiles of the original after processing
+    pub fn original_image(&self) -> Result<Cow<image::RgbaImage>> {
+        if self.history.is_empty() {
+            return Ok(Cow::Borrowed(
+                self.images.first().ok_or(VstImageError::NoImage)?,
+            ));
+        }
+
+        let mut image = self.images.last().unwrap().clone();
+        self.history.restore(&mut image, None)?;
+        Ok(Cow::Owned(image))
+    }
+
+    /// PSNR and SSIM of the processed image against the original
+    pub fn measure_quality(&self) -> Result<Option<Metrics>> {
+        let original = self.original_image()?;
+        let processed = self.images.last().ok_or(VstImageError::NoImage)?;
+        Ok(metrics::measure(&original, processed))
+    }
+
+    /// Saves amplified difference between the original and processed image
+    pub fn save_difference<P: AsRef<std::path::Path>>(&self, file: P, gain: f32) -> Result<()> {
+        let original = self.original_image()?;
+        let processed = self.images.last().ok_or(VstImageError::NoImage)?;
+        let difference = compare::difference(&original, processed, gain)
+            .ok_or_else(|| anyhow::anyhow!("Original and processed image sizes differ"))?;
+        Ok(export::encode_image(
+            &file,
+            &difference,
+            None,
+            &self.export,
+        )?)
+    }
+
+    /// Saves working image (current frame for animations) to the autosave file.
+    /// Image is written into temporary file first, so crash while saving keeps the previous one
+    fn save_partial(&mut self, force: bool) {
+        let autosave = match &mut self.autosave {
+            Some(autosave) if force || autosave.last.elapsed() >= autosave.interval => autosave,
+            _ => return,
+        };
+        autosave.last = Instant::now();
+
+        let path = autosave.path.clone();
+        let mut temp_name = std::ffi::OsString::from("partial.");
+        temp_name.push(path.extension().unwrap_or_default());
+        let temp = path.with_extension(temp_name);
+
+        let result = self
+            .save_image(&temp)
+            .and_then(|_| Ok(std::fs::rename(&temp, &path)?));
+        match result {
+            Ok(()) => log::info!("Partial image saved: {}", path.display()),
+            Err(error) => log::error!("Unable to save partial image: {}", error),
+        }
+    }
+
+    /// Saves image into every target of export profile, returns paths of written files
+    pub fn save_profile<P: AsRef<std::path::Path>>(
+        &self,
+        dir: P,
+        profile: &ExportProfile,
+    ) -> Result<Vec<PathBuf>> {
+        let image = self.images.last().ok_or(VstImageError::NoImage)?;
+        let deep = self.deep_images.last();
+        let chain = self
+            .plugins
+            .iter()
+            .filter(|plugin| !plugin.bypass)
+            .map(|plugin| plugin.get_name())
+            .collect::<Vec<String>>()
+            .join("+");
+
+        let mut saved = Vec::new();
+        for target in &profile.targets {
+            let resized = target
+                .max_size
+                .and_then(|max_size| export::fit_image(image, deep, max_size));
+            let (image, deep) = match &resized {
+                Some((image, deep)) => (image, deep.as_ref()),
+                None => (image, deep),
+            };
+
+            let name = export::expand_template(
+                &target.template,
+                &self.source_name,
+                &chain,
+                image.width(),
+                image.height(),
+            );
+            let file = dir.as_ref().join(name);
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            export::encode_image(&file, image, deep, &self.export)?;
+            self.metadata.write(&file)?;
+            saved.push(file);
+        }
+
+        Ok(saved)
+    }
+
+    /// Loads `count` frames of the video starting from frame `start`
+    pub fn load_video<P: AsRef<std::path::Path>>(
+        &mut self,
+        file: P,
+        start: usize,
+        count: usize,
+    ) -> Result<()> {
+        let animation = video::load(file, start, Some(count))?;
+        self.source_path = None;
+        self.deep_images.clear();
+        self.history.clear();
+        self.proxy = None;
+        self.redo = None;
+        self.images = vec![animation.frames[0].image.clone()];
+        self.animation = Some(animation);
+        Ok(())
+    }
+
+    /// Loads numbered image sequence from directory
+    pub fn load_sequence<P: AsRef<std::path::Path>>(&mut self, dir: P) -> Result<()> {
+        let animation = Animation::load_sequence(dir)?;
+        self.source_path = None;
+        self.deep_images.clear();
+        self.history.clear();
+        self.proxy = None;
+        self.redo = None;
+        self.images = vec![animation.frames[0].image.clone()];
+        self.animation = Some(animation);
+        Ok(())
+    }
+
+    /// Turns current image into animation of `count` identical frames
+    pub fn duplicate_still(&mut self, count: usize) {
+        self.discard_result();
+        if let Some(image) = self.images.first() {
+            let animation = Animation::from_still(image, count);
+            self.deep_images.clear();
+            self.history.clear();
+            self.proxy = None;
+            self.redo = None;
+            self.images = vec![animation.frames[0].image.clone()];
+            self.animation = Some(animation);
+        }
+    }
+
+    pub fn save_sequence<P: AsRef<std::path::Path>>(&self, dir: P) -> Result<()> {
+        match &self.animation {
+            Some(animation) => Ok(animation.save_sequence(dir)?),
+            None => Err(VstImageError::NotAnimated),
+        }
+    }
+
+    fn apply_keyframes(&mut self, frame: usize) {
+        for plugin in &mut self.plugins {
+            plugin.apply_keyframes(frame);
+        }
+    }
+
+    pub fn save_animation<P: AsRef<std::path::Path>>(&self, file: P) -> Result<()> {
+        match &self.animation {
+            Some(animation) => Ok(animation.save(file, &self.export)?),
+            None => Err(VstImageError::NotAnimated),
+        }
+    }
+
+    /// Shows processed (or original) animation frame
+    pub fn show_frame(&mut self, id: usize) {
+        if let Some(animation) = &mut self.animation {
+            animation.current = id;
+            self.images = vec![animation.frames[id].image.clone()];
+            if let Some(processed) = &animation.processed[id] {
+                self.images.push(processed.clone());
+            }
+            self.apply_keyframes(id);
+        }
+    }
+
+    /// Stores processed animation frame and starts the next one,
+    /// returns `false` when there is no frames left
+    fn next_frame(&mut self) -> bool {
+        let animation = match &mut self.animation {
+            Some(animation) => animation,
+            None => return false,
+        };
+
+        animation.processed[animation.current] = self.images.last().cloned();
+        if animation.current + 1 >= animation.frames.len() {
+            return false;
+        }
+
+        animation.current += 1;
+        let current = animation.current;
+        let frame = animation.frames[current].image.clone();
+        self.images = vec![frame.clone(), frame];
+        self.mark_all_dirty();
+        self.build_masks();
+        if self.grayscale == Some(Grayscale::Gray) {
+            desaturate(&mut self.images[1]);
+        }
+        self.apply_keyframes(current);
+        self.position = 0;
+        self.total = 0;
+        self.pass = 0;
+
+        if self.reset_state_per_frame {
+            self.reset_plugin_state();
+        }
+
+        true
+    }
+
+    /// Makes mask weights of plugins from the image which is going to be processed
+    fn build_masks(&mut self) {
+        let image = match self.images.last() {
+            Some(image) => image,
+            None => return,
+        };
+        let scale = self.preview_scale();
+        for plugin in &mut self.plugins {
+            let weights = plugin.mask.as_ref().map(|mask| mask.build(image, scale));
+            plugin.mask_weights = match weights {
+                Some(Ok(weights)) => weights,
+                Some(Err(error)) => {
+                    log::error!("Unable to build mask of {}: {}", plugin.get_name(), error);
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+        }
+    }
+
+    /// Clears internal state (delay lines, filters, etc.) of all plugins
+    pub fn reset_plugin_state(&mut self) {
+        for plugin in &mut self.plugins {
+            if let Some(instance) = plugin.instance.as_mut() {
+                instance.suspend();
+                instance.resume();
+            }
+            if let Some(effect) = plugin.effect.as_mut() {
+                effect.reset();
+            }
+        }
+    }
+
+    /// Serializes plugins (with their states), routing and processing settings into JSON
+    pub fn save_chain(&mut self) -> Result<String> {
+        for plugin in &mut self.plugins {
+            plugin.save_block();
+        }
+
+        let state = RackState {
+            plugins: std::mem::take(&mut self.plugins),
+            graph: self.graph.clone(),
+            groups: self.groups.clone(),
+            iterations: self.iterations,
+            wet_decay: self.wet_decay,
+            interlaced: self.interlaced,
+            sample_rate: self.sample_rate,
+            dither: self.dither,
+            grayscale: self.grayscale,
+            seed: self.seed,
+        };
+        let json = serde_json::to_string(&state);
+        self.plugins = state.plugins;
+        Ok(json?)
+    }
+
+    /// Replaces plugins, routing and processing settings with ones from JSON made by `save_chain`
+    pub fn load_chain(&mut self, json: &str) -> Result<()> {
+        let state = parse_chain(json)?;
+
+        for plugin in &mut self.plugins {
+            if let Some(instance) = plugin.instance.as_mut() {
+                instance.suspend();
+            }
+        }
+
+        self.plugins = state.plugins;
+        self.graph = state.graph;
+        self.groups = state.groups;
+        self.refresh_plugin_states();
+        self.iterations = state.iterations;
+        self.wet_decay = state.wet_decay;
+        self.interlaced = state.interlaced;
+        self.sample_rate = state.sample_rate;
+        self.dither = state.dither;
+        self.grayscale = state.grayscale;
+        self.seed = state.seed;
+        self.load_uninitialzed_plugins()
+    }
+
+    /// Appends plugins and groups from JSON made by `save_chain` after the current plugins,
+    /// processing settings and routing of the current rack are kept
+    pub fn append_chain(&mut self, json: &str) -> Result<()> {
+        let state = parse_chain(json)?;
+        let group_offset = self.groups.len();
+        for mut plugin in state.plugins {
+            plugin.group = plugin.group.map(|group| group + group_offset);
+            self.plugins.push(plugin);
+            if let Some(graph) = self.graph.as_mut() {
+                graph.push_plugin(self.plugins.len() - 1);
+            }
+        }
+        self.groups.extend(state.groups);
+        self.refresh_plugin_states();
+        self.load_uninitialzed_plugins()
+    }
+
+    /// Saves chain without image data, so it can be applied to any other image
+    pub fn save_chain_preset<P: AsRef<std::path::Path>>(&mut self, file: P) -> Result<()> {
+        std::fs::write(file, self.save_chain()?)?;
+        Ok(())
+    }
+
+    pub fn load_chain_preset<P: AsRef<std::path::Path>>(&mut self, file: P) -> Result<()> {
+        let json = std::fs::read_to_string(file)?;
+        self.load_chain(&json)
+    }
+
+    /// Saves chain and image with optional `contents`, `history` is the undo history of the editor
+    pub fn save_project(
+        &mut self,
+        file: std::path::PathBuf,
+        contents: &ProjectContents,
+        history: &[UndoStep],
+    ) -> Result<()> {
+        let j = self.save_chain()?;
+
+        let file = std::fs::File::create(&file)?;
+
+        let mut zip = zip::ZipWriter::new(file);
+
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Zstd);
+
+        zip.start_file("project.json", options)?;
+        zip.write_all(j.as_bytes())?;
+
+        let image = self.images.last().ok_or(VstImageError::NoImage)?;
+        let checkpoint = self.checkpoint_state();
+        if checkpoint.is_some() || (contents.original && self.images.len() >= 2) {
+            let restored = if self.history.is_empty() {
+                None
+            } else {
+                Some(self.snapshot_original_images()?)
+            };
+            let (original, deep) = match &restored {
+                Some((original, deep)) => (original, deep.as_ref()),
+                None => (&self.images[0], self.deep_images.first()),
+            };
+            // unprocessed image is the project image of checkpoint, so the render can be continued
+            let name = if checkpoint.is_some() {
+                "image"
+            } else {
+                "original"
+            };
+            write_project_image(&mut zip, name, original, deep, options)?;
+        }
+
+        match checkpoint {
+            Some(checkpoint) => {
+                write_project_image(
+                    &mut zip,
+                    "checkpoint",
+                    image,
+                    self.deep_images.last(),
+                    options,
+                )?;
+                zip.start_file("checkpoint.json", options)?;
+                zip.write_all(&serde_json::to_vec(&checkpoint)?)?;
+            }
+            None => {
+                write_project_image(&mut zip, "image", image, self.deep_images.last(), options)?
+            }
+        }
+
+        if contents.masks {
+            let (width, height) = image.dimensions();
+            for (slot, plugin) in self.plugins.iter().enumerate() {
+                let pixels = plugin
+                    .mask_weights
+                    .iter()
+                    .map(|weight| (weight.clamp(0.0, 1.0) * 255.0).round() as u8)
+                    .collect();
+                // masks built for proxy preview don't match the image
+                let mask = match image::GrayImage::from_raw(width, height, pixels) {
+                    Some(mask) if plugin.mask.is_some() => mask,
+                    _ => continue,
+                };
+
+                let mut bytes: Vec<u8> = Vec::new();
+                mask.write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)?;
+                zip.start_file(format!("masks/{}.png", slot), options)?;
+                zip.write_all(&bytes)?;
+            }
+        }
+
+        let history = &history[history.len().saturating_sub(contents.history)..];
+        if !history.is_empty() {
+            zip.start_file("history.json", options)?;
+            zip.write_all(&serde_json::to_vec(history)?)?;
+        }
+
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Restores wet masks stored by `save_project`, masks which don't match the image are skipped
+    pub fn load_project_masks<R: Read + Seek>(
+        &mut self,
+        archive: &mut zip::ZipArchive<R>,
+    ) -> Result<()> {
+        let pixel_count = self.images.last().map_or(0, |image| image.pixels().len());
+        for (slot, plugin) in self.plugins.iter_mut().enumerate() {
+            let mut bytes = Vec::new();
+            match archive.by_name(&format!("masks/{}.png", slot)) {
+                Ok(mut file) => file.read_to_end(&mut bytes)?,
+                Err(zip::result::ZipError::FileNotFound) => continue,
+                Err(error) => return Err(error.into()),
+            };
+
+            let mask = image::load_from_memory(&bytes)?.to_luma8();
+            if mask.len() == pixel_count {
+                plugin.mask_weights = mask.iter().map(|value| *value as f32 / 255.0).collect();
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds processed image of the project on top of the loaded original, so it can be undone
+    pub fn push_result_data(&mut self, data: &[u8]) -> Result<()> {
+        let img = ImageReader::new(Cursor::new(data))
+            .with_guessed_format()?
+            .decode()?;
+        let original = self.images.last().ok_or(VstImageError::NoImage)?;
+        if original.dimensions() != (img.width(), img.height()) || self.images.len() != 1 {
+            return Err(anyhow::anyhow!("Processed image doesn't match the original").into());
+        }
+
+        match self.deep_images.first() {
+            Some(DeepImage::Rgba32F(_)) => {
+                let hdr = img.to_rgba32f();
+                self.images.push(self.export.tone_map_image(&hdr));
+                self.deep_images.push(DeepImage::Rgba32F(hdr));
+            }
+            Some(DeepImage::Rgba16(_)) => {
+                self.deep_images.push(DeepImage::Rgba16(img.to_rgba16()));
+                self.images.push(img.to_rgba8());
+            }
+            None => self.images.push(img.to_rgba8()),
+        }
+        Ok(())
+    }
+
+    /// Progress of the running still image processing, animations can't be resumed
+    fn checkpoint_state(&self) -> Option<Checkpoint> {
+        if self.finished
+            || self.images.len() < 2
+            || self.animation.is_some()
+            || self.proxy.is_some()
+        {
+            return None;
         }
-    }
 
-    pub fn get_path(&self) -> PathBuf {
-        self.path.clone()
+        Some(Checkpoint {
+            position: self.position,
+            pass: self.pass,
+            total: self.total,
+        })
     }
 
-    fn initialize(&mut self) -> Result<()> {
-        if let Some(inst) = self.instance.as_mut() {
-            inst.init();
-            self.editor = EditorWrapper::new(inst.get_editor());
-            if !self.plugin_data.is_empty() {
-                println!("found a plugin data LOADING NOW!");
-                self.load_block()?;
+    /// Writes project with processing state into the checkpoint file,
+    /// file is replaced only after it is completely written
+    fn save_checkpoint(&mut self, force: bool) {
+        let checkpoint = match &mut self.checkpoint {
+            Some(checkpoint) if force || checkpoint.last.elapsed() >= checkpoint.interval => {
+                checkpoint
             }
+            _ => return,
+        };
+        checkpoint.last = Instant::now();
+
+        let path = checkpoint.path.clone();
+        let temp = path.with_extension("checkpoint.tmp");
+        let result = self
+            .save_project(temp.clone(), &ProjectContents::default(), &[])
+            .and_then(|_| Ok(std::fs::rename(&temp, &path)?));
+        match result {
+            Ok(()) => log::info!("Checkpoint saved: {}", path.display()),
+            Err(error) => log::error!("Unable to save checkpoint: {}", error),
         }
-        Ok(())
     }
 
-    fn save_block(&mut self) {
-        if let Some(inst) = self.instance.as_mut() {
-            let bank_data = inst.get_parameter_object().get_bank_data();
-            self.plugin_data = base64::encode(bank_data);
+    /// Continues processing of the loaded image from the checkpoint of the project,
+    /// `partial` is the partially processed image stored with it
+    pub fn resume(&mut self, checkpoint: Checkpoint, partial: image::DynamicImage) -> Result<()> {
+        let source = self.images.last().ok_or(VstImageError::NoImage)?;
+        if source.dimensions() != (partial.width(), partial.height()) {
+            return Err(
+                anyhow::anyhow!("Checkpoint image size doesn't match the project image").into(),
+            );
         }
-    }
 
-    fn load_block(&mut self) -> Result<()> {
-        if let Some(inst) = self.instance.as_mut() {
-            inst.get_parameter_object()
-                .load_bank_data(&base64::decode(&self.plugin_data)?);
+        self.start_process()?;
+        self.save_snapshot_tiles(None);
+        if let Some(deep) = self.deep_images.last_mut() {
+            *deep = match deep {
+                DeepImage::Rgba16(_) => DeepImage::Rgba16(partial.to_rgba16()),
+                DeepImage::Rgba32F(_) => DeepImage::Rgba32F(partial.to_rgba32f()),
+            };
         }
+        let preview = match self.deep_images.last() {
+            Some(DeepImage::Rgba32F(hdr)) => self.export.tone_map_image(hdr),
+            _ => partial.to_rgba8(),
+        };
+        *self.images.last_mut().unwrap() = preview;
+
+        self.position = checkpoint.position;
+        self.pass = checkpoint.pass.min(self.iterations.saturating_sub(1));
+        self.total = checkpoint.total;
+        self.mark_all_dirty();
         Ok(())
     }
-}
 
-impl Host for PluginHost {
-    fn automate(&self, index: i32, value: f32) {
-        println!("Parameter {} had its value changed to {}", index, value);
-    }
+    fn insert_plugin(&mut self, mut plugin: PluginRackInstance) -> Result<()> {
+        plugin.initialize()?;
+        self.plugins.push(plugin);
 
-    fn process_events(&self, events: &vst::api::Events) {
-        println!("Plugin called the {:?} event", events.events);
+        if let Some(graph) = self.graph.as_mut() {
+            graph.push_plugin(self.plugins.len() - 1);
+        }
+
+        self.refresh_plugin_states();
+        Ok(())
     }
-}
 
-impl PluginRack {
-    pub fn new() -> Self {
-        let host = Arc::new(Mutex::new(PluginHost));
-        Self {
-            host,
-            plugins: Vec::new(),
-            images: Vec::new(),
-            block_size: 8192,
-            position: 0,
-            total: 0,
-            finished: true,
+    /// Loads plugin library again (e.g. after it was rebuilt) for every plugin of the `path`,
+    /// parameters are preserved
+    pub fn reload_plugins(&mut self, path: &std::path::Path) -> Result<()> {
+        let ids: Vec<usize> = (0..self.plugins.len())
+            .filter(|&id| self.plugins[id].effect.is_none() && self.plugins[id].path == path)
+            .collect();
+
+        // old library is unloaded only after all instances are dropped,
+        // otherwise the system returns it again
+        for &id in &ids {
+            let plugin = &mut self.plugins[id];
+            plugin.save_block();
+            if let Some(instance) = plugin.instance.as_mut() {
+                instance.suspend();
+            }
+            plugin.instance = None;
+        }
+
+        let mut result = Ok(());
+        for id in ids {
+            let events = Arc::clone(&self.plugins[id].events);
+            let reloaded = self.load_instance(path, &events).and_then(|instance| {
+                let plugin = &mut self.plugins[id];
+                plugin.instance = Some(instance);
+                plugin.initialize()
+            });
+            // other instances are still reloaded, the first error is returned
+            if result.is_ok() {
+                result = reloaded;
+            }
         }
+        result
     }
 
-    pub fn undo(&mut self) {
-        if self.images.len() > 1 {
-            self.images.remove(self.images.len() - 1);
+    /// Inserts a copy of the plugin (with fresh instance and the same state) right after it
+    pub fn duplicate_plugin(&mut self, id: usize) -> Result<()> {
+        self.plugins[id].save_block();
+        let json = serde_json::to_string(&self.plugins[id])?;
+        let mut plugin: PluginRackInstance = serde_json::from_str(&json)?;
+
+        self.load_slot(&mut plugin)?;
+        plugin.initialize()?;
+        self.plugins.insert(id + 1, plugin);
+
+        if let Some(graph) = self.graph.as_mut() {
+            graph.insert_plugin_after(id);
         }
+
+        self.refresh_plugin_states();
+        Ok(())
     }
 
-    pub fn calculate_memory_size(&self) -> usize {
-        let mut size = 0;
-        for image in &self.images {
-            size += std::mem::size_of_val(image) * image.len();
+    /// Applies plugin table action which changes the rack, returns edit which reverts it.
+    /// View and clipboard actions are ignored
+    pub fn apply_action(&mut self, action: &Action) -> Result<Option<RackEdit>> {
+        let (plugin_count, group_count) = (self.plugins.len(), self.groups.len());
+        let out_of_range = |id: usize, count: usize| -> Result<()> {
+            if id >= count {
+                return Err(anyhow::anyhow!("Rack has no slot {} for {:?}", id, action).into());
+            }
+            Ok(())
+        };
+
+        let inverse = match action.clone() {
+            Action::OpenEditor(_)
+            | Action::Select(_)
+            | Action::LearnMidi(..)
+            | Action::CopySettings(_)
+            | Action::PasteSettings(_) => return Ok(None),
+            Action::Remove(id) => {
+                out_of_range(id, plugin_count)?;
+                // removed plugin instance can be restored only by loading the whole chain
+                let chain = self.save_chain()?;
+                self.remove_plugin(id);
+                RackEdit::Chain(chain)
+            }
+            Action::Duplicate(id) => {
+                out_of_range(id, plugin_count)?;
+                self.duplicate_plugin(id)?;
+                RackEdit::Action(Action::Remove(id + 1))
+            }
+            Action::Bypass(id) => {
+                out_of_range(id, plugin_count)?;
+                self.plugins[id].bypass = !self.plugins[id].bypass;
+                RackEdit::Action(Action::Bypass(id))
+            }
+            Action::Solo(id) => {
+                out_of_range(id, plugin_count)?;
+                self.toggle_solo(id);
+                RackEdit::Action(Action::Solo(id))
+            }
+            Action::GroupWithNext(id) => {
+                out_of_range(id + 1, plugin_count)?;
+                let groups = self.group_state();
+                self.group_with_next(id);
+                groups
+            }
+            Action::Ungroup(id) => {
+                out_of_range(id, plugin_count)?;
+                let groups = self.group_state();
+                self.ungroup(id);
+                groups
+            }
+            Action::ToggleGroupCollapse(id) => {
+                out_of_range(id, group_count)?;
+                self.groups[id].collapsed = !self.groups[id].collapsed;
+                RackEdit::Action(Action::ToggleGroupCollapse(id))
+            }
+            Action::ToggleGroupBypass(id) => {
+                out_of_range(id, group_count)?;
+                let bypass = !self.groups[id].bypass;
+                self.set_group_bypass(id, bypass);
+                RackEdit::Action(Action::ToggleGroupBypass(id))
+            }
+            Action::ChangeGroupWet(id, wet) => {
+                out_of_range(id, group_count)?;
+                let old = self.groups[id].wet;
+                self.set_group_wet(id, wet);
+                RackEdit::Action(Action::ChangeGroupWet(id, old))
+            }
+            Action::RenameGroup(id, name) => {
+                out_of_range(id, group_count)?;
+                let old = std::mem::replace(&mut self.groups[id].name, name);
+                RackEdit::Action(Action::RenameGroup(id, old))
+            }
+            Action::ChangeInputChannel(id, channel) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].input_channel, channel);
+                RackEdit::Action(Action::ChangeInputChannel(id, old))
+            }
+            Action::ChangeWet(id, wet) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].wet, wet);
+                RackEdit::Action(Action::ChangeWet(id, old))
+            }
+            Action::ChangeOutputChannel(id, value) => {
+                out_of_range(id, plugin_count)?;
+                let outputs = self.plugins[id].output_count();
+                if value >= outputs {
+                    return Err(anyhow::anyhow!(
+                        "Plugin has no output channel {}, it has {} outputs",
+                        value,
+                        outputs
+                    )
+                    .into());
+                }
+                let old = std::mem::replace(&mut self.plugins[id].output_channel, value);
+                self.plugins[id].validate();
+                RackEdit::Action(Action::ChangeOutputChannel(id, old))
+            }
+            Action::ChangeMixOutputs(id, value) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].mix_outputs, value);
+                self.plugins[id].validate();
+                RackEdit::Action(Action::ChangeMixOutputs(id, old))
+            }
+            Action::ChangeOutputRoutes(id, routes) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].output_routes, routes);
+                RackEdit::Action(Action::ChangeOutputRoutes(id, old))
+            }
+            Action::ChangeSampleRate(id, value) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].sample_rate, value);
+                RackEdit::Action(Action::ChangeSampleRate(id, old))
+            }
+            Action::ChangeIterations(id, value) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].iterations, value);
+                RackEdit::Action(Action::ChangeIterations(id, old))
+            }
+            Action::ChangeTilePattern(id, pattern) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].tile_pattern, pattern);
+                RackEdit::Action(Action::ChangeTilePattern(id, old))
+            }
+            Action::ChangeTileSize(id, size) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].tile_size, size);
+                RackEdit::Action(Action::ChangeTileSize(id, old))
+            }
+            Action::ChangeRegion(id, region) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].region, region);
+                RackEdit::Action(Action::ChangeRegion(id, old))
+            }
+            Action::ChangeMask(id, mask) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].mask, mask);
+                RackEdit::Action(Action::ChangeMask(id, old))
+            }
+            Action::ChangeJitter(id, jitter) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].jitter, jitter);
+                RackEdit::Action(Action::ChangeJitter(id, old))
+            }
+            Action::ToggleJitterParameter(id, index) => {
+                out_of_range(id, plugin_count)?;
+                let parameters = &mut self.plugins[id].jitter_parameters;
+                match parameters.iter().position(|parameter| *parameter == index) {
+                    Some(position) => {
+                        parameters.remove(position);
+                    }
+                    None => parameters.push(index),
+                }
+                RackEdit::Action(Action::ToggleJitterParameter(id, index))
+            }
+            Action::ChangeJitterSeed(id, seed) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].jitter_seed, seed);
+                RackEdit::Action(Action::ChangeJitterSeed(id, old))
+            }
+            Action::ChangeSampleOffset(id, offset) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].sample_offset, offset);
+                RackEdit::Action(Action::ChangeSampleOffset(id, old))
+            }
+            Action::ChangeField(id, field) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].field, field);
+                RackEdit::Action(Action::ChangeField(id, old))
+            }
+            Action::ChangeParameter(id, index, value) => {
+                out_of_range(id, plugin_count)?;
+                let old = self.plugins[id].get_parameter(index);
+                self.plugins[id].set_parameter(index, value);
+                self.plugins[id].update_automation(index, value);
+                RackEdit::Action(Action::ChangeParameter(id, index, old))
+            }
+            Action::ChangeAutomation(id, automation) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].automation, automation);
+                RackEdit::Action(Action::ChangeAutomation(id, old))
+            }
+            Action::ChangeKeyframes(id, keyframes) => {
+                out_of_range(id, plugin_count)?;
+                let old = std::mem::replace(&mut self.plugins[id].keyframes, keyframes);
+                RackEdit::Action(Action::ChangeKeyframes(id, old))
+            }
+            Action::ChangeGraph(graph) => {
+                let old = std::mem::replace(&mut self.graph, graph);
+                RackEdit::Action(Action::ChangeGraph(old))
+            }
+            Action::ChangeMidiControl(id, index, control) => {
+                out_of_range(id, plugin_count)?;
+                let old = self.plugins[id].midi_control(index);
+                self.plugins[id].set_midi_control(index, control);
+                RackEdit::Action(Action::ChangeMidiControl(id, index, old))
+            }
+        };
+        Ok(Some(inverse))
+    }
+
+    /// Reverts an edit, returns edit which makes the change again
+    pub fn revert_edit(&mut self, edit: RackEdit) -> Result<RackEdit> {
+        match edit {
+            RackEdit::Action(action) => self
+                .apply_action(&action)?
+                .ok_or_else(|| anyhow::anyhow!("{:?} can't be reverted", action).into()),
+            RackEdit::Chain(chain) => {
+                let current = self.save_chain()?;
+                self.load_chain(&chain)?;
+                Ok(RackEdit::Chain(current))
+            }
+            RackEdit::Groups { groups, members } => {
+                let current = self.group_state();
+                self.groups = groups;
+                for (plugin, group) in self.plugins.iter_mut().zip(members) {
+                    plugin.group = group;
+                }
+                self.refresh_plugin_states();
+                Ok(current)
+            }
         }
-        size
     }
 
-    pub fn is_finished(&self) -> bool {
-        self.finished
+    fn group_state(&self) -> RackEdit {
+        RackEdit::Groups {
+            groups: self.groups.clone(),
+            members: self.plugins.iter().map(|plugin| plugin.group).collect(),
+        }
     }
 
-    pub fn revert(&mut self) {
-        if self.images.len() > 1 {
-            self.images.drain(1..);
+    pub fn remove_plugin(&mut self, id: usize) {
+        log::debug!("removing: {}", id);
+        if let Some(instance) = self.plugins[id].instance.as_mut() {
+            instance.suspend();
+        }
+        self.plugins.remove(id);
+
+        if let Some(graph) = self.graph.as_mut() {
+            graph.remove_plugin(id);
         }
+
+        self.refresh_plugin_states();
     }
 
-    pub fn load_plugin(&mut self, file: PathBuf) -> anyhow::Result<()> {
-        let mut loader = PluginLoader::load(&file, Arc::clone(&self.host))?;
-        let instance = loader.instance()?;
-        self.insert_plugin(file, instance);
+    pub fn start_process(&mut self) -> Result<()> {
+        if self.plugins.is_empty() {
+            return Err(VstImageError::EmptyRack);
+        }
+        let img = self.images.last().ok_or(VstImageError::NoImage)?;
+        // still image is processed in place, tiles of the original are copied on the first change
+        let img = if self.animation.is_none() && self.proxy.is_none() {
+            None
+        } else {
+            Some(img.clone())
+        };
+
+        if let Some(animation) = &mut self.animation {
+            animation.clear_processed();
+            animation.current = 0;
+            self.images = vec![animation.frames[0].image.clone()];
+            self.apply_keyframes(0);
+
+            if self.reset_state_per_frame {
+                self.reset_plugin_state();
+            }
+        }
+
+        match img {
+            Some(img) => {
+                if self.images.len() >= 2 {
+                    //println!("{}", img.len());
+                    self.images.remove(1);
+                }
+                self.images.push(img);
+
+                if let Some(deep) = self.deep_images.last().cloned() {
+                    self.deep_images.truncate(1);
+                    self.deep_images.push(deep);
+                }
+            }
+            None => self.snapshot_original(),
+        }
+
+        self.build_masks();
+        for plugin in &mut self.plugins {
+            plugin.elapsed = Duration::ZERO;
+            plugin.start_automation();
+        }
+        self.started = Instant::now();
+        self.run_time = Duration::ZERO;
+
+        if self.grayscale == Some(Grayscale::Gray) {
+            self.save_snapshot_tiles(None);
+            desaturate(self.images.last_mut().unwrap());
+            match self.deep_images.last_mut() {
+                Some(DeepImage::Rgba16(deep)) => desaturate(deep),
+                Some(DeepImage::Rgba32F(hdr)) => desaturate(hdr),
+                None => {}
+            }
+        }
+        self.finished = false;
+        self.position = 0;
+        self.total = 0;
+        self.pass = 0;
+        self.redo = None;
+
+        if let Some(autosave) = &mut self.autosave {
+            autosave.last = Instant::now();
+        }
         Ok(())
     }
 
-    pub fn compute_complete_percentage(&self) -> usize {
-        self.position.checked_div(self.total).unwrap_or(0) * 100
-    }
+    /// Cancels processing, partially processed image is discarded
+    pub fn stop_process(&mut self) -> Result<()> {
+        if self.images.len() < 2 {
+            return Err(VstImageError::NoImage);
+        }
 
-    pub fn get_processed_position(&self) -> usize {
-        self.position
+        if self.proxy.is_none() && !self.history.is_empty() {
+            let result = self
+                .history
+                .discard(self.images.last_mut().unwrap(), self.deep_images.last_mut());
+            if let Err(error) = result {
+                log::error!("Unable to restore image: {}", error);
+            }
+            if self.history.is_empty() {
+                self.remove_original_placeholder();
+            }
+        } else {
+            self.images.remove(self.images.len() - 1);
+            self.deep_images.truncate(self.images.len());
+        }
+        self.finished = true;
+        self.position = 0;
+        self.total = 0;
+        Ok(())
     }
 
-    pub fn get_processing_size(&self) -> usize {
-        self.total
+    pub fn can_update_ui(&self) -> bool {
+        self.position % self.block_size as usize * 2 == 0
     }
 
-    pub fn load_uninitialzed_plugins(&mut self) -> anyhow::Result<()> {
-        for plugin in &mut self.plugins {
-            if let Ok(mut loader) = PluginLoader::load(&plugin.path, Arc::clone(&self.host)) {
-                if let Ok(instance) = loader.instance() {
-                    plugin.instance = Some(instance);
-                    plugin.initialize()?;
+    fn process_pixels<S: Sample>(
+        plugins: &mut [PluginRackInstance],
+        graph: Option<&ProcessingGraph>,
+        pixels: &mut [S],
+        location: &BlockLocation,
+        decay: f32,
+    ) {
+        match graph {
+            Some(graph) => graph.process(plugins, pixels, location, decay),
+            None => {
+                for plugin in plugins {
+                    plugin.process(pixels, location, decay);
                 }
             }
         }
+    }
+
+    /// Processes downscaled copy of the image to audition chain settings quickly,
+    /// block size is scaled so every block covers the same image area as on full resolution
+    pub fn start_preview(&mut self) {
+        if self.plugins.is_empty() || self.images.is_empty() || self.animation.is_some() {
+            return;
+        }
+        self.end_preview();
 
-        Ok(())
+        let image = self.images.last().unwrap();
+        let longest = image.width().max(image.height());
+        let max_size = (longest as f32 * self.proxy_scale).round() as u32;
+        let proxy = export::fit_image(image, self.deep_images.last(), max_size.max(1));
+        let (image, deep) = match proxy {
+            Some(proxy) => proxy,
+            None => return,
+        };
+
+        let scale = image.width() as f32 / self.images.last().unwrap().width() as f32;
+        self.proxy = Some(ProxySource {
+            images: std::mem::replace(&mut self.images, vec![image]),
+            deep_images: std::mem::replace(&mut self.deep_images, deep.into_iter().collect()),
+            block_size: self.block_size,
+            scale,
+        });
+        self.block_size = ((self.block_size as f32 * scale * scale) as i64).max(64);
+        if self.start_process().is_err() {
+            self.end_preview();
+        }
     }
 
-    pub fn load_image<P: AsRef<std::path::Path>>(&mut self, file: P) -> anyhow::Result<()> {
-        self.images.clear();
-        let img = ImageReader::open(file)?.decode()?;
-        self.images.push(img.to_rgba8());
-        Ok(())
+    /// Returns to full resolution image, proxy result is discarded
+    pub fn end_preview(&mut self) {
+        if let Some(proxy) = self.proxy.take() {
+            self.images = proxy.images;
+            self.deep_images = proxy.deep_images;
+            self.block_size = proxy.block_size;
+            self.finished = true;
+            self.position = 0;
+            self.total = 0;
+            self.pass = 0;
+        }
     }
 
-    pub fn load_image_data(&mut self, file: &[u8]) -> anyhow::Result<()> {
-        self.images.clear();
-        let img = ImageReader::new(Cursor::new(file))
-            .with_guessed_format()?
-            .decode()?;
-        self.images.push(img.to_rgba8());
-        Ok(())
+    pub fn is_preview(&self) -> bool {
+        self.proxy.is_some()
     }
 
-    pub fn save_image<P: AsRef<std::path::Path>>(&self, file: P) -> Result<(), image::ImageError> {
-        self.images.last().unwrap().save(file)
+    /// Size of the shown image relative to the source image
+    pub fn preview_scale(&self) -> f32 {
+        self.proxy.as_ref().map_or(1.0, |proxy| proxy.scale)
     }
 
-    pub fn save_project(&mut self, file: std::path::PathBuf) -> anyhow::Result<()> {
-        for plugin in &mut self.plugins {
-            plugin.save_block();
+    /// Processes square tile around pixel (`x`, `y`) without changing the image,
+    /// returns position of the tile and the result
+    pub fn audition_tile(
+        &mut self,
+        x: u32,
+        y: u32,
+        size: u32,
+    ) -> Option<(u32, u32, image::RgbaImage)> {
+        if self.plugins.is_empty() || !self.finished {
+            return None;
         }
 
-        let file = std::fs::File::create(&file).unwrap();
+        let image = self.images.last()?;
+        let (width, height) = image.dimensions();
+        let (tile_width, tile_height) = (size.min(width), size.min(height));
+        let left = x.saturating_sub(tile_width / 2).min(width - tile_width);
+        let top = y.saturating_sub(tile_height / 2).min(height - tile_height);
+        let area = (left, top, tile_width, tile_height);
 
-        let mut zip = zip::ZipWriter::new(file);
+        let tile = match self.deep_images.last() {
+            Some(DeepImage::Rgba16(deep)) => {
+                let tile = copy_area(deep, width, area);
+                let pixels = self.process_tile(tile, width, height, area);
+                pixels.iter().map(|value| (value >> 8) as u8).collect()
+            }
+            Some(DeepImage::Rgba32F(hdr)) => {
+                let tile = copy_area(hdr, width, area);
+                let pixels = self.process_tile(tile, width, height, area);
+                pixels
+                    .chunks_exact(4)
+                    .flat_map(|pixel| self.export.tone_map(pixel))
+                    .collect()
+            }
+            None => {
+                let tile = copy_area(image, width, area);
+                self.process_tile(tile, width, height, area)
+            }
+        };
 
-        let options =
-            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Zstd);
+        let tile = image::RgbaImage::from_raw(tile_width, tile_height, tile)?;
+        Some((left, top, tile))
+    }
 
-        zip.start_file("project.json", options)?;
-        let j = serde_json::to_string(&self.plugins)?;
-        zip.write_all(j.as_bytes())?;
+    /// Processes rectangle of the image row by row, so regions and tile patterns match the full run
+    fn process_tile<S: Sample>(
+        &mut self,
+        mut tile: Vec<S>,
+        width: u32,
+        height: u32,
+        (left, top, tile_width, _): (u32, u32, u32, u32),
+    ) -> Vec<S> {
+        let (width, left, tile_width) = (width as usize, left as usize, tile_width as usize);
+        let scale = self.preview_scale();
+        let sample_rate = self.source_sample_rate(width, scale);
+        self.clear_dither_errors();
+        for pass in 0..self.iterations {
+            let decay = (1.0 - self.wet_decay).powi(pass as i32);
+            for (id, row) in tile.chunks_mut(tile_width * 4).enumerate() {
+                let location = BlockLocation {
+                    offset: (top as usize + id) * width + left,
+                    width,
+                    height: height as usize,
+                    scale,
+                    field: None,
+                    sample_rate,
+                    dither: self.dither,
+                    pass,
+                    grayscale: self.grayscale,
+                    channel: None,
+                };
+                Self::process_pixels(
+                    &mut self.plugins,
+                    self.graph.as_ref(),
+                    row,
+                    &location,
+                    decay,
+                );
+            }
+        }
 
-        zip.start_file("image.png", options)?;
-        let mut bytes: Vec<u8> = Vec::new();
-        self.images
-            .last()
-            .unwrap()
-            .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)?;
-        zip.write_all(&bytes)?;
+        tile
+    }
 
-        zip.finish()?;
-        Ok(())
+    /// Runs all passes over part of the image which starts at pixel `offset`,
+    /// used for images which are processed without loading them completely
+    pub fn process_strip<S: Sample>(
+        &mut self,
+        pixels: &mut [S],
+        offset: usize,
+        width: usize,
+        height: usize,
+    ) {
+        let block_size = self.block_size as usize;
+        let sample_rate = self.source_sample_rate(width, 1.0);
+        if offset == 0 {
+            self.clear_dither_errors();
+        }
+        for pass in 0..self.iterations {
+            let decay = (1.0 - self.wet_decay).powi(pass as i32);
+            for (id, block) in pixels.chunks_mut(block_size * 4).enumerate() {
+                let location = BlockLocation {
+                    offset: offset + id * block_size,
+                    width,
+                    height,
+                    scale: 1.0,
+                    field: None,
+                    sample_rate,
+                    dither: self.dither,
+                    pass,
+                    grayscale: self.grayscale,
+                    channel: None,
+                };
+                Self::process_pixels(
+                    &mut self.plugins,
+                    self.graph.as_ref(),
+                    block,
+                    &location,
+                    decay,
+                );
+            }
+        }
     }
 
-    fn insert_plugin(&mut self, file: PathBuf, instance: PluginInstance) {
-        self.plugins.push(PluginRackInstance::new(file, instance));
-        self.plugins.last_mut().unwrap().initialize().unwrap();
+    /// Drops dither errors kept by plugins, called before the first block of the image
+    fn clear_dither_errors(&mut self) {
+        for plugin in &mut self.plugins {
+            plugin.quantizers.clear();
+        }
     }
 
-    pub fn remove_plugin(&mut self, id: usize) {
-        println!("removing: {}", id);
-        if let Some(instance) = self.plugins[id].instance.as_mut() {
-            instance.suspend();
+    /// Project sample rate for image of `width` pixels scaled by `scale` from the source
+    fn source_sample_rate(&self, width: usize, scale: f32) -> f32 {
+        self.sample_rate
+            .rate((width as f32 / scale).round() as usize)
+    }
+
+    /// Locations and pixel ranges of the image which make up the current block. Interlaced block
+    /// is split at the field boundary, its ranges are parts of rows of one field
+    fn field_blocks(&self) -> Vec<(BlockLocation, Vec<Range<usize>>)> {
+        let image = self.images.last().unwrap();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let scale = self.proxy.as_ref().map_or(1.0, |proxy| proxy.scale);
+        let total = width * height;
+        let start = self.position.min(total);
+        let end = (self.position + self.block_size as usize).min(total);
+        let sample_rate = self.source_sample_rate(width, scale);
+
+        if !self.interlaced {
+            let location = BlockLocation {
+                offset: self.position,
+                width,
+                height,
+                scale,
+                field: None,
+                sample_rate,
+                dither: self.dither,
+                pass: self.pass,
+                grayscale: self.grayscale,
+                channel: None,
+            };
+            return vec![(location, vec![start..end])];
         }
-        self.plugins.remove(id);
+
+        // even field goes first in the processing order
+        let even_length = (height + 1) / 2 * width;
+        [
+            (Field::Even, 0, even_length),
+            (Field::Odd, even_length, total),
+        ]
+        .into_iter()
+        .filter_map(|(field, field_start, field_end)| {
+            let (start, end) = (start.max(field_start), end.min(field_end));
+            if start >= end {
+                return None;
+            }
+
+            let location = BlockLocation {
+                offset: start - field_start,
+                width,
+                height,
+                scale,
+                field: Some(field),
+                sample_rate,
+                dither: self.dither,
+                pass: self.pass,
+                grayscale: self.grayscale,
+                channel: None,
+            };
+            let mut segments = Vec::new();
+            let mut index = 0;
+            while start + index < end {
+                let (x, y) = location.pixel(index);
+                let length = (width - x).min(end - start - index);
+                segments.push(y * width + x..y * width + x + length);
+                index += length;
+            }
+            Some((location, segments))
+        })
+        .collect()
     }
 
-    pub fn start_process(&mut self) {
-        if self.plugins.is_empty() || self.images.is_empty() {
+    /// Processes pixel ranges of `data` as one continuous block
+    fn process_segments<S: Sample>(
+        plugins: &mut [PluginRackInstance],
+        graph: Option<&ProcessingGraph>,
+        data: &mut [S],
+        segments: &[Range<usize>],
+        location: &BlockLocation,
+        decay: f32,
+    ) {
+        if let [segment] = segments {
+            let pixels = &mut data[segment.start * 4..segment.end * 4];
+            Self::process_pixels(plugins, graph, pixels, location, decay);
             return;
         }
 
-        let img = self.images.last().unwrap().clone();
+        let mut block: Vec<S> = segments
+            .iter()
+            .flat_map(|segment| data[segment.start * 4..segment.end * 4].iter().copied())
+            .collect();
+        Self::process_pixels(plugins, graph, &mut block, location, decay);
 
-        if self.images.len() >= 2 {
-            //println!("{}", img.len());
-            self.images.remove(1);
+        let mut start = 0;
+        for segment in segments {
+            let range = segment.start * 4..segment.end * 4;
+            let length = range.len();
+            data[range].copy_from_slice(&block[start..start + length]);
+            start += length;
         }
-
-        self.images.push(img);
-        self.finished = false;
-        self.position = 0;
-        self.total = 0;
     }
 
-    pub fn stop_process(&mut self) {
-        self.images.remove(self.images.len() - 1);
-        self.finished = true;
-        self.position = 0;
-        self.total = 0;
+    /// Marks the whole working image as changed
+    fn mark_all_dirty(&mut self) {
+        if let Some(image) = self.images.last() {
+            let (columns, rows) = swap::tile_grid(image.width() as usize, image.height() as usize);
+            self.dirty.extend(0..columns * rows);
+        }
     }
 
-    pub fn can_update_ui(&self) -> bool {
-        self.position % self.block_size as usize * 2 == 0
+    /// Returns tiles of the working image changed since the last call
+    pub fn take_dirty_tiles(&mut self) -> Option<Vec<Area>> {
+        if self.dirty.is_empty() {
+            return None;
+        }
+
+        let dirty = std::mem::take(&mut self.dirty);
+        let image = self.images.last()?;
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let (columns, rows) = swap::tile_grid(width, height);
+        Some(
+            dirty
+                .into_iter()
+                .filter(|&index| index < columns * rows)
+                .map(|index| swap::tile_area(width, height, index))
+                .collect(),
+        )
     }
 
     /// Lazy iterative processing of VST effects (should called in a loop)
     pub fn process_next(&mut self) {
+        // changes made in plugin GUIs since the last block are recorded at its position
+        let position = (!self.finished).then(|| self.position);
+        for plugin in &mut self.plugins {
+            plugin.collect_automation(position);
+            if plugin.events.display_changed.swap(false, Ordering::Relaxed) {
+                plugin.refresh_display();
+            }
+        }
+
         if self.plugins.is_empty() {
             self.finished = true;
             return;
@@ -287,120 +3458,82 @@ impl PluginRack {
             return;
         }
 
-        //let full_process_time = std::time::Instant::now();
-
         for plugin in &mut self.plugins {
-            let instance = plugin.instance.as_mut();
+            plugin.apply_automation(self.position);
+        }
 
-            if instance.is_none() {
-                continue;
+        //let full_process_time = std::time::Instant::now();
+        if self.position == 0 {
+            self.clear_dither_errors();
+        }
+        let decay = (1.0 - self.wet_decay).powi(self.pass as i32);
+        let blocks = self.field_blocks();
+        for (_, segments) in &blocks {
+            for segment in segments {
+                self.save_snapshot_tiles(Some(segment.clone()));
             }
+        }
+        let image = self.images.last_mut().unwrap();
+        let width = image.width() as usize;
+        let data: &mut [u8] = image;
 
-            let instance = instance.unwrap();
-            //let start = std::time::Instant::now();
-            let input_count = instance.get_info().inputs as usize;
-            let output_count = instance.get_info().outputs as usize;
-
-            if plugin.bypass || input_count == 0 {
-                continue;
+        for (location, segments) in &blocks {
+            for segment in segments {
+                self.dirty.extend(swap::range_tiles(width, segment.clone()));
             }
-            //println!("i: {} o: {}", input_count, output_count);
-            // zeroing buffers
-            let mut buf: HostBuffer<f32> = HostBuffer::new(input_count, output_count);
-            let mut inputs: Vec<Vec<f32>> = vec![vec![0.0]; input_count];
-            let mut outputs = vec![vec![0.0]; output_count];
-
-            for sample in self
-                .images
-                .last()
-                .unwrap()
-                .pixels()
-                .skip(self.position)
-                .take(self.block_size as usize)
-            {
-                let srgb = Srgba::new(
-                    sample.0[0] as f32 / 255.0,
-                    sample.0[1] as f32 / 255.0,
-                    sample.0[2] as f32 / 255.0,
-                    sample.0[3] as f32 / 255.0,
-                );
-                let hsv = Hsva::from_color(srgb);
-                match plugin.input_channel {
-                    InputChannelType::Hue => {
-                        for i in 0..input_count {
-                            inputs[i].push(hsv.hue.to_positive_degrees() / 360.0);
-                        }
-                    }
-                    InputChannelType::Saturation => {
-                        for i in 0..input_count {
-                            inputs[i].push(hsv.saturation);
-                        }
-                    }
-                    InputChannelType::Value => {
-                        for i in 0..input_count {
-                            inputs[i].push(hsv.value);
+
+            match self.deep_images.last_mut() {
+                Some(DeepImage::Rgba16(deep)) => {
+                    let deep_data: &mut [u16] = deep;
+                    Self::process_segments(
+                        &mut self.plugins,
+                        self.graph.as_ref(),
+                        deep_data,
+                        segments,
+                        location,
+                        decay,
+                    );
+
+                    // 8 bit image is used only for preview
+                    for segment in segments {
+                        let range = segment.start * 4..segment.end * 4;
+                        for (pixel, deep_pixel) in
+                            data[range.clone()].iter_mut().zip(&deep_data[range])
+                        {
+                            *pixel = (deep_pixel >> 8) as u8;
                         }
                     }
                 }
+                Some(DeepImage::Rgba32F(hdr)) => {
+                    let hdr_data: &mut [f32] = hdr;
+                    Self::process_segments(
+                        &mut self.plugins,
+                        self.graph.as_ref(),
+                        hdr_data,
+                        segments,
+                        location,
+                        decay,
+                    );
 
-                for i in 0..output_count {
-                    outputs[i].push(0.0);
-                }
-            }
-
-            let mut audio_buffer = buf.bind(&inputs, &mut outputs);
-
-            //println!("Mapping took: {} ms", start.elapsed().as_millis());
-
-            //let start = std::time::Instant::now();
-            //println!("processing");
-            instance.suspend();
-            instance.set_sample_rate(plugin.sample_rate);
-            instance.set_block_size(inputs[0].len() as i64);
-            instance.resume();
-            instance.start_process();
-            instance.process(&mut audio_buffer);
-            instance.stop_process();
-            instance.suspend();
-
-            //println!("VST Processing took: {} ms", start.elapsed().as_millis());
-            //let start = std::time::Instant::now();
-            for (pixel, sample) in self
-                .images
-                .last_mut()
-                .unwrap()
-                .pixels_mut()
-                .skip(self.position)
-                .take(self.block_size as usize)
-                .zip(&outputs[plugin.output_channel])
-            {
-                let srgb = Srgba::new(
-                    pixel.0[0] as f32 / 255.0,
-                    pixel.0[1] as f32 / 255.0,
-                    pixel.0[2] as f32 / 255.0,
-                    pixel.0[3] as f32 / 255.0,
-                );
-                let mut hsv = Hsva::from_color(srgb);
-
-                match plugin.input_channel {
-                    InputChannelType::Hue => {
-                        hsv.hue = RgbHue::from_degrees((*sample * 360.0) * plugin.wet);
-                    }
-                    InputChannelType::Saturation => {
-                        hsv.saturation = *sample * plugin.wet;
-                    }
-                    InputChannelType::Value => {
-                        hsv.value = *sample * plugin.wet;
+                    for segment in segments {
+                        let range = segment.start * 4..segment.end * 4;
+                        for (pixel, hdr_pixel) in data[range.clone()]
+                            .chunks_exact_mut(4)
+                            .zip(hdr_data[range].chunks_exact(4))
+                        {
+                            pixel.copy_from_slice(&self.export.tone_map(hdr_pixel));
+                        }
                     }
                 }
-                let srgb = Srgba::from_color(hsv);
-
-                pixel.0[0] = (srgb.red * 255.0) as u8;
-                pixel.0[1] = (srgb.green * 255.0) as u8;
-                pixel.0[2] = (srgb.blue * 255.0) as u8;
-                pixel.0[3] = (srgb.alpha * 255.0) as u8;
+                None => Self::process_segments(
+                    &mut self.plugins,
+                    self.graph.as_ref(),
+                    data,
+                    segments,
+                    location,
+                    decay,
+                ),
             }
-            //println!("Image return took: {} ms", start.elapsed().as_millis());
         }
 
         if self.total == 0 {
@@ -408,10 +3541,47 @@ impl PluginRack {
         }
 
         if ((self.total as f32 * 1.2) as usize) < self.position {
-            self.finished = true;
+            if self.pass + 1 < self.iterations {
+                self.pass += 1;
+                self.position = 0;
+            } else if !self.next_frame() {
+                self.finished = true;
+            }
         } else {
             self.position += self.block_size as usize;
             //println!("processing: {} {} {}", len, self.position, self.block_size);
         }
+
+        self.run_time = self.started.elapsed();
+        self.save_partial(self.finished);
+        self.save_checkpoint(self.finished);
+    }
+}
+
+/// Writes image into project archive as `name.exr` for HDR images or `name.png` otherwise
+fn write_project_image<W: Write + Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    name: &str,
+    image: &image::RgbaImage,
+    deep: Option<&DeepImage>,
+    options: zip::write::FileOptions,
+) -> Result<()> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut cursor = Cursor::new(&mut bytes);
+    match deep {
+        Some(DeepImage::Rgba32F(image)) => {
+            zip.start_file(format!("{}.exr", name), options)?;
+            image.write_to(&mut cursor, image::ImageOutputFormat::OpenExr)?
+        }
+        Some(DeepImage::Rgba16(image)) => {
+            zip.start_file(format!("{}.png", name), options)?;
+            image.write_to(&mut cursor, image::ImageOutputFormat::Png)?
+        }
+        None => {
+            zip.start_file(format!("{}.png", name), options)?;
+            image.write_to(&mut cursor, image::ImageOutputFormat::Png)?
+        }
     }
+    zip.write_all(&bytes)?;
+    Ok(())
 }
@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use image::{DynamicImage, ImageBuffer};
+
+pub const RAW_EXTENSIONS: [&str; 12] = [
+    "cr2", "cr3", "crw", "nef", "nrw", "arw", "srf", "sr2", "orf", "rw2", "raf", "dng",
+];
+
+pub fn is_raw<P: AsRef<Path>>(file: P) -> bool {
+    file.as_ref()
+        .extension()
+        .map(|ext| {
+            RAW_EXTENSIONS
+                .iter()
+                .any(|raw| ext.eq_ignore_ascii_case(raw))
+        })
+        .unwrap_or(false)
+}
+
+/// Demosaics camera RAW file into 16 bit sRGB image
+pub fn load<P: AsRef<Path>>(file: P) -> anyhow::Result<DynamicImage> {
+    let mut pipeline =
+        imagepipe::Pipeline::new_from_file(file.as_ref()).map_err(|error| anyhow!(error))?;
+    let decoded = pipeline
+        .output_16bit(None)
+        .map_err(|error| anyhow!(error))?;
+
+    let image = ImageBuffer::from_raw(decoded.width as u32, decoded.height as u32, decoded.data)
+        .ok_or_else(|| anyhow!("RAW image data does not match its size"))?;
+    Ok(DynamicImage::ImageRgb16(image))
+}
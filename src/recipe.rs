@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::dither::Dither;
+use crate::effects;
+use crate::mask::ImageMask;
+use crate::plugin_rack::{
+    Field, Grayscale, InputChannelType, PluginRack, Region, SampleRate, TilePattern,
+};
+use crate::routing::{GraphNode, ProcessingGraph};
+use crate::settings::Settings;
+
+/// Image channel names used in recipes instead of numeric project values
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipeChannel {
+    Hue,
+    Saturation,
+    Value,
+}
+
+impl From<RecipeChannel> for InputChannelType {
+    fn from(channel: RecipeChannel) -> Self {
+        match channel {
+            RecipeChannel::Hue => InputChannelType::Hue,
+            RecipeChannel::Saturation => InputChannelType::Saturation,
+            RecipeChannel::Value => InputChannelType::Value,
+        }
+    }
+}
+
+/// Plugin slot of a recipe, omitted fields keep plugin defaults
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecipePlugin {
+    /// Plugin file, file name (without extension) of a plugin found in the plugin folders
+    /// or built-in effect path like `builtin:pixel-sort`
+    pub plugin: String,
+    /// Parameter values (0.0 - 1.0) by parameter name or index
+    #[serde(default)]
+    pub parameters: HashMap<String, f32>,
+    pub wet: Option<f32>,
+    pub input: Option<RecipeChannel>,
+    pub output_channel: Option<usize>,
+    /// Average all plugin outputs instead of using `output_channel`
+    pub mix_outputs: Option<bool>,
+    pub sample_rate: Option<f32>,
+    pub iterations: Option<usize>,
+    pub tile_pattern: Option<TilePattern>,
+    pub tile_size: Option<usize>,
+    pub region: Option<Region>,
+    /// Wet mask made from brightness, edges or color of the image
+    pub mask: Option<ImageMask>,
+    /// Field processed in interlaced mode, both fields if not set
+    pub field: Option<Field>,
+    /// Output shift in pixels
+    pub sample_offset: Option<isize>,
+}
+
+/// Text description of a plugin chain which can be applied to any image from the command line
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Recipe {
+    pub plugins: Vec<RecipePlugin>,
+    /// Order in which plugins are applied as indices of `plugins`, listed order if not set
+    pub order: Option<Vec<usize>>,
+    /// Processing graph, overrides `order`
+    pub routing: Option<ProcessingGraph>,
+    pub iterations: Option<usize>,
+    pub wet_decay: Option<f32>,
+    /// Processes even and odd scanlines as separate streams
+    pub interlaced: Option<bool>,
+    /// Sample rate of plugins which don't set their own
+    pub sample_rate: Option<SampleRate>,
+    pub dither: Option<Dither>,
+    /// Processes image luminance as a single stream
+    pub grayscale: Option<Grayscale>,
+    pub block_size: Option<i64>,
+}
+
+impl Recipe {
+    /// Loads YAML or JSON recipe depending on file extension
+    pub fn load<P: AsRef<Path>>(file: P) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(&file)?;
+        let is_json = file
+            .as_ref()
+            .extension()
+            .map_or(false, |ext| ext.eq_ignore_ascii_case("json"));
+
+        if is_json {
+            Ok(serde_json::from_str(&text)?)
+        } else {
+            Ok(serde_yaml::from_str(&text)?)
+        }
+    }
+
+    /// Replaces the chain of `rack` with plugins of the recipe
+    pub fn apply(&self, rack: &mut PluginRack) -> anyhow::Result<()> {
+        let library = Settings::load().scan_plugins();
+
+        rack.plugins.clear();
+        rack.groups.clear();
+        rack.graph = None;
+
+        for slot in &self.plugins {
+            let path = resolve_plugin(&slot.plugin, &library)?;
+            rack.load_plugin(path)?;
+            let plugin = rack.plugins.last_mut().unwrap();
+
+            let count = plugin.parameter_count();
+            let names: Vec<String> = (0..count).map(|i| plugin.parameter_name(i)).collect();
+
+            for (key, value) in &slot.parameters {
+                let index = key
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|index| (0..count).contains(index))
+                    .or_else(|| {
+                        names
+                            .iter()
+                            .position(|name| name.trim().eq_ignore_ascii_case(key.trim()))
+                            .map(|index| index as i32)
+                    })
+                    .with_context(|| format!("Plugin {} has no parameter {}", slot.plugin, key))?;
+                plugin.set_parameter(index, value.clamp(0.0, 1.0));
+            }
+
+            if let Some(wet) = slot.wet {
+                plugin.wet = wet.clamp(0.0, 1.0);
+            }
+            if let Some(input) = slot.input {
+                plugin.input_channel = input.into();
+            }
+            if let Some(output_channel) = slot.output_channel {
+                let outputs = plugin.output_count();
+                if output_channel >= outputs {
+                    anyhow::bail!(
+                        "Plugin {} has no output channel {}, it has {} outputs",
+                        slot.plugin,
+                        output_channel,
+                        outputs
+                    );
+                }
+                plugin.output_channel = output_channel;
+            }
+            if let Some(mix_outputs) = slot.mix_outputs {
+                plugin.mix_outputs = mix_outputs;
+            }
+            if slot.sample_rate.is_some() {
+                plugin.sample_rate = slot.sample_rate;
+            }
+            if let Some(iterations) = slot.iterations {
+                plugin.iterations = iterations.max(1);
+            }
+            if let Some(tile_pattern) = slot.tile_pattern {
+                plugin.tile_pattern = tile_pattern;
+            }
+            if let Some(tile_size) = slot.tile_size {
+                plugin.tile_size = tile_size.max(1);
+            }
+            plugin.region = slot.region;
+            plugin.mask = slot.mask;
+            plugin.field = slot.field;
+            if let Some(sample_offset) = slot.sample_offset {
+                plugin.sample_offset = sample_offset;
+            }
+        }
+
+        if let Some(routing) = &self.routing {
+            rack.graph = Some(routing.clone());
+        } else if let Some(order) = &self.order {
+            if let Some(id) = order.iter().find(|id| **id >= self.plugins.len()) {
+                anyhow::bail!("Plugin {} in the recipe order doesn't exist", id);
+            }
+            rack.graph = Some(ProcessingGraph {
+                nodes: order.iter().copied().map(GraphNode::Plugin).collect(),
+            });
+        }
+
+        if let Some(iterations) = self.iterations {
+            rack.iterations = iterations.max(1);
+        }
+        if let Some(wet_decay) = self.wet_decay {
+            rack.wet_decay = wet_decay;
+        }
+        if let Some(interlaced) = self.interlaced {
+            rack.interlaced = interlaced;
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            rack.sample_rate = sample_rate;
+        }
+        if let Some(dither) = self.dither {
+            rack.dither = dither;
+        }
+        if let Some(grayscale) = self.grayscale {
+            rack.grayscale = Some(grayscale);
+        }
+        if let Some(block_size) = self.block_size {
+            rack.block_size = block_size;
+        }
+        Ok(())
+    }
+}
+
+/// Finds plugin by path or by file name in the scanned plugin library
+fn resolve_plugin(plugin: &str, library: &[PathBuf]) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(plugin);
+    if path.exists() || effects::create_effect(&path).is_some() {
+        return Ok(path);
+    }
+
+    library
+        .iter()
+        .find(|file| {
+            file.file_stem().map_or(false, |stem| {
+                stem.to_string_lossy().eq_ignore_ascii_case(plugin)
+            })
+        })
+        .cloned()
+        .with_context(|| format!("Plugin {} is not found in the plugin folders", plugin))
+}
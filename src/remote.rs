@@ -0,0 +1,371 @@
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::state_headless::StateHeadless;
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Worker accepts connections only from this machine unless other address is given
+pub const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1";
+
+/// Progress messages are sent not more often than this
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Header size limit, image data goes into the payload
+const MAX_HEADER_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Payload size limit, enough for encoded images of any sane resolution
+const MAX_PAYLOAD_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Header size limit of the first message, read before the master is authenticated
+const MAX_HELLO_SIZE: u32 = 4 * 1024;
+
+/// Connections served at the same time, other connections are closed right away
+const MAX_CONNECTIONS: usize = 16;
+
+/// Time given to the master to authenticate
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Authenticated connection is closed when the master sends nothing for this long
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time the master waits for the worker, progress is reported while the job is rendered
+const WORKER_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Jobs rendered by this worker, used for temporary file names
+static JOB_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Protocol message, every message is followed by a binary payload (possibly empty)
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+enum Message {
+    /// First message of the connection, worker replies with `Done` or `Failed`
+    Hello {
+        token: String,
+    },
+    /// Render the payload image with the chain and reply with the image encoded as `extension`
+    Job {
+        chain: String,
+        extension: String,
+        /// Export options in the command line form
+        export: Vec<String>,
+    },
+    Progress {
+        position: usize,
+        total: usize,
+    },
+    /// Payload is the encoded result
+    Done,
+    Failed {
+        error: String,
+    },
+}
+
+/// Frame: header length (u32 BE), JSON header, payload length (u64 BE), payload
+fn write_message(stream: &mut TcpStream, message: &Message, payload: &[u8]) -> anyhow::Result<()> {
+    let header = serde_json::to_vec(message)?;
+    stream.write_all(&(header.len() as u32).to_be_bytes())?;
+    stream.write_all(&header)?;
+    stream.write_all(&(payload.len() as u64).to_be_bytes())?;
+    stream.write_all(payload)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_message(stream: &mut TcpStream) -> anyhow::Result<(Message, Vec<u8>)> {
+    read_limited_message(stream, MAX_HEADER_SIZE, MAX_PAYLOAD_SIZE)
+}
+
+fn read_limited_message(
+    stream: &mut TcpStream,
+    max_header: u32,
+    max_payload: u64,
+) -> anyhow::Result<(Message, Vec<u8>)> {
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length);
+    if length > max_header {
+        anyhow::bail!("Message header is too large: {} bytes", length);
+    }
+    let mut header = vec![0; length as usize];
+    stream.read_exact(&mut header)?;
+
+    let mut length = [0; 8];
+    stream.read_exact(&mut length)?;
+    let length = u64::from_be_bytes(length);
+    if length > max_payload {
+        anyhow::bail!("Message payload is too large: {} bytes", length);
+    }
+    let mut payload = Vec::new();
+    stream.take(length).read_to_end(&mut payload)?;
+
+    Ok((serde_json::from_slice(&header)?, payload))
+}
+
+/// Compares tokens in constant time
+fn token_matches(token: &str, expected: &str) -> bool {
+    token.len() == expected.len()
+        && token
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Only encoders known to the image crate are accepted, the extension becomes a part of the
+/// temporary file name
+fn is_image_extension(extension: &str) -> bool {
+    extension.chars().all(|c| c.is_ascii_alphanumeric())
+        && ImageFormat::from_extension(extension).is_some()
+}
+
+fn set_timeout(stream: &TcpStream, timeout: Duration) -> io::Result<()> {
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))
+}
+
+/// Counts the connection as served until dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn acquire(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < MAX_CONNECTIONS).then(|| count + 1)
+            })
+            .ok()
+            .map(|_| Self(Arc::clone(connections)))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Runs `photoconsequences worker`, every connection is served in its own thread.
+/// Masters must authenticate with the same `token`
+pub fn serve(address: &str, port: u16, token: &str) -> anyhow::Result<()> {
+    if token.is_empty() {
+        anyhow::bail!("Worker token must not be empty");
+    }
+    let listener = TcpListener::bind((address, port))?;
+    log::info!("Render worker is listening on {}:{}", address, port);
+
+    let connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                log::warn!("Unable to accept connection: {}", error);
+                continue;
+            }
+        };
+        let peer = stream
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+        let slot = match ConnectionSlot::acquire(&connections) {
+            Some(slot) => slot,
+            None => {
+                log::warn!("Too many connections, {} is refused", peer);
+                continue;
+            }
+        };
+        if let Err(error) = set_timeout(&stream, HELLO_TIMEOUT) {
+            log::warn!("Unable to configure connection {}: {}", peer, error);
+            continue;
+        }
+
+        let token = token.to_string();
+        std::thread::spawn(move || {
+            let _slot = slot;
+            log::info!("Connected: {}", peer);
+            if let Err(error) = handle_connection(stream, &token) {
+                log::warn!("Connection {} closed: {}", peer, error);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, token: &str) -> anyhow::Result<()> {
+    // hello carries no payload
+    match read_limited_message(&mut stream, MAX_HELLO_SIZE, 0)? {
+        (Message::Hello { token: received }, _) if token_matches(&received, token) => {
+            set_timeout(&stream, IDLE_TIMEOUT)?;
+            write_message(&mut stream, &Message::Done, &[])?;
+        }
+        _ => {
+            let message = Message::Failed {
+                error: String::from("Invalid worker token"),
+            };
+            write_message(&mut stream, &message, &[])?;
+            anyhow::bail!("Invalid worker token");
+        }
+    }
+
+    loop {
+        let (message, payload) = match read_message(&mut stream) {
+            Ok(message) => message,
+            // master has no more jobs
+            Err(_) => return Ok(()),
+        };
+
+        match message {
+            Message::Job {
+                chain,
+                extension,
+                export,
+            } => match render(&mut stream, &chain, &payload, &extension, &export) {
+                Ok(result) => write_message(&mut stream, &Message::Done, &result)?,
+                Err(error) => {
+                    log::error!("Unable to render job: {}", error);
+                    let message = Message::Failed {
+                        error: error.to_string(),
+                    };
+                    write_message(&mut stream, &message, &[])?;
+                }
+            },
+            message => anyhow::bail!("Unexpected message: {:?}", message),
+        }
+    }
+}
+
+fn render(
+    stream: &mut TcpStream,
+    chain: &str,
+    image: &[u8],
+    extension: &str,
+    export: &[String],
+) -> anyhow::Result<Vec<u8>> {
+    if !is_image_extension(extension) {
+        anyhow::bail!("Unsupported output format: {}", extension);
+    }
+    let mut state = StateHeadless::new();
+    state.rack.block_size = 16384;
+    state.rack.load_chain(chain)?;
+    state.rack.export.parse_args(export)?;
+    state.rack.load_image_data(image)?;
+    state.rack.start_process()?;
+
+    let mut last_progress = Instant::now();
+    while !state.rack.is_finished() {
+        state.rack.process_next();
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            let progress = state.rack.progress();
+            let progress = Message::Progress {
+                position: progress.done,
+                total: progress.total,
+            };
+            // write fails when master cancels the job
+            write_message(stream, &progress, &[])?;
+        }
+    }
+
+    // encoder is picked by the file extension
+    let id = JOB_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let file = std::env::temp_dir().join(format!(
+        "photoconsequences-worker-{}-{}.{}",
+        std::process::id(),
+        id,
+        extension
+    ));
+    state.rack.save_image(&file)?;
+    let result = std::fs::read(&file);
+    std::fs::remove_file(&file)?;
+    Ok(result?)
+}
+
+/// Failure of a job sent to the worker
+#[derive(Error, Debug)]
+pub enum JobError {
+    /// Connection is lost or the worker is misbehaving, the job can be given to another worker
+    #[error("Connection error: {0}")]
+    Connection(#[from] anyhow::Error),
+    /// Worker was unable to render the image
+    #[error("{0}")]
+    Failed(String),
+    /// Unable to read the input or write the result on this machine
+    #[error("Unable to read or write file: {0}")]
+    Local(#[source] io::Error),
+}
+
+/// Connection to a machine running `photoconsequences worker`
+pub struct RemoteWorker {
+    pub address: String,
+    stream: TcpStream,
+}
+
+impl RemoteWorker {
+    /// `address` is `host` or `host:port`, `token` must match the token of the worker
+    pub fn connect(address: &str, token: &str) -> anyhow::Result<Self> {
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_PORT)
+        };
+        let mut stream = TcpStream::connect(&address)?;
+        set_timeout(&stream, WORKER_TIMEOUT)?;
+        let hello = Message::Hello {
+            token: token.to_string(),
+        };
+        write_message(&mut stream, &hello, &[])?;
+        match read_message(&mut stream)? {
+            (Message::Done, _) => Ok(Self { address, stream }),
+            (Message::Failed { error }, _) => anyhow::bail!(error),
+            (message, _) => anyhow::bail!("Unexpected message: {:?}", message),
+        }
+    }
+
+    /// Renders `image` on the worker and writes result into `output`,
+    /// plugins of the chain must be installed at the same paths on the worker machine
+    pub fn render<F: FnMut(usize, usize)>(
+        &mut self,
+        chain: &str,
+        image: &Path,
+        output: &Path,
+        export: &[String],
+        mut progress: F,
+    ) -> Result<(), JobError> {
+        let extension = output
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("png"));
+        let job = Message::Job {
+            chain: chain.to_string(),
+            extension,
+            export: export.to_vec(),
+        };
+        let image = std::fs::read(image).map_err(JobError::Local)?;
+        write_message(&mut self.stream, &job, &image)?;
+
+        loop {
+            match read_message(&mut self.stream)? {
+                (Message::Progress { position, total }, _) => progress(position, total),
+                (Message::Done, result) => {
+                    return std::fs::write(output, result).map_err(JobError::Local);
+                }
+                (Message::Failed { error }, _) => return Err(JobError::Failed(error)),
+                (message, _) => {
+                    return Err(anyhow::anyhow!("Unexpected message: {:?}", message).into())
+                }
+            }
+        }
+    }
+}
@@ -4,6 +4,8 @@ use image::RgbaImage;
 use wgpu::{Device, Queue, Surface, SurfaceConfiguration, Texture};
 use winit::window::Window;
 
+use crate::swap::Area;
+
 pub enum Event {
     RequestRedraw,
 }
@@ -24,6 +26,11 @@ pub struct Renderer {
     /// Image preview texture
     pub texture: Option<egui::TextureId>,
     pub texture_native: Option<Texture>,
+    /// Size of `texture_native`
+    pub texture_size: (u32, u32),
+    /// Unprocessed image texture used by compare views
+    pub original: Option<egui::TextureId>,
+    original_native: Option<Texture>,
     pub device: Device,
     pub queue: Queue,
     pub render_pass: RenderPass,
@@ -69,6 +76,9 @@ impl Renderer {
             windows: Vec::new(),
             texture: None,
             texture_native: None,
+            texture_size: (0, 0),
+            original: None,
+            original_native: None,
             device,
             queue,
             surface,
@@ -93,14 +103,76 @@ impl Renderer {
         }
     }
 
+    /// Writes tiles of the image into already uploaded texture,
+    /// returns `false` if the texture has to be uploaded again
+    pub fn update_texture_tiles(&mut self, image: &RgbaImage, tiles: &[Area]) -> bool {
+        let texture = match &self.texture_native {
+            Some(texture) if self.texture.is_some() => texture,
+            _ => return false,
+        };
+
+        if self.texture_size != image.dimensions() {
+            return false;
+        }
+
+        let row_size = 4 * image.width() as usize;
+        for &(left, top, width, height) in tiles {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: left as u32,
+                        y: top as u32,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &image[top * row_size + left * 4..],
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(row_size as u32),
+                    rows_per_image: std::num::NonZeroU32::new(height as u32),
+                },
+                wgpu::Extent3d {
+                    width: width as u32,
+                    height: height as u32,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        true
+    }
+
     pub fn upload_texture(&mut self, image: &RgbaImage) -> TextureId {
+        self.texture_size = image.dimensions();
+        let (texture, id) = self.create_texture(image);
+        self.texture_native = Some(texture);
+        id
+    }
+
+    pub fn upload_original(&mut self, image: &RgbaImage) {
+        self.destroy_original();
+        let (texture, id) = self.create_texture(image);
+        self.original_native = Some(texture);
+        self.original = Some(id);
+    }
+
+    pub fn destroy_original(&mut self) {
+        if let Some(texture) = self.original_native.take() {
+            texture.destroy();
+        }
+        self.original = None;
+    }
+
+    fn create_texture(&mut self, image: &RgbaImage) -> (Texture, TextureId) {
         let texture_size = wgpu::Extent3d {
             width: image.width(),
             height: image.height(),
             depth_or_array_layers: 1,
         };
 
-        self.texture_native = Some(self.device.create_texture(&wgpu::TextureDescriptor {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             // All textures are stored as 3D, we represent our 2D texture
             // by setting depth to 1.
             size: texture_size,
@@ -113,12 +185,12 @@ impl Renderer {
             // COPY_DST means that we want to copy data to this texture
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             label: Some("diffuse_texture"),
-        }));
+        });
 
         self.queue.write_texture(
             // Tells wgpu where to copy the pixel data
             wgpu::ImageCopyTexture {
-                texture: self.texture_native.as_ref().unwrap(),
+                texture: &texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
@@ -134,16 +206,13 @@ impl Renderer {
             texture_size,
         );
 
-        let view = self
-            .texture_native
-            .as_ref()
-            .unwrap()
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        self.render_pass.egui_texture_from_wgpu_texture(
+        let id = self.render_pass.egui_texture_from_wgpu_texture(
             &self.device,
             &view,
             wgpu::FilterMode::Nearest,
-        )
+        );
+        (texture, id)
     }
 }
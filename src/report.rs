@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::{Result, VstImageError};
+use crate::plugin_rack::PluginRack;
+use crate::VERSION;
+
+#[derive(Serialize, Debug)]
+pub struct ParameterReport {
+    pub name: String,
+    pub value: f32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PluginReport {
+    pub name: String,
+    pub path: String,
+    pub bypass: bool,
+    pub wet: f32,
+    pub iterations: usize,
+    /// Processing time in seconds
+    pub time: f32,
+    pub parameters: Vec<ParameterReport>,
+}
+
+/// Description of a processing run, written after the run to document and reproduce the result
+#[derive(Serialize, Debug)]
+pub struct ProcessingReport {
+    pub version: String,
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    pub seed: Option<u64>,
+    pub iterations: usize,
+    /// Duration of the whole run in seconds
+    pub time: f32,
+    /// FNV-1a hash of the processed 8 bit RGBA pixels
+    pub output_hash: String,
+    pub plugins: Vec<PluginReport>,
+}
+
+impl ProcessingReport {
+    pub fn new(rack: &PluginRack) -> Result<Self> {
+        let image = rack.images.last().ok_or(VstImageError::NoImage)?;
+        let plugins = rack
+            .plugins
+            .iter()
+            .map(|plugin| PluginReport {
+                name: plugin.get_name(),
+                path: plugin.get_path().display().to_string(),
+                bypass: plugin.bypass,
+                wet: plugin.wet,
+                iterations: plugin.iterations,
+                time: plugin.elapsed.as_secs_f32(),
+                parameters: (0..plugin.parameter_count())
+                    .map(|index| ParameterReport {
+                        name: plugin.parameter_name(index),
+                        value: plugin.get_parameter(index),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Self {
+            version: VERSION.to_string(),
+            image: rack.source_name.clone(),
+            width: image.width(),
+            height: image.height(),
+            seed: rack.seed,
+            iterations: rack.iterations,
+            time: rack.progress().elapsed.as_secs_f32(),
+            output_hash: format!("{:016x}", fnv1a(image)),
+            plugins,
+        })
+    }
+
+    /// Writes CSV if the file has `.csv` extension or JSON otherwise
+    pub fn save<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        let is_csv = file
+            .as_ref()
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("csv"));
+        let text = if is_csv {
+            self.to_csv()
+        } else {
+            serde_json::to_string_pretty(self)?
+        };
+        std::fs::write(file, text)?;
+        Ok(())
+    }
+
+    /// One row per plugin parameter, run fields are repeated on every row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "version,image,width,height,seed,iterations,time,output_hash,\
+             slot,plugin,path,bypass,wet,plugin_iterations,plugin_time,parameter,value\n",
+        );
+        let run = [
+            csv_field(&self.version),
+            csv_field(&self.image),
+            self.width.to_string(),
+            self.height.to_string(),
+            self.seed.map(|seed| seed.to_string()).unwrap_or_default(),
+            self.iterations.to_string(),
+            self.time.to_string(),
+            self.output_hash.clone(),
+        ]
+        .join(",");
+
+        for (slot, plugin) in self.plugins.iter().enumerate() {
+            let plugin_fields = [
+                slot.to_string(),
+                csv_field(&plugin.name),
+                csv_field(&plugin.path),
+                plugin.bypass.to_string(),
+                plugin.wet.to_string(),
+                plugin.iterations.to_string(),
+                plugin.time.to_string(),
+            ]
+            .join(",");
+
+            // plugins without parameters still get a row
+            let parameters: Vec<(String, String)> = if plugin.parameters.is_empty() {
+                vec![(String::new(), String::new())]
+            } else {
+                plugin
+                    .parameters
+                    .iter()
+                    .map(|parameter| (csv_field(&parameter.name), parameter.value.to_string()))
+                    .collect()
+            };
+            for (name, value) in parameters {
+                csv.push_str(&format!("{},{},{},{}\n", run, plugin_fields, name, value));
+            }
+        }
+        csv
+    }
+}
+
+/// Quotes the field if it contains separators or quotes
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// 64 bit FNV-1a, stable between builds unlike the standard library hasher
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
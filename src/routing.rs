@@ -0,0 +1,329 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    i18n,
+    plugin_rack::{BlockLocation, PluginRackInstance, Sample},
+};
+
+/// Node of the processing graph
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum GraphNode {
+    /// Rack slot index
+    Plugin(usize),
+    /// Parallel branches processed from the same input and merged back by their mix amounts
+    Split(Vec<Branch>),
+}
+
+/// How branch output is combined with the result of the other branches
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Averaged with other normal branches by their gain
+    #[default]
+    Normal,
+    Add,
+    Multiply,
+    Screen,
+    Difference,
+    Lighten,
+    Darken,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 7] = [
+        BlendMode::Normal,
+        BlendMode::Add,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Difference,
+        BlendMode::Lighten,
+        BlendMode::Darken,
+    ];
+
+    pub fn name(&self) -> String {
+        let id = match self {
+            BlendMode::Normal => "blend-normal",
+            BlendMode::Add => "blend-add",
+            BlendMode::Multiply => "blend-multiply",
+            BlendMode::Screen => "blend-screen",
+            BlendMode::Difference => "blend-difference",
+            BlendMode::Lighten => "blend-lighten",
+            BlendMode::Darken => "blend-darken",
+        };
+        i18n::tr(id)
+    }
+
+    /// Blends normalized (0.0 - 1.0) values
+    pub fn blend(&self, base: f32, layer: f32) -> f32 {
+        match self {
+            BlendMode::Normal => layer,
+            BlendMode::Add => (base + layer).min(1.0),
+            BlendMode::Multiply => base * layer,
+            BlendMode::Screen => 1.0 - (1.0 - base) * (1.0 - layer),
+            BlendMode::Difference => (base - layer).abs(),
+            BlendMode::Lighten => base.max(layer),
+            BlendMode::Darken => base.min(layer),
+        }
+    }
+}
+
+/// RGB channel which is processed by a per-channel branch
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChannel {
+    Red,
+    Green,
+    Blue,
+}
+
+impl ColorChannel {
+    pub const ALL: [ColorChannel; 3] = [ColorChannel::Red, ColorChannel::Green, ColorChannel::Blue];
+
+    pub fn name(&self) -> String {
+        let id = match self {
+            ColorChannel::Red => "channel-red",
+            ColorChannel::Green => "channel-green",
+            ColorChannel::Blue => "channel-blue",
+        };
+        i18n::tr(id)
+    }
+
+    /// Index of the channel in RGBA pixel
+    pub fn index(&self) -> usize {
+        match self {
+            ColorChannel::Red => 0,
+            ColorChannel::Green => 1,
+            ColorChannel::Blue => 2,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Branch {
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "Nodes")]
+    pub nodes: Vec<GraphNode>,
+    /// Branch gain, relative weight for normal branches and opacity for the others
+    #[serde(rename = "Mix")]
+    pub mix: f32,
+    #[serde(rename = "Blend", default)]
+    pub blend: BlendMode,
+    /// Plugins of the branch process only this channel and only it is blended back
+    #[serde(rename = "Channel", default)]
+    pub channel: Option<ColorChannel>,
+}
+
+impl Branch {
+    pub fn new(nodes: Vec<GraphNode>) -> Self {
+        Self {
+            name: String::new(),
+            nodes,
+            mix: 1.0,
+            blend: BlendMode::Normal,
+            channel: None,
+        }
+    }
+
+    pub fn named(name: String, nodes: Vec<GraphNode>) -> Self {
+        Self {
+            name,
+            ..Self::new(nodes)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ProcessingGraph {
+    #[serde(rename = "Nodes")]
+    pub nodes: Vec<GraphNode>,
+}
+
+impl ProcessingGraph {
+    /// Creates a graph which behaves exactly like a simple chain of `count` plugins
+    pub fn from_chain(count: usize) -> Self {
+        Self {
+            nodes: (0..count).map(GraphNode::Plugin).collect(),
+        }
+    }
+
+    /// Adds a new named chain processed in parallel from the same source,
+    /// existing nodes become the first chain if graph is not a parallel mix yet
+    pub fn add_parallel_chain(&mut self) {
+        let is_mixer = matches!(self.nodes.as_slice(), [GraphNode::Split(_)]);
+
+        if !is_mixer {
+            let nodes = std::mem::take(&mut self.nodes);
+            self.nodes.push(GraphNode::Split(vec![Branch::named(
+                "Chain 1".to_string(),
+                nodes,
+            )]));
+        }
+
+        if let Some(GraphNode::Split(branches)) = self.nodes.first_mut() {
+            let name = format!("Chain {}", branches.len() + 1);
+            branches.push(Branch::named(name, Vec::new()));
+        }
+    }
+
+    /// Adds empty red, green and blue chains after the current nodes, processed in one run
+    pub fn add_channel_chains(&mut self) {
+        let branches = ColorChannel::ALL
+            .into_iter()
+            .map(|channel| Branch {
+                channel: Some(channel),
+                ..Branch::named(format!("{:?}", channel), Vec::new())
+            })
+            .collect();
+        self.nodes.push(GraphNode::Split(branches));
+    }
+
+    pub fn push_plugin(&mut self, id: usize) {
+        self.nodes.push(GraphNode::Plugin(id));
+    }
+
+    /// Removes all references to the plugin and shifts indices of the following plugins
+    pub fn remove_plugin(&mut self, id: usize) {
+        remove_from_nodes(&mut self.nodes, id);
+    }
+
+    /// Shifts indices for a plugin inserted at `id + 1` and routes it right after plugin `id`
+    pub fn insert_plugin_after(&mut self, id: usize) {
+        insert_after_in_nodes(&mut self.nodes, id);
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        nodes_contain(&self.nodes, id)
+    }
+
+    pub fn process<S: Sample>(
+        &self,
+        plugins: &mut [PluginRackInstance],
+        pixels: &mut [S],
+        location: &BlockLocation,
+        decay: f32,
+    ) {
+        process_nodes(&self.nodes, plugins, pixels, location, decay);
+    }
+}
+
+fn nodes_contain(nodes: &[GraphNode], id: usize) -> bool {
+    nodes.iter().any(|node| match node {
+        GraphNode::Plugin(index) => *index == id,
+        GraphNode::Split(branches) => branches
+            .iter()
+            .any(|branch| nodes_contain(&branch.nodes, id)),
+    })
+}
+
+fn remove_from_nodes(nodes: &mut Vec<GraphNode>, id: usize) {
+    nodes.retain(|node| *node != GraphNode::Plugin(id));
+
+    for node in nodes.iter_mut() {
+        match node {
+            GraphNode::Plugin(index) => {
+                if *index > id {
+                    *index -= 1;
+                }
+            }
+            GraphNode::Split(branches) => {
+                for branch in branches {
+                    remove_from_nodes(&mut branch.nodes, id);
+                }
+            }
+        }
+    }
+}
+
+fn insert_after_in_nodes(nodes: &mut Vec<GraphNode>, id: usize) {
+    let mut idx = 0;
+    while idx < nodes.len() {
+        match &mut nodes[idx] {
+            GraphNode::Plugin(index) => {
+                if *index > id {
+                    *index += 1;
+                } else if *index == id {
+                    nodes.insert(idx + 1, GraphNode::Plugin(id + 1));
+                    idx += 1;
+                }
+            }
+            GraphNode::Split(branches) => {
+                for branch in branches {
+                    insert_after_in_nodes(&mut branch.nodes, id);
+                }
+            }
+        }
+        idx += 1;
+    }
+}
+
+fn process_nodes<S: Sample>(
+    nodes: &[GraphNode],
+    plugins: &mut [PluginRackInstance],
+    pixels: &mut [S],
+    location: &BlockLocation,
+    decay: f32,
+) {
+    for node in nodes {
+        match node {
+            GraphNode::Plugin(id) => {
+                if let Some(plugin) = plugins.get_mut(*id) {
+                    plugin.process(pixels, location, decay);
+                }
+            }
+            GraphNode::Split(branches) => {
+                let mut mixed = vec![0.0f32; pixels.len()];
+                let mut total_mix = 0.0;
+                let mut layers = Vec::new();
+
+                for branch in branches {
+                    let mut buffer = pixels.to_vec();
+                    let location = BlockLocation {
+                        channel: branch.channel.or(location.channel),
+                        ..*location
+                    };
+                    process_nodes(&branch.nodes, plugins, &mut buffer, &location, decay);
+
+                    if branch.blend == BlendMode::Normal && branch.channel.is_none() {
+                        for (value, sample) in mixed.iter_mut().zip(&buffer) {
+                            *value += sample.to_f32() * branch.mix;
+                        }
+                        total_mix += branch.mix;
+                    } else {
+                        layers.push((branch, buffer));
+                    }
+                }
+
+                if total_mix > 0.0 {
+                    for (pixel, value) in pixels.iter_mut().zip(&mixed) {
+                        let value = value / total_mix;
+                        *pixel = if S::INTEGER {
+                            S::from_f32(value.round().min(S::MAX))
+                        } else {
+                            S::from_f32(value)
+                        };
+                    }
+                }
+
+                for (branch, buffer) in layers {
+                    let opacity = branch.mix.clamp(0.0, 1.0);
+                    for (index, (pixel, layer)) in pixels.iter_mut().zip(&buffer).enumerate() {
+                        // per-channel branches leave the other channels untouched
+                        if let Some(channel) = branch.channel {
+                            if index % 4 != channel.index() {
+                                continue;
+                            }
+                        }
+                        let base = pixel.to_f32() / S::MAX;
+                        let blended = branch.blend.blend(base, layer.to_f32() / S::MAX);
+                        let value = base + (blended - base) * opacity;
+                        // float images keep values out of the 0.0 - 1.0 range
+                        *pixel = if S::INTEGER {
+                            S::from_f32((value.clamp(0.0, 1.0) * S::MAX).round())
+                        } else {
+                            S::from_f32(value * S::MAX)
+                        };
+                    }
+                }
+            }
+        }
+    }
+}
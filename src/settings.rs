@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Language;
+use crate::keymap::{self, Binding};
+use crate::plugin_rack::ProjectContents;
+use crate::theme::Theme;
+
+/// Application preferences stored in the platform config directory
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// Folders scanned for plugins used by randomizer
+    pub plugin_dirs: Vec<PathBuf>,
+    /// Plugins which are never loaded
+    pub plugin_blacklist: Vec<PathBuf>,
+    /// Offer to reload plugins when their files change
+    pub watch_plugins: bool,
+    /// Re-import source image when it is changed by another program
+    pub watch_image: bool,
+    /// Tile size of newly added plugins
+    pub tile_size: usize,
+    /// Processing threads in CLI mode, 0 uses every CPU
+    pub threads: usize,
+    /// Partial save interval in minutes
+    pub autosave_minutes: u64,
+    /// Extension used when exported file name has none
+    pub export_format: String,
+    /// Program used to edit the result
+    pub external_editor: Option<PathBuf>,
+    pub dock_editors: bool,
+    pub pixel_grid: bool,
+    pub show_rulers: bool,
+    pub language: Language,
+    // tables have to follow plain values in TOML
+    pub keymap: Vec<Binding>,
+    pub theme: Theme,
+    /// Optional parts of saved projects
+    pub project_contents: ProjectContents,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            plugin_dirs: Vec::new(),
+            plugin_blacklist: Vec::new(),
+            watch_plugins: false,
+            watch_image: false,
+            tile_size: 64,
+            threads: 0,
+            autosave_minutes: 5,
+            export_format: String::from("png"),
+            external_editor: None,
+            dock_editors: false,
+            pixel_grid: true,
+            show_rulers: true,
+            keymap: keymap::default_bindings(),
+            theme: Theme::default(),
+            project_contents: ProjectContents::default(),
+            language: Language::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("photoconsequences").join("settings.toml"))
+    }
+
+    /// Reads settings file, defaults are used if it is missing or broken
+    pub fn load() -> Self {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Self::default(),
+        };
+
+        let result = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Ok(toml::from_str(&text)?));
+        result.unwrap_or_else(|error| {
+            log::error!("Unable to load settings {}: {}", path.display(), error);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path =
+            Self::path().ok_or_else(|| anyhow::anyhow!("Config directory is not available"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Plugins found in every plugin directory
+    pub fn scan_plugins(&self) -> Vec<PathBuf> {
+        let mut plugins: Vec<PathBuf> = self
+            .plugin_dirs
+            .iter()
+            .flat_map(crate::plugin_library::scan)
+            .collect();
+        plugins.sort();
+        plugins.dedup();
+        plugins
+    }
+}
@@ -0,0 +1,359 @@
+//! Batch conversion between RGB and HSV channels of pixel blocks,
+//! 8 pixels per iteration with AVX when the CPU supports it (checked at runtime)
+
+use crate::plugin_rack::InputChannelType;
+
+/// Normalized (0.0 - 1.0) channels of RGB pixels, one slice per channel
+pub struct Planar {
+    pub red: Vec<f32>,
+    pub green: Vec<f32>,
+    pub blue: Vec<f32>,
+}
+
+impl Planar {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            red: Vec::with_capacity(capacity),
+            green: Vec::with_capacity(capacity),
+            blue: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, [red, green, blue]: [f32; 3]) {
+        self.red.push(red);
+        self.green.push(green);
+        self.blue.push(blue);
+    }
+
+    pub fn len(&self) -> usize {
+        self.red.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.red.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> [f32; 3] {
+        [self.red[index], self.green[index], self.blue[index]]
+    }
+}
+
+/// HSV channel (0.0 - 1.0) of every pixel, hue is a fraction of the full circle
+pub fn rgb_to_channel(rgb: &Planar, channel: InputChannelType) -> Vec<f32> {
+    let mut result = vec![0.0; rgb.len()];
+    let done = {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx") {
+                // SAFETY: AVX support is checked above
+                unsafe { avx::rgb_to_channel(rgb, channel, &mut result) }
+            } else {
+                0
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            0
+        }
+    };
+
+    for (index, value) in result.iter_mut().enumerate().skip(done) {
+        let hsv = rgb_to_hsv(rgb.get(index));
+        *value = hsv[channel as usize];
+    }
+    result
+}
+
+/// Replaces HSV `channel` of every pixel with `values`, hue values wrap around
+pub fn replace_channel(rgb: &mut Planar, values: &[f32], channel: InputChannelType) {
+    let count = rgb.len().min(values.len());
+    let done = {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx") {
+                // SAFETY: AVX support is checked above
+                unsafe { avx::replace_channel(rgb, &values[..count], channel) }
+            } else {
+                0
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            0
+        }
+    };
+
+    for (index, value) in values.iter().enumerate().take(count).skip(done) {
+        let mut hsv = rgb_to_hsv(rgb.get(index));
+        hsv[channel as usize] = match channel {
+            InputChannelType::Hue => value - value.floor(),
+            _ => *value,
+        };
+        let [red, green, blue] = hsv_to_rgb(hsv);
+        rgb.red[index] = red;
+        rgb.green[index] = green;
+        rgb.blue[index] = blue;
+    }
+}
+
+fn rgb_to_hsv([red, green, blue]: [f32; 3]) -> [f32; 3] {
+    let max = red.max(green).max(blue);
+    let delta = max - red.min(green).min(blue);
+    let saturation = if max != 0.0 { delta / max } else { 0.0 };
+    if delta <= 0.0 {
+        return [0.0, saturation, max];
+    }
+
+    let hue = if max == red {
+        (green - blue) / delta
+    } else if max == green {
+        (blue - red) / delta + 2.0
+    } else {
+        (red - green) / delta + 4.0
+    } / 6.0;
+    [hue - hue.floor(), saturation, max]
+}
+
+fn hsv_to_rgb([hue, saturation, value]: [f32; 3]) -> [f32; 3] {
+    let chroma = value * saturation;
+    let sector = hue * 6.0;
+    let x = chroma * (1.0 - ((sector % 2.0) - 1.0).abs());
+    let (red, green, blue) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let min = value - chroma;
+    [red + min, green + min, blue + min]
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx {
+    use std::arch::x86_64::*;
+
+    use super::Planar;
+    use crate::plugin_rack::InputChannelType;
+
+    const LANES: usize = 8;
+
+    /// Converts whole 8 pixel chunks, returns the number of converted pixels
+    #[target_feature(enable = "avx")]
+    pub unsafe fn rgb_to_channel(
+        rgb: &Planar,
+        channel: InputChannelType,
+        result: &mut [f32],
+    ) -> usize {
+        let count = rgb.len() / LANES * LANES;
+        for start in (0..count).step_by(LANES) {
+            let hsv = rgb_to_hsv(load(rgb, start));
+            _mm256_storeu_ps(result[start..].as_mut_ptr(), hsv[channel as usize]);
+        }
+        count
+    }
+
+    /// Replaces the channel of whole 8 pixel chunks, returns the number of changed pixels
+    #[target_feature(enable = "avx")]
+    pub unsafe fn replace_channel(
+        rgb: &mut Planar,
+        values: &[f32],
+        channel: InputChannelType,
+    ) -> usize {
+        let count = values.len() / LANES * LANES;
+        for start in (0..count).step_by(LANES) {
+            let mut hsv = rgb_to_hsv(load(rgb, start));
+            let value = _mm256_loadu_ps(values[start..].as_ptr());
+            hsv[channel as usize] = match channel {
+                InputChannelType::Hue => _mm256_sub_ps(value, _mm256_floor_ps(value)),
+                _ => value,
+            };
+            let [red, green, blue] = hsv_to_rgb(hsv);
+            _mm256_storeu_ps(rgb.red[start..].as_mut_ptr(), red);
+            _mm256_storeu_ps(rgb.green[start..].as_mut_ptr(), green);
+            _mm256_storeu_ps(rgb.blue[start..].as_mut_ptr(), blue);
+        }
+        count
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn load(rgb: &Planar, start: usize) -> [__m256; 3] {
+        assert!(start + LANES <= rgb.len());
+        [
+            _mm256_loadu_ps(rgb.red[start..].as_ptr()),
+            _mm256_loadu_ps(rgb.green[start..].as_ptr()),
+            _mm256_loadu_ps(rgb.blue[start..].as_ptr()),
+        ]
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn rgb_to_hsv([red, green, blue]: [__m256; 3]) -> [__m256; 3] {
+        let zero = _mm256_setzero_ps();
+        let max = _mm256_max_ps(_mm256_max_ps(red, green), blue);
+        let min = _mm256_min_ps(_mm256_min_ps(red, green), blue);
+        let delta = _mm256_sub_ps(max, min);
+
+        // lanes divided by zero are masked out
+        let saturation = _mm256_and_ps(
+            _mm256_div_ps(delta, max),
+            _mm256_cmp_ps(max, zero, _CMP_NEQ_OQ),
+        );
+
+        let hue_red = _mm256_div_ps(_mm256_sub_ps(green, blue), delta);
+        let hue_green = _mm256_add_ps(
+            _mm256_div_ps(_mm256_sub_ps(blue, red), delta),
+            _mm256_set1_ps(2.0),
+        );
+        let hue_blue = _mm256_add_ps(
+            _mm256_div_ps(_mm256_sub_ps(red, green), delta),
+            _mm256_set1_ps(4.0),
+        );
+        let hue = _mm256_blendv_ps(hue_blue, hue_green, _mm256_cmp_ps(max, green, _CMP_EQ_OQ));
+        let hue = _mm256_blendv_ps(hue, hue_red, _mm256_cmp_ps(max, red, _CMP_EQ_OQ));
+        let hue = _mm256_mul_ps(hue, _mm256_set1_ps(1.0 / 6.0));
+        let hue = _mm256_sub_ps(hue, _mm256_floor_ps(hue));
+        // gray pixels have no hue
+        let hue = _mm256_and_ps(hue, _mm256_cmp_ps(delta, zero, _CMP_GT_OQ));
+
+        [hue, saturation, max]
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn hsv_to_rgb([hue, saturation, value]: [__m256; 3]) -> [__m256; 3] {
+        let zero = _mm256_setzero_ps();
+        let chroma = _mm256_mul_ps(value, saturation);
+        let sector = _mm256_mul_ps(hue, _mm256_set1_ps(6.0));
+
+        // x = chroma * (1 - |sector % 2 - 1|)
+        let half = _mm256_floor_ps(_mm256_mul_ps(sector, _mm256_set1_ps(0.5)));
+        let modulo = _mm256_sub_ps(sector, _mm256_mul_ps(half, _mm256_set1_ps(2.0)));
+        let distance = _mm256_andnot_ps(
+            _mm256_set1_ps(-0.0),
+            _mm256_sub_ps(modulo, _mm256_set1_ps(1.0)),
+        );
+        let x = _mm256_mul_ps(chroma, _mm256_sub_ps(_mm256_set1_ps(1.0), distance));
+
+        // sectors are picked from the last one down, so lower sectors overwrite higher ones
+        let red = _mm256_blendv_ps(chroma, x, below(sector, 5.0));
+        let red = _mm256_blendv_ps(red, zero, below(sector, 4.0));
+        let red = _mm256_blendv_ps(red, x, below(sector, 2.0));
+        let red = _mm256_blendv_ps(red, chroma, below(sector, 1.0));
+
+        let green = _mm256_blendv_ps(zero, x, below(sector, 4.0));
+        let green = _mm256_blendv_ps(green, chroma, below(sector, 3.0));
+        let green = _mm256_blendv_ps(green, x, below(sector, 1.0));
+
+        let blue = _mm256_blendv_ps(x, chroma, below(sector, 5.0));
+        let blue = _mm256_blendv_ps(blue, x, below(sector, 3.0));
+        let blue = _mm256_blendv_ps(blue, zero, below(sector, 2.0));
+
+        let min = _mm256_sub_ps(value, chroma);
+        [
+            _mm256_add_ps(red, min),
+            _mm256_add_ps(green, min),
+            _mm256_add_ps(blue, min),
+        ]
+    }
+
+    /// Lanes with `sector` lower than `bound`
+    #[inline]
+    #[target_feature(enable = "avx")]
+    unsafe fn below(sector: __m256, bound: f32) -> __m256 {
+        _mm256_cmp_ps(sector, _mm256_set1_ps(bound), _CMP_LT_OQ)
+    }
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests {
+    use super::*;
+
+    const CHANNELS: [InputChannelType; 3] = [
+        InputChannelType::Hue,
+        InputChannelType::Saturation,
+        InputChannelType::Value,
+    ];
+
+    /// Two full AVX chunks with every hue sector, gray and zero value pixels
+    fn pixels() -> Planar {
+        let mut rgb = Planar::with_capacity(16);
+        for pixel in [
+            [0.0, 0.0, 0.0],
+            [0.5, 0.5, 0.5],
+            [1.0, 1.0, 1.0],
+            [0.0, 0.0, 0.25],
+            [1.0, 0.2, 0.1],
+            [0.8, 0.9, 0.1],
+            [0.1, 0.7, 0.2],
+            [0.1, 0.6, 0.9],
+            [0.3, 0.1, 0.8],
+            [0.9, 0.1, 0.7],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.25, 0.25, 0.0],
+            [0.33, 0.66, 0.99],
+            [0.01, 0.02, 0.03],
+        ] {
+            rgb.push(pixel);
+        }
+        rgb
+    }
+
+    fn assert_close(avx: &[f32], scalar: &[f32]) {
+        for (index, (avx, scalar)) in avx.iter().zip(scalar).enumerate() {
+            assert!(
+                (avx - scalar).abs() < 1e-5,
+                "pixel {}: avx {} scalar {}",
+                index,
+                avx,
+                scalar
+            );
+        }
+    }
+
+    #[test]
+    fn avx_channel_matches_scalar() {
+        if !is_x86_feature_detected!("avx") {
+            return;
+        }
+
+        let rgb = pixels();
+        for channel in CHANNELS {
+            let mut result = vec![0.0; rgb.len()];
+            // SAFETY: AVX support is checked above
+            let done = unsafe { avx::rgb_to_channel(&rgb, channel, &mut result) };
+            assert_eq!(done, rgb.len());
+
+            let scalar: Vec<f32> = (0..rgb.len())
+                .map(|index| rgb_to_hsv(rgb.get(index))[channel as usize])
+                .collect();
+            assert_close(&result, &scalar);
+        }
+    }
+
+    #[test]
+    fn avx_replacement_matches_scalar() {
+        if !is_x86_feature_detected!("avx") {
+            return;
+        }
+
+        let values: Vec<f32> = (0..16).map(|index| index as f32 / 10.0 - 0.3).collect();
+        for channel in CHANNELS {
+            let mut rgb = pixels();
+            // SAFETY: AVX support is checked above
+            let done = unsafe { avx::replace_channel(&mut rgb, &values, channel) };
+            assert_eq!(done, values.len());
+
+            let original = pixels();
+            for (index, value) in values.iter().enumerate() {
+                let mut hsv = rgb_to_hsv(original.get(index));
+                hsv[channel as usize] = match channel {
+                    InputChannelType::Hue => value - value.floor(),
+                    _ => *value,
+                };
+                assert_close(&rgb.get(index), &hsv_to_rgb(hsv));
+            }
+        }
+    }
+}
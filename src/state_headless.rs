@@ -1,4 +1,5 @@
-use crate::plugin_rack::PluginRack;
+use crate::error::Result;
+use crate::plugin_rack::{self, PluginRack};
 use std::io::Read;
 
 pub struct StateHeadless {
@@ -12,27 +13,31 @@ impl StateHeadless {
         }
     }
 
-    pub fn load_project<P: AsRef<std::path::Path>>(&mut self, file: P) -> anyhow::Result<()> {
-        let zip_file = std::fs::File::open(&file)?;
-        let mut archive = zip::ZipArchive::new(zip_file)?;
-        let mut proj_file = archive.by_name("project.json")?;
-
-        let mut proj_file_string = String::new();
-        proj_file.read_to_string(&mut proj_file_string)?;
-        let instacnes: Vec<crate::plugin_rack::PluginRackInstance> =
-            serde_json::from_str(&proj_file_string)?;
-
+    /// Loads project or standalone chain preset
+    pub fn load_project<P: AsRef<std::path::Path>>(&mut self, file: P) -> Result<()> {
+        let chain = read_chain(file)?;
         self.rack = PluginRack::new();
-
-        self.rack.plugins.extend(instacnes);
-        self.rack.load_uninitialzed_plugins()?;
+        self.rack.load_chain(&chain)?;
         Ok(())
     }
 
-    pub fn load_image<P: AsRef<std::path::Path>>(&mut self, file: P) -> anyhow::Result<()> {
+    pub fn load_image<P: AsRef<std::path::Path>>(&mut self, file: P) -> Result<()> {
         self.rack.load_image(file)?;
         Ok(())
     }
+}
+
+/// Chain JSON of project or standalone chain preset, plugins are not loaded
+pub fn read_chain<P: AsRef<std::path::Path>>(file: P) -> Result<String> {
+    if plugin_rack::is_chain_file(&file) {
+        return Ok(std::fs::read_to_string(file)?);
+    }
+
+    let zip_file = std::fs::File::open(&file)?;
+    let mut archive = zip::ZipArchive::new(zip_file)?;
+    let mut proj_file = archive.by_name("project.json")?;
 
-    pub fn process(&mut self) {}
+    let mut proj_file_string = String::new();
+    proj_file.read_to_string(&mut proj_file_string)?;
+    Ok(proj_file_string)
 }
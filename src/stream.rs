@@ -0,0 +1,103 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::bail;
+
+use crate::plugin_rack::PluginRack;
+
+/// Approximate pixel count of a strip which is kept in memory
+const STRIP_PIXELS: usize = 4 * 1024 * 1024;
+
+/// Processes PNG image strip by strip: decoded rows go through the chain
+/// and are written straight into the output PNG, so the whole image is never loaded.
+/// Images are processed in 16 bit and saved with the bit depth of the source
+pub fn process_png<P: AsRef<Path>, Q: AsRef<Path>>(
+    rack: &mut PluginRack,
+    input: P,
+    output: Q,
+) -> anyhow::Result<()> {
+    let mut decoder = png::Decoder::new_with_limits(
+        BufReader::new(File::open(input)?),
+        png::Limits { bytes: usize::MAX },
+    );
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+
+    if reader.info().interlaced {
+        bail!("Interlaced PNG images can't be processed in strips");
+    }
+
+    let (width, height) = (reader.info().width, reader.info().height);
+    let (color, depth) = reader.output_color_type();
+    let sixteen = depth == png::BitDepth::Sixteen;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(output)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(if sixteen {
+        png::BitDepth::Sixteen
+    } else {
+        png::BitDepth::Eight
+    });
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+
+    let rows_per_strip = (STRIP_PIXELS / width as usize).max(1);
+    let mut strip: Vec<u16> = Vec::with_capacity(rows_per_strip * width as usize * 4);
+    let mut offset = 0;
+    let mut bytes = Vec::new();
+
+    loop {
+        let row = reader.next_row()?;
+        if let Some(row) = &row {
+            row_to_rgba(row.data(), color.samples(), sixteen, &mut strip);
+        }
+
+        let rows = strip.len() / 4 / width as usize;
+        if rows < rows_per_strip && row.is_some() {
+            continue;
+        }
+
+        rack.process_strip(&mut strip, offset, width as usize, height as usize);
+
+        bytes.clear();
+        if sixteen {
+            bytes.extend(strip.iter().flat_map(|value| value.to_be_bytes()));
+        } else {
+            bytes.extend(strip.iter().map(|value| (value >> 8) as u8));
+        }
+        stream.write_all(&bytes)?;
+
+        offset += rows * width as usize;
+        strip.clear();
+
+        if row.is_none() {
+            break;
+        }
+    }
+
+    stream.finish()?;
+    Ok(())
+}
+
+/// Converts decoded PNG row into RGBA 16 bit pixels
+fn row_to_rgba(row: &[u8], samples: usize, sixteen: bool, pixels: &mut Vec<u16>) {
+    let values: Vec<u16> = if sixteen {
+        row.chunks_exact(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .collect()
+    } else {
+        row.iter().map(|value| *value as u16 * 257).collect()
+    };
+
+    for pixel in values.chunks_exact(samples) {
+        match samples {
+            1 => pixels.extend([pixel[0], pixel[0], pixel[0], u16::MAX]),
+            2 => pixels.extend([pixel[0], pixel[0], pixel[0], pixel[1]]),
+            3 => pixels.extend([pixel[0], pixel[1], pixel[2], u16::MAX]),
+            _ => pixels.extend(&pixel[..4]),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use image::RgbaImage;
+
+pub fn is_svg<P: AsRef<Path>>(file: P) -> bool {
+    file.as_ref()
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"))
+        .unwrap_or(false)
+}
+
+/// Rasterizes SVG file to `width` pixels wide image keeping its aspect ratio
+pub fn rasterize<P: AsRef<Path>>(file: P, width: u32) -> anyhow::Result<RgbaImage> {
+    let data = std::fs::read(file)?;
+    let options = usvg::Options::default();
+    let tree = usvg::Tree::from_data(&data, &options.to_ref())?;
+
+    let size = tree
+        .size
+        .to_screen_size()
+        .scale_to_width(width)
+        .ok_or_else(|| anyhow!("Invalid image size"))?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| anyhow!("Invalid image size"))?;
+
+    resvg::render(
+        &tree,
+        usvg::FitTo::Width(width),
+        tiny_skia::Transform::default(),
+        pixmap.as_mut(),
+    )
+    .ok_or_else(|| anyhow!("Unable to render SVG"))?;
+
+    pixmap_to_image(&pixmap)
+}
+
+/// Rasterizes SVG document at its own size, text is rendered with system fonts
+pub fn rasterize_str(svg: &str) -> anyhow::Result<RgbaImage> {
+    let mut options = usvg::Options::default();
+    options.fontdb.load_system_fonts();
+    let tree = usvg::Tree::from_str(svg, &options.to_ref())?;
+
+    let size = tree.size.to_screen_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| anyhow!("Invalid image size"))?;
+    resvg::render(
+        &tree,
+        usvg::FitTo::Original,
+        tiny_skia::Transform::default(),
+        pixmap.as_mut(),
+    )
+    .ok_or_else(|| anyhow!("Unable to render SVG"))?;
+
+    pixmap_to_image(&pixmap)
+}
+
+fn pixmap_to_image(pixmap: &tiny_skia::Pixmap) -> anyhow::Result<RgbaImage> {
+    // pixmap is stored with premultiplied alpha
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    RgbaImage::from_raw(pixmap.width(), pixmap.height(), pixels)
+        .ok_or_else(|| anyhow!("Rendered image does not match its size"))
+}
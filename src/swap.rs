@@ -0,0 +1,699 @@
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use image::RgbaImage;
+
+use crate::plugin_rack::DeepImage;
+
+/// Undo copies of images larger than this (in bytes) are kept on disk
+pub const SWAP_THRESHOLD: usize = 256 * 1024 * 1024;
+
+/// Side of the square tiles undo copies are made of
+pub const TILE_SIZE: usize = 256;
+
+/// Processing runs which can be undone one by one, the oldest runs are merged together
+const MAX_RUNS: usize = 8;
+
+static SWAP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Left, top, width and height of the tile in pixels
+pub type Area = (usize, usize, usize, usize);
+
+/// Number of tile columns and rows of the image
+pub fn tile_grid(width: usize, height: usize) -> (usize, usize) {
+    (
+        (width + TILE_SIZE - 1) / TILE_SIZE,
+        (height + TILE_SIZE - 1) / TILE_SIZE,
+    )
+}
+
+/// Area of the tile `index` (in row-major order) of the image
+pub fn tile_area(width: usize, height: usize, index: usize) -> Area {
+    let (columns, _) = tile_grid(width, height);
+    let left = index % columns * TILE_SIZE;
+    let top = index / columns * TILE_SIZE;
+    (
+        left,
+        top,
+        TILE_SIZE.min(width - left),
+        TILE_SIZE.min(height - top),
+    )
+}
+
+/// Tiles of the image `width` pixels wide which cover pixels of `range` (in row-major order),
+/// tiles may repeat
+pub fn range_tiles(width: usize, range: Range<usize>) -> Vec<usize> {
+    let columns = (width + TILE_SIZE - 1) / TILE_SIZE;
+    let mut tiles = Vec::new();
+    let mut start = range.start;
+    while start < range.end {
+        let (x, y) = (start % width, start / width);
+        let length = (width - x).min(range.end - start);
+        let row = y / TILE_SIZE * columns;
+        tiles.extend((x / TILE_SIZE..=(x + length - 1) / TILE_SIZE).map(|column| row + column));
+        start += length;
+    }
+    tiles
+}
+
+/// Channel value which is stored in the tile as bytes
+trait TileSample: Copy {
+    const SIZE: usize;
+
+    fn extend_bytes(self, bytes: &mut Vec<u8>);
+    fn from_bytes(bytes: &[u8]) -> Self;
+}
+
+impl TileSample for u8 {
+    const SIZE: usize = 1;
+
+    fn extend_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.push(self);
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+impl TileSample for u16 {
+    const SIZE: usize = 2;
+
+    fn extend_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_ne_bytes());
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_ne_bytes(bytes.try_into().unwrap())
+    }
+}
+
+impl TileSample for f32 {
+    const SIZE: usize = 4;
+
+    fn extend_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_ne_bytes());
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_ne_bytes(bytes.try_into().unwrap())
+    }
+}
+
+fn read_area<T: TileSample>(data: &[T], width: usize, area: Area, bytes: &mut Vec<u8>) {
+    let (left, top, tile_width, tile_height) = area;
+    for y in top..top + tile_height {
+        let start = (y * width + left) * 4;
+        for value in &data[start..start + tile_width * 4] {
+            value.extend_bytes(bytes);
+        }
+    }
+}
+
+/// Fills the area from the start of `bytes`, returns the rest of them
+fn write_area<'a, T: TileSample>(
+    data: &mut [T],
+    width: usize,
+    area: Area,
+    bytes: &'a [u8],
+) -> &'a [u8] {
+    let (left, top, tile_width, tile_height) = area;
+    let (used, rest) = bytes.split_at(tile_width * tile_height * 4 * T::SIZE);
+    let mut values = used.chunks_exact(T::SIZE);
+    for y in top..top + tile_height {
+        let start = (y * width + left) * 4;
+        for (value, bytes) in data[start..start + tile_width * 4]
+            .iter_mut()
+            .zip(&mut values)
+        {
+            *value = T::from_bytes(bytes);
+        }
+    }
+    rest
+}
+
+/// 8 bit pixels of the area followed by the high precision ones
+fn read_tile(image: &RgbaImage, deep: Option<&DeepImage>, width: usize, area: Area) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    read_area(image, width, area, &mut bytes);
+    match deep {
+        Some(DeepImage::Rgba16(deep)) => read_area(deep, width, area, &mut bytes),
+        Some(DeepImage::Rgba32F(hdr)) => read_area(hdr, width, area, &mut bytes),
+        None => {}
+    }
+    bytes
+}
+
+fn write_tile(
+    image: &mut RgbaImage,
+    deep: Option<&mut DeepImage>,
+    width: usize,
+    area: Area,
+    bytes: &[u8],
+) {
+    let rest = write_area(image, width, area, bytes);
+    match deep {
+        Some(DeepImage::Rgba16(deep)) => {
+            write_area(deep, width, area, rest);
+        }
+        Some(DeepImage::Rgba32F(hdr)) => {
+            write_area(hdr, width, area, rest);
+        }
+        None => {}
+    }
+}
+
+enum DeepKind {
+    Rgba16,
+    Rgba32F,
+}
+
+/// Saved tile, 8 bit pixels are followed by the high precision ones
+enum Tile {
+    Memory(Vec<u8>),
+    /// Offset in the swap file
+    Disk(u64),
+}
+
+/// Temporary file with the tiles, removed on drop
+struct SwapFile {
+    path: PathBuf,
+    file: File,
+    end: u64,
+}
+
+impl SwapFile {
+    fn create() -> anyhow::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "photoconsequences-{}-{}.swap",
+            std::process::id(),
+            SWAP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        Ok(Self { path, file, end: 0 })
+    }
+
+    /// Appends the tile, returns its offset
+    fn append(&mut self, bytes: &[u8]) -> anyhow::Result<u64> {
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(bytes)?;
+        let offset = self.end;
+        self.end += bytes.len() as u64;
+        Ok(offset)
+    }
+
+    fn write_at(&mut self, offset: u64, bytes: &[u8]) -> anyhow::Result<()> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn read(&self, offset: u64, bytes: &mut [u8]) -> anyhow::Result<()> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(bytes)?;
+        Ok(())
+    }
+}
+
+impl Drop for SwapFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Copies of image tiles of `TILE_SIZE` made right before the tiles are changed, tiles of images
+/// larger than `SWAP_THRESHOLD` are paged from the temporary file one by one
+pub struct TileSnapshot {
+    width: usize,
+    height: usize,
+    deep: Option<DeepKind>,
+    /// Tiles in row-major order, `None` for tiles which weren't saved
+    tiles: Vec<Option<Tile>>,
+    swap: Option<SwapFile>,
+}
+
+impl TileSnapshot {
+    pub fn new(image: &RgbaImage, deep: Option<&DeepImage>) -> Self {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let (columns, rows) = tile_grid(width, height);
+
+        let size = image.len() + deep.map_or(0, DeepImage::memory_size);
+        let swap = if size >= SWAP_THRESHOLD {
+            SwapFile::create()
+                .map_err(|error| log::error!("Unable to create swap file: {}", error))
+                .ok()
+        } else {
+            None
+        };
+
+        Self {
+            width,
+            height,
+            deep: deep.map(|deep| match deep {
+                DeepImage::Rgba16(_) => DeepKind::Rgba16,
+                DeepImage::Rgba32F(_) => DeepKind::Rgba32F,
+            }),
+            tiles: (0..columns * rows).map(|_| None).collect(),
+            swap,
+        }
+    }
+
+    fn area(&self, index: usize) -> Area {
+        tile_area(self.width, self.height, index)
+    }
+
+    /// Saves tiles covering pixels of `range` (in row-major order) which weren't saved yet
+    pub fn save_range(&mut self, image: &RgbaImage, deep: Option<&DeepImage>, range: Range<usize>) {
+        for index in range_tiles(self.width, range) {
+            self.save_tile(index, image, deep);
+        }
+    }
+
+    /// Saves tiles of `original` which differ from `changed`
+    pub fn save_changed(
+        &mut self,
+        (original, original_deep): (&RgbaImage, Option<&DeepImage>),
+        (changed, changed_deep): (&RgbaImage, Option<&DeepImage>),
+    ) {
+        for index in 0..self.tiles.len() {
+            let area = self.area(index);
+            let before = read_tile(original, original_deep, self.width, area);
+            if before != read_tile(changed, changed_deep, self.width, area) {
+                self.store(index, before);
+            }
+        }
+    }
+
+    /// Saves every tile which wasn't saved yet
+    pub fn save_all(&mut self, image: &RgbaImage, deep: Option<&DeepImage>) {
+        for index in 0..self.tiles.len() {
+            self.save_tile(index, image, deep);
+        }
+    }
+
+    fn save_tile(&mut self, index: usize, image: &RgbaImage, deep: Option<&DeepImage>) {
+        if self.tiles[index].is_none() {
+            let bytes = read_tile(image, deep, self.width, self.area(index));
+            self.store(index, bytes);
+        }
+    }
+
+    fn store(&mut self, index: usize, bytes: Vec<u8>) {
+        let tile = match self.swap.as_mut().map(|swap| swap.append(&bytes)) {
+            Some(Ok(offset)) => Tile::Disk(offset),
+            Some(Err(error)) => {
+                // tile is kept in memory rather than lost
+                log::error!("Unable to write tile into swap file: {}", error);
+                Tile::Memory(bytes)
+            }
+            None => Tile::Memory(bytes),
+        };
+        self.tiles[index] = Some(tile);
+    }
+
+    /// Size of the saved tile in bytes
+    fn tile_size(&self, index: usize) -> usize {
+        let (_, _, width, height) = self.area(index);
+        let deep = match self.deep {
+            Some(DeepKind::Rgba16) => 2,
+            Some(DeepKind::Rgba32F) => 4,
+            None => 0,
+        };
+        width * height * 4 * (1 + deep)
+    }
+
+    fn tile_bytes(&self, index: usize) -> anyhow::Result<Option<Cow<[u8]>>> {
+        match &self.tiles[index] {
+            Some(Tile::Memory(bytes)) => Ok(Some(Cow::Borrowed(bytes))),
+            Some(Tile::Disk(offset)) => {
+                let mut bytes = vec![0; self.tile_size(index)];
+                self.swap.as_ref().unwrap().read(*offset, &mut bytes)?;
+                Ok(Some(Cow::Owned(bytes)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Pastes saved tiles into the image of the same size
+    pub fn restore(
+        &self,
+        image: &mut RgbaImage,
+        mut deep: Option<&mut DeepImage>,
+    ) -> anyhow::Result<()> {
+        for index in 0..self.tiles.len() {
+            if let Some(bytes) = self.tile_bytes(index)? {
+                write_tile(
+                    image,
+                    deep.as_deref_mut(),
+                    self.width,
+                    self.area(index),
+                    &bytes,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Exchanges saved tiles with the same tiles of the image, so the change can be redone
+    pub fn exchange(
+        &mut self,
+        image: &mut RgbaImage,
+        mut deep: Option<&mut DeepImage>,
+    ) -> anyhow::Result<()> {
+        for index in 0..self.tiles.len() {
+            let saved = match self.tile_bytes(index)? {
+                Some(bytes) => bytes.into_owned(),
+                None => continue,
+            };
+
+            let area = self.area(index);
+            let current = read_tile(image, deep.as_deref(), self.width, area);
+            match &mut self.tiles[index] {
+                Some(Tile::Disk(offset)) => {
+                    self.swap.as_mut().unwrap().write_at(*offset, &current)?
+                }
+                tile => *tile = Some(Tile::Memory(current)),
+            }
+            write_tile(image, deep.as_deref_mut(), self.width, area, &saved);
+        }
+        Ok(())
+    }
+
+    /// Adds tiles of the next snapshot which aren't saved in this one,
+    /// so restoring it undoes both changes
+    fn merge(&mut self, next: &TileSnapshot) -> anyhow::Result<()> {
+        for index in 0..self.tiles.len() {
+            if self.tiles[index].is_some() {
+                continue;
+            }
+            if let Some(bytes) = next.tile_bytes(index)? {
+                self.store(index, bytes.into_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Size of the tiles kept in memory
+    pub fn memory_size(&self) -> usize {
+        self.tiles
+            .iter()
+            .map(|tile| match tile {
+                Some(Tile::Memory(bytes)) => bytes.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+/// Tile snapshots of the processing runs of still image, the first one has tiles of the original
+#[derive(Default)]
+pub struct TileHistory {
+    runs: Vec<TileSnapshot>,
+    /// Snapshots of undone runs, they keep tiles of the undone results
+    undone: Vec<TileSnapshot>,
+}
+
+impl TileHistory {
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.runs.clear();
+        self.undone.clear();
+    }
+
+    /// Starts snapshot of the next run, undone runs can't be redone after that
+    pub fn push(&mut self, snapshot: TileSnapshot) {
+        self.undone.clear();
+        self.runs.push(snapshot);
+
+        if self.runs.len() > MAX_RUNS {
+            let (first, rest) = self.runs.split_at_mut(1);
+            match first[0].merge(&rest[0]) {
+                Ok(()) => {
+                    self.runs.remove(1);
+                }
+                Err(error) => log::error!("Unable to merge undo tiles: {}", error),
+            }
+        }
+    }
+
+    /// Snapshot of the current run
+    pub fn current(&mut self) -> Option<&mut TileSnapshot> {
+        self.runs.last_mut()
+    }
+
+    /// Brings back tiles changed by the last run
+    pub fn undo(
+        &mut self,
+        image: &mut RgbaImage,
+        deep: Option<&mut DeepImage>,
+    ) -> anyhow::Result<()> {
+        if let Some(mut snapshot) = self.runs.pop() {
+            snapshot.exchange(image, deep)?;
+            self.undone.push(snapshot);
+        }
+        Ok(())
+    }
+
+    pub fn redo(
+        &mut self,
+        image: &mut RgbaImage,
+        deep: Option<&mut DeepImage>,
+    ) -> anyhow::Result<()> {
+        if let Some(mut snapshot) = self.undone.pop() {
+            snapshot.exchange(image, deep)?;
+            self.runs.push(snapshot);
+        }
+        Ok(())
+    }
+
+    /// Drops the last run, its changes are discarded
+    pub fn discard(
+        &mut self,
+        image: &mut RgbaImage,
+        deep: Option<&mut DeepImage>,
+    ) -> anyhow::Result<()> {
+        match self.runs.pop() {
+            Some(snapshot) => snapshot.restore(image, deep),
+            None => Ok(()),
+        }
+    }
+
+    /// Pastes tiles of all runs, so the image becomes the original
+    pub fn restore(
+        &self,
+        image: &mut RgbaImage,
+        mut deep: Option<&mut DeepImage>,
+    ) -> anyhow::Result<()> {
+        for snapshot in self.runs.iter().rev() {
+            snapshot.restore(image, deep.as_deref_mut())?;
+        }
+        Ok(())
+    }
+
+    /// Size of the tiles kept in memory
+    pub fn memory_size(&self) -> usize {
+        self.runs
+            .iter()
+            .chain(&self.undone)
+            .map(TileSnapshot::memory_size)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin_rack::Rgba16Image;
+
+    /// Two tile columns and rows, the last ones are partial
+    const SIDE: u32 = TILE_SIZE as u32 + 44;
+
+    fn image(seed: u8) -> RgbaImage {
+        RgbaImage::from_fn(SIDE, SIDE, |x, y| {
+            image::Rgba([x as u8, y as u8, seed, (x ^ y) as u8])
+        })
+    }
+
+    fn deep(seed: u16) -> DeepImage {
+        DeepImage::Rgba16(Rgba16Image::from_fn(SIDE, SIDE, |x, y| {
+            image::Rgba([x as u16 * 7, y as u16 * 11, seed, u16::MAX])
+        }))
+    }
+
+    /// Changes the pixel as a processing run would, saving its tile first
+    fn change_pixel(snapshot: &mut TileSnapshot, image: &mut RgbaImage, x: u32, y: u32) {
+        let index = (y * SIDE + x) as usize;
+        snapshot.save_range(image, None, index..index + 1);
+        image.get_pixel_mut(x, y).0[2] = image.get_pixel(x, y).0[2].wrapping_add(1);
+    }
+
+    fn swapped(image: &RgbaImage, deep: Option<&DeepImage>) -> TileSnapshot {
+        let mut snapshot = TileSnapshot::new(image, deep);
+        snapshot.swap = Some(SwapFile::create().unwrap());
+        snapshot
+    }
+
+    #[test]
+    fn range_covers_tiles() {
+        let width = SIDE as usize;
+        assert_eq!(range_tiles(width, 0..1), vec![0]);
+        assert_eq!(range_tiles(width, TILE_SIZE - 1..TILE_SIZE + 1), vec![0, 1]);
+        assert_eq!(
+            range_tiles(width, width * TILE_SIZE..width * TILE_SIZE + 1),
+            vec![2]
+        );
+        assert_eq!(tile_area(width, width, 3), (TILE_SIZE, TILE_SIZE, 44, 44));
+    }
+
+    #[test]
+    fn restore_brings_back_changed_tiles() {
+        let original = image(0);
+        let mut changed = original.clone();
+        let mut snapshot = TileSnapshot::new(&changed, None);
+        change_pixel(&mut snapshot, &mut changed, 10, 10);
+        change_pixel(&mut snapshot, &mut changed, SIDE - 1, SIDE - 1);
+        // tile is saved only once, so the second change doesn't overwrite the original
+        change_pixel(&mut snapshot, &mut changed, 11, 10);
+
+        assert!(snapshot.tiles[1].is_none() && snapshot.tiles[2].is_none());
+        assert_ne!(changed, original);
+        snapshot.restore(&mut changed, None).unwrap();
+        assert_eq!(changed, original);
+    }
+
+    #[test]
+    fn save_changed_keeps_deep_tiles() {
+        let (original, original_deep) = (image(0), deep(0));
+        let (mut changed, mut changed_deep) = (original.clone(), deep(1));
+        changed.get_pixel_mut(0, 0).0[0] = 255;
+
+        let mut snapshot = TileSnapshot::new(&original, Some(&original_deep));
+        snapshot.save_changed(
+            (&original, Some(&original_deep)),
+            (&changed, Some(&changed_deep)),
+        );
+        snapshot
+            .restore(&mut changed, Some(&mut changed_deep))
+            .unwrap();
+        assert_eq!(changed, original);
+        match (changed_deep, original_deep) {
+            (DeepImage::Rgba16(changed), DeepImage::Rgba16(original)) => {
+                assert_eq!(changed, original)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_exchange_tiles() {
+        let original = image(0);
+        let mut current = original.clone();
+        let mut history = TileHistory::default();
+        history.push(TileSnapshot::new(&current, None));
+        change_pixel(history.current().unwrap(), &mut current, SIDE - 10, 5);
+        let result = current.clone();
+
+        history.undo(&mut current, None).unwrap();
+        assert_eq!(current, original);
+        assert!(history.is_empty() && history.can_redo());
+        history.redo(&mut current, None).unwrap();
+        assert_eq!(current, result);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn merged_runs_restore_original() {
+        let original = image(0);
+        let mut current = original.clone();
+        let mut history = TileHistory::default();
+        for run in 0..MAX_RUNS as u32 + 3 {
+            history.push(TileSnapshot::new(&current, None));
+            // runs change different tiles, so merged snapshots must take tiles of each other
+            change_pixel(
+                history.current().unwrap(),
+                &mut current,
+                run * 37 % SIDE,
+                run * 97 % SIDE,
+            );
+        }
+        assert_eq!(history.runs.len(), MAX_RUNS);
+
+        history.restore(&mut current, None).unwrap();
+        assert_eq!(current, original);
+    }
+
+    #[test]
+    fn swapped_tiles_are_not_kept_in_memory() {
+        let (original, original_deep) = (image(0), deep(0));
+        let (mut current, mut current_deep) = (original.clone(), original_deep.clone());
+        let mut snapshot = swapped(&current, Some(&current_deep));
+        snapshot.save_all(&current, Some(&current_deep));
+        assert_eq!(snapshot.memory_size(), 0);
+        assert!(snapshot
+            .tiles
+            .iter()
+            .all(|tile| matches!(tile, Some(Tile::Disk(_)))));
+
+        let (result, result_deep) = (image(1), deep(1));
+        current.clone_from(&result);
+        current_deep.clone_from(&result_deep);
+        snapshot
+            .exchange(&mut current, Some(&mut current_deep))
+            .unwrap();
+        assert_eq!(current, original);
+        assert_eq!(snapshot.memory_size(), 0);
+
+        // exchanged tiles were written over the old ones in the swap file
+        snapshot
+            .exchange(&mut current, Some(&mut current_deep))
+            .unwrap();
+        assert_eq!(current, result);
+        snapshot
+            .restore(&mut current, Some(&mut current_deep))
+            .unwrap();
+        assert_eq!(current, original);
+    }
+
+    #[test]
+    fn swapped_snapshot_merges_into_memory() {
+        let original = image(0);
+        let mut current = original.clone();
+        let mut first = TileSnapshot::new(&current, None);
+        change_pixel(&mut first, &mut current, 0, 0);
+        let mut next = swapped(&current, None);
+        change_pixel(&mut next, &mut current, SIDE - 1, 0);
+
+        first.merge(&next).unwrap();
+        drop(next);
+        first.restore(&mut current, None).unwrap();
+        assert_eq!(current, original);
+    }
+
+    #[test]
+    fn swap_file_is_removed_on_drop() {
+        let snapshot = swapped(&image(0), None);
+        let path = snapshot.swap.as_ref().unwrap().path.clone();
+        assert!(path.exists());
+        drop(snapshot);
+        assert!(!path.exists());
+    }
+}
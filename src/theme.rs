@@ -0,0 +1,74 @@
+use egui::{Color32, Stroke, Visuals};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ThemeKind {
+    Dark,
+    Light,
+    /// Dark theme with custom panel and text colors
+    Custom,
+}
+
+/// UI colors, all colors are RGB
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Theme {
+    pub kind: ThemeKind,
+    /// Selection and highlight color
+    pub accent: [u8; 3],
+    /// Panel and window background of custom theme
+    pub background: [u8; 3],
+    /// Text color of custom theme
+    pub text: [u8; 3],
+    /// Background behind the image preview
+    pub canvas: [u8; 3],
+    /// Pixel grid lines
+    pub grid: [u8; 3],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            kind: ThemeKind::Dark,
+            accent: [0, 92, 128],
+            background: [32, 32, 40],
+            text: [220, 220, 220],
+            canvas: [27, 27, 27],
+            grid: [128, 128, 128],
+        }
+    }
+}
+
+impl Theme {
+    pub fn visuals(&self) -> Visuals {
+        let mut visuals = match self.kind {
+            ThemeKind::Light => Visuals::light(),
+            ThemeKind::Dark | ThemeKind::Custom => Visuals::dark(),
+        };
+
+        let accent = rgb(self.accent);
+        visuals.selection.bg_fill = accent;
+        visuals.hyperlink_color = accent;
+        visuals.widgets.hovered.bg_stroke = Stroke::new(1.0, accent);
+
+        if self.kind == ThemeKind::Custom {
+            let background = rgb(self.background);
+            visuals.window_fill = background;
+            visuals.panel_fill = background;
+            visuals.override_text_color = Some(rgb(self.text));
+        }
+        visuals
+    }
+
+    pub fn canvas_color(&self) -> Color32 {
+        rgb(self.canvas)
+    }
+
+    pub fn grid_color(&self) -> Color32 {
+        rgb(self.grid).linear_multiply(0.5)
+    }
+}
+
+fn rgb(color: [u8; 3]) -> Color32 {
+    Color32::from_rgb(color[0], color[1], color[2])
+}
@@ -1,50 +1,1110 @@
 use egui::{
     menu,
-    plot::{Corner, Legend, Plot, PlotImage, PlotPoint, Text},
-    vec2, Align2, Color32, Context, RichText,
+    plot::{
+        Corner, HLine, Legend, Line, Plot, PlotBounds, PlotImage, PlotPoint, PlotPoints, Polygon,
+        Text, VLine,
+    },
+    pos2, vec2, Align2, Color32, Context, Rect, RichText, Sense, Stroke, TextureId,
 };
 use egui_extras::{Size, TableBody, TableBuilder};
-use std::{io::Read, path::PathBuf, time::Instant};
-use vst::prelude::Plugin;
-use winit::{dpi::PhysicalSize, event_loop::EventLoopWindowTarget, window::WindowId};
+use image::codecs::png::CompressionType;
+use log::{Level, LevelFilter};
+use nokhwa::{
+    pixel_format::RgbFormat,
+    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    Camera,
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime},
+};
+use vst::{plugin::Supported, prelude::Plugin};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::EventLoopWindowTarget,
+    window::{Window, WindowId},
+};
 
 use crate::{
+    animation::{self, Keyframe, KeyframeTarget},
+    compare, console,
+    dither::Dither,
+    effects::{self, EffectInput},
+    export::{
+        ChromaSubsampling, ExportProfile, ExportTarget, PngBitDepth, ToneMapping, PROFILE_EXTENSION,
+    },
+    histogram::{self, Histogram},
+    i18n::{self, tr, tr_args, Language},
     image_generators,
+    indexed::IndexedPalette,
+    jobs::{JobQueue, JobStatus},
+    keymap::{self, Command},
+    macros::{self, Macro, MacroStep, MACRO_EXTENSION},
+    mask::{ImageMask, MaskLayer, MaskOperation, MaskSource},
+    metrics::Metrics,
+    midi::MidiInput,
     msgboxwrapper::messagebox,
-    plugin_rack::{InputChannelType, PluginRack},
+    palette::{self, PaletteEntry, PaletteItem},
+    plugin_library::{self, PLUGIN_EXTENSION},
+    plugin_rack::{
+        self, Autosave, Checkpoint, DeepImage, Field, Grayscale, InputChannelType, OutputRoute,
+        PluginRack, PluginRackInstance, RackEdit, Region, SampleRate, TilePattern, CHAIN_EXTENSION,
+        SIDECAR_EXTENSION,
+    },
+    raw,
     renderer::{self, Renderer},
-    ui_enums::{Action, DialogVariant, ModalWindows},
+    report::ProcessingReport,
+    routing::{BlendMode, Branch, ColorChannel, GraphNode, ProcessingGraph},
+    settings::Settings,
+    state_headless, svg,
+    theme::{Theme, ThemeKind},
+    ui_enums::{
+        Action, CanvasZoom, CompareMode, DialogVariant, GraphEdit, Guide, ModalWindows, TabAction,
+        UndoStep,
+    },
+    video,
 };
 
+/// Delay after the last parameter change before live preview is processed
+const LIVE_PREVIEW_DEBOUNCE: Duration = Duration::from_millis(300);
+/// How often loaded plugin and image files are checked for changes
+const FILE_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Undo history length, older steps are dropped
+const UNDO_LIMIT: usize = 100;
+
+/// Largest image which can be downloaded from URL
+const MAX_DOWNLOAD_SIZE: u64 = 64 * 1024 * 1024;
+
+const DOWNLOAD_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest pause between received parts of the downloaded image
+const DOWNLOAD_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Downloads image from URL, runs on a worker thread so slow servers don't block the interface
+fn download_image(url: &str) -> anyhow::Result<Vec<u8>> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(DOWNLOAD_CONNECT_TIMEOUT)
+        .timeout_read(DOWNLOAD_READ_TIMEOUT)
+        .build();
+    let response = agent.get(url).call()?;
+
+    if let Some(length) = response
+        .header("Content-Length")
+        .and_then(|length| length.parse::<u64>().ok())
+    {
+        if length > MAX_DOWNLOAD_SIZE {
+            anyhow::bail!(
+                "Image is too large: {} MiB (limit is {} MiB)",
+                length / 1024 / 1024,
+                MAX_DOWNLOAD_SIZE / 1024 / 1024
+            );
+        }
+    }
+
+    let mut buf = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_DOWNLOAD_SIZE + 1)
+        .read_to_end(&mut buf)?;
+
+    if buf.len() as u64 > MAX_DOWNLOAD_SIZE {
+        anyhow::bail!(
+            "Image is too large (limit is {} MiB)",
+            MAX_DOWNLOAD_SIZE / 1024 / 1024
+        );
+    }
+    Ok(buf)
+}
+
+fn set_clipboard_text(text: String) -> anyhow::Result<()> {
+    arboard::Clipboard::new()?.set_text(text)?;
+    Ok(())
+}
+
+/// Remembers modification time of the file, returns `true` if it differs from the remembered one
+fn file_changed(remembered: &mut Option<(PathBuf, SystemTime)>, path: &Path) -> bool {
+    let modified = match std::fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return false,
+    };
+
+    match remembered.replace((path.to_path_buf(), modified)) {
+        Some((previous_path, previous)) => previous_path == path && previous != modified,
+        None => false,
+    }
+}
+
+fn get_clipboard_text() -> anyhow::Result<String> {
+    Ok(arboard::Clipboard::new()?.get_text()?)
+}
+
+/// Converts preview plot coordinates into image pixel, image is centered with size (1 / height, 1 / width)
+fn plot_to_pixel(point: PlotPoint, width: f32, height: f32) -> Option<(u32, u32)> {
+    let (x, y) = plot_to_pixel_unclamped(point.x, point.y, width, height);
+    if x < 0.0 || y < 0.0 || x >= width || y >= height {
+        return None;
+    }
+    Some((x as u32, y as u32))
+}
+
+fn plot_to_pixel_unclamped(x: f64, y: f64, width: f32, height: f32) -> (f32, f32) {
+    (
+        (x as f32 * height + 0.5) * width,
+        (0.5 - y as f32 * width) * height,
+    )
+}
+
+/// Rectangle covering image rows from `top` to `bottom` on the preview plot
+fn rows_polygon(top: f32, bottom: f32, width: f32, height: f32) -> Polygon {
+    let (left, right) = (-0.5 / height, 0.5 / height);
+    let row_to_y = |row: f32| ((0.5 - row / height) / width) as f64;
+    let (top, bottom) = (row_to_y(top), row_to_y(bottom));
+    Polygon::new(PlotPoints::new(vec![
+        [left as f64, top],
+        [right as f64, top],
+        [right as f64, bottom],
+        [left as f64, bottom],
+    ]))
+    .width(0.0)
+}
+
+/// Cells along the longest image side in checkerboard compare mode
+const CHECKER_CELLS: usize = 8;
+
+/// Guides closer than this (relative to the image size) attract region edges
+const GUIDE_SNAP_DISTANCE: f32 = 0.01;
+
+/// Moves region edges onto guides which are close enough
+fn snap_region(mut region: Region, guides: &[Guide]) -> Region {
+    let snap = |value: &mut f32, vertical: bool| {
+        let guide = guides
+            .iter()
+            .filter(|guide| guide.vertical == vertical)
+            .map(|guide| guide.position)
+            .min_by(|a, b| (*a - *value).abs().total_cmp(&(*b - *value).abs()));
+        if let Some(guide) = guide {
+            if (guide - *value).abs() <= GUIDE_SNAP_DISTANCE {
+                *value = guide;
+            }
+        }
+    };
+
+    snap(&mut region.left, true);
+    snap(&mut region.right, true);
+    snap(&mut region.top, false);
+    snap(&mut region.bottom, false);
+    region
+}
+
+/// Converts normalized image coordinates (0.0 - 1.0, top to bottom) into preview plot coordinates
+fn image_to_plot(x: f32, y: f32, width: f32, height: f32) -> [f64; 2] {
+    [((x - 0.5) / height) as f64, ((0.5 - y) / width) as f64]
+}
+
+/// Screen pixels covered by one image pixel
+fn preview_zoom(plot_ui: &egui::plot::PlotUi, width: f32, height: f32) -> f64 {
+    let bounds = plot_ui.plot_bounds();
+    let min = plot_ui.screen_from_plot(PlotPoint::new(bounds.min()[0], bounds.min()[1]));
+    let max = plot_ui.screen_from_plot(PlotPoint::new(bounds.max()[0], bounds.max()[1]));
+    let pixel_size = 1.0 / (width as f64 * height as f64);
+    (max.x - min.x).abs() as f64 / bounds.width() * pixel_size
+}
+
+fn apply_zoom(
+    plot_ui: &mut egui::plot::PlotUi,
+    zoom: CanvasZoom,
+    selection: Option<Region>,
+    width: f32,
+    height: f32,
+) {
+    let bounds = plot_ui.plot_bounds();
+    let (min, max) = match zoom {
+        CanvasZoom::Fit => (
+            image_to_plot(0.0, 1.0, width, height),
+            image_to_plot(1.0, 0.0, width, height),
+        ),
+        CanvasZoom::ActualPixels => {
+            let scale = preview_zoom(plot_ui, width, height);
+            let center = [
+                (bounds.min()[0] + bounds.max()[0]) / 2.0,
+                (bounds.min()[1] + bounds.max()[1]) / 2.0,
+            ];
+            let half_size = [bounds.width() * scale / 2.0, bounds.height() * scale / 2.0];
+            (
+                [center[0] - half_size[0], center[1] - half_size[1]],
+                [center[0] + half_size[0], center[1] + half_size[1]],
+            )
+        }
+        CanvasZoom::Selection => match selection {
+            Some(region) => (
+                image_to_plot(region.left, region.bottom, width, height),
+                image_to_plot(region.right, region.top, width, height),
+            ),
+            None => return,
+        },
+    };
+    plot_ui.set_plot_bounds(PlotBounds::from_min_max(min, max));
+}
+
+/// Draws pixel borders when one image pixel is larger than 8 screen pixels
+fn draw_pixel_grid(plot_ui: &mut egui::plot::PlotUi, width: f32, height: f32, color: Color32) {
+    if preview_zoom(plot_ui, width, height) <= 8.0 {
+        return;
+    }
+
+    let bounds = plot_ui.plot_bounds();
+    let (left, top) = plot_to_pixel_unclamped(bounds.min()[0], bounds.max()[1], width, height);
+    let (right, bottom) = plot_to_pixel_unclamped(bounds.max()[0], bounds.min()[1], width, height);
+    let (left, right) = (left.clamp(0.0, width), right.clamp(0.0, width));
+    let (top, bottom) = (top.clamp(0.0, height), bottom.clamp(0.0, height));
+
+    for column in left.floor() as u32..=right.ceil() as u32 {
+        let x = column as f32 / width;
+        plot_ui.line(
+            Line::new(PlotPoints::new(vec![
+                image_to_plot(x, top / height, width, height),
+                image_to_plot(x, bottom / height, width, height),
+            ]))
+            .color(color),
+        );
+    }
+    for row in top.floor() as u32..=bottom.ceil() as u32 {
+        let y = row as f32 / height;
+        plot_ui.line(
+            Line::new(PlotPoints::new(vec![
+                image_to_plot(left / width, y, width, height),
+                image_to_plot(right / width, y, width, height),
+            ]))
+            .color(color),
+        );
+    }
+}
+
+/// Draws guides, returns guide under the cursor and new position of the dragged guide
+fn draw_guides(
+    plot_ui: &mut egui::plot::PlotUi,
+    guides: &[Guide],
+    dragging: Option<usize>,
+    width: f32,
+    height: f32,
+) -> (Option<usize>, Option<(usize, f32)>) {
+    let pointer = plot_ui.pointer_coordinate();
+    let color = Color32::from_rgb(0, 200, 255);
+    let mut hovered = None;
+
+    for (id, guide) in guides.iter().enumerate() {
+        let point = image_to_plot(guide.position, guide.position, width, height);
+        if guide.vertical {
+            plot_ui.vline(VLine::new(point[0]).color(color));
+        } else {
+            plot_ui.hline(HLine::new(point[1]).color(color));
+        }
+
+        // guides are grabbed within 4 screen pixels
+        if let Some(pointer) = pointer {
+            let line = plot_ui.screen_from_plot(PlotPoint::new(point[0], point[1]));
+            let cursor = plot_ui.screen_from_plot(pointer);
+            let distance = if guide.vertical {
+                (line.x - cursor.x).abs()
+            } else {
+                (line.y - cursor.y).abs()
+            };
+            if distance <= 4.0 {
+                hovered = Some(id);
+            }
+        }
+    }
+
+    let moved = dragging.zip(pointer).map(|(id, pointer)| {
+        let (x, y) = plot_to_pixel_unclamped(pointer.x, pointer.y, width, height);
+        let position = if guides[id].vertical {
+            x / width
+        } else {
+            y / height
+        };
+        (id, position)
+    });
+    (hovered, moved)
+}
+
+/// Draws original image left of the split view divider,
+/// returns if the divider is hovered and its new position while dragged
+fn draw_split(
+    plot_ui: &mut egui::plot::PlotUi,
+    original: TextureId,
+    position: f32,
+    dragging: bool,
+    width: f32,
+    height: f32,
+) -> (bool, Option<f32>) {
+    let area = Rect::from_min_max(pos2(0.0, 0.0), pos2(position, 1.0));
+    draw_original(plot_ui, original, area, width, height);
+    let divider = image_to_plot(position, 0.0, width, height)[0];
+    plot_ui.vline(VLine::new(divider).color(Color32::WHITE).width(2.0));
+
+    let pointer = match plot_ui.pointer_coordinate() {
+        Some(pointer) => pointer,
+        None => return (false, None),
+    };
+    let line = plot_ui.screen_from_plot(PlotPoint::new(divider, 0.0));
+    let hovered = (plot_ui.screen_from_plot(pointer).x - line.x).abs() <= 4.0;
+    let moved = dragging.then(|| {
+        let (x, _) = plot_to_pixel_unclamped(pointer.x, pointer.y, width, height);
+        (x / width).clamp(0.0, 1.0)
+    });
+    (hovered, moved)
+}
+
+/// Draws original image in every other cell of the checkerboard
+fn draw_checkerboard(
+    plot_ui: &mut egui::plot::PlotUi,
+    original: TextureId,
+    width: f32,
+    height: f32,
+) {
+    let cell = width.max(height) / CHECKER_CELLS as f32;
+    let (columns, rows) = (
+        (width / cell).ceil() as usize,
+        (height / cell).ceil() as usize,
+    );
+    for row in 0..rows {
+        for column in (row % 2..columns).step_by(2) {
+            let min = pos2(column as f32 * cell / width, row as f32 * cell / height);
+            let max = pos2(
+                ((column + 1) as f32 * cell / width).min(1.0),
+                ((row + 1) as f32 * cell / height).min(1.0),
+            );
+            draw_original(
+                plot_ui,
+                original,
+                Rect::from_min_max(min, max),
+                width,
+                height,
+            );
+        }
+    }
+}
+
+/// Draws part of the original image, `area` is relative to the image size
+fn draw_original(
+    plot_ui: &mut egui::plot::PlotUi,
+    original: TextureId,
+    area: Rect,
+    width: f32,
+    height: f32,
+) {
+    let center = image_to_plot(area.center().x, area.center().y, width, height);
+    let size = vec2(area.width() / height, area.height() / width);
+    plot_ui.image(PlotImage::new(original, PlotPoint::new(center[0], center[1]), size).uv(area));
+}
+
+/// Editor of mask source parameters, returns `true` if they were changed
+fn mask_source_ui(ui: &mut egui::Ui, source: &mut MaskSource) -> bool {
+    let mut changed = false;
+    match source {
+        MaskSource::Brightness { low, high } => {
+            changed |= ui
+                .add(egui::Slider::new(low, 0.0..=1.0).prefix(format!("{}: ", tr("mask-low"))))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(high, 0.0..=1.0).prefix(format!("{}: ", tr("mask-high"))))
+                .changed();
+        }
+        MaskSource::Edges { threshold } => {
+            changed |= ui
+                .add(
+                    egui::Slider::new(threshold, 0.0..=1.0)
+                        .prefix(format!("{}: ", tr("mask-threshold"))),
+                )
+                .changed();
+        }
+        MaskSource::Color { hue, tolerance } => {
+            changed |= ui
+                .add(
+                    egui::Slider::new(hue, 0.0..=360.0)
+                        .prefix(format!("{}: ", tr("mask-hue")))
+                        .suffix("°"),
+                )
+                .changed();
+            changed |= ui
+                .add(
+                    egui::Slider::new(tolerance, 0.0..=180.0)
+                        .prefix(format!("{}: ", tr("mask-tolerance")))
+                        .suffix("°"),
+                )
+                .changed();
+        }
+        MaskSource::Text {
+            text,
+            font,
+            x,
+            y,
+            size,
+        } => {
+            changed |= ui.text_edit_singleline(text).changed();
+            changed |= ui
+                .add(egui::TextEdit::singleline(font).hint_text(tr("mask-font-family")))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(x, 0.0..=1.0).prefix("X: "))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(y, 0.0..=1.0).prefix(format!("{}: ", tr("mask-baseline"))))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(size, 0.01..=1.0).prefix(format!("{}: ", tr("mask-size"))))
+                .changed();
+        }
+        MaskSource::Ellipse {
+            left,
+            top,
+            right,
+            bottom,
+        } => {
+            changed |= ui
+                .add(egui::Slider::new(left, 0.0..=1.0).prefix(format!("{}: ", tr("region-left"))))
+                .changed();
+            changed |= ui
+                .add(
+                    egui::Slider::new(right, 0.0..=1.0).prefix(format!("{}: ", tr("region-right"))),
+                )
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(top, 0.0..=1.0).prefix(format!("{}: ", tr("region-top"))))
+                .changed();
+            changed |= ui
+                .add(
+                    egui::Slider::new(bottom, 0.0..=1.0)
+                        .prefix(format!("{}: ", tr("region-bottom"))),
+                )
+                .changed();
+        }
+        MaskSource::Polygon { points } => {
+            let mut removed = None;
+            for (point_idx, (x, y)) in points.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(x)
+                                .speed(0.005)
+                                .clamp_range(0.0..=1.0)
+                                .prefix("x: "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(y)
+                                .speed(0.005)
+                                .clamp_range(0.0..=1.0)
+                                .prefix("y: "),
+                        )
+                        .changed();
+                    if ui
+                        .small_button("❎")
+                        .on_hover_text(tr("mask-remove-point"))
+                        .clicked()
+                    {
+                        removed = Some(point_idx);
+                    }
+                });
+            }
+            if let Some(point_idx) = removed {
+                points.remove(point_idx);
+                changed = true;
+            }
+            if ui.button(tr("mask-add-point")).clicked() {
+                points.push(points.last().copied().unwrap_or((0.5, 0.5)));
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Mask presets, parameters and selection operations, returns the new mask if it was changed
+fn mask_ui(ui: &mut egui::Ui, mask: Option<&ImageMask>) -> Option<Option<ImageMask>> {
+    let mut result = None;
+    ui.horizontal(|ui| {
+        if ui.button(tr("plugin-off")).clicked() {
+            result = Some(None);
+        }
+        ui.menu_button(tr("mask-new"), |ui| {
+            for (label, source) in MaskSource::presets() {
+                if ui.button(label).clicked() {
+                    result = Some(Some(ImageMask::new(source)));
+                    ui.close_menu();
+                }
+            }
+        });
+    });
+
+    let mut mask = match mask {
+        Some(mask) => mask.clone(),
+        None => return result,
+    };
+    let mut changed = false;
+    ui.label(tr_args("mask-source", &[("name", &mask.source.name())]));
+    changed |= mask_source_ui(ui, &mut mask.source);
+
+    let mut removed = None;
+    for (layer_idx, layer) in mask.layers.iter_mut().enumerate() {
+        ui.separator();
+        ui.horizontal(|ui| {
+            for operation in MaskOperation::ALL {
+                changed |= ui
+                    .selectable_value(&mut layer.operation, operation, operation.name())
+                    .changed();
+            }
+            ui.label(layer.source.name());
+            if ui
+                .small_button("❎")
+                .on_hover_text(tr("mask-remove-layer"))
+                .clicked()
+            {
+                removed = Some(layer_idx);
+            }
+        });
+        changed |= mask_source_ui(ui, &mut layer.source);
+    }
+    if let Some(layer_idx) = removed {
+        mask.layers.remove(layer_idx);
+        changed = true;
+    }
+    ui.menu_button(tr("mask-add-layer"), |ui| {
+        for (label, source) in MaskSource::presets() {
+            if ui.button(label).clicked() {
+                mask.layers.push(MaskLayer {
+                    operation: MaskOperation::Add,
+                    source,
+                });
+                changed = true;
+                ui.close_menu();
+            }
+        }
+    });
+
+    ui.separator();
+    changed |= ui
+        .add(
+            egui::Slider::new(&mut mask.softness, 0.0..=1.0)
+                .prefix(format!("{}: ", tr("mask-softness"))),
+        )
+        .changed();
+    changed |= ui
+        .add(
+            egui::Slider::new(&mut mask.grow, -64..=64)
+                .prefix(format!("{}: ", tr("mask-grow")))
+                .suffix("px"),
+        )
+        .on_hover_text(tr("mask-grow-hint"))
+        .changed();
+    changed |= ui
+        .add(
+            egui::Slider::new(&mut mask.border, 0..=64)
+                .prefix(format!("{}: ", tr("mask-border")))
+                .suffix("px"),
+        )
+        .on_hover_text(tr("mask-border-hint"))
+        .changed();
+    changed |= ui.checkbox(&mut mask.invert, tr("mask-invert")).changed();
+
+    if changed {
+        result = Some(Some(mask));
+    }
+    result
+}
+
+fn graph_nodes_ui(
+    ui: &mut egui::Ui,
+    nodes: &mut Vec<GraphNode>,
+    names: &[String],
+    unrouted: &[usize],
+) {
+    let mut edit = None;
+
+    for (idx, node) in nodes.iter_mut().enumerate() {
+        match node {
+            GraphNode::Plugin(id) => {
+                ui.horizontal(|ui| {
+                    ui.label(names.get(*id).map(|name| name.as_str()).unwrap_or("?"));
+                    if ui
+                        .small_button("⑂")
+                        .on_hover_text(tr("graph-split-hint"))
+                        .clicked()
+                    {
+                        edit = Some(GraphEdit::Split(idx));
+                    }
+                    if ui
+                        .small_button("❎")
+                        .on_hover_text(tr("graph-unroute"))
+                        .clicked()
+                    {
+                        edit = Some(GraphEdit::Remove(idx));
+                    }
+                });
+            }
+            GraphNode::Split(branches) => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("graph-split"));
+                    if ui
+                        .small_button("➕")
+                        .on_hover_text(tr("graph-add-branch"))
+                        .clicked()
+                    {
+                        branches.push(Branch::new(Vec::new()));
+                    }
+                    if ui
+                        .small_button("⬌")
+                        .on_hover_text(tr("graph-flatten"))
+                        .clicked()
+                    {
+                        edit = Some(GraphEdit::Flatten(idx));
+                    }
+                });
+
+                let mut removed_branch = None;
+                for (branch_idx, branch) in branches.iter_mut().enumerate() {
+                    ui.indent((idx, branch_idx), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut branch.name)
+                                    .hint_text(tr_args(
+                                        "graph-branch",
+                                        &[("number", &(branch_idx + 1).to_string())],
+                                    ))
+                                    .desired_width(80.0),
+                            );
+                            if ui
+                                .small_button("❎")
+                                .on_hover_text(tr("graph-remove-branch"))
+                                .clicked()
+                            {
+                                removed_branch = Some(branch_idx);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::Slider::new(&mut branch.mix, 0.0..=1.0)
+                                    .text(tr("graph-gain")),
+                            );
+                            egui::ComboBox::from_id_source((idx, branch_idx, "blend"))
+                                .selected_text(branch.blend.name())
+                                .show_ui(ui, |ui| {
+                                    for mode in BlendMode::ALL {
+                                        ui.selectable_value(&mut branch.blend, mode, mode.name());
+                                    }
+                                });
+                            egui::ComboBox::from_id_source((idx, branch_idx, "channel"))
+                                .selected_text(
+                                    branch
+                                        .channel
+                                        .map_or(String::from("RGB"), |channel| channel.name()),
+                                )
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut branch.channel, None, "RGB");
+                                    for channel in ColorChannel::ALL {
+                                        ui.selectable_value(
+                                            &mut branch.channel,
+                                            Some(channel),
+                                            channel.name(),
+                                        );
+                                    }
+                                })
+                                .response
+                                .on_hover_text(tr("graph-channel-hint"));
+                        });
+                        graph_nodes_ui(ui, &mut branch.nodes, names, unrouted);
+                    });
+                }
+
+                if let Some(branch_idx) = removed_branch {
+                    branches.remove(branch_idx);
+                }
+            }
+        }
+    }
+
+    ui.add_enabled_ui(!unrouted.is_empty(), |ui| {
+        ui.menu_button(tr("graph-route-plugin"), |ui| {
+            for id in unrouted {
+                if ui.button(names[*id].as_str()).clicked() {
+                    edit = Some(GraphEdit::Add(*id));
+                    ui.close_menu();
+                }
+            }
+        });
+    });
+
+    match edit {
+        Some(GraphEdit::Add(id)) => nodes.push(GraphNode::Plugin(id)),
+        Some(GraphEdit::Remove(idx)) => {
+            nodes.remove(idx);
+        }
+        Some(GraphEdit::Split(idx)) => {
+            let node = nodes.remove(idx);
+            nodes.insert(
+                idx,
+                GraphNode::Split(vec![Branch::new(vec![node]), Branch::new(Vec::new())]),
+            );
+        }
+        Some(GraphEdit::Flatten(idx)) => {
+            if let GraphNode::Split(branches) = nodes.remove(idx) {
+                let flattened: Vec<GraphNode> = branches
+                    .into_iter()
+                    .flat_map(|branch| branch.nodes)
+                    .collect();
+                nodes.splice(idx..idx, flattened);
+            }
+        }
+        None => {}
+    }
+}
+
+/// Document open in a background tab
+struct Tab {
+    rack: PluginRack,
+    save_path: Option<PathBuf>,
+    snapshots: [Option<String>; 2],
+    active_snapshot: usize,
+    selected_plugin: Option<usize>,
+    undo_history: Vec<UndoStep>,
+    redo_history: Vec<UndoStep>,
+}
+
+fn tab_title(rack: &PluginRack, save_path: &Option<PathBuf>) -> String {
+    match save_path.as_ref().and_then(|path| path.file_stem()) {
+        Some(name) => name.to_string_lossy().to_string(),
+        None if !rack.images.is_empty() => rack.source_name.clone(),
+        None => tr("tab-untitled"),
+    }
+}
+
+/// Processing state and partially processed image saved into project by checkpoints
+fn read_checkpoint<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> anyhow::Result<Option<(Checkpoint, image::DynamicImage)>> {
+    let checkpoint: Checkpoint = match archive.by_name("checkpoint.json") {
+        Ok(file) => serde_json::from_reader(file)?,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+
+    let image_name = if archive.file_names().any(|name| name == "checkpoint.exr") {
+        "checkpoint.exr"
+    } else {
+        "checkpoint.png"
+    };
+    let mut buf = Vec::new();
+    archive.by_name(image_name)?.read_to_end(&mut buf)?;
+    let image = image::load_from_memory(&buf)?;
+    Ok(Some((checkpoint, image)))
+}
+
 pub struct State {
     rack: PluginRack,
     modal: ModalWindows,
     save_path: Option<PathBuf>,
     timer: Instant,
+    /// Serialized A/B chain snapshots
+    snapshots: [Option<String>; 2],
+    active_snapshot: usize,
+    /// Plugins found in the library folder, used by randomizer
+    plugin_library: Vec<PathBuf>,
+    random_count: usize,
+    /// Seed used by replay, shows the seed of the current randomized chain
+    random_seed: u64,
+    /// Attach plugin editors to the right side of the main window
+    dock_editors: bool,
+    /// Plugin shown in the inspector panel
+    selected_plugin: Option<usize>,
+    image_url: String,
+    /// Image download started from the URL window
+    download: Option<JoinHandle<anyhow::Result<Vec<u8>>>>,
+    /// Screen capture rectangle: x, y, width, height
+    capture_region: [u32; 4],
+    capture_whole_screen: bool,
+    video_path: Option<PathBuf>,
+    /// First frame and frame count of the video to load
+    video_range: (usize, usize),
+    svg_path: Option<PathBuf>,
+    /// Width of rasterized SVG image
+    svg_width: u32,
+    /// Frame count of animation made from still image
+    still_frames: usize,
+    keyframe_target: KeyframeTarget,
+    /// Pixel under the cursor when the preview context menu was opened
+    tile_pointer: Option<(u32, u32)>,
+    audition_size: u32,
+    /// Processed tile shown over the image: left, top, tile
+    audition: Option<(u32, u32, image::RgbaImage)>,
+    /// Re-process preview when plugin parameters are changed
+    live_preview: bool,
+    zoom_request: Option<CanvasZoom>,
+    /// Draw pixel borders when the preview is zoomed in enough
+    pixel_grid: bool,
+    /// Re-import source image when it is saved by another program
+    watch_image: bool,
+    /// Source image file and its modification time
+    image_modified: Option<(PathBuf, SystemTime)>,
+    /// File opened in the external editor and its modification time
+    external_edit: Option<(PathBuf, SystemTime)>,
+    show_navigator: bool,
+    /// Visible part of the image preview in plot coordinates
+    view_bounds: Option<PlotBounds>,
+    /// Plot point which should be moved to the center of the preview
+    navigate_to: Option<[f64; 2]>,
+    /// Pixel rulers along the preview edges
+    show_rulers: bool,
+    guides: Vec<Guide>,
+    hovered_guide: Option<usize>,
+    dragging_guide: Option<usize>,
+    show_histogram: bool,
+    /// Histogram of the shown image, recalculated when preview is updated
+    histogram: Option<Histogram>,
+    /// Paint clipped pixels over the preview
+    show_clipping: bool,
+    eyedropper: bool,
+    /// Pixel under the cursor shown in the pixel inspector
+    inspected_pixel: Option<(u32, u32)>,
+    compare: CompareMode,
+    /// Split view divider position relative to the image width
+    split_position: f32,
+    split_hovered: bool,
+    dragging_split: bool,
+    /// Blink compare mode shows the original image
+    blink_original: bool,
+    /// Multiplier of the difference view and export
+    difference_gain: f32,
+    show_metrics: bool,
+    show_console: bool,
+    show_jobs: bool,
+    /// Background render queue
+    jobs: JobQueue,
+    /// Jobs rendered at once, 1 renders them one by one
+    job_workers: usize,
+    /// Least severe level shown in the log console
+    console_level: LevelFilter,
+    /// Quality metrics of the shown image, measured when processing is finished
+    metrics: Option<Metrics>,
+    /// Open documents, slot of the active one is empty since it lives in the state itself
+    tabs: Vec<Option<Tab>>,
+    active_tab: usize,
+    settings: Settings,
+    /// Command which gets the next pressed key combination in preferences
+    capturing_shortcut: Option<Command>,
+    /// Language of loaded catalog and fonts
+    language: Option<Language>,
+    /// Modification times of loaded plugin files
+    plugin_modified: HashMap<PathBuf, SystemTime>,
+    /// Plugin files changed on disk, user is asked to reload them
+    changed_plugins: Vec<PathBuf>,
+    /// Processing runs and rack edits in the order they were made
+    undo_history: Vec<UndoStep>,
+    redo_history: Vec<UndoStep>,
+    /// Macro being recorded
+    recording: Option<Macro>,
+    /// Macro being replayed and index of its next step
+    replaying: Option<(Macro, usize)>,
+    /// Interrupted render found in the opened project, user is asked to continue it
+    resume_checkpoint: Option<(Checkpoint, image::DynamicImage)>,
+    watch_timer: Instant,
+    /// Control changes of connected MIDI controllers
+    midi: MidiInput,
+    /// Plugin parameter waiting for a MIDI control change to be mapped to it
+    midi_learn: Option<(usize, i32)>,
+    palette_open: bool,
+    palette_query: String,
+    /// Highlighted item of the palette search results
+    palette_selected: usize,
 }
 
+/// Formats offered as default export format in preferences
+const EXPORT_FORMATS: [&str; 7] = ["png", "jpg", "webp", "avif", "tiff", "exr", "hdr"];
+
 impl State {
     pub fn new() -> Self {
+        let settings = Settings::load();
+        plugin_library::set_blacklist(settings.plugin_blacklist.clone());
         Self {
             rack: PluginRack::new(),
             modal: ModalWindows::None,
             save_path: None,
             timer: Instant::now(),
+            snapshots: [None, None],
+            active_snapshot: 0,
+            plugin_library: settings.scan_plugins(),
+            random_count: 3,
+            random_seed: 0,
+            dock_editors: settings.dock_editors,
+            selected_plugin: None,
+            image_url: String::new(),
+            download: None,
+            capture_region: [0, 0, 640, 480],
+            capture_whole_screen: true,
+            video_path: None,
+            video_range: (0, 100),
+            svg_path: None,
+            svg_width: 1920,
+            still_frames: 25,
+            keyframe_target: KeyframeTarget::Wet,
+            tile_pointer: None,
+            audition_size: 256,
+            audition: None,
+            live_preview: false,
+            zoom_request: None,
+            pixel_grid: settings.pixel_grid,
+            watch_image: settings.watch_image,
+            image_modified: None,
+            external_edit: None,
+            show_navigator: false,
+            view_bounds: None,
+            navigate_to: None,
+            show_rulers: settings.show_rulers,
+            guides: Vec::new(),
+            hovered_guide: None,
+            dragging_guide: None,
+            show_histogram: false,
+            histogram: None,
+            show_clipping: false,
+            eyedropper: false,
+            inspected_pixel: None,
+            compare: CompareMode::None,
+            split_position: 0.5,
+            split_hovered: false,
+            dragging_split: false,
+            blink_original: false,
+            difference_gain: 1.0,
+            show_metrics: false,
+            show_console: false,
+            show_jobs: false,
+            jobs: JobQueue::new(),
+            job_workers: 1,
+            console_level: LevelFilter::Info,
+            metrics: None,
+            tabs: vec![None],
+            active_tab: 0,
+            settings,
+            capturing_shortcut: None,
+            language: None,
+            plugin_modified: HashMap::new(),
+            changed_plugins: Vec::new(),
+            undo_history: Vec::new(),
+            redo_history: Vec::new(),
+            recording: None,
+            replaying: None,
+            resume_checkpoint: None,
+            watch_timer: Instant::now(),
+            midi: MidiInput::new(),
+            midi_learn: None,
+            palette_open: false,
+            palette_query: String::new(),
+            palette_selected: 0,
         }
     }
 
     pub fn load_image(&mut self, renderer: &mut Renderer, file: PathBuf) -> anyhow::Result<()> {
+        if svg::is_svg(&file) {
+            // resolution is chosen before rasterization
+            self.svg_path = Some(file);
+            self.modal = ModalWindows::OpenSvg;
+            return Ok(());
+        }
+
         renderer.cleanup_image();
+        self.audition = None;
         self.rack.load_image(file)?;
         Ok(())
     }
 
+    /// Loads image downloaded by the worker thread once it is finished,
+    /// returns `false` while the download is still running
+    fn finish_download(&mut self, renderer: &mut Renderer) -> anyhow::Result<bool> {
+        match &self.download {
+            Some(download) if download.is_finished() => {}
+            Some(_) => return Ok(false),
+            None => return Ok(true),
+        }
+
+        let buf = self
+            .download
+            .take()
+            .unwrap()
+            .join()
+            .map_err(|_| anyhow::anyhow!("Download thread panicked"))??;
+        renderer.cleanup_image();
+        self.rack.load_image_data(&buf)?;
+        Ok(true)
+    }
+
+    /// Captures primary display or its rectangle as working image
+    pub fn capture_screen(
+        &mut self,
+        renderer: &mut Renderer,
+        region: Option<[u32; 4]>,
+    ) -> anyhow::Result<()> {
+        let screen = screenshots::Screen::all()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No displays found"))?;
+
+        let image = match region {
+            Some([x, y, width, height]) => {
+                screen.capture_area(x as i32, y as i32, width, height)?
+            }
+            None => screen.capture()?,
+        };
+
+        renderer.cleanup_image();
+        self.rack.load_image_data(image.buffer())?;
+        Ok(())
+    }
+
+    /// Snaps a single frame from the first available camera
+    pub fn capture_webcam(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        let format =
+            RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestResolution);
+        let mut camera = Camera::new(CameraIndex::Index(0), format)?;
+        camera.open_stream()?;
+        let frame = camera.frame();
+        camera.stop_stream()?;
+
+        let image = frame?.decode_image::<RgbFormat>()?;
+        let image = image::DynamicImage::ImageRgb8(image).to_rgba8();
+
+        renderer.cleanup_image();
+        self.rack
+            .load_image_rgba(image.width(), image.height(), image.into_raw())?;
+        Ok(())
+    }
+
+    /// Loads bitmap from the system clipboard
+    pub fn paste_image(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        let mut clipboard = arboard::Clipboard::new()?;
+        let image = clipboard.get_image()?;
+        renderer.cleanup_image();
+        self.rack.load_image_rgba(
+            image.width as u32,
+            image.height as u32,
+            image.bytes.into_owned(),
+        )?;
+        Ok(())
+    }
+
     pub fn resize_editors(&mut self, renderer: &mut Renderer) {
         for window in renderer.windows.iter_mut() {
             for plugin in self.rack.plugins.iter_mut() {
                 if let Some(editor) = &mut plugin.editor.editor {
                     if editor.is_open() && plugin.editor.window_id == Some(window.id()) {
-                        window.set_inner_size(PhysicalSize::new(editor.size().0, editor.size().1));
+                        let size =
+                            PhysicalSize::new(editor.size().0 as u32, editor.size().1 as u32);
+                        if window.inner_size() != size {
+                            window.set_inner_size(size);
+                        }
                     }
                 }
             }
@@ -57,22 +1117,71 @@ impl State {
         renderer: &mut Renderer,
         event_loop: &EventLoopWindowTarget<renderer::Event>,
     ) {
-        let editor_window = self.rack.plugins[plugin_index].editor.show(event_loop);
+        if let Some(window_id) = self.rack.plugins[plugin_index].editor.window_id {
+            if let Some(window) = renderer.windows.iter().find(|w| w.id() == window_id) {
+                window.focus_window();
+                return;
+            }
+        }
+
+        let editor_window = self.rack.plugins[plugin_index]
+            .editor
+            .show(event_loop, self.dock_editors);
 
         match editor_window {
             Ok(editor) => {
                 renderer.windows.push(editor);
             }
             Err(error) => {
-                messagebox("Unable to open editor", &error.to_string());
+                messagebox(&tr("error-open-editor"), &error.to_string());
             }
         }
     }
 
-    pub fn close_editor(&mut self, window_id: WindowId) {
-        for plugin in self.rack.plugins.iter_mut() {
-            plugin.editor.close(window_id);
+    /// Stacks editor windows along the right edge of the main window
+    pub fn place_docked_editors(&self, renderer: &Renderer, main_window: &Window) {
+        if !self.dock_editors {
+            return;
+        }
+
+        let origin = match main_window.outer_position() {
+            Ok(origin) => origin,
+            Err(_) => return,
+        };
+
+        let x = origin.x + main_window.outer_size().width as i32;
+        let mut y = origin.y;
+        for window in &renderer.windows {
+            let position = PhysicalPosition::new(x, y);
+            if window.outer_position().ok() != Some(position) {
+                window.set_outer_position(position);
+            }
+            y += window.outer_size().height as i32;
+        }
+    }
+
+    pub fn close_editor(&mut self, renderer: &mut Renderer, window_id: WindowId) {
+        if let Some(window) = renderer.windows.iter().find(|w| w.id() == window_id) {
+            for plugin in self.rack.plugins.iter_mut() {
+                plugin.editor.close(window);
+            }
+        }
+        renderer.windows.retain(|w| w.id() != window_id);
+    }
+
+    pub fn close_plugin_editor(&mut self, renderer: &mut Renderer, plugin_index: usize) {
+        if let Some(window_id) = self.rack.plugins[plugin_index].editor.window_id {
+            self.close_editor(renderer, window_id);
+        }
+    }
+
+    pub fn close_all_editors(&mut self, renderer: &mut Renderer) {
+        for window in &renderer.windows {
+            for plugin in self.rack.plugins.iter_mut() {
+                plugin.editor.close(window);
+            }
         }
+        renderer.windows.clear();
     }
 
     pub fn load_project(&mut self, renderer: &mut Renderer, file: PathBuf) -> anyhow::Result<()> {
@@ -82,212 +1191,2569 @@ impl State {
 
         let mut proj_file_string = String::new();
         proj_file.read_to_string(&mut proj_file_string)?;
-        let instacnes: Vec<crate::plugin_rack::PluginRackInstance> =
-            serde_json::from_str(&proj_file_string)?;
 
         renderer.cleanup_image();
-        renderer.windows.clear();
+        self.close_all_editors(renderer);
         self.rack = PluginRack::new();
-
-        self.rack.plugins.extend(instacnes);
-        self.rack.load_uninitialzed_plugins()?;
+        self.undo_history.clear();
+        self.redo_history.clear();
+        self.rack.load_chain(&proj_file_string)?;
 
         drop(proj_file);
 
-        let mut image_file = archive.by_name("image.png")?;
+        // HDR images are stored in OpenEXR
+        let image_name = if archive.file_names().any(|name| name == "image.exr") {
+            "image.exr"
+        } else {
+            "image.png"
+        };
+        let mut image_file = archive.by_name(image_name)?;
 
         let mut buf = Vec::new();
         image_file.read_to_end(&mut buf)?;
-        self.rack.load_image_data(&buf)?;
+        drop(image_file);
+
+        let original_name = ["original.exr", "original.png"]
+            .into_iter()
+            .find(|name| archive.file_names().any(|file| file == *name));
+        match original_name {
+            Some(name) => {
+                let mut original = Vec::new();
+                archive.by_name(name)?.read_to_end(&mut original)?;
+                self.rack.load_image_data(&original)?;
+                self.rack.push_result_data(&buf)?;
+            }
+            None => self.rack.load_image_data(&buf)?,
+        }
+        self.rack
+            .load_project_masks(&mut archive)
+            .unwrap_or_else(|error| log::error!("Unable to read project masks: {}", error));
+        if let Ok(history) = archive.by_name("history.json") {
+            self.undo_history = serde_json::from_reader(history).unwrap_or_else(|error| {
+                log::error!("Unable to read undo history: {}", error);
+                Vec::new()
+            });
+        }
+        self.random_seed = self.rack.seed.unwrap_or_default();
+        self.resume_checkpoint = read_checkpoint(&mut archive).unwrap_or_else(|error| {
+            log::error!("Unable to read checkpoint: {}", error);
+            None
+        });
         self.save_path = Some(file);
         Ok(())
     }
 
-    pub fn export_image(&self) {
-        let files = rfd::FileDialog::new()
-            .set_title("Export image")
-            .add_filter("JPEG Image", &["jpg", "jpeg"])
-            .add_filter("PNG Image", &["png"])
-            .add_filter("GIF Image", &["gif"])
-            .add_filter("BMP Image", &["bmp"])
-            .add_filter("ICO Image", &["ico"])
-            .add_filter("TIFF Image", &["tiff"])
-            .add_filter("WebP Image", &["webp"])
-            .add_filter("TGA Image", &["tga"])
-            .save_file();
+    /// Opens file dropped onto the window depending on its type
+    pub fn open_dropped_file(&mut self, renderer: &mut Renderer, file: PathBuf) {
+        let extension = file
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
 
-        if let Some(file) = files {
-            self.rack.save_image(file).unwrap_or_else(|op| {
-                messagebox(
-                    "Image saving error",
-                    &format!("Cannot save image: \n{}", op),
-                );
-            });
-        }
+        let result = if plugin_library::is_plugin(&file) {
+            self.add_plugin(file)
+        } else if matches!(extension.as_str(), "viproj" | "zip") {
+            self.load_project(renderer, file)
+        } else if plugin_rack::is_chain_file(&file) {
+            self.change_chain(renderer, |rack| Ok(rack.load_chain_preset(file)?))
+        } else {
+            self.load_image(renderer, file)
+        };
+
+        result.unwrap_or_else(|error| {
+            messagebox(&tr("error-open-dropped-file"), &error.to_string());
+        });
     }
 
-    pub fn exit(&mut self, renderer: &mut Renderer) {
-        match self.modal {
-            ModalWindows::Exit => renderer.close_render(),
-            _ => self.modal = ModalWindows::Exit,
+    /// Stores current chain into active snapshot slot and switches to another one,
+    /// image is re-rendered from the original if switched snapshot was stored before
+    pub fn switch_snapshot(&mut self, slot: usize, renderer: &mut Renderer) -> anyhow::Result<()> {
+        if slot == self.active_snapshot {
+            return Ok(());
         }
-    }
+
+        self.snapshots[self.active_snapshot] = Some(self.rack.save_chain()?);
+        self.active_snapshot = slot;
+
+        if let Some(chain) = self.snapshots[slot].clone() {
+            self.change_chain(renderer, |rack| Ok(rack.load_chain(&chain)?))?;
+
+            if !self.rack.images.is_empty() {
+                renderer.cleanup_image();
+                self.rack.revert();
+                self.process();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds plugin with the default tile size from preferences
+    fn add_plugin(&mut self, file: PathBuf) -> anyhow::Result<()> {
+        self.rack.load_plugin(file.clone())?;
+        if let Some(recording) = &mut self.recording {
+            recording.steps.push(MacroStep::AddPlugin(file));
+        }
+        let id = self.rack.plugins.len() - 1;
+        self.rack.plugins[id].tile_size = self.settings.tile_size;
+        self.push_undo(UndoStep::Rack(RackEdit::Action(Action::Remove(id))));
+        if let Some(recording) = &mut self.recording {
+            recording.record_action(&Action::ChangeTileSize(id, self.settings.tile_size));
+        }
+        Ok(())
+    }
+
+    /// Applies plugin table action to the rack and records it into the macro
+    fn apply_action(&mut self, action: Action) {
+        if let Some(recording) = &mut self.recording {
+            recording.record_action(&action);
+        }
+        match self.rack.apply_action(&action) {
+            Ok(Some(inverse)) => self.push_undo(UndoStep::Rack(inverse)),
+            Ok(None) => {}
+            Err(error) => messagebox(&tr("error-change-plugin"), &error.to_string()),
+        }
+    }
+
+    /// Adds step to the undo history, redo history is no longer valid after that
+    fn push_undo(&mut self, step: UndoStep) {
+        if self.undo_history.len() == UNDO_LIMIT {
+            self.undo_history.remove(0);
+        }
+        self.undo_history.push(step);
+        self.redo_history.clear();
+    }
+
+    /// Replaces plugins of the chain, whole chain is stored in the undo history
+    /// since plugin instances can't be restored separately
+    fn change_chain<F>(&mut self, renderer: &mut Renderer, change: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut PluginRack) -> anyhow::Result<()>,
+    {
+        let undo = self.rack.save_chain()?;
+        self.close_all_editors(renderer);
+        change(&mut self.rack)?;
+        self.push_undo(UndoStep::Rack(RackEdit::Chain(undo)));
+        Ok(())
+    }
+
+    fn can_undo(&self) -> bool {
+        self.rack.is_finished() && !self.rack.is_preview() && !self.undo_history.is_empty()
+    }
+
+    fn can_redo(&self) -> bool {
+        self.rack.is_finished() && !self.rack.is_preview() && !self.redo_history.is_empty()
+    }
+
+    fn undo(&mut self, renderer: &mut Renderer) {
+        while let Some(step) = self.undo_history.pop() {
+            if let Some(redo) = self.revert_step(renderer, step, false) {
+                self.redo_history.push(redo);
+                return;
+            }
+        }
+    }
+
+    fn redo(&mut self, renderer: &mut Renderer) {
+        while let Some(step) = self.redo_history.pop() {
+            if let Some(undo) = self.revert_step(renderer, step, true) {
+                self.undo_history.push(undo);
+                return;
+            }
+        }
+    }
+
+    /// Reverts history step, returns step which makes the change again or `None`
+    /// if the step is no longer valid (e.g. processing was cancelled)
+    fn revert_step(
+        &mut self,
+        renderer: &mut Renderer,
+        step: UndoStep,
+        redo: bool,
+    ) -> Option<UndoStep> {
+        let edit = match step {
+            UndoStep::Image => {
+                if (redo && !self.rack.can_redo()) || (!redo && self.rack.images.len() < 2) {
+                    return None;
+                }
+                renderer.cleanup_image();
+                self.audition = None;
+                if redo {
+                    self.rack.redo();
+                } else {
+                    self.rack.undo();
+                }
+                return Some(UndoStep::Image);
+            }
+            UndoStep::Rack(edit) => edit,
+        };
+
+        // plugins are replaced or removed, so their editors can't stay open
+        match &edit {
+            RackEdit::Chain(_) => self.close_all_editors(renderer),
+            RackEdit::Action(Action::Remove(id)) if *id < self.rack.plugins.len() => {
+                self.close_plugin_editor(renderer, *id);
+            }
+            _ => {}
+        }
+        if !matches!(edit, RackEdit::Action(_)) {
+            self.selected_plugin = None;
+        }
+
+        match self.rack.revert_edit(edit) {
+            Ok(edit) => Some(UndoStep::Rack(edit)),
+            Err(error) => {
+                let title = if redo {
+                    tr("error-redo")
+                } else {
+                    tr("error-undo")
+                };
+                messagebox(&title, &error.to_string());
+                None
+            }
+        }
+    }
+
+    fn stop_recording(&mut self) {
+        let recording = match self.recording.take() {
+            Some(recording) => recording,
+            None => return,
+        };
+        if let Some(file) = rfd::FileDialog::new()
+            .set_title(&tr("dialog-save-macro"))
+            .add_filter("PhotoConsequences macro", &[MACRO_EXTENSION])
+            .save_file()
+        {
+            recording.save(file).unwrap_or_else(|error| {
+                messagebox(&tr("error-save-macro"), &error.to_string());
+            });
+        }
+    }
+
+    fn replay_macro_ui(&mut self) {
+        if let Some(file) = rfd::FileDialog::new()
+            .set_title(&tr("dialog-replay-macro"))
+            .add_filter("PhotoConsequences macro", &[MACRO_EXTENSION])
+            .pick_file()
+        {
+            match Macro::load(file) {
+                Ok(recorded) => self.replaying = Some((recorded, 0)),
+                Err(error) => messagebox(&tr("error-load-macro"), &error.to_string()),
+            }
+        }
+    }
+
+    /// Runs macro steps until the next processing run, which is finished in the following frames
+    fn replay_macro(&mut self, renderer: &mut Renderer) {
+        if !self.rack.is_finished() {
+            return;
+        }
+        let (recorded, mut step) = match self.replaying.take() {
+            Some(replaying) => replaying,
+            None => return,
+        };
+
+        while let Some(macro_step) = recorded.steps.get(step) {
+            step += 1;
+            if let MacroStep::Action(Action::Remove(id)) = macro_step {
+                if *id < self.rack.plugins.len() {
+                    self.close_plugin_editor(renderer, *id);
+                }
+                self.selected_plugin = None;
+            }
+            match macros::apply_step(&mut self.rack, macro_step) {
+                Ok(false) => {}
+                Ok(true) => {
+                    self.replaying = Some((recorded, step));
+                    return;
+                }
+                Err(error) => {
+                    messagebox(&tr("error-replay-macro"), &error.to_string());
+                    return;
+                }
+            }
+        }
+    }
+
+    fn pick_plugin_library(&mut self) {
+        if let Some(dir) = rfd::FileDialog::new()
+            .set_title(&tr("dialog-plugin-folder"))
+            .pick_folder()
+        {
+            if !self.settings.plugin_dirs.contains(&dir) {
+                self.settings.plugin_dirs.push(dir);
+            }
+            self.plugin_library = self.settings.scan_plugins();
+            self.save_settings();
+        }
+    }
+
+    fn save_settings(&self) {
+        self.settings.save().unwrap_or_else(|error| {
+            log::error!("Unable to save settings: {}", error);
+        });
+    }
+
+    /// Keeps UI options changed outside of preferences dialog in the settings file
+    fn store_ui_settings(&mut self) {
+        let mut settings = self.settings.clone();
+        settings.dock_editors = self.dock_editors;
+        settings.pixel_grid = self.pixel_grid;
+        settings.watch_image = self.watch_image;
+        settings.show_rulers = self.show_rulers;
+        if settings != self.settings {
+            self.settings = settings;
+            self.save_settings();
+        }
+    }
+
+    fn preferences_window(&mut self, context: &Context, renderer: &mut Renderer) {
+        let before = self.settings.clone();
+
+        egui::Window::new(tr("prefs-title"))
+            .collapsible(false)
+            .auto_sized()
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                let settings = &mut self.settings;
+
+                ui.heading(tr("prefs-plugin-dirs"));
+                let mut remove = None;
+                for (id, dir) in settings.plugin_dirs.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(dir.display().to_string());
+                        if ui.button("🗑").clicked() {
+                            remove = Some(id);
+                        }
+                    });
+                }
+                if let Some(id) = remove {
+                    settings.plugin_dirs.remove(id);
+                }
+                if ui.button(tr("prefs-add-folder")).clicked() {
+                    if let Some(dir) = rfd::FileDialog::new()
+                        .set_title(&tr("dialog-plugin-folder"))
+                        .pick_folder()
+                    {
+                        settings.plugin_dirs.push(dir);
+                    }
+                }
+
+                ui.separator();
+                ui.heading(tr("prefs-blacklist"));
+                let mut remove = None;
+                for (id, plugin) in settings.plugin_blacklist.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(plugin.display().to_string());
+                        if ui.button("🗑").clicked() {
+                            remove = Some(id);
+                        }
+                    });
+                }
+                if let Some(id) = remove {
+                    settings.plugin_blacklist.remove(id);
+                }
+                if ui.button(tr("prefs-blacklist-add")).clicked() {
+                    if let Some(file) = rfd::FileDialog::new()
+                        .add_filter("VST 2.4 Plugin", &[PLUGIN_EXTENSION])
+                        .pick_file()
+                    {
+                        settings.plugin_blacklist.push(file);
+                    }
+                }
+
+                ui.separator();
+                ui.heading(tr("prefs-processing"));
+                ui.add(
+                    egui::DragValue::new(&mut settings.tile_size)
+                        .clamp_range(1..=4096)
+                        .prefix(format!("{}: ", tr("prefs-tile-size"))),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut settings.threads)
+                        .clamp_range(0..=256)
+                        .prefix(format!("{}: ", tr("prefs-cli-threads"))),
+                )
+                .on_hover_text(tr("prefs-cli-threads-hint"));
+                ui.add(
+                    egui::DragValue::new(&mut settings.autosave_minutes)
+                        .clamp_range(1..=240)
+                        .prefix(format!("{}: ", tr("prefs-autosave")))
+                        .suffix(format!(" {}", tr("prefs-minutes"))),
+                );
+
+                ui.separator();
+                ui.heading(tr("prefs-project"));
+                let contents = &mut settings.project_contents;
+                ui.checkbox(&mut contents.original, tr("prefs-project-original"))
+                    .on_hover_text(tr("prefs-project-original-hint"));
+                ui.checkbox(&mut contents.masks, tr("prefs-project-masks"));
+                ui.add(
+                    egui::DragValue::new(&mut contents.history)
+                        .clamp_range(0..=UNDO_LIMIT)
+                        .prefix(format!("{}: ", tr("prefs-project-history"))),
+                );
+
+                ui.separator();
+                ui.heading(tr("prefs-export"));
+                egui::ComboBox::from_label(tr("prefs-default-format"))
+                    .selected_text(settings.export_format.as_str())
+                    .show_ui(ui, |ui| {
+                        for format in EXPORT_FORMATS {
+                            ui.selectable_value(
+                                &mut settings.export_format,
+                                format.to_string(),
+                                format,
+                            );
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", tr("prefs-external-editor")));
+                    match &settings.external_editor {
+                        Some(editor) => ui.label(editor.display().to_string()),
+                        None => ui.label(tr("prefs-not-set")),
+                    };
+                    if ui.button("📂").clicked() {
+                        if let Some(file) = rfd::FileDialog::new().pick_file() {
+                            settings.external_editor = Some(file);
+                        }
+                    }
+                    if settings.external_editor.is_some() && ui.button("🗑").clicked() {
+                        settings.external_editor = None;
+                    }
+                });
+
+                ui.separator();
+                ui.heading(tr("prefs-interface"));
+                egui::ComboBox::from_label(tr("prefs-language"))
+                    .selected_text(settings.language.native_name())
+                    .show_ui(ui, |ui| {
+                        for language in Language::ALL {
+                            ui.selectable_value(
+                                &mut settings.language,
+                                language,
+                                language.native_name(),
+                            );
+                        }
+                    });
+                ui.checkbox(&mut settings.dock_editors, tr("dock-editors"));
+                ui.checkbox(&mut settings.watch_plugins, tr("prefs-watch-plugins"))
+                    .on_hover_text(tr("prefs-watch-plugins-hint"));
+                ui.checkbox(&mut settings.watch_image, tr("watch-image"));
+                ui.checkbox(&mut settings.pixel_grid, tr("prefs-pixel-grid"));
+                ui.checkbox(&mut settings.show_rulers, tr("prefs-rulers"));
+
+                ui.separator();
+                ui.heading(tr("prefs-theme"));
+                let theme = &mut settings.theme;
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut theme.kind, ThemeKind::Dark, tr("theme-dark"));
+                    ui.selectable_value(&mut theme.kind, ThemeKind::Light, tr("theme-light"));
+                    ui.selectable_value(&mut theme.kind, ThemeKind::Custom, tr("theme-custom"));
+                });
+                egui::Grid::new("theme_colors").show(ui, |ui| {
+                    let mut colors = vec![
+                        (tr("theme-accent"), &mut theme.accent),
+                        (tr("theme-canvas"), &mut theme.canvas),
+                        (tr("theme-grid"), &mut theme.grid),
+                    ];
+                    if theme.kind == ThemeKind::Custom {
+                        colors.push((tr("theme-panels"), &mut theme.background));
+                        colors.push((tr("theme-text"), &mut theme.text));
+                    }
+                    for (name, color) in colors {
+                        ui.label(name);
+                        ui.color_edit_button_srgb(color);
+                        ui.end_row();
+                    }
+                });
+                if ui.button(tr("theme-reset")).clicked() {
+                    *theme = Theme::default();
+                }
+
+                ui.separator();
+                ui.heading(tr("prefs-shortcuts"));
+                if let Some(command) = self.capturing_shortcut {
+                    if let Some(binding) = keymap::capture_binding(&ui.input(), command) {
+                        settings.keymap.retain(|binding| binding.command != command);
+                        settings.keymap.push(binding);
+                        self.capturing_shortcut = None;
+                    }
+                }
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for command in Command::ALL {
+                        ui.label(command.name());
+                        if self.capturing_shortcut == Some(command) {
+                            ui.label(tr("prefs-press-key"));
+                        } else {
+                            let keys: Vec<String> = settings
+                                .keymap
+                                .iter()
+                                .filter(|binding| binding.command == command)
+                                .map(|binding| binding.to_string())
+                                .collect();
+                            ui.label(keys.join(", "));
+                        }
+                        if ui.button(tr("prefs-set")).clicked() {
+                            self.capturing_shortcut = Some(command);
+                        }
+                        if ui.button("🗑").clicked() {
+                            settings.keymap.retain(|binding| binding.command != command);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if ui.button(tr("prefs-reset-shortcuts")).clicked() {
+                    settings.keymap = keymap::default_bindings();
+                }
+
+                ui.separator();
+                if ui.button(tr("prefs-ok")).clicked() {
+                    self.capturing_shortcut = None;
+                    self.modal = ModalWindows::None;
+                }
+            });
+
+        if self.settings == before {
+            return;
+        }
+
+        if self.settings.plugin_dirs != before.plugin_dirs {
+            self.plugin_library = self.settings.scan_plugins();
+        }
+        plugin_library::set_blacklist(self.settings.plugin_blacklist.clone());
+        if self.settings.dock_editors != self.dock_editors {
+            for window in &renderer.windows {
+                window.set_decorations(!self.settings.dock_editors);
+            }
+        }
+        self.dock_editors = self.settings.dock_editors;
+        self.pixel_grid = self.settings.pixel_grid;
+        self.watch_image = self.settings.watch_image;
+        self.show_rulers = self.settings.show_rulers;
+        self.save_settings();
+    }
+
+    /// Replaces the chain with random effects, the same seed gives the same chain
+    fn surprise_me(&mut self, renderer: &mut Renderer, seed: u64) {
+        if self.plugin_library.is_empty() {
+            self.pick_plugin_library();
+        }
+
+        self.random_seed = seed;
+        let (library, count) = (self.plugin_library.clone(), self.random_count);
+        match self.change_chain(renderer, |rack| {
+            Ok(rack.randomize_chain(&library, count, seed)?)
+        }) {
+            Ok(()) => {
+                if !self.rack.images.is_empty() {
+                    renderer.cleanup_image();
+                    self.rack.revert();
+                    self.process();
+                }
+            }
+            Err(error) => messagebox(&tr("error-randomize-chain"), &error.to_string()),
+        }
+    }
+
+    fn save_chain_preset_ui(&mut self) {
+        let file = rfd::FileDialog::new()
+            .set_title(&tr("dialog-save-chain-preset"))
+            .add_filter("PhotoConsequences chain preset", &[CHAIN_EXTENSION])
+            .save_file();
+
+        if let Some(mut file) = file {
+            if file.extension().is_none() {
+                file.set_extension(CHAIN_EXTENSION);
+            }
+
+            self.rack.save_chain_preset(file).unwrap_or_else(|error| {
+                messagebox(&tr("error-save-chain-preset"), &error.to_string());
+            });
+        }
+    }
+
+    fn load_chain_preset_ui(&mut self, renderer: &mut Renderer) {
+        let file = rfd::FileDialog::new()
+            .set_title(&tr("dialog-load-chain-preset"))
+            .add_filter("PhotoConsequences chain preset", &[CHAIN_EXTENSION])
+            .add_filter("Image chain sidecar", &["json"])
+            .pick_file();
+
+        if let Some(file) = file {
+            self.change_chain(renderer, |rack| Ok(rack.load_chain_preset(file)?))
+                .unwrap_or_else(|error| {
+                    messagebox(&tr("error-load-chain-preset"), &error.to_string());
+                });
+        }
+    }
+
+    /// Appends (or replaces the current chain with) plugins from another project or chain file
+    fn import_chain_ui(&mut self, renderer: &mut Renderer, append: bool) {
+        let file = rfd::FileDialog::new()
+            .set_title(&tr("dialog-import-chain"))
+            .add_filter("PhotoConsequences project", &["viproj", "zip"])
+            .add_filter("PhotoConsequences chain preset", &[CHAIN_EXTENSION])
+            .add_filter("Image chain sidecar", &["json"])
+            .pick_file();
+
+        if let Some(file) = file {
+            let result = state_headless::read_chain(file).and_then(|chain| {
+                self.change_chain(renderer, |rack| {
+                    if append {
+                        rack.append_chain(&chain)?;
+                    } else {
+                        rack.load_chain(&chain)?;
+                    }
+                    Ok(())
+                })
+            });
+
+            result.unwrap_or_else(|error| {
+                messagebox(&tr("error-import-chain"), &error.to_string());
+            });
+        }
+    }
+
+    pub fn export_image(&mut self) {
+        let files = rfd::FileDialog::new()
+            .set_title(&tr("dialog-export-image"))
+            .add_filter("JPEG Image", &["jpg", "jpeg"])
+            .add_filter("PNG Image", &["png"])
+            .add_filter("Animated PNG Image", &["apng"])
+            .add_filter("GIF Image", &["gif"])
+            .add_filter("BMP Image", &["bmp"])
+            .add_filter("ICO Image", &["ico"])
+            .add_filter("TIFF Image", &["tiff", "tif"])
+            .add_filter("WebP Image", &["webp"])
+            .add_filter("AVIF Image", &["avif"])
+            .add_filter("OpenEXR Image", &["exr"])
+            .add_filter("Radiance HDR Image", &["hdr"])
+            .add_filter("TGA Image", &["tga"])
+            .add_filter("MP4 Video", &["mp4"])
+            .add_filter("WebM Video", &["webm"])
+            .save_file();
+
+        if let Some(mut file) = files {
+            if file.extension().is_none() {
+                file.set_extension(&self.settings.export_format);
+            }
+            let animated = self.rack.animation.is_some() && animation::is_animated_format(&file);
+
+            let result = if animated {
+                self.rack.save_animation(file)
+            } else {
+                self.rack.export_image(file)
+            };
+
+            result.unwrap_or_else(|op| {
+                messagebox(
+                    &tr("error-save-image"),
+                    &format!("{}\n{}", tr("error-cannot-save-image"), op),
+                );
+            });
+        }
+    }
+
+    /// Saves image into every output of the export profile
+    pub fn export_profile(&self) {
+        if self.rack.export_profile.targets.is_empty() {
+            messagebox(&tr("error-save-image"), &tr("error-profile-no-outputs"));
+            return;
+        }
+
+        let dir = rfd::FileDialog::new()
+            .set_title(&tr("dialog-export-profile"))
+            .pick_folder();
+
+        if let Some(dir) = dir {
+            self.rack
+                .save_profile(dir, &self.rack.export_profile)
+                .unwrap_or_else(|op| {
+                    messagebox(
+                        &tr("error-save-image"),
+                        &format!("{}\n{}", tr("error-cannot-save-image"), op),
+                    );
+                });
+        }
+    }
+
+    /// Saves difference between the original and processed image with the current gain
+    pub fn export_difference(&self) {
+        let file = rfd::FileDialog::new()
+            .set_title(&tr("dialog-export-difference"))
+            .add_filter("PNG Image", &["png"])
+            .add_filter("TIFF Image", &["tiff", "tif"])
+            .add_filter("BMP Image", &["bmp"])
+            .save_file();
+
+        if let Some(file) = file {
+            self.rack
+                .save_difference(file, self.difference_gain)
+                .unwrap_or_else(|op| {
+                    messagebox(
+                        &tr("error-save-image"),
+                        &format!("{}\n{}", tr("error-cannot-save-image"), op),
+                    );
+                });
+        }
+    }
+
+    /// Saves chain, timings and output hash of the last processing run
+    pub fn export_report(&self) {
+        let file = rfd::FileDialog::new()
+            .set_title(&tr("dialog-export-report"))
+            .add_filter("JSON", &["json"])
+            .add_filter("CSV", &["csv"])
+            .save_file();
+
+        if let Some(file) = file {
+            ProcessingReport::new(&self.rack)
+                .and_then(|report| report.save(file))
+                .unwrap_or_else(|error| {
+                    messagebox(&tr("error-export-report"), &error.to_string());
+                });
+        }
+    }
+
+    fn save_export_profile_ui(&self) {
+        let file = rfd::FileDialog::new()
+            .set_title(&tr("dialog-save-export-profile"))
+            .add_filter("PhotoConsequences export profile", &[PROFILE_EXTENSION])
+            .save_file();
+
+        if let Some(mut file) = file {
+            if file.extension().is_none() {
+                file.set_extension(PROFILE_EXTENSION);
+            }
+
+            self.rack.export_profile.save(file).unwrap_or_else(|error| {
+                messagebox(&tr("error-save-export-profile"), &error.to_string());
+            });
+        }
+    }
+
+    fn load_export_profile_ui(&mut self) {
+        let file = rfd::FileDialog::new()
+            .set_title(&tr("dialog-load-export-profile"))
+            .add_filter("PhotoConsequences export profile", &[PROFILE_EXTENSION])
+            .pick_file();
+
+        if let Some(file) = file {
+            match ExportProfile::load(file) {
+                Ok(profile) => self.rack.export_profile = profile,
+                Err(error) => messagebox(&tr("error-load-export-profile"), &error.to_string()),
+            }
+        }
+    }
+
+    /// Places processed image onto the system clipboard
+    pub fn copy_image(&self) -> anyhow::Result<()> {
+        let image = self
+            .rack
+            .images
+            .last()
+            .ok_or_else(|| anyhow::anyhow!("There is no image to copy"))?;
+
+        let mut clipboard = arboard::Clipboard::new()?;
+        clipboard.set_image(arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+        })?;
+        Ok(())
+    }
+
+    pub fn exit(&mut self, renderer: &mut Renderer) {
+        match self.modal {
+            ModalWindows::Exit => renderer.close_render(),
+            _ => self.modal = ModalWindows::Exit,
+        }
+    }
 
     fn plugin_table_draw(&self, mut body: TableBody) -> Option<Action> {
         let mut action: Option<Action> = None;
+        let mut last_group = None;
         for (idx, name) in self.rack.plugins.iter().enumerate() {
-            if name.instance.is_none() {
-                body.row(20.0, |mut row| {
-                    row.col(|ui| {
-                        ui.label(format!("{}", name.get_path().display()))
-                            .on_hover_text(
-                                "This plugin is not initialized\nEffect configuration is preserved",
-                            );
+            if name.group != last_group {
+                last_group = name.group;
+
+                if let Some((group_id, group)) = name
+                    .group
+                    .and_then(|id| self.rack.groups.get(id).map(|group| (id, group)))
+                {
+                    body.row(20.0, |mut row| {
+                        row.col(|ui| {
+                            let icon = if group.collapsed { "⏵" } else { "⏷" };
+                            if ui.small_button(icon).clicked() {
+                                action = Some(Action::ToggleGroupCollapse(group_id));
+                            }
+                            ui.strong(group.name.as_str()).context_menu(|ui| {
+                                let mut group_name = group.name.clone();
+                                if ui.text_edit_singleline(&mut group_name).changed() {
+                                    action = Some(Action::RenameGroup(group_id, group_name));
+                                }
+                            });
+                        });
+                        row.col(|ui| {
+                            let color = if group.bypass {
+                                Color32::DARK_RED
+                            } else {
+                                ui.visuals().widgets.active.bg_fill
+                            };
+
+                            if ui
+                                .add(egui::Button::new("M").fill(color))
+                                .on_hover_text(tr("plugin-bypass-group"))
+                                .clicked()
+                            {
+                                action = Some(Action::ToggleGroupBypass(group_id));
+                            }
+
+                            let mut wet = group.wet * 100.0;
+                            if ui
+                                .add(
+                                    egui::DragValue::new(&mut wet)
+                                        .clamp_range(0.0..=100.0)
+                                        .suffix("%"),
+                                )
+                                .on_hover_text(tr("plugin-group-wet"))
+                                .changed()
+                            {
+                                action = Some(Action::ChangeGroupWet(group_id, wet / 100.0));
+                            }
+                        });
+                    });
+                }
+            }
+
+            if let Some(group) = name.group.and_then(|id| self.rack.groups.get(id)) {
+                if group.collapsed {
+                    continue;
+                }
+            }
+
+            if !name.is_loaded() {
+                body.row(20.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(format!("{}", name.get_path().display()))
+                            .on_hover_text(format!(
+                                "{}\n{}",
+                                tr("plugin-not-initialized"),
+                                tr("plugin-configuration-preserved")
+                            ));
+                    });
+                    row.col(|ui| {
+                        if ui.button("❎").on_hover_text(tr("plugin-remove")).clicked() {
+                            action = Some(Action::Remove(idx));
+                        }
+                    });
+                });
+                continue;
+            }
+            body.row(20.0, |mut row| {
+                let info = name.instance.as_ref().map(|inst| inst.get_info());
+                let hover = match &info {
+                    Some(info) => format!("{}\n{} ({})\n{}: {:?}\n{}: {}\nI/O: {}/{}\n{}: {}",
+                        tr("plugin-hint"), info.name, info.vendor, tr("plugin-category"), info.category,
+                        tr("plugin-initial-delay"), info.initial_delay, info.inputs, info.outputs,
+                        tr("plugin-f64-mixing"), info.f64_precision),
+                    None => format!("{}\n{}", tr("plugin-hint"), tr("plugin-built-in")),
+                };
+                row.col(|ui| {
+                    if ui.selectable_label(self.selected_plugin == Some(idx), name.get_name())
+                        .on_hover_text(hover)
+                        .clicked() {
+                        action = Some(Action::Select(idx));
+                    }
+                    if let Some(reason) = &name.inactive {
+                        ui.colored_label(Color32::RED, "⛔").on_hover_text(reason);
+                    } else if let Some(warning) = &name.warning {
+                        ui.colored_label(Color32::YELLOW, "⚠").on_hover_text(warning);
+                    }
+                }).context_menu(|ui| {
+                    if ui.button(tr("plugin-duplicate")).clicked() {
+                        action = Some(Action::Duplicate(idx));
+                        ui.close_menu();
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("plugin-group-with-next")).clicked() {
+                            action = Some(Action::GroupWithNext(idx));
+                            ui.close_menu();
+                        }
+                        if name.group.is_some() && ui.button(tr("plugin-ungroup")).clicked() {
+                            action = Some(Action::Ungroup(idx));
+                            ui.close_menu();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("plugin-copy-settings")).clicked() {
+                            action = Some(Action::CopySettings(idx));
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("plugin-paste-settings")).clicked() {
+                            action = Some(Action::PasteSettings(idx));
+                            ui.close_menu();
+                        }
+                    });
+
+                    // grayscale mode processes luminance instead of the input channel
+                    let samples = info.is_some() || name.effect.as_ref().map_or(false, |effect| effect.input() == EffectInput::Samples);
+                    if samples && self.rack.grayscale.is_none() {
+                        ui.label(tr("plugin-input-channel"));
+                        ui.separator();
+                        let mut radio = name.input_channel;
+
+                        if ui.selectable_value(&mut radio, InputChannelType::Hue, "H").clicked() {
+                            action = Some(Action::ChangeInputChannel(idx, radio))
+                        };
+                        if ui.selectable_value(&mut radio, InputChannelType::Saturation, "S").clicked() {
+                            action = Some(Action::ChangeInputChannel(idx, radio))
+                        };
+                        if ui.selectable_value(&mut radio, InputChannelType::Value, "V").clicked() {
+                            action = Some(Action::ChangeInputChannel(idx, radio))
+                        };
+                    }
+
+                    if let Some(info) = &info {
+                    ui.label(tr("plugin-output-channel"));
+                    ui.separator();
+                    let mut output = name.output_channel;
+                    let prefix = match output {
+                        0 => format!("{} ", tr("plugin-left-mono")),
+                        1 => format!("{} ", tr("plugin-right")),
+                        _ => String::new(),
+                    };
+
+                    ui.add_enabled_ui(!name.mix_outputs, |ui| {
+                        if info.outputs > 0 && ui.add(egui::Slider::new(&mut output, 0..=(info.outputs - 1) as usize).prefix(prefix)).changed() {
+                            action = Some(Action::ChangeOutputChannel(idx, output));
+                        }
+                    });
+                    if info.outputs > 1 {
+                        let mut mix = name.mix_outputs;
+                        if ui.checkbox(&mut mix, tr("plugin-mix-outputs")).on_hover_text(tr("plugin-mix-outputs-hint")).changed() {
+                            action = Some(Action::ChangeMixOutputs(idx, mix));
+                        }
+                    }
+                    if info.outputs > 1 && !name.mix_outputs && self.rack.grayscale.is_none() {
+                        for output in (0..info.outputs as usize).filter(|output| *output != name.output_channel) {
+                            ui.horizontal(|ui| {
+                                ui.label(tr_args("plugin-output-to", &[("output", &output.to_string())])).on_hover_text(tr("plugin-output-to-hint"));
+                                let current = name.output_routes.iter().find(|route| route.output == output).map(|route| route.channel);
+                                let mut selected = current;
+                                ui.selectable_value(&mut selected, None, tr("plugin-off"));
+                                ui.selectable_value(&mut selected, Some(InputChannelType::Hue), "H");
+                                ui.selectable_value(&mut selected, Some(InputChannelType::Saturation), "S");
+                                ui.selectable_value(&mut selected, Some(InputChannelType::Value), "V");
+                                if selected != current {
+                                    let mut routes: Vec<OutputRoute> = name.output_routes.iter().copied().filter(|route| route.output != output).collect();
+                                    if let Some(channel) = selected {
+                                        routes.push(OutputRoute { output, channel });
+                                    }
+                                    action = Some(Action::ChangeOutputRoutes(idx, routes));
+                                }
+                            });
+                        }
+                    }
+                    }
+
+                    if !name.program_name.is_empty() {
+                        ui.label(tr_args("plugin-program", &[("name", &name.program_name)]));
+                    }
+
+                    if !name.cached_parameters().is_empty() {
+                        ui.label(tr("plugin-parameters"));
+                        ui.separator();
+                        let search_id = egui::Id::new(("parameter_search", idx));
+                        let mut search = ui.data().get_temp::<String>(search_id).unwrap_or_default();
+                        if ui.add(egui::TextEdit::singleline(&mut search).hint_text(tr("plugin-search-parameters"))).changed() {
+                            ui.data().insert_temp(search_id, search.clone());
+                        }
+                        let search = search.to_lowercase();
+                        egui::ScrollArea::vertical().id_source(search_id).max_height(300.0).show(ui, |ui| {
+                            for (index, (parameter, value)) in name.cached_parameters().iter().enumerate() {
+                                let index = index as i32;
+                                if !parameter.to_lowercase().contains(&search) {
+                                    continue;
+                                }
+                                ui.horizontal(|ui| {
+                                    let control = name.midi_control(index);
+                                    let learning = self.midi_learn == Some((idx, index));
+                                    let text = match (learning, control) {
+                                        (true, _) => String::from("🎹 ..."),
+                                        (false, Some(control)) => format!("🎹 {}", control),
+                                        (false, None) => String::from("🎹"),
+                                    };
+                                    let learn = ui.selectable_label(learning, text)
+                                        .on_hover_text(tr("plugin-midi-learn-hint"));
+                                    if learn.clicked() {
+                                        action = Some(Action::LearnMidi(idx, index));
+                                    }
+                                    if control.is_some() && learn.secondary_clicked() {
+                                        action = Some(Action::ChangeMidiControl(idx, index, None));
+                                    }
+
+                                    let mut value = *value;
+                                    if ui.add(egui::Slider::new(&mut value, 0.0..=1.0).text(parameter.as_str())).changed() {
+                                        action = Some(Action::ChangeParameter(idx, index, value));
+                                    }
+                                });
+                            }
+                        });
+                    }
+
+                    if !name.automation.is_empty() {
+                        ui.horizontal(|ui| {
+                            let count = name.automation.len().to_string();
+                            ui.label(tr_args("plugin-automation", &[("count", &count)]))
+                                .on_hover_text(tr("plugin-automation-hint"));
+                            if ui.button(tr("plugin-clear-automation")).clicked() {
+                                action = Some(Action::ChangeAutomation(idx, Vec::new()));
+                            }
+                        });
+                    }
+
+                    let mut wet = name.wet * 100.0;
+                    ui.label(tr("plugin-wet"));
+                    ui.separator();
+                    if ui.add(egui::Slider::new(&mut wet, 0.0..=100.0).suffix("%")).changed() {
+                        action = Some(Action::ChangeWet(idx, wet / 100.0));
+                    }
+
+                    if name.instance.is_some() {
+                        ui.label(tr("plugin-sample-rate"));
+                        ui.separator();
+                        let mut custom = name.sample_rate.is_some();
+                        if ui.checkbox(&mut custom, tr("plugin-override-rate")).changed() {
+                            action = Some(Action::ChangeSampleRate(idx, custom.then(|| 44100.0)));
+                        }
+                        if let Some(mut sample_rate) = name.sample_rate {
+                            if ui.add(egui::Slider::new(&mut sample_rate, 1102.0..=768000.0).suffix("Hz")).changed() {
+                                action = Some(Action::ChangeSampleRate(idx, Some(sample_rate)));
+                            }
+                        }
+                    }
+
+                    let mut iterations = name.iterations;
+                    ui.label(tr("plugin-iterations"));
+                    ui.separator();
+                    if ui.add(egui::Slider::new(&mut iterations, 1..=16)).changed() {
+                        action = Some(Action::ChangeIterations(idx, iterations));
+                    }
+
+                    let mut sample_offset = name.sample_offset;
+                    ui.label(tr("plugin-sample-offset"));
+                    ui.separator();
+                    if ui.add(egui::Slider::new(&mut sample_offset, -4096..=4096).suffix("px")).on_hover_text(tr("plugin-sample-offset-hint")).changed() {
+                        action = Some(Action::ChangeSampleOffset(idx, sample_offset));
+                    }
+
+                    if self.rack.interlaced {
+                        ui.label(tr("plugin-field"));
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            if ui.selectable_label(name.field.is_none(), tr("plugin-field-both")).clicked() {
+                                action = Some(Action::ChangeField(idx, None));
+                            }
+                            if ui.selectable_label(name.field == Some(Field::Even), tr("plugin-field-even")).clicked() {
+                                action = Some(Action::ChangeField(idx, Some(Field::Even)));
+                            }
+                            if ui.selectable_label(name.field == Some(Field::Odd), tr("plugin-field-odd")).clicked() {
+                                action = Some(Action::ChangeField(idx, Some(Field::Odd)));
+                            }
+                        });
+                    }
+
+                    ui.label(tr("plugin-tile-pattern"));
+                    ui.separator();
+                    let pattern = name.tile_pattern;
+                    let nth = match pattern {
+                        TilePattern::EveryNth(n) => n,
+                        _ => 2,
+                    };
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(pattern == TilePattern::All, tr("plugin-tiles-all")).clicked() {
+                            action = Some(Action::ChangeTilePattern(idx, TilePattern::All));
+                        }
+                        if ui.selectable_label(matches!(pattern, TilePattern::EveryNth(_)), tr("plugin-tiles-every-nth")).clicked() {
+                            action = Some(Action::ChangeTilePattern(idx, TilePattern::EveryNth(nth)));
+                        }
+                        if ui.selectable_label(pattern == TilePattern::Checkerboard, tr("plugin-tiles-checkerboard")).clicked() {
+                            action = Some(Action::ChangeTilePattern(idx, TilePattern::Checkerboard));
+                        }
+                        if ui.selectable_label(pattern == TilePattern::EvenRows, tr("plugin-tiles-even-rows")).clicked() {
+                            action = Some(Action::ChangeTilePattern(idx, TilePattern::EvenRows));
+                        }
+                    });
+
+                    if let TilePattern::EveryNth(mut n) = pattern {
+                        if ui.add(egui::Slider::new(&mut n, 2..=16).prefix("N: ")).changed() {
+                            action = Some(Action::ChangeTilePattern(idx, TilePattern::EveryNth(n)));
+                        }
+                    }
+
+                    if pattern != TilePattern::All {
+                        let mut tile_size = name.tile_size;
+                        if ui.add(egui::Slider::new(&mut tile_size, 8..=1024).suffix("px").logarithmic(true)).changed() {
+                            action = Some(Action::ChangeTileSize(idx, tile_size));
+                        }
+                    }
+
+                    ui.label(tr("plugin-jitter"));
+                    ui.separator();
+                    let mut jitter = name.jitter * 100.0;
+                    if ui.add(egui::Slider::new(&mut jitter, 0.0..=100.0).suffix("%")).on_hover_text(tr("plugin-jitter-hint")).changed() {
+                        action = Some(Action::ChangeJitter(idx, jitter / 100.0));
+                    }
+
+                    if name.jitter > 0.0 {
+                        ui.horizontal(|ui| {
+                            let selected = match name.jitter_parameters.len() {
+                                0 => tr("plugin-all-parameters"),
+                                count => tr_args("plugin-parameter-count", &[("count", &count.to_string())]),
+                            };
+                            ui.menu_button(selected, |ui| {
+                                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                                    for index in 0..name.parameter_count() {
+                                        let mut checked = name.jitter_parameters.contains(&index);
+                                        if ui.checkbox(&mut checked, name.parameter_name(index)).changed() {
+                                            action = Some(Action::ToggleJitterParameter(idx, index));
+                                        }
+                                    }
+                                });
+                            });
+                            if ui.button(tr("plugin-reseed")).clicked() {
+                                action = Some(Action::ChangeJitterSeed(idx, rand::random()));
+                            }
+                        });
+                    }
+
+                    ui.label(tr("plugin-region"));
+                    ui.separator();
+                    let mut limited = name.region.is_some();
+                    if ui.checkbox(&mut limited, tr("plugin-limit-region")).changed() {
+                        action = Some(Action::ChangeRegion(idx, if limited { Some(Region::default()) } else { None }));
+                    }
+
+                    if let Some(mut region) = name.region {
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("region-top")).clicked() {
+                                action = Some(Action::ChangeRegion(idx, Some(Region::top_half())));
+                            }
+                            if ui.button(tr("region-bottom")).clicked() {
+                                action = Some(Action::ChangeRegion(idx, Some(Region::bottom_half())));
+                            }
+                            if ui.button(tr("region-left")).clicked() {
+                                action = Some(Action::ChangeRegion(idx, Some(Region::left_half())));
+                            }
+                            if ui.button(tr("region-right")).clicked() {
+                                action = Some(Action::ChangeRegion(idx, Some(Region::right_half())));
+                            }
+                        });
+
+                        let mut changed = false;
+                        changed |= ui.add(egui::Slider::new(&mut region.left, 0.0..=1.0).prefix(format!("{}: ", tr("region-left")))).changed();
+                        changed |= ui.add(egui::Slider::new(&mut region.right, 0.0..=1.0).prefix(format!("{}: ", tr("region-right")))).changed();
+                        changed |= ui.add(egui::Slider::new(&mut region.top, 0.0..=1.0).prefix(format!("{}: ", tr("region-top")))).changed();
+                        changed |= ui.add(egui::Slider::new(&mut region.bottom, 0.0..=1.0).prefix(format!("{}: ", tr("region-bottom")))).changed();
+                        if changed {
+                            action = Some(Action::ChangeRegion(idx, Some(region)));
+                        }
+                    }
+
+                    ui.label(tr("plugin-mask"));
+                    ui.separator();
+                    if let Some(mask) = mask_ui(ui, name.mask.as_ref()) {
+                        action = Some(Action::ChangeMask(idx, mask));
+                    }
+                });
+                
+                row.col(|ui| {
+                        ui.add_enabled_ui(self.rack.is_finished(), |ui| {
+                            if ui.button("❎").on_hover_text(tr("plugin-remove")).clicked() {
+                                action = Some(Action::Remove(idx));
+                            }
+    
+                            let color = if name.bypass {
+                                Color32::DARK_RED
+                            } else {
+                                ui.visuals().widgets.active.bg_fill
+                            };
+    
+                            if ui.add(egui::Button::new("M").fill(color)).on_hover_text(tr("plugin-bypass")).clicked() {
+                                action = Some(Action::Bypass(idx));
+                            }
+
+                            let color = if name.solo {
+                                Color32::from_rgb(160, 140, 0)
+                            } else if self.rack.is_muted(idx) {
+                                Color32::DARK_GRAY
+                            } else {
+                                ui.visuals().widgets.active.bg_fill
+                            };
+
+                            if ui.add(egui::Button::new("S").fill(color)).on_hover_text(tr("plugin-solo")).clicked() {
+                                action = Some(Action::Solo(idx));
+                            }
+                        });
+
+                        if name.instance.is_some() && ui.button("🔧").on_hover_text(tr("plugin-open-editor")).clicked() {
+                            action = Some(Action::OpenEditor(idx));
+                        }
+                });
+            });
+        }
+        action
+    }
+
+    fn routing_ui(&mut self, ui: &mut egui::Ui) {
+        let plugin_count = self.rack.plugins.len();
+        let mut graph = self.rack.graph.clone();
+        let mut use_graph = graph.is_some();
+        if ui
+            .checkbox(&mut use_graph, tr("graph-use"))
+            .on_hover_text(tr("graph-use-hint"))
+            .changed()
+        {
+            graph = if use_graph {
+                Some(ProcessingGraph::from_chain(plugin_count))
+            } else {
+                None
+            };
+        }
+
+        if ui
+            .button(tr("graph-parallel-chain"))
+            .on_hover_text(tr("graph-parallel-chain-hint"))
+            .clicked()
+        {
+            graph
+                .get_or_insert_with(|| ProcessingGraph::from_chain(plugin_count))
+                .add_parallel_chain();
+        }
+        if ui
+            .button(tr("graph-channel-chains"))
+            .on_hover_text(tr("graph-channel-chains-hint"))
+            .clicked()
+        {
+            graph
+                .get_or_insert_with(|| ProcessingGraph::from_chain(plugin_count))
+                .add_channel_chains();
+        }
+
+        let names: Vec<String> = self
+            .rack
+            .plugins
+            .iter()
+            .map(|plugin| plugin.get_name())
+            .collect();
+
+        if let Some(graph) = graph.as_mut() {
+            let unrouted: Vec<usize> = (0..names.len()).filter(|id| !graph.contains(*id)).collect();
+            graph_nodes_ui(ui, &mut graph.nodes, &names, &unrouted);
+        }
+
+        if graph != self.rack.graph {
+            self.apply_action(Action::ChangeGraph(graph));
+        }
+    }
+
+    fn inspector_ui(&mut self, ui: &mut egui::Ui) {
+        let plugin = match self
+            .selected_plugin
+            .and_then(|id| self.rack.plugins.get(id))
+        {
+            Some(plugin) => plugin,
+            None => return,
+        };
+
+        ui.horizontal(|ui| {
+            ui.heading(plugin.get_name());
+            if ui
+                .small_button("❎")
+                .on_hover_text(tr("inspector-close"))
+                .clicked()
+            {
+                self.selected_plugin = None;
+            }
+        });
+        ui.label(format!("{}", plugin.get_path().display()));
+        ui.separator();
+
+        if plugin.effect.is_some() {
+            ui.label(tr("plugin-built-in"));
+            return;
+        }
+        let instance = match &plugin.instance {
+            Some(instance) => instance,
+            None => {
+                ui.label(tr("plugin-not-initialized"));
+                return;
+            }
+        };
+
+        let info = instance.get_info();
+        egui::Grid::new("inspector_info")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(tr("inspector-vendor"));
+                ui.label(&info.vendor);
+                ui.end_row();
+                ui.label(tr("inspector-product"));
+                ui.label(&info.name);
+                ui.end_row();
+                ui.label(tr("inspector-version"));
+                ui.label(info.version.to_string());
+                ui.end_row();
+                ui.label(tr("inspector-unique-id"));
+                ui.label(format!("{} ({:#010X})", info.unique_id, info.unique_id));
+                ui.end_row();
+                ui.label(tr("plugin-category"));
+                ui.label(format!("{:?}", info.category));
+                ui.end_row();
+                ui.label(tr("inspector-audio-io"));
+                ui.label(format!("{}/{}", info.inputs, info.outputs));
+                ui.end_row();
+                ui.label(tr("inspector-midi-io"));
+                ui.label(format!("{}/{}", info.midi_inputs, info.midi_outputs));
+                ui.end_row();
+                ui.label(tr("inspector-parameters"));
+                ui.label(info.parameters.to_string());
+                ui.end_row();
+                ui.label(tr("inspector-presets"));
+                ui.label(info.presets.to_string());
+                ui.end_row();
+                ui.label(tr("inspector-latency"));
+                ui.label(tr_args(
+                    "inspector-samples",
+                    &[("count", &info.initial_delay.to_string())],
+                ));
+                ui.end_row();
+                ui.label(tr("inspector-chunks"));
+                ui.label(info.preset_chunks.to_string());
+                ui.end_row();
+                ui.label(tr("inspector-f64"));
+                ui.label(info.f64_precision.to_string());
+                ui.end_row();
+            });
+
+        ui.separator();
+        ui.label(tr("inspector-can-do"));
+        egui::Grid::new("inspector_can_do")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, supported) in plugin.capabilities() {
+                    ui.label(name);
+                    ui.label(tr(match supported {
+                        Supported::Yes => "inspector-yes",
+                        Supported::Maybe => "inspector-maybe",
+                        Supported::No => "inspector-no",
+                        Supported::Custom(_) => "inspector-custom",
+                    }));
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn keyframes_ui(&mut self, ui: &mut egui::Ui) {
+        let frame = match &self.rack.animation {
+            Some(animation) => animation.current,
+            None => return,
+        };
+        let (id, plugin) = match self
+            .selected_plugin
+            .and_then(|id| Some((id, self.rack.plugins.get(id)?)))
+        {
+            Some(selected) => selected,
+            None => return,
+        };
+        let mut keyframes = plugin.keyframes.clone();
+
+        let target_name = |plugin: &PluginRackInstance, target: KeyframeTarget| match target {
+            KeyframeTarget::Wet => tr("keyframes-wet"),
+            KeyframeTarget::Parameter(index) if plugin.is_loaded() => plugin.parameter_name(index),
+            KeyframeTarget::Parameter(index) => {
+                tr_args("keyframes-parameter", &[("index", &index.to_string())])
+            }
+        };
+        let parameters = plugin.parameter_count();
+
+        ui.separator();
+        ui.label(tr("keyframes-title"));
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("keyframe_target")
+                .selected_text(target_name(plugin, self.keyframe_target))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.keyframe_target,
+                        KeyframeTarget::Wet,
+                        tr("keyframes-wet"),
+                    );
+                    for index in 0..parameters {
+                        let target = KeyframeTarget::Parameter(index);
+                        ui.selectable_value(
+                            &mut self.keyframe_target,
+                            target,
+                            target_name(plugin, target),
+                        );
+                    }
+                });
+
+            if ui
+                .button(tr_args(
+                    "keyframes-add",
+                    &[("frame", &(frame + 1).to_string())],
+                ))
+                .on_hover_text(tr("keyframes-add-hint"))
+                .clicked()
+            {
+                let target = self.keyframe_target;
+                let value = plugin.target_value(target);
+                keyframes.retain(|key| !(key.target == target && key.frame == frame));
+                keyframes.push(Keyframe {
+                    frame,
+                    target,
+                    value,
+                });
+            }
+        });
+
+        let mut remove = None;
+        egui::Grid::new("keyframes").striped(true).show(ui, |ui| {
+            for (index, keyframe) in keyframes.iter_mut().enumerate() {
+                ui.label(match keyframe.target {
+                    KeyframeTarget::Wet => tr("keyframes-wet"),
+                    KeyframeTarget::Parameter(index) => format!("#{}", index),
+                });
+                ui.add(
+                    egui::DragValue::new(&mut keyframe.frame)
+                        .prefix(format!("{}: ", tr("keyframes-frame"))),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut keyframe.value)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.01),
+                );
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(index) = remove {
+            keyframes.remove(index);
+        }
+        if keyframes != plugin.keyframes {
+            self.apply_action(Action::ChangeKeyframes(id, keyframes));
+        }
+    }
+
+    fn init(&mut self, renderer: &mut Renderer) {
+        renderer.cleanup_image();
+        self.close_all_editors(renderer);
+        renderer.destroy_texture();
+        self.rack = PluginRack::new();
+        self.save_path = None;
+        self.snapshots = [None, None];
+        self.active_snapshot = 0;
+        self.selected_plugin = None;
+        self.undo_history.clear();
+        self.redo_history.clear();
+    }
+
+    /// Moves the active document out of the state, leaving an empty one
+    fn take_document(&mut self, renderer: &mut Renderer) -> Tab {
+        self.close_all_editors(renderer);
+        renderer.cleanup_image();
+        renderer.destroy_texture();
+        self.audition = None;
+        Tab {
+            rack: std::mem::replace(&mut self.rack, PluginRack::new()),
+            save_path: self.save_path.take(),
+            snapshots: std::mem::take(&mut self.snapshots),
+            active_snapshot: std::mem::take(&mut self.active_snapshot),
+            selected_plugin: self.selected_plugin.take(),
+            undo_history: std::mem::take(&mut self.undo_history),
+            redo_history: std::mem::take(&mut self.redo_history),
+        }
+    }
+
+    fn restore_document(&mut self, tab: Tab) {
+        self.rack = tab.rack;
+        self.save_path = tab.save_path;
+        self.snapshots = tab.snapshots;
+        self.active_snapshot = tab.active_snapshot;
+        self.selected_plugin = tab.selected_plugin;
+        self.undo_history = tab.undo_history;
+        self.redo_history = tab.redo_history;
+    }
+
+    pub fn new_tab(&mut self, renderer: &mut Renderer) {
+        self.tabs[self.active_tab] = Some(self.take_document(renderer));
+        self.tabs.push(None);
+        self.active_tab = self.tabs.len() - 1;
+    }
+
+    /// Makes document of the tab active, processing of background tabs is paused
+    pub fn switch_tab(&mut self, renderer: &mut Renderer, id: usize) {
+        if id == self.active_tab || id >= self.tabs.len() {
+            return;
+        }
+
+        self.tabs[self.active_tab] = Some(self.take_document(renderer));
+        if let Some(tab) = self.tabs[id].take() {
+            self.restore_document(tab);
+        }
+        self.active_tab = id;
+    }
+
+    pub fn close_tab(&mut self, renderer: &mut Renderer, id: usize) {
+        if self.tabs.len() < 2 || id >= self.tabs.len() {
+            return;
+        }
+
+        if id == self.active_tab {
+            let next = if id + 1 < self.tabs.len() {
+                id + 1
+            } else {
+                id - 1
+            };
+            self.switch_tab(renderer, next);
+        }
+        self.tabs.remove(id);
+        if self.active_tab > id {
+            self.active_tab -= 1;
+        }
+    }
+
+    /// Copies plugin chain between the active document and background tab
+    fn copy_chain(
+        &mut self,
+        renderer: &mut Renderer,
+        id: usize,
+        to_tab: bool,
+    ) -> anyhow::Result<()> {
+        if !to_tab {
+            self.close_all_editors(renderer);
+        }
+
+        let tab = match self.tabs.get_mut(id).and_then(Option::as_mut) {
+            Some(tab) => tab,
+            None => return Ok(()),
+        };
+
+        if to_tab {
+            tab.rack.load_chain(&self.rack.save_chain()?)?;
+            tab.selected_plugin = None;
+        } else {
+            self.rack.load_chain(&tab.rack.save_chain()?)?;
+            self.selected_plugin = None;
+        }
+        Ok(())
+    }
+
+    fn tab_bar(&mut self, ui: &mut egui::Ui, renderer: &mut Renderer) {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            for (id, tab) in self.tabs.iter().enumerate() {
+                let title = match tab {
+                    Some(tab) => tab_title(&tab.rack, &tab.save_path),
+                    None => tab_title(&self.rack, &self.save_path),
+                };
+
+                let response = ui.selectable_label(id == self.active_tab, title);
+                if response.clicked() {
+                    action = Some(TabAction::Switch(id));
+                }
+                if tab.is_some() {
+                    response.context_menu(|ui| {
+                        if ui.button(tr("tabs-copy-chain-here")).clicked() {
+                            action = Some(TabAction::CopyChainTo(id));
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("tabs-use-chain")).clicked() {
+                            action = Some(TabAction::CopyChainFrom(id));
+                            ui.close_menu();
+                        }
+                    });
+                }
+                if self.tabs.len() > 1 && ui.small_button("✖").clicked() {
+                    action = Some(TabAction::Close(id));
+                }
+                ui.separator();
+            }
+            if ui
+                .small_button("➕")
+                .on_hover_text(tr("tabs-new"))
+                .clicked()
+            {
+                action = Some(TabAction::New);
+            }
+        });
+
+        let copy = match action {
+            Some(TabAction::New) => return self.new_tab(renderer),
+            Some(TabAction::Switch(id)) => return self.switch_tab(renderer, id),
+            Some(TabAction::Close(id)) => return self.close_tab(renderer, id),
+            Some(TabAction::CopyChainTo(id)) => self.copy_chain(renderer, id, true),
+            Some(TabAction::CopyChainFrom(id)) => self.copy_chain(renderer, id, false),
+            None => return,
+        };
+        copy.unwrap_or_else(|error| {
+            messagebox(&tr("error-copy-chain"), &error.to_string());
+        });
+    }
+
+    fn handle_shortcuts(&mut self, context: &Context, renderer: &mut Renderer) {
+        if !matches!(self.modal, ModalWindows::None) || context.wants_keyboard_input() {
+            return;
+        }
+
+        let commands = keymap::pressed_commands(&context.input(), &self.settings.keymap);
+        for command in commands {
+            self.run_command(command, renderer);
+        }
+    }
+
+    fn run_command(&mut self, command: Command, renderer: &mut Renderer) {
+        let editable =
+            !self.rack.images.is_empty() && self.rack.is_finished() && !self.rack.is_preview();
+
+        match command {
+            Command::Undo if self.can_undo() => self.undo(renderer),
+            Command::Redo if self.can_redo() => self.redo(renderer),
+            Command::Process if self.rack.is_finished() && !self.rack.images.is_empty() => {
+                self.process();
+            }
+            Command::Stop if !self.rack.is_finished() => {
+                renderer.cleanup_image();
+                self.stop_process();
+            }
+            Command::ToggleEyedropper => self.eyedropper = !self.eyedropper,
+            Command::NextCompareMode => {
+                self.compare = self.compare.next();
+                self.blink_original = false;
+                renderer.cleanup_image();
+            }
+            Command::Blink if self.compare == CompareMode::Blink => {
+                self.blink_original = !self.blink_original;
+            }
+            Command::BypassSelected => {
+                let selected = self.selected_plugin;
+                if let Some(plugin) = selected.and_then(|id| self.rack.plugins.get_mut(id)) {
+                    plugin.bypass = !plugin.bypass;
+                }
+            }
+            Command::ZoomFit => self.zoom_request = Some(CanvasZoom::Fit),
+            Command::ZoomActualPixels => self.zoom_request = Some(CanvasZoom::ActualPixels),
+            Command::ZoomSelection => self.zoom_request = Some(CanvasZoom::Selection),
+            Command::SaveProject if editable => self.save_project_ui(),
+            Command::ExportImage if editable => self.export_image(),
+            Command::Preferences => self.modal = ModalWindows::Preferences,
+            Command::CommandPalette => {
+                self.palette_open = true;
+                self.palette_query.clear();
+                self.palette_selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Every action available from the command palette
+    fn palette_items(&self) -> Vec<PaletteItem> {
+        let mut items: Vec<PaletteItem> = Command::ALL
+            .into_iter()
+            .filter(|command| *command != Command::CommandPalette)
+            .map(|command| PaletteItem {
+                label: command.name(),
+                entry: PaletteEntry::Command(command),
+            })
+            .collect();
+
+        items.extend(
+            self.rack
+                .plugins
+                .iter()
+                .enumerate()
+                .map(|(id, plugin)| PaletteItem {
+                    label: tr_args("palette-toggle-bypass", &[("name", &plugin.get_name())]),
+                    entry: PaletteEntry::Bypass(id),
+                }),
+        );
+        items.extend(self.plugin_library.iter().map(|path| PaletteItem {
+            label: tr_args(
+                "palette-add-plugin",
+                &[(
+                    "name",
+                    &path.file_stem().unwrap_or_default().to_string_lossy(),
+                )],
+            ),
+            entry: PaletteEntry::AddPlugin(path.clone()),
+        }));
+        items
+    }
+
+    /// Fuzzy search over every action, arrows move the selection and Enter runs it
+    fn command_palette(&mut self, context: &Context, renderer: &mut Renderer) {
+        let items = palette::search(self.palette_items(), &self.palette_query);
+        let mut run = None;
+
+        let (up, down, enter, escape) = {
+            let input = context.input();
+            (
+                input.key_pressed(egui::Key::ArrowUp),
+                input.key_pressed(egui::Key::ArrowDown),
+                input.key_pressed(egui::Key::Enter),
+                input.key_pressed(egui::Key::Escape),
+            )
+        };
+        if down && self.palette_selected + 1 < items.len() {
+            self.palette_selected += 1;
+        }
+        if up {
+            self.palette_selected = self.palette_selected.saturating_sub(1);
+        }
+        self.palette_selected = self.palette_selected.min(items.len().saturating_sub(1));
+        if enter && !items.is_empty() {
+            run = Some(self.palette_selected);
+        }
+
+        egui::Window::new(tr("command-command-palette"))
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(false)
+            .anchor(Align2::CENTER_TOP, vec2(0.0, 48.0))
+            .show(context, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.palette_query)
+                        .hint_text(tr("palette-hint"))
+                        .desired_width(400.0),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.palette_selected = 0;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for (id, item) in items.iter().enumerate() {
+                            let selected = id == self.palette_selected;
+                            let label = ui.selectable_label(selected, item.label.as_str());
+                            if selected && (up || down) {
+                                label.scroll_to_me(None);
+                            }
+                            if label.clicked() {
+                                run = Some(id);
+                            }
+                        }
+                    });
+            });
+
+        if escape {
+            self.palette_open = false;
+        }
+        if let Some(id) = run {
+            self.palette_open = false;
+            match &items[id].entry {
+                PaletteEntry::Command(command) => self.run_command(*command, renderer),
+                PaletteEntry::Bypass(id) => {
+                    self.rack.plugins[*id].bypass = !self.rack.plugins[*id].bypass;
+                }
+                PaletteEntry::AddPlugin(path) => {
+                    self.add_plugin(path.clone()).unwrap_or_else(|op| {
+                        messagebox(&tr("error-plugin-loading"), &op.to_string());
+                    });
+                }
+            }
+        }
+    }
+
+    fn exit_window(&mut self, context: &Context) -> DialogVariant {
+        if self.rack.images.is_empty() && self.rack.plugins.is_empty() {
+            return DialogVariant::No;
+        }
+        let mut res = DialogVariant::None;
+        egui::Window::new(tr("exit-title"))
+            .collapsible(false)
+            .auto_sized()
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                if self.save_path.is_some() {
+                    let path = self.save_path.as_ref().unwrap().display().to_string();
+                    ui.label(tr_args("exit-save-file", &[("path", &path)]));
+                } else {
+                    ui.label(tr("exit-save-project"));
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr("exit-yes")).clicked() {
+                        res = DialogVariant::Yes;
+                    }
+                    if ui.button(tr("exit-no")).clicked() {
+                        res = DialogVariant::No;
+                    }
+                    if ui.button(tr("exit-cancel")).clicked() {
+                        self.modal = ModalWindows::None;
+                        res = DialogVariant::Cancel;
+                    }
+                });
+            });
+
+        res
+    }
+
+    fn about_window(&mut self, context: &Context) {
+        egui::Window::new(tr("menu-about"))
+            .collapsible(false)
+            .auto_sized()
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                let text = RichText::new("PhotoConsequences").heading();
+                ui.label(text);
+                ui.label(tr_args("about-version", &[("version", crate::VERSION)]));
+                ui.label(tr("about-description"));
+                ui.label(tr("about-trademark"));
+
+                if ui.button(tr("dialog-ok")).clicked() {
+                    self.modal = ModalWindows::None;
+                }
+            });
+    }
+
+    fn open_url_window(&mut self, context: &Context, renderer: &mut Renderer) {
+        egui::Window::new(tr("url-title"))
+            .collapsible(false)
+            .auto_sized()
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.image_url)
+                        .hint_text("https://example.com/image.png"),
+                );
+
+                ui.horizontal(|ui| {
+                    let downloading = self.download.is_some();
+                    if ui
+                        .add_enabled(!downloading, egui::Button::new(tr("dialog-open")))
+                        .clicked()
+                    {
+                        let url = self.image_url.trim().to_string();
+                        self.download = Some(std::thread::spawn(move || download_image(&url)));
+                    }
+
+                    if ui.button(tr("dialog-cancel")).clicked() {
+                        // download thread is left to time out, its result is dropped
+                        self.download = None;
+                        self.modal = ModalWindows::None;
+                    }
+
+                    if downloading {
+                        ui.spinner();
+                        ui.label(tr("url-downloading"));
+                    }
+                });
+
+                if self.download.is_some() {
+                    match self.finish_download(renderer) {
+                        Ok(true) => self.modal = ModalWindows::None,
+                        Ok(false) => {}
+                        Err(error) => messagebox(&tr("error-open-url"), &error.to_string()),
+                    }
+                }
+            });
+    }
+
+    fn capture_screen_window(&mut self, context: &Context, renderer: &mut Renderer) {
+        egui::Window::new(tr("capture-title"))
+            .collapsible(false)
+            .auto_sized()
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                ui.checkbox(&mut self.capture_whole_screen, tr("capture-whole-screen"));
+
+                ui.add_enabled_ui(!self.capture_whole_screen, |ui| {
+                    egui::Grid::new("capture_region").show(ui, |ui| {
+                        ui.label(tr("capture-position"));
+                        ui.add(egui::DragValue::new(&mut self.capture_region[0]).prefix("x: "));
+                        ui.add(egui::DragValue::new(&mut self.capture_region[1]).prefix("y: "));
+                        ui.end_row();
+                        ui.label(tr("capture-size"));
+                        ui.add(
+                            egui::DragValue::new(&mut self.capture_region[2])
+                                .clamp_range(1..=u16::MAX as u32),
+                        );
+                        ui.add(
+                            egui::DragValue::new(&mut self.capture_region[3])
+                                .clamp_range(1..=u16::MAX as u32),
+                        );
+                        ui.end_row();
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr("capture-capture")).clicked() {
+                        let region = if self.capture_whole_screen {
+                            None
+                        } else {
+                            Some(self.capture_region)
+                        };
+
+                        match self.capture_screen(renderer, region) {
+                            Ok(()) => self.modal = ModalWindows::None,
+                            Err(error) => {
+                                messagebox(&tr("error-capture-screen"), &error.to_string())
+                            }
+                        }
+                    }
+
+                    if ui.button(tr("dialog-cancel")).clicked() {
+                        self.modal = ModalWindows::None;
+                    }
+                });
+            });
+    }
+
+    fn open_video_window(&mut self, context: &Context, renderer: &mut Renderer) {
+        let path = match &self.video_path {
+            Some(path) => path.clone(),
+            None => {
+                self.modal = ModalWindows::None;
+                return;
+            }
+        };
+
+        egui::Window::new(tr("video-title"))
+            .collapsible(false)
+            .auto_sized()
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                ui.label(format!("{}", path.display()));
+                ui.add(
+                    egui::DragValue::new(&mut self.video_range.0)
+                        .prefix(format!("{}: ", tr("video-first-frame"))),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.video_range.1)
+                        .clamp_range(1..=10000)
+                        .prefix(format!("{}: ", tr("video-frame-count"))),
+                );
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr("dialog-open")).clicked() {
+                        renderer.cleanup_image();
+                        match self
+                            .rack
+                            .load_video(&path, self.video_range.0, self.video_range.1)
+                        {
+                            Ok(()) => self.modal = ModalWindows::None,
+                            Err(error) => messagebox(&tr("error-open-video"), &error.to_string()),
+                        }
+                    }
+
+                    if ui.button(tr("dialog-cancel")).clicked() {
+                        self.modal = ModalWindows::None;
+                    }
+                });
+            });
+    }
+
+    fn open_svg_window(&mut self, context: &Context, renderer: &mut Renderer) {
+        let path = match &self.svg_path {
+            Some(path) => path.clone(),
+            None => {
+                self.modal = ModalWindows::None;
+                return;
+            }
+        };
+
+        egui::Window::new(tr("svg-title"))
+            .collapsible(false)
+            .auto_sized()
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                ui.label(format!("{}", path.display()));
+                ui.add(
+                    egui::DragValue::new(&mut self.svg_width)
+                        .clamp_range(16..=16384)
+                        .prefix(format!("{}: ", tr("svg-width")))
+                        .suffix(" px"),
+                );
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr("dialog-open")).clicked() {
+                        renderer.cleanup_image();
+                        match self.rack.load_svg(&path, self.svg_width) {
+                            Ok(()) => self.modal = ModalWindows::None,
+                            Err(error) => messagebox(&tr("error-open-svg"), &error.to_string()),
+                        }
+                    }
+
+                    if ui.button(tr("dialog-cancel")).clicked() {
+                        self.modal = ModalWindows::None;
+                    }
+                });
+            });
+    }
+
+    fn export_options_window(&mut self, context: &Context, renderer: &mut Renderer) {
+        let mut tone_mapping_changed = false;
+        let mut load_profile = false;
+        let mut save_profile = false;
+
+        egui::Window::new(tr("export-title"))
+            .collapsible(false)
+            .auto_sized()
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                let export = &mut self.rack.export;
+
+                ui.heading("JPEG");
+                ui.add(
+                    egui::Slider::new(&mut export.jpeg_quality, 1..=100).text(tr("export-quality")),
+                );
+                egui::ComboBox::from_label(tr("export-subsampling"))
+                    .selected_text(format!("{:?}", export.jpeg_subsampling))
+                    .show_ui(ui, |ui| {
+                        for subsampling in ChromaSubsampling::ALL {
+                            ui.selectable_value(
+                                &mut export.jpeg_subsampling,
+                                subsampling,
+                                format!("{:?}", subsampling),
+                            );
+                        }
+                    });
+
+                ui.separator();
+                ui.heading("PNG");
+                egui::ComboBox::from_label(tr("export-compression"))
+                    .selected_text(format!("{:?}", export.png_compression))
+                    .show_ui(ui, |ui| {
+                        for compression in [
+                            CompressionType::Fast,
+                            CompressionType::Default,
+                            CompressionType::Best,
+                        ] {
+                            ui.selectable_value(
+                                &mut export.png_compression,
+                                compression,
+                                format!("{:?}", compression),
+                            );
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.label(tr("export-bit-depth"));
+                    ui.selectable_value(
+                        &mut export.png_bit_depth,
+                        PngBitDepth::Source,
+                        tr("export-source-depth"),
+                    );
+                    ui.selectable_value(
+                        &mut export.png_bit_depth,
+                        PngBitDepth::Eight,
+                        tr("export-8-bit"),
+                    );
+                    ui.selectable_value(
+                        &mut export.png_bit_depth,
+                        PngBitDepth::Sixteen,
+                        tr("export-16-bit"),
+                    );
+                });
+                ui.checkbox(&mut export.png_indexed, tr("export-indexed"));
+
+                ui.separator();
+                ui.heading(tr("export-palette-heading"));
+                egui::ComboBox::from_label(tr("export-palette"))
+                    .selected_text(export.palette.name())
+                    .show_ui(ui, |ui| {
+                        for palette in IndexedPalette::ALL {
+                            ui.selectable_value(&mut export.palette, palette, palette.name());
+                        }
                     });
-                    row.col(|ui| {
-                        if ui.button("❎").on_hover_text("Remove").clicked() {
-                            action = Some(Action::Remove(idx));
+                ui.add_enabled(
+                    export.palette.has_color_count(),
+                    egui::Slider::new(&mut export.palette_colors, 2..=256)
+                        .text(tr("export-colors")),
+                );
+                egui::ComboBox::from_label(tr("export-palette-dither"))
+                    .selected_text(export.palette_dither.name())
+                    .show_ui(ui, |ui| {
+                        for dither in Dither::ALL {
+                            ui.selectable_value(&mut export.palette_dither, dither, dither.name());
                         }
                     });
-                });
-                continue;
-            }
-            body.row(20.0, |mut row| {
-                let inst = name.instance.as_ref().unwrap();
-                let info = inst.get_info();
-                row.col(|ui| {
-                    ui.label(&info.name)
-                        .on_hover_text(
-                        format!("Right-click for more options\n{} ({})\nCategory: {:?}\nInitial delay: {}\nI/O: {}/{}\n64 bit mixing support: {}", 
-                        info.name, info.vendor, info.category, info.initial_delay, info.inputs, info.outputs, info.f64_precision));
-                }).context_menu(|ui| {
-                    ui.label("Image input channel processing:");
-                    ui.separator();
-                    let mut radio = name.input_channel;
-                                    
-                    if ui.selectable_value(&mut radio, InputChannelType::Hue, "H").clicked() {
-                        action = Some(Action::ChangeInputChannel(idx, radio))
-                    };
-                    if ui.selectable_value(&mut radio, InputChannelType::Saturation, "S").clicked() {
-                        action = Some(Action::ChangeInputChannel(idx, radio))
-                    };
-                    if ui.selectable_value(&mut radio, InputChannelType::Value, "V").clicked() {
-                        action = Some(Action::ChangeInputChannel(idx, radio))
-                    };
 
-                    ui.label("Output audio channel:");
-                    ui.separator();
-                    let mut output = name.output_channel;
-                    let prefix = match output {
-                        0 => { "Left/Mono " }
-                        1 => { "Right " }
-                        _ => { "" }
-                    };
+                ui.separator();
+                ui.heading("WebP");
+                ui.checkbox(&mut export.webp_lossless, tr("export-lossless"));
+                ui.add_enabled(
+                    !export.webp_lossless,
+                    egui::Slider::new(&mut export.webp_quality, 0..=100).text(tr("export-quality")),
+                );
 
-                    if ui.add(egui::Slider::new(&mut output, 0..=(info.outputs - 1) as usize).prefix(prefix)).changed() {
-                        action = Some(Action::ChangeOutputChannel(idx, output));
-                    }
-                    
+                ui.separator();
+                ui.heading("AVIF");
+                ui.add(
+                    egui::Slider::new(&mut export.avif_quality, 0..=100).text(tr("export-quality")),
+                );
+                ui.add(egui::Slider::new(&mut export.avif_speed, 1..=10).text(tr("export-speed")));
 
-                    let mut wet = name.wet * 100.0;
-                    ui.label("Wet:");
-                    ui.separator();
-                    if ui.add(egui::Slider::new(&mut wet, 0.0..=100.0).suffix("%")).changed() {
-                        action = Some(Action::ChangeWet(idx, wet / 100.0));
-                    }
+                ui.separator();
+                ui.heading("HDR");
+                egui::ComboBox::from_label(tr("export-tone-mapping"))
+                    .selected_text(format!("{:?}", export.tone_mapping))
+                    .show_ui(ui, |ui| {
+                        for mapping in ToneMapping::ALL {
+                            tone_mapping_changed |= ui
+                                .selectable_value(
+                                    &mut export.tone_mapping,
+                                    mapping,
+                                    format!("{:?}", mapping),
+                                )
+                                .changed();
+                        }
+                    });
+                tone_mapping_changed |= ui
+                    .add(
+                        egui::Slider::new(&mut export.exposure, 0.01..=16.0)
+                            .logarithmic(true)
+                            .text(tr("export-exposure")),
+                    )
+                    .changed();
 
-                    let mut sample_rate = name.sample_rate;
-                    ui.label("Sample rate:");
-                    ui.separator();
-                    if ui.add(egui::Slider::new(&mut sample_rate, 1102.0..=768000.0).suffix("Hz")).changed() {
-                        action = Some(Action::ChangeSampleRate(idx, sample_rate));
-                    }
-                });
-                
-                row.col(|ui| {
-                        ui.add_enabled_ui(self.rack.is_finished(), |ui| {
-                            if ui.button("❎").on_hover_text("Remove").clicked() {
-                                action = Some(Action::Remove(idx));
-                            }
-    
-                            let color = if name.bypass {
-                                Color32::DARK_RED
-                            } else {
-                                ui.visuals().widgets.active.bg_fill
-                            };
-    
-                            if ui.add(egui::Button::new("M").fill(color)).on_hover_text("Bypass/Mute processing").clicked() {
-                                action = Some(Action::Bypass(idx));
-                            } 
-                        });
+                ui.separator();
+                ui.checkbox(&mut export.sidecar_chain, tr("export-sidecar"))
+                    .on_hover_text(tr_args(
+                        "export-sidecar-hint",
+                        &[("extension", SIDECAR_EXTENSION)],
+                    ));
 
-                        if ui.button("🔧").on_hover_text("Open GUI Editor").clicked() {
-                            action = Some(Action::OpenEditor(idx));
+                ui.separator();
+                ui.heading(tr("export-profile"));
+                ui.label(format!(
+                    "{}: {{name}}, {{date}}, {{chain}}, {{width}}, {{height}}",
+                    tr("export-tokens")
+                ));
+                let profile = &mut self.rack.export_profile;
+                let mut remove = None;
+                for (id, target) in profile.targets.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut target.template);
+                        let mut limit = target.max_size.is_some();
+                        if ui.checkbox(&mut limit, tr("export-max-size")).changed() {
+                            target.max_size = if limit { Some(2048) } else { None };
+                        }
+                        if let Some(max_size) = &mut target.max_size {
+                            ui.add(egui::DragValue::new(max_size).clamp_range(1..=65535));
+                        }
+                        if ui.button("🗑").clicked() {
+                            remove = Some(id);
                         }
+                    });
+                }
+                if let Some(id) = remove {
+                    profile.targets.remove(id);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(tr("export-add-output")).clicked() {
+                        profile.targets.push(ExportTarget {
+                            template: String::from("{name}.png"),
+                            max_size: None,
+                        });
+                    }
+                    load_profile = ui.button(tr("export-load-profile")).clicked();
+                    save_profile = ui.button(tr("export-save-profile")).clicked();
                 });
+
+                ui.separator();
+                if ui.button(tr("prefs-ok")).clicked() {
+                    self.modal = ModalWindows::None;
+                }
             });
+
+        if load_profile {
+            self.load_export_profile_ui();
+        }
+        if save_profile {
+            self.save_export_profile_ui();
         }
-        action
-    }
 
-    fn init(&mut self, renderer: &mut Renderer) {
-        renderer.cleanup_image();
-        renderer.windows.clear();
-        renderer.destroy_texture();
-        self.rack = PluginRack::new();
-        self.save_path = None;
+        if tone_mapping_changed && self.rack.is_finished() {
+            self.rack.refresh_tone_mapping();
+            renderer.cleanup_image();
+        }
     }
 
-    fn exit_window(&mut self, context: &Context) -> DialogVariant {
-        if self.rack.images.is_empty() && self.rack.plugins.is_empty() {
-            return DialogVariant::No;
-        }
-        let mut res = DialogVariant::None;
-        egui::Window::new("Project management")
+    /// Image overview with the visible part of the preview, dragging moves the view
+    fn navigator_window(&mut self, context: &Context, texture: TextureId, width: f32, height: f32) {
+        egui::Window::new(tr("navigator-title"))
             .collapsible(false)
-            .auto_sized()
-            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .resizable(false)
+            .open(&mut self.show_navigator)
+            .anchor(Align2::RIGHT_BOTTOM, vec2(-8.0, -8.0))
             .show(context, |ui| {
-                if self.save_path.is_some() {
-                    ui.label(format!(
-                        "Save {} before exiting?",
-                        self.save_path.as_ref().unwrap().display()
-                    ));
-                } else {
-                    ui.label("Save project before exiting?");
+                let scale = 200.0 / width.max(height);
+                let size = vec2(width * scale, height * scale);
+                let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+
+                let painter = ui.painter_at(rect);
+                painter.image(
+                    texture,
+                    rect,
+                    Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+                    Color32::WHITE,
+                );
+
+                if let Some(bounds) = self.view_bounds {
+                    let (left, top) =
+                        plot_to_pixel_unclamped(bounds.min()[0], bounds.max()[1], width, height);
+                    let (right, bottom) =
+                        plot_to_pixel_unclamped(bounds.max()[0], bounds.min()[1], width, height);
+                    let viewport = Rect::from_min_max(
+                        rect.min + vec2(left, top) * scale,
+                        rect.min + vec2(right, bottom) * scale,
+                    );
+                    painter.rect_stroke(viewport, 0.0, Stroke::new(2.0, Color32::RED));
+                }
+
+                if let Some(pointer) = response.interact_pointer_pos() {
+                    let position = (pointer - rect.min) / size;
+                    self.navigate_to = Some(image_to_plot(position.x, position.y, width, height));
                 }
+            });
+    }
+
+    /// RGB and luma histograms of the processed image with clipped pixel share
+    fn histogram_window(&mut self, context: &Context) {
+        let image = match self.rack.images.last() {
+            Some(image) => image,
+            None => return,
+        };
+        let histogram = self.histogram.get_or_insert_with(|| Histogram::new(image));
+        let peak = histogram.peak() as f64;
+
+        egui::Window::new(tr("histogram-title"))
+            .resizable(false)
+            .open(&mut self.show_histogram)
+            .show(context, |ui| {
+                let colors = [Color32::RED, Color32::GREEN, Color32::BLUE, Color32::WHITE];
+                Plot::new("histogram")
+                    .width(256.0)
+                    .height(128.0)
+                    .allow_drag(false)
+                    .allow_zoom(false)
+                    .show_axes([false; 2])
+                    .include_y(0.0)
+                    .include_y(1.0)
+                    .legend(Legend::default().position(Corner::LeftTop))
+                    .show(ui, |plot_ui| {
+                        let channels = histogram.channels.iter().zip(histogram::CHANNEL_NAMES);
+                        for ((channel, name), color) in channels.zip(colors) {
+                            let points: PlotPoints = channel
+                                .iter()
+                                .enumerate()
+                                .map(|(value, count)| [value as f64, *count as f64 / peak])
+                                .collect();
+                            plot_ui.line(Line::new(points).color(color).name(name));
+                        }
+                    });
+
+                let shadows = format!("{:.2}", histogram.clipped_shadows * 100.0);
+                ui.label(tr_args(
+                    "histogram-clipped-shadows",
+                    &[("percentage", &shadows)],
+                ));
+                let highlights = format!("{:.2}", histogram.clipped_highlights * 100.0);
+                ui.label(tr_args(
+                    "histogram-clipped-highlights",
+                    &[("percentage", &highlights)],
+                ));
+            });
+    }
 
+    fn console_window(&mut self, context: &Context) {
+        let entries = console::entries(self.console_level);
+
+        egui::Window::new(tr("console-title"))
+            .open(&mut self.show_console)
+            .default_size(vec2(600.0, 300.0))
+            .show(context, |ui| {
                 ui.horizontal(|ui| {
-                    if ui.button("✅ Yes").clicked() {
-                        res = DialogVariant::Yes;
+                    egui::ComboBox::from_label(tr("console-level"))
+                        .selected_text(self.console_level.to_string())
+                        .show_ui(ui, |ui| {
+                            for level in [
+                                LevelFilter::Error,
+                                LevelFilter::Warn,
+                                LevelFilter::Info,
+                                LevelFilter::Debug,
+                            ] {
+                                ui.selectable_value(
+                                    &mut self.console_level,
+                                    level,
+                                    level.to_string(),
+                                );
+                            }
+                        });
+                    if ui.button(tr("console-copy")).clicked() {
+                        let text: Vec<String> =
+                            entries.iter().map(|entry| entry.to_string()).collect();
+                        set_clipboard_text(text.join("\n")).unwrap_or_else(|error| {
+                            messagebox(&tr("error-copy-log"), &error.to_string());
+                        });
                     }
-                    if ui.button("❎ No").clicked() {
-                        res = DialogVariant::No;
+                    if ui.button(tr("console-clear")).clicked() {
+                        console::clear();
                     }
-                    if ui.button("🚫 Cancel").clicked() {
-                        self.modal = ModalWindows::None;
-                        res = DialogVariant::Cancel;
+                });
+                ui.separator();
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .stick_to_bottom()
+                    .show_rows(ui, row_height, entries.len(), |ui, rows| {
+                        for entry in &entries[rows] {
+                            let color = match entry.level {
+                                Level::Error => Color32::RED,
+                                Level::Warn => Color32::YELLOW,
+                                Level::Info => ui.visuals().text_color(),
+                                Level::Debug | Level::Trace => Color32::GRAY,
+                            };
+                            ui.label(RichText::new(entry.to_string()).monospace().color(color));
+                        }
+                    });
+            });
+    }
+
+    /// Adds images picked by user to the render queue with the current chain
+    fn enqueue_images(&mut self) {
+        let images = rfd::FileDialog::new()
+            .set_title(&tr("dialog-render-images"))
+            .add_filter(
+                "Images",
+                &[
+                    "png", "jpg", "jpeg", "bmp", "tiff", "tif", "webp", "avif", "tga", "exr", "hdr",
+                ],
+            )
+            .pick_files();
+        let images = match images {
+            Some(images) => images,
+            None => return,
+        };
+        let dir = match rfd::FileDialog::new()
+            .set_title(&tr("dialog-output-folder"))
+            .pick_folder()
+        {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let chain = match self.rack.save_chain() {
+            Ok(chain) => chain,
+            Err(error) => {
+                messagebox(&tr("error-save-chain"), &error.to_string());
+                return;
+            }
+        };
+        let chain_name = tab_title(&self.rack, &self.save_path);
+        for image in images {
+            let mut output = dir.join(image.file_name().unwrap_or_default());
+            output.set_extension(&self.settings.export_format);
+            self.jobs.enqueue(
+                image,
+                output,
+                chain_name.clone(),
+                chain.clone(),
+                self.rack.export,
+            );
+        }
+    }
+
+    fn jobs_window(&mut self, context: &Context) {
+        let jobs = self.jobs.jobs();
+        let running = self.jobs.is_running();
+        let mut add_images = false;
+
+        egui::Window::new(tr("jobs-title"))
+            .open(&mut self.show_jobs)
+            .default_size(vec2(500.0, 300.0))
+            .show(context, |ui| {
+                ui.horizontal(|ui| {
+                    add_images = ui.button(tr("jobs-add")).clicked();
+                    ui.add(
+                        egui::DragValue::new(&mut self.job_workers)
+                            .clamp_range(1..=num_cpus::get())
+                            .prefix(format!("{}: ", tr("jobs-workers"))),
+                    )
+                    .on_hover_text(tr("jobs-workers-hint"));
+                    if ui
+                        .add_enabled(!running, egui::Button::new(tr("jobs-start")))
+                        .clicked()
+                    {
+                        self.jobs.start(self.job_workers);
+                    }
+                    if ui.button(tr("jobs-clear")).clicked() {
+                        self.jobs.clear_finished();
                     }
                 });
+                ui.separator();
+
+                if jobs.is_empty() {
+                    ui.label(tr("jobs-empty"));
+                }
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        for job in &jobs {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("⏶").clicked() {
+                                    self.jobs.move_job(job.id, -1);
+                                }
+                                if ui.small_button("⏷").clicked() {
+                                    self.jobs.move_job(job.id, 1);
+                                }
+                                let cancel = egui::Button::new("✖").small();
+                                if ui
+                                    .add_enabled(!job.is_finished(), cancel)
+                                    .on_hover_text(tr("jobs-cancel"))
+                                    .clicked()
+                                {
+                                    self.jobs.cancel(job.id);
+                                }
+                                ui.label(format!(
+                                    "{} ({})",
+                                    job.image.file_name().unwrap_or_default().to_string_lossy(),
+                                    job.chain_name
+                                ))
+                                .on_hover_text(job.output.display().to_string());
+
+                                match &job.status {
+                                    JobStatus::Queued => {
+                                        ui.label(tr("jobs-queued"));
+                                    }
+                                    JobStatus::Running => {
+                                        ui.add(
+                                            egui::ProgressBar::new(job.progress).show_percentage(),
+                                        );
+                                    }
+                                    JobStatus::Done => {
+                                        ui.label(tr("jobs-done"));
+                                    }
+                                    JobStatus::Failed(error) => {
+                                        ui.colored_label(Color32::RED, tr("jobs-failed"))
+                                            .on_hover_text(error.as_str());
+                                    }
+                                    JobStatus::Cancelled => {
+                                        ui.label(tr("jobs-cancelled"));
+                                    }
+                                }
+                            });
+                        }
+                    });
             });
 
-        res
+        if add_images {
+            self.enqueue_images();
+        }
     }
 
-    fn about_window(&mut self, context: &Context) {
-        egui::Window::new("About")
-            .collapsible(false)
-            .auto_sized()
-            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+    fn metrics_window(&mut self, context: &Context) {
+        if self.metrics.is_none() && self.rack.is_finished() && !self.rack.images.is_empty() {
+            self.metrics = self.rack.measure_quality().unwrap_or_else(|op| {
+                log::error!("Unable to measure image quality: {}", op);
+                None
+            });
+        }
+
+        egui::Window::new(tr("metrics-title"))
+            .resizable(false)
+            .open(&mut self.show_metrics)
+            .show(context, |ui| match self.metrics {
+                Some(metrics) => {
+                    if metrics.psnr.is_infinite() {
+                        ui.label(tr("metrics-identical"));
+                    } else {
+                        ui.label(format!("PSNR: {:.2} dB", metrics.psnr));
+                    }
+                    ui.label(format!("SSIM: {:.4}", metrics.ssim));
+                }
+                None if !self.rack.is_finished() => {
+                    ui.label(tr("metrics-processing"));
+                }
+                None => {
+                    ui.label(tr("metrics-unavailable"));
+                }
+            });
+    }
+
+    /// Pixel values under the cursor and how the selected plugin sees them
+    fn inspector_window(&mut self, context: &Context) {
+        egui::Window::new(tr("pixel-title"))
+            .resizable(false)
+            .open(&mut self.eyedropper)
             .show(context, |ui| {
-                let text = RichText::new("PhotoConsequences").heading();
-                ui.label(text);
-                ui.label(format!("Version: {}", crate::VERSION));
-                ui.label("Tool to apply VST™ effects on the images");
-                ui.label("VST™ is a trademark of Steinberg Media Technologies GmbH.");
+                let (image, (x, y)) = match (self.rack.images.last(), self.inspected_pixel) {
+                    (Some(image), Some(pixel)) => (image, pixel),
+                    _ => {
+                        ui.label(tr("pixel-hover"));
+                        return;
+                    }
+                };
+                let [r, g, b, a] = image.get_pixel(x, y).0;
 
-                if ui.button("Ok").clicked() {
-                    self.modal = ModalWindows::None;
+                ui.horizontal(|ui| {
+                    egui::widgets::color_picker::show_color(
+                        ui,
+                        Color32::from_rgba_unmultiplied(r, g, b, a),
+                        vec2(32.0, 32.0),
+                    );
+                    ui.vertical(|ui| {
+                        ui.label(tr_args(
+                            "pixel-position",
+                            &[("x", &x.to_string()), ("y", &y.to_string())],
+                        ));
+                        ui.label(format!("RGBA: {} {} {} {}", r, g, b, a));
+                        ui.label(format!("Hex: #{:02X}{:02X}{:02X}{:02X}", r, g, b, a));
+                    });
+                });
+
+                let plugin = self
+                    .selected_plugin
+                    .and_then(|id| self.rack.plugins.get(id))
+                    .or_else(|| self.rack.plugins.first());
+                if let Some(plugin) = plugin {
+                    // processing uses high bit depth pixels when they are available
+                    let sample = match self.rack.deep_images.last() {
+                        Some(DeepImage::Rgba16(deep)) => plugin.map_sample(&deep.get_pixel(x, y).0),
+                        Some(DeepImage::Rgba32F(deep)) => {
+                            plugin.map_sample(&deep.get_pixel(x, y).0)
+                        }
+                        None => plugin.map_sample(&[r, g, b, a]),
+                    };
+                    let scale = self.rack.preview_scale();
+                    let (column, row) = plugin.tile_at(x as usize, y as usize, scale);
+                    let (width, height) = (image.width() as usize, image.height() as usize);
+
+                    ui.separator();
+                    ui.label(plugin.get_name());
+                    let channel = format!("{:?}", plugin.input_channel);
+                    let sample = format!("{:.4}", sample);
+                    ui.label(tr_args(
+                        "pixel-sample",
+                        &[("channel", &channel), ("value", &sample)],
+                    ));
+                    ui.label(tr_args(
+                        "pixel-tile",
+                        &[("column", &column.to_string()), ("row", &row.to_string())],
+                    ));
+                    if !plugin.affects(x as usize, y as usize, width, height, scale) {
+                        ui.label(tr("pixel-not-affected"));
+                    }
                 }
             });
     }
@@ -298,43 +3764,342 @@ impl State {
         }
 
         let files = rfd::FileDialog::new()
-            .set_title("Save project")
+            .set_title(&tr("dialog-save-project"))
             .add_filter("PhotoCosnequences project file (*.viproj)", &["viproj"])
             .save_file();
         self.save_path = files;
         self.save_project_ui();
     }
 
-    fn save_project_ui(&mut self) {
-        if self.rack.images.is_empty() && self.rack.plugins.is_empty() {
-            return;
+    fn save_project_ui(&mut self) {
+        if self.rack.images.is_empty() && self.rack.plugins.is_empty() {
+            return;
+        }
+        // project stores full resolution image
+        self.rack.end_preview();
+        if self.save_path.is_some() {
+            let mut save_path = self.save_path.clone().unwrap();
+            log::debug!("{}", save_path.display());
+            if save_path.extension().is_none() {
+                save_path.set_extension("viproj");
+            }
+
+            let contents = self.settings.project_contents;
+            let result = self
+                .rack
+                .save_project(save_path, &contents, &self.undo_history);
+            result.unwrap_or_else(|error| {
+                messagebox(&tr("error-load-project"), &error.to_string());
+            });
+        } else {
+            self.save_project_as_ui();
+        }
+    }
+
+    fn process(&mut self) {
+        self.audition = None;
+        self.rack.end_preview();
+        if let Some(recording) = &mut self.recording {
+            recording.record_process(&mut self.rack);
+        }
+        match self.rack.start_process() {
+            Ok(()) => self.push_undo(UndoStep::Image),
+            Err(error) => messagebox(&tr("error-process-image"), &error.to_string()),
+        }
+    }
+
+    fn stop_process(&mut self) {
+        let preview = self.rack.is_preview();
+        match self.rack.stop_process() {
+            // cancelled run can't be undone, so the step of it is dropped
+            Ok(()) if !preview => {
+                let step = self
+                    .undo_history
+                    .iter()
+                    .rposition(|step| matches!(step, UndoStep::Image));
+                if let Some(step) = step {
+                    self.undo_history.remove(step);
+                }
+            }
+            Ok(()) => {}
+            Err(error) => messagebox(&tr("error-cancel-processing"), &error.to_string()),
+        }
+        self.rack.end_preview();
+    }
+
+    /// Re-processes shown tile or proxy preview after plugin parameters were changed
+    fn live_preview_update(&mut self, renderer: &mut Renderer) {
+        if !self.live_preview || !self.rack.take_parameter_change(LIVE_PREVIEW_DEBOUNCE) {
+            return;
+        }
+
+        if let (Some(_), Some((x, y))) = (&self.audition, self.tile_pointer) {
+            if self.rack.is_finished() {
+                self.audition = self.rack.audition_tile(x, y, self.audition_size);
+                renderer.cleanup_image();
+            }
+            return;
+        }
+
+        if self.rack.is_finished() || self.rack.is_preview() {
+            if !self.rack.is_finished() {
+                self.stop_process();
+            }
+            renderer.cleanup_image();
+            self.rack.start_preview();
+        }
+    }
+
+    fn watch_files(&mut self, renderer: &mut Renderer) {
+        if self.watch_timer.elapsed() < FILE_WATCH_INTERVAL {
+            return;
+        }
+        self.watch_timer = Instant::now();
+
+        if self.settings.watch_plugins {
+            self.watch_plugins();
+        }
+        // controllers plugged in later are connected too
+        if self.midi_learn.is_some() || self.rack.has_midi_mappings() {
+            self.midi.refresh().unwrap_or_else(|error| {
+                log::warn!("Unable to open MIDI input: {}", error);
+            });
+        }
+        self.watch_external_edit(renderer);
+        if self.watch_image {
+            self.watch_image(renderer);
+        }
+    }
+
+    /// Maps learned parameter to the first received control change,
+    /// other control changes set mapped parameters
+    fn poll_midi(&mut self) {
+        for (control, value) in self.midi.poll() {
+            match self.midi_learn.take() {
+                Some((id, parameter)) => {
+                    self.apply_action(Action::ChangeMidiControl(id, parameter, Some(control)))
+                }
+                None => self.rack.apply_midi(control, value),
+            }
+        }
+    }
+
+    /// Remembers modification times of plugin files and collects changed ones
+    fn watch_plugins(&mut self) {
+        for plugin in &self.rack.plugins {
+            let path = plugin.get_path();
+            let modified = match plugin_library::modified(&path) {
+                Some(modified) => modified,
+                None => continue,
+            };
+
+            match self.plugin_modified.insert(path.clone(), modified) {
+                Some(previous) if previous != modified && !self.changed_plugins.contains(&path) => {
+                    log::info!("Plugin changed on disk: {}", path.display());
+                    self.changed_plugins.push(path);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Re-imports source image after it was saved by another program, chain is kept
+    /// and processed again if the image was processed
+    fn watch_image(&mut self, renderer: &mut Renderer) {
+        let path = match &self.rack.source_path {
+            Some(path) if self.rack.is_finished() => path.clone(),
+            _ => return,
+        };
+        if !file_changed(&mut self.image_modified, &path) {
+            return;
+        }
+
+        let processed = self.rack.images.len() > 1;
+        renderer.cleanup_image();
+        self.audition = None;
+        match self.rack.load_image(&path) {
+            Ok(()) => {
+                log::info!("Source image reloaded: {}", path.display());
+                if processed {
+                    self.process();
+                }
+            }
+            // file may be still being written, it is loaded on the next change
+            Err(error) => log::warn!("Unable to reload {}: {}", path.display(), error),
+        }
+    }
+
+    /// Exports result into temporary file and opens it in the external editor,
+    /// the file is imported back when the editor saves it
+    fn edit_externally(&mut self) -> anyhow::Result<()> {
+        let editor = match &self.settings.external_editor {
+            Some(editor) => editor.clone(),
+            None => anyhow::bail!("External editor is not set in Preferences"),
+        };
+
+        let path = std::env::temp_dir().join(format!(
+            "photoconsequences-edit-{}.{}",
+            std::process::id(),
+            self.settings.export_format
+        ));
+        self.rack.save_image(&path)?;
+
+        // application bundles are started through `open`
+        if cfg!(target_os = "macos") && editor.extension() == Some(std::ffi::OsStr::new("app")) {
+            std::process::Command::new("open")
+                .arg("-a")
+                .arg(&editor)
+                .arg(&path)
+                .spawn()?;
+        } else {
+            std::process::Command::new(&editor).arg(&path).spawn()?;
+        }
+
+        self.external_edit = None;
+        file_changed(&mut self.external_edit, &path);
+        Ok(())
+    }
+
+    /// Imports image saved by the external editor
+    fn watch_external_edit(&mut self, renderer: &mut Renderer) {
+        let path = match &self.external_edit {
+            Some((path, _)) if self.rack.is_finished() => path.clone(),
+            _ => return,
+        };
+        if !file_changed(&mut self.external_edit, &path) {
+            return;
+        }
+
+        renderer.cleanup_image();
+        self.audition = None;
+        match self.rack.load_image(&path) {
+            Ok(()) => {
+                log::info!("Edited image imported: {}", path.display());
+                // source image watcher must not load it again
+                self.image_modified = self.external_edit.clone();
+            }
+            Err(error) => log::warn!("Unable to import {}: {}", path.display(), error),
+        }
+    }
+
+    fn plugin_reload_window(&mut self, context: &Context, renderer: &mut Renderer) {
+        let mut reload = None;
+        let mut ignore = None;
+
+        egui::Window::new(tr("plugins-changed-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::RIGHT_BOTTOM, vec2(-10.0, -10.0))
+            .show(context, |ui| {
+                for (id, path) in self.changed_plugins.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(path.display().to_string());
+                        if ui
+                            .add_enabled(
+                                self.rack.is_finished(),
+                                egui::Button::new(tr("plugins-changed-reload")),
+                            )
+                            .on_disabled_hover_text(tr("plugins-changed-reload-hint"))
+                            .clicked()
+                        {
+                            reload = Some(id);
+                        }
+                        if ui.button(tr("plugins-changed-ignore")).clicked() {
+                            ignore = Some(id);
+                        }
+                    });
+                }
+            });
+
+        if let Some(id) = ignore {
+            self.changed_plugins.remove(id);
         }
-        if self.save_path.is_some() {
-            let mut save_path = self.save_path.clone().unwrap();
-            println!("{}", save_path.display());
-            if save_path.extension().is_none() {
-                save_path.set_extension("viproj");
-            }
 
-            self.rack.save_project(save_path).unwrap_or_else(|error| {
-                messagebox("Unable to load project", &error.to_string());
+        if let Some(id) = reload {
+            let path = self.changed_plugins.remove(id);
+            for plugin_id in 0..self.rack.plugins.len() {
+                if self.rack.plugins[plugin_id].get_path() == path {
+                    self.close_plugin_editor(renderer, plugin_id);
+                }
+            }
+            self.rack.reload_plugins(&path).unwrap_or_else(|error| {
+                messagebox(&tr("error-reload-plugin"), &error.to_string());
             });
-        } else {
-            self.save_project_as_ui();
         }
     }
 
-    fn process(&mut self) {
-        self.rack.start_process();
+    fn resume_window(&mut self, context: &Context) {
+        let mut resume = false;
+        let mut discard = false;
+
+        egui::Window::new(tr("resume-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(context, |ui| {
+                if let Some((checkpoint, _)) = &self.resume_checkpoint {
+                    let percentage = checkpoint.position * 100 / checkpoint.total.max(1);
+                    ui.label(tr_args(
+                        "resume-message",
+                        &[
+                            ("percentage", &percentage.min(100).to_string()),
+                            ("pass", &(checkpoint.pass + 1).to_string()),
+                        ],
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    resume = ui.button(tr("resume-continue")).clicked();
+                    discard = ui.button(tr("resume-discard")).clicked();
+                });
+            });
+
+        if discard {
+            self.resume_checkpoint = None;
+        }
+
+        if resume {
+            if let Some((checkpoint, partial)) = self.resume_checkpoint.take() {
+                self.audition = None;
+                self.rack.end_preview();
+                self.rack
+                    .resume(checkpoint, partial)
+                    .unwrap_or_else(|error| {
+                        messagebox(&tr("error-resume-processing"), &error.to_string());
+                    });
+            }
+        }
     }
 
-    pub fn update(&mut self, renderer: &mut Renderer) {
+    pub fn update(&mut self, renderer: &mut Renderer, main_window: &Window) {
+        self.live_preview_update(renderer);
+        self.watch_files(renderer);
+        self.poll_midi();
+        self.replay_macro(renderer);
         self.rack.process_next();
         self.resize_editors(renderer);
+        self.place_docked_editors(renderer, main_window);
+
+        for plugin in self.rack.plugins.iter_mut() {
+            plugin.editor.idle();
+        }
         //println!("{:#?}", renderer.windows);
 
-        if !self.rack.is_finished() && self.timer.elapsed().as_millis() > 100 {
-            renderer.texture = None;
+        // tiles processed at the end of the run are shown right away
+        if self.rack.is_finished() || self.timer.elapsed().as_millis() > 100 {
+            // only tiles processed since the last update are uploaded
+            let updated = match self.rack.take_dirty_tiles() {
+                // overlays are painted over the whole image on upload
+                Some(_) if self.show_clipping || self.compare == CompareMode::Difference => false,
+                Some(tiles) => {
+                    self.histogram = None;
+                    self.metrics = None;
+                    renderer.update_texture_tiles(self.rack.images.last().unwrap(), &tiles)
+                }
+                None => true,
+            };
+            if !updated {
+                renderer.texture = None;
+            }
             self.timer = Instant::now();
         }
     }
@@ -345,6 +4110,31 @@ impl State {
         renderer: &mut Renderer,
         event_loop: &EventLoopWindowTarget<renderer::Event>,
     ) {
+        if self.language != Some(self.settings.language) {
+            i18n::set_language(self.settings.language);
+            context.set_fonts(i18n::font_definitions(self.settings.language));
+            self.language = Some(self.settings.language);
+        }
+        let visuals = self.settings.theme.visuals();
+        if context.style().visuals != visuals {
+            context.set_visuals(visuals);
+        }
+        self.handle_shortcuts(context, renderer);
+        if self.palette_open {
+            self.command_palette(context, renderer);
+        }
+        if self.show_console {
+            self.console_window(context);
+        }
+        if self.show_jobs {
+            self.jobs_window(context);
+        }
+        if !self.changed_plugins.is_empty() {
+            self.plugin_reload_window(context, renderer);
+        }
+        if self.resume_checkpoint.is_some() {
+            self.resume_window(context);
+        }
         match self.modal {
             ModalWindows::Exit => match self.exit_window(context) {
                 DialogVariant::Yes => {
@@ -371,70 +4161,261 @@ impl State {
             ModalWindows::About => {
                 self.about_window(context);
             }
+            ModalWindows::OpenUrl => {
+                self.open_url_window(context, renderer);
+            }
+            ModalWindows::CaptureScreen => {
+                self.capture_screen_window(context, renderer);
+            }
+            ModalWindows::OpenVideo => {
+                self.open_video_window(context, renderer);
+            }
+            ModalWindows::OpenSvg => {
+                self.open_svg_window(context, renderer);
+            }
+            ModalWindows::ExportOptions => {
+                self.export_options_window(context, renderer);
+            }
+            ModalWindows::Preferences => {
+                self.preferences_window(context, renderer);
+            }
             _ => {}
         }
         egui::TopBottomPanel::bottom("statusbar").show(context, |ui| {
-            ui.label(format!(
-                "Memory used: {} MiB Processed: {}%",
-                self.rack.calculate_memory_size() / 1024 / 1024,
-                self.rack.compute_complete_percentage()
-            ));
+            ui.horizontal(|ui| {
+                let progress = self.rack.progress();
+                ui.label(tr_args(
+                    "status-progress",
+                    &[
+                        (
+                            "memory",
+                            &(self.rack.calculate_memory_size() / 1024 / 1024).to_string(),
+                        ),
+                        ("percentage", &format!("{:.0}", progress.fraction * 100.0)),
+                        ("pass", &(self.rack.get_current_pass() + 1).to_string()),
+                        ("passes", &self.rack.iterations.to_string()),
+                        ("time", &format!("{:.1}", progress.elapsed.as_secs_f32())),
+                    ],
+                ));
+                if !self.rack.is_finished() {
+                    if let Some(plugin) = &progress.plugin {
+                        ui.label(tr_args("status-slowest", &[("name", plugin)]));
+                    }
+                }
+
+                if let Some(animation) = &self.rack.animation {
+                    ui.label(tr_args(
+                        "status-frame",
+                        &[
+                            ("frame", &(animation.current + 1).to_string()),
+                            ("count", &animation.frames.len().to_string()),
+                        ],
+                    ));
+                }
+            });
         });
         egui::SidePanel::left("left_panel").show(context, |ui| {
             menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("🖹 New").clicked() {
+                ui.menu_button(tr("menu-file"), |ui| {
+                    if ui.button(tr("menu-new")).clicked() {
                         self.modal = ModalWindows::ExitNew;
                     }
-                    if ui.button("📂 Open project").clicked() {
+                    if ui.button(tr("menu-open-project")).clicked() {
                         let files = rfd::FileDialog::new()
-                            .set_title("Open project")
+                            .set_title(&tr("dialog-open-project"))
                             .add_filter("PhotoCosnequences project file (*.viproj)", &["viproj"])
                             .pick_file();
 
                         if let Some(file) = files {
                             self.load_project(renderer, file).unwrap_or_else(|error| {
-                                messagebox("Unable to load project", &error.to_string());
+                                messagebox(&tr("error-load-project"), &error.to_string());
                             });
                         }
                     }
                     ui.separator();
+                    ui.add_enabled_ui(self.rack.is_finished(), |ui| {
+                        if ui.button(tr("menu-load-chain")).clicked() {
+                            self.load_chain_preset_ui(renderer);
+                        }
+                        ui.menu_button(tr("menu-import-chain"), |ui| {
+                            if ui.button(tr("menu-import-chain-append")).clicked() {
+                                self.import_chain_ui(renderer, true);
+                                ui.close_menu();
+                            }
+                            if ui.button(tr("menu-import-chain-replace")).clicked() {
+                                self.import_chain_ui(renderer, false);
+                                ui.close_menu();
+                            }
+                        });
+                        ui.add_enabled_ui(!self.rack.plugins.is_empty(), |ui| {
+                            if ui.button(tr("menu-save-chain")).clicked() {
+                                self.save_chain_preset_ui();
+                            }
+                        });
+                    });
+                    ui.separator();
                     ui.add_enabled_ui(
                         !self.rack.images.is_empty()
                             && !self.rack.plugins.is_empty()
-                            && self.rack.is_finished(),
+                            && self.rack.is_finished()
+                            && !self.rack.is_preview(),
                         |ui| {
-                            if ui.button("💾 Save").clicked() {
+                            if ui.button(tr("menu-save")).clicked() {
                                 self.save_project_ui();
                             }
 
-                            if ui.button("💾 Save as").clicked() {
+                            if ui.button(tr("menu-save-as")).clicked() {
                                 self.save_project_as_ui();
                             }
                             ui.separator();
-                            if ui.button("🖼 Export image").clicked() {
+                            if ui.button(tr("menu-export-image")).clicked() {
                                 self.export_image();
                             }
+                            if ui.button(tr("menu-export-profile")).clicked() {
+                                self.export_profile();
+                            }
+                            if ui.button(tr("menu-export-difference")).clicked() {
+                                self.export_difference();
+                            }
+                            if ui.button(tr("menu-export-report")).clicked() {
+                                self.export_report();
+                            }
+                            if ui.button(tr("menu-export-options")).clicked() {
+                                self.modal = ModalWindows::ExportOptions;
+                            }
+                            if ui.button(tr("menu-edit-externally")).clicked() {
+                                self.edit_externally().unwrap_or_else(|error| {
+                                    messagebox(
+                                        &tr("error-open-external-editor"),
+                                        &error.to_string(),
+                                    );
+                                });
+                            }
+                            if ui.button(tr("menu-copy-image")).clicked() {
+                                self.copy_image().unwrap_or_else(|error| {
+                                    messagebox(&tr("error-copy-image"), &error.to_string());
+                                });
+                            }
                         },
                     );
-                    if ui.button("❎ Exit").clicked() {
+                    ui.separator();
+                    ui.checkbox(&mut self.watch_image, tr("watch-image"))
+                        .on_hover_text(tr("watch-image-hint"));
+                    if ui.button(tr("menu-exit")).clicked() {
                         self.modal = ModalWindows::Exit;
                     }
                 });
 
-                ui.menu_button("Tools", |ui| {
-                    if ui.button("⧯ Generate noise image").clicked() {
+                ui.menu_button(tr("menu-tools"), |ui| {
+                    if ui.button(tr("menu-noise")).clicked() {
                         renderer.cleanup_image();
-                        self.rack.images.clear();
-                        self.rack.images.push(image_generators::generate_noise());
+                        self.rack.set_image(image::DynamicImage::ImageRgba8(
+                            image_generators::generate_noise(),
+                        ));
+                    }
+                    ui.separator();
+                    ui.add_enabled_ui(self.rack.is_finished(), |ui| {
+                        if ui.button(tr("menu-open-sequence")).clicked() {
+                            if let Some(dir) = rfd::FileDialog::new()
+                                .set_title(&tr("dialog-sequence-folder"))
+                                .pick_folder()
+                            {
+                                renderer.cleanup_image();
+                                self.rack.load_sequence(dir).unwrap_or_else(|error| {
+                                    messagebox(&tr("error-load-sequence"), &error.to_string());
+                                });
+                            }
+                        }
+
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("menu-animation-from-image")).clicked() {
+                                renderer.cleanup_image();
+                                self.rack.duplicate_still(self.still_frames);
+                            }
+                            ui.add(
+                                egui::DragValue::new(&mut self.still_frames)
+                                    .clamp_range(2..=10000)
+                                    .suffix(format!(" {}", tr("menu-frames"))),
+                            );
+                        });
+
+                        if ui.button(tr("menu-export-sequence")).clicked() {
+                            if let Some(dir) = rfd::FileDialog::new()
+                                .set_title(&tr("dialog-output-folder"))
+                                .pick_folder()
+                            {
+                                self.rack.save_sequence(dir).unwrap_or_else(|error| {
+                                    messagebox(&tr("error-save-sequence"), &error.to_string());
+                                });
+                            }
+                        }
+                    });
+                    ui.separator();
+                    ui.add_enabled_ui(self.rack.is_finished(), |ui| {
+                        if ui.button(tr("menu-surprise")).clicked() {
+                            self.surprise_me(renderer, rand::random());
+                        }
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut self.random_seed)
+                                    .prefix(format!("{}: ", tr("menu-seed"))),
+                            );
+                            if ui
+                                .button(tr("menu-replay-seed"))
+                                .on_hover_text(tr("menu-replay-seed-hint"))
+                                .clicked()
+                            {
+                                self.surprise_me(renderer, self.random_seed);
+                            }
+                        });
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut self.random_count, 1..=8)
+                            .text(tr("menu-random-effects")),
+                    );
+                    ui.separator();
+                    if ui
+                        .checkbox(&mut self.dock_editors, tr("dock-editors"))
+                        .changed()
+                    {
+                        for window in &renderer.windows {
+                            window.set_decorations(!self.dock_editors);
+                        }
+                    }
+                    if ui
+                        .button(tr_args(
+                            "menu-plugin-library",
+                            &[("count", &self.plugin_library.len().to_string())],
+                        ))
+                        .clicked()
+                    {
+                        self.pick_plugin_library();
+                    }
+                    ui.checkbox(&mut self.show_jobs, tr("menu-render-queue"));
+                    if self.recording.is_some() {
+                        if ui.button(tr("menu-stop-recording")).clicked() {
+                            self.stop_recording();
+                        }
+                    } else if ui.button(tr("menu-record-macro")).clicked() {
+                        self.recording = Some(Macro::default());
+                    }
+                    ui.add_enabled_ui(self.replaying.is_none(), |ui| {
+                        if ui.button(tr("menu-replay-macro")).clicked() {
+                            self.replay_macro_ui();
+                        }
+                    });
+                    ui.checkbox(&mut self.show_console, tr("menu-log-console"));
+                    ui.separator();
+                    if ui.button(tr("menu-preferences")).clicked() {
+                        self.modal = ModalWindows::Preferences;
                     }
                 });
 
-                ui.menu_button("About", |ui| {
-                    if ui.button("ℹ About").clicked() {
+                ui.menu_button(tr("menu-about"), |ui| {
+                    if ui.button(tr("menu-about-window")).clicked() {
                         self.modal = ModalWindows::About;
                     }
-                    if ui.button("⬌ GitHub repository page").clicked() {
+                    if ui.button(tr("menu-github")).clicked() {
                         webbrowser::open("http://github.com/LaineZ/PhotoConsequences").unwrap();
                     }
                 });
@@ -450,29 +4431,61 @@ impl State {
                     if let Some(act) = self.plugin_table_draw(body) {
                         match act {
                             Action::OpenEditor(id) => self.open_editor(id, renderer, event_loop),
-                            Action::Remove(id) => {
-                                self.rack.remove_plugin(id);
-                                renderer.windows.clear();
+                            Action::Select(id) => {
+                                self.selected_plugin = Some(id);
                             }
-                            Action::Bypass(id) => {
-                                self.rack.plugins[id].bypass = !self.rack.plugins[id].bypass;
+                            Action::LearnMidi(id, parameter) => {
+                                if self.midi_learn == Some((id, parameter)) {
+                                    self.midi_learn = None;
+                                } else {
+                                    match self.midi.refresh() {
+                                        Ok(()) => self.midi_learn = Some((id, parameter)),
+                                        Err(error) => {
+                                            messagebox(&tr("error-open-midi"), &error.to_string())
+                                        }
+                                    }
+                                }
                             }
-                            Action::ChangeInputChannel(id, channel) => {
-                                self.rack.plugins[id].input_channel = channel;
+                            Action::CopySettings(id) => {
+                                if let Some(settings) = self.rack.plugins[id].export_settings() {
+                                    set_clipboard_text(settings).unwrap_or_else(|error| {
+                                        messagebox(&tr("error-copy-settings"), &error.to_string());
+                                    });
+                                }
                             }
-                            Action::ChangeWet(id, wet) => {
-                                self.rack.plugins[id].wet = wet;
+                            Action::PasteSettings(id) => {
+                                get_clipboard_text()
+                                    .and_then(|settings| {
+                                        Ok(self.rack.plugins[id].import_settings(&settings)?)
+                                    })
+                                    .unwrap_or_else(|error| {
+                                        messagebox(&tr("error-paste-settings"), &error.to_string());
+                                    });
                             }
-                            Action::ChangeOutputChannel(id, value) => {
-                                self.rack.plugins[id].output_channel = value;
+                            Action::Remove(id) => {
+                                self.close_plugin_editor(renderer, id);
+                                self.apply_action(Action::Remove(id));
+                                self.selected_plugin = match self.selected_plugin {
+                                    Some(selected) if selected == id => None,
+                                    Some(selected) if selected > id => Some(selected - 1),
+                                    selected => selected,
+                                };
                             }
-                            Action::ChangeSampleRate(id, value) => {
-                                self.rack.plugins[id].sample_rate = value;
+                            Action::ChangeRegion(id, region) => {
+                                let region = region.map(|r| snap_region(r, &self.guides));
+                                self.apply_action(Action::ChangeRegion(id, region));
                             }
+                            action => self.apply_action(action),
                         }
                     }
                 });
 
+            egui::CollapsingHeader::new(tr("graph-title")).show(ui, |ui| {
+                ui.add_enabled_ui(self.rack.is_finished(), |ui| {
+                    self.routing_ui(ui);
+                });
+            });
+
             ui.with_layout(
                 egui::Layout::from_main_dir_and_cross_align(
                     egui::Direction::TopDown,
@@ -480,95 +4493,637 @@ impl State {
                 ),
                 |ui| {
                     if ui
-                        .add_sized([140.0, 1.0], egui::Button::new("➕ Add VST Effect"))
+                        .add_sized([140.0, 1.0], egui::Button::new(tr("rack-add-vst")))
                         .clicked()
                     {
-                        let mut extensions = ["so"];
-
-                        if cfg!(target_os = "windows") {
-                            extensions = ["dll"];
-                        }
-                        if cfg!(target_os = "macos") {
-                            extensions = ["vst"];
-                        }
-
                         let file = rfd::FileDialog::new()
-                            .add_filter("VST 2.4 Plugin", &extensions)
+                            .add_filter("VST 2.4 Plugin", &[PLUGIN_EXTENSION])
                             .pick_file();
 
                         if let Some(file) = file {
-                            self.rack.load_plugin(file).unwrap_or_else(|op| {
-                                messagebox("Plugin loading failed!", &op.to_string());
+                            self.add_plugin(file).unwrap_or_else(|op| {
+                                messagebox(&tr("error-plugin-loading"), &op.to_string());
                             });
                         }
                     }
+
+                    ui.menu_button(tr("rack-add-built-in"), |ui| {
+                        for (path, name) in effects::available_effects() {
+                            if ui.button(name).clicked() {
+                                self.add_plugin(path).unwrap_or_else(|op| {
+                                    messagebox(&tr("error-effect-loading"), &op.to_string());
+                                });
+                                ui.close_menu();
+                            }
+                        }
+                    });
                 },
             );
         });
 
+        if self.selected_plugin.is_some() {
+            egui::SidePanel::right("inspector").show(context, |ui| {
+                self.inspector_ui(ui);
+                self.keyframes_ui(ui);
+            });
+        }
+
         egui::CentralPanel::default().show(context, |ui| {
+            self.tab_bar(ui, renderer);
             ui.horizontal(|ui| {
-                if ui.button("📂 Open image").clicked() {
+                if ui.button(tr("open-image")).clicked() {
                     let files = rfd::FileDialog::new()
                         .add_filter(
                             "Images",
                             &[
-                                "png", "jpg", "jpeg", "gif", "bmp", "ico", "tiff", "webp", "avif",
-                                "dds", "tga",
+                                "png", "apng", "jpg", "jpeg", "gif", "bmp", "ico", "tiff", "tif",
+                                "webp", "avif", "dds", "tga", "exr", "hdr", "svg", "svgz",
                             ],
                         )
+                        .add_filter("Camera RAW", &raw::RAW_EXTENSIONS)
                         .pick_file();
 
                     if let Some(file) = files {
-                        self.load_image(renderer, file).unwrap();
+                        self.load_image(renderer, file).unwrap_or_else(|error| {
+                            messagebox(&tr("error-open-image"), &error.to_string());
+                        });
+                    }
+                }
+
+                if ui.button(tr("open-video")).clicked() {
+                    self.video_path = rfd::FileDialog::new()
+                        .add_filter("Videos", &video::VIDEO_EXTENSIONS)
+                        .pick_file();
+
+                    if self.video_path.is_some() {
+                        self.modal = ModalWindows::OpenVideo;
                     }
                 }
 
+                if ui.button(tr("open-url")).clicked() {
+                    self.modal = ModalWindows::OpenUrl;
+                }
+
+                if ui.button(tr("open-capture-screen")).clicked() {
+                    self.modal = ModalWindows::CaptureScreen;
+                }
+
+                if ui.button(tr("open-webcam")).clicked() {
+                    self.capture_webcam(renderer).unwrap_or_else(|error| {
+                        messagebox(&tr("error-capture-webcam"), &error.to_string());
+                    });
+                }
+
                 ui.add_enabled_ui(!self.rack.images.is_empty(), |ui| {
                     if self.rack.is_finished() {
-                        if ui.button("✅ Apply FX on image").clicked() {
+                        if ui.button(tr("process-apply")).clicked() {
                             self.process();
                         }
+                        if ui
+                            .button(tr("process-preview"))
+                            .on_hover_text(tr("process-preview-hint"))
+                            .clicked()
+                        {
+                            renderer.cleanup_image();
+                            self.audition = None;
+                            self.rack.start_preview();
+                        }
+                        ui.checkbox(&mut self.live_preview, tr("process-live"))
+                            .on_hover_text(tr("process-live-hint"));
+                        if self.rack.is_preview()
+                            && ui.button(tr("process-full-resolution")).clicked()
+                        {
+                            renderer.cleanup_image();
+                            self.rack.end_preview();
+                        }
                     } else {
-                        if ui.button("☠ Cancel").clicked() {
+                        if ui.button(tr("process-cancel")).clicked() {
                             renderer.cleanup_image();
-                            self.rack.stop_process();
+                            self.stop_process();
+                        }
+                    }
+                });
+
+                ui.add_enabled_ui(self.can_undo(), |ui| {
+                    if ui.button(tr("process-undo")).clicked() {
+                        self.undo(renderer);
+                    }
+                });
+                ui.add_enabled_ui(self.can_redo(), |ui| {
+                    if ui.button(tr("process-redo")).clicked() {
+                        self.redo(renderer);
+                    }
+                });
+
+                ui.add_enabled_ui(self.rack.is_finished(), |ui| {
+                    for (slot, label) in ["A", "B"].iter().enumerate() {
+                        if ui
+                            .selectable_label(self.active_snapshot == slot, *label)
+                            .on_hover_text(tr("process-snapshot-hint"))
+                            .clicked()
+                        {
+                            self.switch_snapshot(slot, renderer)
+                                .unwrap_or_else(|error| {
+                                    messagebox(&tr("error-switch-snapshot"), &error.to_string());
+                                });
+                        }
+                    }
+
+                    let mut proxy_scale = self.rack.proxy_scale * 100.0;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut proxy_scale)
+                                .clamp_range(5.0..=100.0)
+                                .prefix(format!("{}: ", tr("process-preview-size")))
+                                .suffix("%"),
+                        )
+                        .changed()
+                    {
+                        self.rack.proxy_scale = proxy_scale / 100.0;
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut self.rack.iterations)
+                            .clamp_range(1..=64)
+                            .prefix(format!("{}: ", tr("process-passes"))),
+                    );
+                    let mut decay = self.rack.wet_decay * 100.0;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut decay)
+                                .clamp_range(0.0..=100.0)
+                                .prefix(format!("{}: ", tr("process-wet-decay")))
+                                .suffix("%"),
+                        )
+                        .changed()
+                    {
+                        self.rack.wet_decay = decay / 100.0;
+                    }
+                    ui.horizontal(|ui| {
+                        let mut auto = matches!(self.rack.sample_rate, SampleRate::PerRow(_));
+                        if ui
+                            .checkbox(&mut auto, tr("process-auto-sample-rate"))
+                            .on_hover_text(tr("process-auto-sample-rate-hint"))
+                            .changed()
+                        {
+                            self.rack.sample_rate = if auto {
+                                SampleRate::PerRow(0.01)
+                            } else {
+                                SampleRate::default()
+                            };
+                        }
+
+                        match &mut self.rack.sample_rate {
+                            SampleRate::Fixed(rate) => {
+                                ui.add(
+                                    egui::DragValue::new(rate)
+                                        .clamp_range(1102.0..=768000.0)
+                                        .prefix(format!("{} ", tr("plugin-sample-rate")))
+                                        .suffix("Hz"),
+                                );
+                            }
+                            SampleRate::PerRow(seconds) => {
+                                let mut milliseconds = *seconds * 1000.0;
+                                if ui
+                                    .add(
+                                        egui::DragValue::new(&mut milliseconds)
+                                            .clamp_range(0.01..=10000.0)
+                                            .prefix(format!("{}: ", tr("process-row-length")))
+                                            .suffix("ms"),
+                                    )
+                                    .changed()
+                                {
+                                    *seconds = milliseconds / 1000.0;
+                                }
+                            }
+                        }
+                    });
+                    ui.checkbox(&mut self.rack.interlaced, tr("process-interlaced"))
+                        .on_hover_text(tr("process-interlaced-hint"));
+                    egui::ComboBox::from_label(tr("process-dither"))
+                        .selected_text(self.rack.dither.name())
+                        .show_ui(ui, |ui| {
+                            for dither in Dither::ALL {
+                                ui.selectable_value(&mut self.rack.dither, dither, dither.name());
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr("process-dither-hint"));
+                    egui::ComboBox::from_label(tr("process-grayscale"))
+                        .selected_text(
+                            self.rack
+                                .grayscale
+                                .map_or(tr("plugin-off"), |mode| mode.name().to_string()),
+                        )
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.rack.grayscale, None, tr("plugin-off"));
+                            for mode in Grayscale::ALL {
+                                let grayscale = &mut self.rack.grayscale;
+                                ui.selectable_value(grayscale, Some(mode), mode.name());
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr("process-grayscale-hint"));
+
+                    let mut autosave = self.rack.autosave.is_some();
+                    if ui
+                        .checkbox(&mut autosave, tr("process-partial-save"))
+                        .on_hover_text(tr("process-partial-save-hint"))
+                        .changed()
+                    {
+                        self.rack.autosave = if autosave {
+                            rfd::FileDialog::new()
+                                .set_title(&tr("dialog-partial-image"))
+                                .add_filter("PNG Image", &["png"])
+                                .add_filter("TIFF Image", &["tiff", "tif"])
+                                .add_filter("OpenEXR Image", &["exr"])
+                                .save_file()
+                                .map(|file| {
+                                    let minutes = self.settings.autosave_minutes;
+                                    Autosave::new(file, Duration::from_secs(minutes * 60))
+                                })
+                        } else {
+                            None
+                        };
+                    }
+                    if let Some(autosave) = &mut self.rack.autosave {
+                        let mut minutes = autosave.interval.as_secs() / 60;
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut minutes)
+                                    .clamp_range(1..=240)
+                                    .prefix(format!("{}: ", tr("process-every")))
+                                    .suffix(format!(" {}", tr("prefs-minutes"))),
+                            )
+                            .changed()
+                        {
+                            autosave.interval = Duration::from_secs(minutes * 60);
+                        }
+                    }
+
+                    let mut checkpoint = self.rack.checkpoint.is_some();
+                    if ui
+                        .checkbox(&mut checkpoint, tr("checkpoints"))
+                        .on_hover_text(tr("checkpoints-hint"))
+                        .changed()
+                    {
+                        self.rack.checkpoint = if checkpoint {
+                            self.save_path
+                                .clone()
+                                .or_else(|| {
+                                    rfd::FileDialog::new()
+                                        .set_title(&tr("dialog-checkpoint-file"))
+                                        .add_filter("Project file (*.viproj)", &["viproj"])
+                                        .save_file()
+                                })
+                                .map(|file| {
+                                    let minutes = self.settings.autosave_minutes;
+                                    Autosave::new(file, Duration::from_secs(minutes * 60))
+                                })
+                        } else {
+                            None
+                        };
+                    }
+                    if let Some(checkpoint) = &mut self.rack.checkpoint {
+                        let mut minutes = checkpoint.interval.as_secs() / 60;
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut minutes)
+                                    .clamp_range(1..=240)
+                                    .prefix(format!("{}: ", tr("process-every")))
+                                    .suffix(format!(" {}", tr("prefs-minutes"))),
+                            )
+                            .on_hover_text(checkpoint.path.display().to_string())
+                            .changed()
+                        {
+                            checkpoint.interval = Duration::from_secs(minutes * 60);
                         }
                     }
                 });
+            });
+
+            if let Some(animation) = &self.rack.animation {
+                let mut frame = animation.current;
+                let count = animation.frames.len();
+                let processed = animation.processed_count();
 
-                ui.add_enabled_ui(
-                    self.rack.images.len() > 1 && self.rack.is_finished(),
-                    |ui| {
-                        if ui.button("↻ Undo").clicked() {
+                ui.add_enabled_ui(self.rack.is_finished(), |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut frame, 0..=count - 1)
+                                    .text(tr("keyframes-frame")),
+                            )
+                            .changed()
+                        {
                             renderer.cleanup_image();
-                            self.rack.undo();
+                            self.rack.show_frame(frame);
                         }
-                    },
+
+                        ui.checkbox(
+                            &mut self.rack.reset_state_per_frame,
+                            tr("animation-reset-plugins"),
+                        )
+                        .on_hover_text(tr("animation-reset-plugins-hint"));
+                        ui.label(tr_args(
+                            "animation-processed",
+                            &[
+                                ("processed", &processed.to_string()),
+                                ("count", &count.to_string()),
+                            ],
+                        ));
+                    });
+                });
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button(tr("view-fit")).clicked() {
+                    self.zoom_request = Some(CanvasZoom::Fit);
+                }
+                if ui
+                    .button("1:1")
+                    .on_hover_text(tr("command-actual-pixels"))
+                    .clicked()
+                {
+                    self.zoom_request = Some(CanvasZoom::ActualPixels);
+                }
+                let has_region = self
+                    .selected_plugin
+                    .and_then(|id| self.rack.plugins.get(id))
+                    .map_or(false, |plugin| plugin.region.is_some());
+                if ui
+                    .add_enabled(has_region, egui::Button::new(tr("view-selection")))
+                    .on_hover_text(tr("view-selection-hint"))
+                    .clicked()
+                {
+                    self.zoom_request = Some(CanvasZoom::Selection);
+                }
+                ui.checkbox(&mut self.pixel_grid, tr("prefs-pixel-grid"));
+                ui.checkbox(&mut self.show_navigator, tr("navigator-title"));
+                ui.checkbox(&mut self.show_rulers, tr("prefs-rulers"));
+                ui.checkbox(&mut self.show_histogram, tr("histogram-title"));
+                ui.checkbox(&mut self.show_metrics, tr("view-metrics"))
+                    .on_hover_text(tr("view-metrics-hint"));
+                if ui
+                    .checkbox(&mut self.show_clipping, tr("view-clipping"))
+                    .on_hover_text(tr("view-clipping-hint"))
+                    .changed()
+                {
+                    renderer.cleanup_image();
+                }
+                ui.checkbox(&mut self.eyedropper, tr("command-eyedropper"))
+                    .on_hover_text(tr("view-eyedropper-hint"));
+
+                let compare = self.compare;
+                ui.label(tr("compare-title"));
+                ui.selectable_value(&mut self.compare, CompareMode::None, tr("plugin-off"));
+                ui.selectable_value(&mut self.compare, CompareMode::Split, tr("compare-split"))
+                    .on_hover_text(tr("compare-split-hint"));
+                ui.selectable_value(&mut self.compare, CompareMode::Blink, tr("compare-blink"))
+                    .on_hover_text(tr("compare-blink-hint"));
+                ui.selectable_value(
+                    &mut self.compare,
+                    CompareMode::Checkerboard,
+                    tr("compare-checkerboard"),
                 );
+                ui.selectable_value(
+                    &mut self.compare,
+                    CompareMode::Difference,
+                    tr("compare-difference"),
+                );
+                if compare != self.compare {
+                    self.blink_original = false;
+                    renderer.cleanup_image();
+                }
+                if self.compare == CompareMode::Blink {
+                    ui.checkbox(&mut self.blink_original, tr("compare-original"));
+                }
+                if self.compare == CompareMode::Difference {
+                    let gain = egui::DragValue::new(&mut self.difference_gain)
+                        .clamp_range(1.0..=64.0)
+                        .speed(0.1)
+                        .prefix(format!("{}: ", tr("graph-gain")));
+                    if ui.add(gain).changed() {
+                        renderer.cleanup_image();
+                    }
+                }
+                if ui.button(tr("guides-vertical")).clicked() {
+                    self.guides.push(Guide {
+                        vertical: true,
+                        position: 0.5,
+                    });
+                }
+                if ui.button(tr("guides-horizontal")).clicked() {
+                    self.guides.push(Guide {
+                        vertical: false,
+                        position: 0.5,
+                    });
+                }
+                if !self.guides.is_empty() && ui.button(tr("guides-clear")).clicked() {
+                    self.guides.clear();
+                }
             });
 
-            let plot = Plot::new("items_demo")
+            let mut plot = Plot::new("items_demo")
                 .legend(Legend::default().position(Corner::RightBottom))
                 .show_x(false)
                 .show_y(false)
                 .show_background(false)
                 .show_axes([false; 2])
+                .allow_drag(
+                    self.hovered_guide.is_none()
+                        && self.dragging_guide.is_none()
+                        && !self.split_hovered
+                        && !self.dragging_split,
+                )
                 .data_aspect(1.0);
+            if let Some(image) = self.rack.images.last().filter(|_| self.show_rulers) {
+                // axes show image pixels instead of plot coordinates
+                let (w, h) = (image.width() as f32, image.height() as f32);
+                plot = plot
+                    .show_axes([true; 2])
+                    .x_axis_formatter(move |x, _| {
+                        format!("{:.0}", plot_to_pixel_unclamped(x, 0.0, w, h).0)
+                    })
+                    .y_axis_formatter(move |y, _| {
+                        format!("{:.0}", plot_to_pixel_unclamped(0.0, y, w, h).1)
+                    });
+            }
+            ui.painter().rect_filled(
+                ui.available_rect_before_wrap(),
+                0.0,
+                self.settings.theme.canvas_color(),
+            );
             if let Some(texture) = &renderer.texture {
                 let w = self.rack.images.last().unwrap().width() as f32;
                 let h = self.rack.images.last().unwrap().height() as f32;
                 let image =
                     PlotImage::new(*texture, PlotPoint::new(0.0, 0.0), vec2(1.0 / h, 1.0 / w));
 
-                plot.show(ui, |plot_ui| {
-                    plot_ui.image(image);
+                // processed pixel range of the current pass
+                let progress = (!self.rack.is_finished()).then(|| {
+                    let position = self.rack.progress().done as f32;
+                    (position, position + self.rack.block_size as f32)
+                });
+
+                let zoom_request = self.zoom_request.take();
+                let selection = self
+                    .selected_plugin
+                    .and_then(|id| self.rack.plugins.get(id))
+                    .and_then(|plugin| plugin.region);
+                let pixel_grid = self.pixel_grid;
+                let grid_color = self.settings.theme.grid_color();
+                let navigate_to = self.navigate_to.take();
+                let texture_id = *texture;
+
+                let guides = self.guides.clone();
+                let dragging_guide = self.dragging_guide;
+                let (mut hovered_guide, mut moved_guide) = (None, None);
+
+                let original = renderer.original;
+                let compare = self.compare;
+                let blink_original = self.blink_original;
+                let (split_position, dragging_split) = (self.split_position, self.dragging_split);
+                let (mut split_hovered, mut moved_split) = (false, None);
+
+                let mut view_bounds = None;
+                let mut pointer = None;
+                let response = plot
+                    .show(ui, |plot_ui| {
+                        plot_ui.image(image);
+                        if let Some(original) = original {
+                            match compare {
+                                CompareMode::Split => {
+                                    (split_hovered, moved_split) = draw_split(
+                                        plot_ui,
+                                        original,
+                                        split_position,
+                                        dragging_split,
+                                        w,
+                                        h,
+                                    );
+                                }
+                                CompareMode::Blink if blink_original => {
+                                    let area = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+                                    draw_original(plot_ui, original, area, w, h);
+                                }
+                                CompareMode::Checkerboard => {
+                                    draw_checkerboard(plot_ui, original, w, h)
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        if let Some(zoom) = zoom_request {
+                            apply_zoom(plot_ui, zoom, selection, w, h);
+                        }
+                        if let Some(center) = navigate_to {
+                            let bounds = plot_ui.plot_bounds();
+                            let half_size = [bounds.width() / 2.0, bounds.height() / 2.0];
+                            plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+                                [center[0] - half_size[0], center[1] - half_size[1]],
+                                [center[0] + half_size[0], center[1] + half_size[1]],
+                            ));
+                        }
+                        view_bounds = Some(plot_ui.plot_bounds());
+                        if pixel_grid {
+                            draw_pixel_grid(plot_ui, w, h, grid_color);
+                        }
+                        if let Some((start, end)) = progress {
+                            let (start, end) = ((start / w).min(h), (end / w).min(h));
+                            let bands = [
+                                (0.0, start, Color32::from_rgb(0, 200, 80), 0.1),
+                                (start, end, Color32::YELLOW, 0.4),
+                                (end, h, Color32::BLACK, 0.5),
+                            ];
+                            for (top, bottom, color, alpha) in bands {
+                                if top < bottom {
+                                    plot_ui.polygon(
+                                        rows_polygon(top, bottom, w, h)
+                                            .color(color)
+                                            .fill_alpha(alpha),
+                                    );
+                                }
+                            }
+                        }
+                        pointer = plot_ui.pointer_coordinate();
+
+                        (hovered_guide, moved_guide) =
+                            draw_guides(plot_ui, &guides, dragging_guide, w, h);
+                    })
+                    .response;
+
+                self.hovered_guide = hovered_guide;
+                if let Some((id, position)) = moved_guide {
+                    self.guides[id].position = position;
+                }
+                self.split_hovered = split_hovered;
+                if let Some(position) = moved_split {
+                    self.split_position = position;
+                }
+                if response.drag_started() {
+                    self.dragging_guide = hovered_guide;
+                    self.dragging_split = split_hovered && hovered_guide.is_none();
+                }
+                if response.drag_released() {
+                    self.dragging_split = false;
+                    // guides dropped outside of the image are removed
+                    if let Some(id) = self.dragging_guide.take() {
+                        if !(0.0..=1.0).contains(&self.guides[id].position) {
+                            self.guides.remove(id);
+                        }
+                    }
+                }
+
+                self.view_bounds = view_bounds;
+                if self.show_navigator {
+                    self.navigator_window(ui.ctx(), texture_id, w, h);
+                }
+                if self.show_histogram {
+                    self.histogram_window(ui.ctx());
+                }
+                if self.show_metrics {
+                    self.metrics_window(ui.ctx());
+                }
+                if self.eyedropper {
+                    if response.hovered() {
+                        self.inspected_pixel = pointer.and_then(|point| plot_to_pixel(point, w, h));
+                    }
+                    self.inspector_window(ui.ctx());
+                }
+
+                if response.secondary_clicked() {
+                    self.tile_pointer = pointer.and_then(|point| plot_to_pixel(point, w, h));
+                }
+                response.context_menu(|ui| {
+                    let enabled = self.tile_pointer.is_some()
+                        && self.rack.is_finished()
+                        && !self.rack.plugins.is_empty();
+                    ui.add_enabled_ui(enabled, |ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut self.audition_size)
+                                .clamp_range(16..=2048)
+                                .prefix(format!("{}: ", tr("canvas-tile-size"))),
+                        );
+                        if ui.button(tr("canvas-preview-tile")).clicked() {
+                            let (x, y) = self.tile_pointer.unwrap();
+                            self.audition = self.rack.audition_tile(x, y, self.audition_size);
+                            renderer.cleanup_image();
+                            ui.close_menu();
+                        }
+                    });
+                    if self.audition.is_some()
+                        && ui.button(tr("canvas-hide-tile-preview")).clicked()
+                    {
+                        self.audition = None;
+                        renderer.cleanup_image();
+                        ui.close_menu();
+                    }
                 });
             } else {
                 plot.show(ui, |plot_ui| {
-                    let mut text = RichText::new("Processing image, please wait...").heading();
+                    let mut text = RichText::new(tr("canvas-processing")).heading();
                     if self.rack.is_finished() {
-                        text = RichText::new("Welcome to PhotoConsequences!").heading();
+                        text = RichText::new(tr("canvas-welcome")).heading();
                     }
 
                     plot_ui.text(Text::new(PlotPoint::new(0.0, 0.0), text));
@@ -576,10 +5131,48 @@ impl State {
 
                 if !self.rack.images.is_empty() {
                     renderer.destroy_texture();
-                    renderer.texture =
-                        Some(renderer.upload_texture(self.rack.images.last().unwrap()));
+                    self.histogram = None;
+                    self.metrics = None;
+                    let mut image = Cow::Borrowed(self.rack.images.last().unwrap());
+                    if let Some((left, top, tile)) = &self.audition {
+                        if self.rack.is_finished() {
+                            let image = image.to_mut();
+                            image::imageops::replace(image, tile, *left as i64, *top as i64);
+                        }
+                    }
+                    // undo copy of huge image is read back from disk only for compare views
+                    let original = match self.compare {
+                        CompareMode::None => None,
+                        _ => self
+                            .rack
+                            .original_image()
+                            .map_err(|error| log::error!("Unable to read original: {}", error))
+                            .ok(),
+                    };
+                    if let (CompareMode::Difference, Some(original)) = (self.compare, &original) {
+                        let gain = self.difference_gain;
+                        if let Some(difference) = compare::difference(original, &image, gain) {
+                            image = Cow::Owned(difference);
+                        }
+                    }
+                    if self.show_clipping {
+                        histogram::highlight_clipping(image.to_mut());
+                    }
+                    renderer.texture = Some(renderer.upload_texture(&image));
+
+                    match original {
+                        Some(original)
+                            if self.compare != CompareMode::Difference
+                                && original.dimensions() == image.dimensions() =>
+                        {
+                            renderer.upload_original(&original);
+                        }
+                        _ => renderer.destroy_original(),
+                    }
                 }
             }
         });
+
+        self.store_ui_settings();
     }
 }
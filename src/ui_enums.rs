@@ -1,14 +1,66 @@
-use crate::plugin_rack::InputChannelType;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::animation::Keyframe;
+use crate::mask::ImageMask;
+use crate::midi::MidiControl;
+use crate::plugin_rack::{
+    AutomationPoint, Field, InputChannelType, OutputRoute, RackEdit, Region, TilePattern,
+};
+use crate::routing::ProcessingGraph;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Action {
     OpenEditor(usize),
+    Select(usize),
     Remove(usize),
+    Duplicate(usize),
+    CopySettings(usize),
+    PasteSettings(usize),
     Bypass(usize),
+    Solo(usize),
+    GroupWithNext(usize),
+    Ungroup(usize),
+    ToggleGroupCollapse(usize),
+    ToggleGroupBypass(usize),
+    ChangeGroupWet(usize, f32),
+    RenameGroup(usize, String),
     ChangeInputChannel(usize, InputChannelType),
     ChangeOutputChannel(usize, usize),
+    ChangeMixOutputs(usize, bool),
+    /// Plugin outputs written into other HSV channels
+    ChangeOutputRoutes(usize, Vec<OutputRoute>),
     ChangeWet(usize, f32),
-    ChangeSampleRate(usize, f32),
+    /// Plugin sample rate, project sample rate if `None`
+    ChangeSampleRate(usize, Option<f32>),
+    ChangeIterations(usize, usize),
+    ChangeField(usize, Option<Field>),
+    ChangeSampleOffset(usize, isize),
+    ChangeTilePattern(usize, TilePattern),
+    ChangeTileSize(usize, usize),
+    ChangeRegion(usize, Option<Region>),
+    ChangeMask(usize, Option<ImageMask>),
+    ChangeJitter(usize, f32),
+    ToggleJitterParameter(usize, i32),
+    ChangeJitterSeed(usize, u64),
+    /// Parameter index and value (0.0 - 1.0)
+    ChangeParameter(usize, i32, f32),
+    /// Waits for the next MIDI control change to map it to the parameter
+    LearnMidi(usize, i32),
+    /// Parameter index and MIDI controller, mapping is removed if `None`
+    ChangeMidiControl(usize, i32, Option<MidiControl>),
+    /// Parameter changes recorded from the plugin GUI
+    ChangeAutomation(usize, Vec<AutomationPoint>),
+    ChangeKeyframes(usize, Vec<Keyframe>),
+    /// Processing graph, plugins are processed as a simple chain if `None`
+    ChangeGraph(Option<ProcessingGraph>),
+}
+
+/// Entry of the undo history
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum UndoStep {
+    /// Processing run, the image itself is kept by the rack
+    Image,
+    Rack(RackEdit),
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -16,7 +68,56 @@ pub enum ModalWindows {
     Exit,
     ExitNew,
     About,
+    OpenUrl,
+    CaptureScreen,
+    OpenVideo,
+    OpenSvg,
+    ExportOptions,
+    Preferences,
+    None,
+}
+
+/// View change of the image preview requested from the toolbar
+#[derive(Clone, Copy, Debug)]
+pub enum CanvasZoom {
+    Fit,
+    /// One image pixel per screen pixel
+    ActualPixels,
+    /// Region of the selected plugin
+    Selection,
+}
+
+/// How the original image is shown together with the processed result
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum CompareMode {
     None,
+    /// Original image left of the draggable divider
+    Split,
+    /// Original image is shown instead of the result while toggled
+    Blink,
+    /// Every other cell shows the original image
+    Checkerboard,
+    /// Amplified absolute difference between the original image and the result
+    Difference,
+}
+
+impl CompareMode {
+    pub fn next(self) -> Self {
+        match self {
+            CompareMode::None => CompareMode::Split,
+            CompareMode::Split => CompareMode::Blink,
+            CompareMode::Blink => CompareMode::Checkerboard,
+            CompareMode::Checkerboard => CompareMode::Difference,
+            CompareMode::Difference => CompareMode::None,
+        }
+    }
+}
+
+/// Guide line on the image preview, position is relative to the image size (0.0 - 1.0)
+#[derive(Clone, Copy, Debug)]
+pub struct Guide {
+    pub vertical: bool,
+    pub position: f32,
 }
 
 pub enum DialogVariant {
@@ -25,3 +126,22 @@ pub enum DialogVariant {
     Cancel,
     None,
 }
+
+/// Tab bar actions
+pub enum TabAction {
+    New,
+    Switch(usize),
+    Close(usize),
+    /// Replace chain of the tab with the active one
+    CopyChainTo(usize),
+    /// Replace active chain with the chain of the tab
+    CopyChainFrom(usize),
+}
+
+/// Processing graph edits made from the routing panel
+pub enum GraphEdit {
+    Add(usize),
+    Remove(usize),
+    Split(usize),
+    Flatten(usize),
+}
@@ -0,0 +1,223 @@
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+use anyhow::{bail, Context};
+use image::{Delay, RgbaImage};
+
+use crate::animation::{Animation, AnimationFrame};
+
+pub const VIDEO_EXTENSIONS: [&str; 4] = ["mp4", "webm", "mkv", "mov"];
+
+/// Original video file, used to copy the audio track into the processed video
+pub struct VideoSource {
+    pub path: PathBuf,
+    /// Time of the first loaded frame in seconds
+    pub start: f64,
+}
+
+struct VideoInfo {
+    width: u32,
+    height: u32,
+    fps_numer: u32,
+    fps_denom: u32,
+}
+
+pub fn is_video<P: AsRef<Path>>(file: P) -> bool {
+    file.as_ref()
+        .extension()
+        .map(|ext| {
+            VIDEO_EXTENSIONS
+                .iter()
+                .any(|video| ext.eq_ignore_ascii_case(video))
+        })
+        .unwrap_or(false)
+}
+
+fn probe(file: &Path) -> anyhow::Result<VideoInfo> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=width,height,r_frame_rate",
+            "-of",
+            "csv=p=0",
+        ])
+        .arg(file)
+        .output()
+        .context("Unable to run ffprobe, make sure FFmpeg is installed")?;
+
+    if !output.status.success() {
+        bail!(
+            "Unable to read video: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = text.trim().split(',').collect();
+    if fields.len() < 3 {
+        bail!("File does not contain a video stream");
+    }
+
+    let (numer, denom) = fields[2].split_once('/').unwrap_or((fields[2], "1"));
+    let info = VideoInfo {
+        width: fields[0].parse()?,
+        height: fields[1].parse()?,
+        fps_numer: numer.parse()?,
+        fps_denom: denom.parse()?,
+    };
+
+    if info.fps_numer == 0 || info.fps_denom == 0 {
+        bail!("Video has unknown frame rate");
+    }
+
+    Ok(info)
+}
+
+/// Decodes `count` frames (or all remaining frames) starting from frame `start`
+pub fn load<P: AsRef<Path>>(
+    file: P,
+    start: usize,
+    count: Option<usize>,
+) -> anyhow::Result<Animation> {
+    let file = file.as_ref();
+    let info = probe(file)?;
+    let frame_duration = info.fps_denom as f64 / info.fps_numer as f64;
+
+    let mut command = Command::new("ffmpeg");
+    command.args(["-v", "error"]);
+    if start > 0 {
+        // input seeking skips decoding of the preceding frames, half a frame earlier
+        // so rounding of the timestamp doesn't drop the first frame
+        let seek = (start as f64 - 0.5) * frame_duration;
+        command.args(["-ss", &seek.to_string()]);
+    }
+    command.arg("-i").arg(file).args(["-vsync", "0"]);
+    if let Some(count) = count {
+        command.args(["-frames:v", &count.to_string()]);
+    }
+    let mut child = command
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Unable to run ffmpeg, make sure FFmpeg is installed")?;
+
+    let mut stdout = child.stdout.take().unwrap();
+    let frame_size = info.width as usize * info.height as usize * 4;
+    let delay = Delay::from_numer_denom_ms(1000 * info.fps_denom, info.fps_numer);
+    let mut frames = Vec::new();
+
+    loop {
+        let mut buf = vec![0; frame_size];
+        if stdout.read_exact(&mut buf).is_err() {
+            break;
+        }
+
+        frames.push(AnimationFrame {
+            image: RgbaImage::from_raw(info.width, info.height, buf).unwrap(),
+            delay,
+        });
+    }
+    child.wait()?;
+
+    if frames.is_empty() {
+        bail!("No frames were decoded from the video");
+    }
+
+    let mut animation = Animation::new(frames);
+    animation.video = Some(VideoSource {
+        path: file.to_path_buf(),
+        start: start as f64 * frame_duration,
+    });
+    Ok(animation)
+}
+
+/// Encodes frames into mp4 (H.264) or webm (VP9), audio of the source video is copied if present
+pub fn save<P: AsRef<Path>>(animation: &Animation, file: P) -> anyhow::Result<()> {
+    let file = file.as_ref();
+    let first = &animation.frames[0];
+    let (mut numer, mut denom) = first.delay.numer_denom_ms();
+    if numer == 0 {
+        // 25 fps
+        numer = 40;
+        denom = 1;
+    }
+
+    let mut command = Command::new("ffmpeg");
+    command.args([
+        "-v",
+        "error",
+        "-y",
+        "-f",
+        "rawvideo",
+        "-pix_fmt",
+        "rgba",
+        "-s",
+        &format!("{}x{}", first.image.width(), first.image.height()),
+        "-r",
+        &format!("{}/{}", 1000 * denom, numer),
+        "-i",
+        "-",
+    ]);
+
+    if let Some(source) = &animation.video {
+        command
+            .args(["-ss", &source.start.to_string(), "-i"])
+            .arg(&source.path)
+            .args(["-map", "0:v", "-map", "1:a?", "-shortest"]);
+    }
+
+    let webm = file
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("webm"))
+        .unwrap_or(false);
+    if webm {
+        command.args(["-c:v", "libvpx-vp9", "-c:a", "libopus"]);
+    } else {
+        command.args(["-c:v", "libx264", "-c:a", "aac"]);
+    }
+
+    let mut child = command
+        // yuv420p requires even frame size
+        .args([
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(file)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Unable to run ffmpeg, make sure FFmpeg is installed")?;
+
+    // stderr is drained while frames are written, otherwise ffmpeg blocks on the full pipe
+    let mut stderr = child.stderr.take().unwrap();
+    let errors = thread::spawn(move || {
+        let mut errors = Vec::new();
+        let _ = stderr.read_to_end(&mut errors);
+        String::from_utf8_lossy(&errors).into_owned()
+    });
+
+    let written = {
+        let mut stdin = child.stdin.take().unwrap();
+        (0..animation.frames.len()).try_for_each(|id| stdin.write_all(animation.frame(id)))
+    };
+
+    let status = child.wait()?;
+    let errors = errors.join().unwrap_or_default();
+    if !status.success() {
+        bail!("Video encoding failed: {}", errors);
+    }
+    written.context("Unable to write frames to ffmpeg")?;
+
+    Ok(())
+}